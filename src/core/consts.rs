@@ -4,4 +4,5 @@ pub const DIRECTORY: &str = "Astro";
 pub const RAW_FRAMES_DIR: &str = "RawFrames";
 pub const LIVE_STACKING_DIR: &str = "LiveStacking";
 
-pub const MAX_GOTO_TIME: usize = 180; // seconds
\ No newline at end of file
+pub const MAX_GOTO_TIME: usize = 180; // seconds
+pub const AFTER_MOUNT_MOVE_WAIT_TIME: usize = 3; // seconds
\ No newline at end of file
@@ -1,15 +1,15 @@
 use std::{
     any::Any, path::Path, sync::{
-        atomic::{AtomicBool, AtomicU16, Ordering }, mpsc, Arc, Mutex, RwLock, RwLockReadGuard
-    }
+        atomic::{AtomicBool, AtomicU16, AtomicU32, Ordering }, mpsc, Arc, Mutex, RwLock, RwLockReadGuard
+    }, time::{Duration, Instant}
 };
 use gtk::glib::PropertySet;
 
 use crate::{
-    core::consts::*, guiding::{external_guider::*, phd2_conn, phd2_guider::*}, image::stars_offset::*, indi, options::*, ui::sky_map::math::EqCoord, utils::timer::*
+    core::consts::*, guiding::{external_guider::*, phd2_conn, phd2_guider::*}, image::{info::LightFrameInfo, stars_offset::*}, indi, options::*, ui::sky_map::math::*, utils::timer::*
 };
 use super::{
-    events::*, frame_processing::*, mode_capture_platesolve::*, mode_darks_library::*, mode_focusing::*, mode_goto::*, mode_mount_calibration::*, mode_polar_align::PolarAlignMode, mode_tacking_pictures::*, mode_waiting::*
+    events::*, frame_processing::*, mode_capture_platesolve::*, mode_darks_library::*, mode_flats_session::*, mode_focusing::*, mode_goto::*, mode_mount_calibration::*, mode_polar_align::PolarAlignMode, mode_sequence::*, mode_tacking_pictures::*, mode_targets::*, mode_video_stream::*, mode_waiting::*, utils::{get_focuser_comp_temperature, focuser_set_abs_value_with_backlash, meridian_limit_is_reached, DarkMatchTolerance}
 };
 
 #[derive(PartialEq, Copy, Clone, Debug)]
@@ -31,6 +31,10 @@ pub enum ModeType {
     Goto,
     CapturePlatesolve,
     PolarAlignment,
+    Targets,
+    Sequence,
+    FlatsSession,
+    VideoStream,
 }
 
 pub type ModeBox = Box<dyn Mode + Send + Sync>;
@@ -39,10 +43,12 @@ pub trait Mode {
     fn get_type(&self) -> ModeType;
     fn progress_string(&self) -> String;
     fn cam_device(&self) -> Option<&DeviceAndProp> { None }
+    fn used_devices(&self) -> Vec<&str> { self.cam_device().map(|cam| vec![cam.name.as_str()]).unwrap_or_default() }
     fn progress(&self) -> Option<Progress> { None }
     fn get_cur_exposure(&self) -> Option<f64> { None }
     fn can_be_stopped(&self) -> bool { true }
     fn can_be_continued_after_stop(&self) -> bool { false }
+    fn is_paused(&self) -> bool { false }
     fn start(&mut self) -> anyhow::Result<()> { Ok(()) }
     fn abort(&mut self) -> anyhow::Result<()> { Ok(()) }
     fn continue_work(&mut self) -> anyhow::Result<()> { Ok(()) }
@@ -66,8 +72,30 @@ pub enum NotifyResult {
     StartCreatingDefectPixelsFile(MasterFileCreationProgramItem),
     StartCreatingMasterDarkFile(MasterFileCreationProgramItem),
     StartCreatingMasterBiasFile(MasterFileCreationProgramItem),
+    StartTargetGoto(EqCoord),
+    StartTargetCapture(TargetItem),
+    StartRecenterAfterFlip(Arc<LightFrameInfo>),
+    StartMeridianFlip(Arc<LightFrameInfo>),
+    StartSequenceStep { step: SequenceStep, filter_changed: bool },
+    StartFlatsSessionStep { step: FlatsSessionStep, sky_flats: bool },
+}
+
+/// State of focuser temperature compensation
+#[derive(Default)]
+struct FocuserTempComp {
+    ref_temp:  Option<f64>, // reference temperature compensation is started from
+    applied:   f64,         // steps moved since reference temperature was captured
+    last_move: Option<Instant>,
 }
 
+const FOC_TEMP_COMP_MIN_INTERVAL: Duration = Duration::from_secs(60);
+const FOC_TEMP_COMP_HYSTERESIS: f64 = 0.3; // °C
+const FOC_TEMP_COMP_MOVE_TIMEOUT: Option<u64> = Some(60_000); // ms
+
+const SHUTDOWN_WARMUP_TARGET: f64 = 10.0; // °C
+const SHUTDOWN_WARMUP_STEP: f64 = 5.0; // °C
+const SHUTDOWN_WARMUP_STEP_TIME: Duration = Duration::from_secs(30);
+
 pub struct ModeData {
     pub mode:          ModeBox,
     pub finished_mode: Option<ModeBox>,
@@ -98,6 +126,15 @@ pub struct Core {
     live_stacking:      Arc<LiveStackingData>,
     timer:              Arc<Timer>,
     exp_stuck_wd:       AtomicU16,
+    no_blob_wd:         AtomicU16, // seconds since exposure end without received blob
+    blob_received:      AtomicBool, // blob is received for last exposure
+    blob_reenabled:     AtomicBool, // BLOB was re-enabled because of lost image
+    merid_limit_flag:   AtomicBool, // mount is stopped at meridian limit
+    weather_flag:       AtomicBool, // weather is reported as unsafe
+    mode_wd_cnt:        AtomicU32, // seconds since last activity of mode
+    mode_wd_flag:       AtomicBool, // mode is reported as stuck
+    shutdown_flag:      Arc<AtomicBool>, // emergency shutdown is in progress
+    foc_temp_comp:      Mutex<FocuserTempComp>,
     img_proc_stop_flag: Mutex<Arc<AtomicBool>>, // stop flag for last command
 
     /// commands for passing into frame processing thread
@@ -123,6 +160,15 @@ impl Core {
             live_stacking:      Arc::new(LiveStackingData::new()),
             timer:              Arc::new(Timer::new()),
             exp_stuck_wd:       AtomicU16::new(0),
+            no_blob_wd:         AtomicU16::new(0),
+            blob_received:      AtomicBool::new(false),
+            blob_reenabled:     AtomicBool::new(false),
+            merid_limit_flag:   AtomicBool::new(false),
+            weather_flag:       AtomicBool::new(false),
+            mode_wd_cnt:        AtomicU32::new(0),
+            mode_wd_flag:       AtomicBool::new(false),
+            shutdown_flag:      Arc::new(AtomicBool::new(false)),
+            foc_temp_comp:      Mutex::new(FocuserTempComp::default()),
             img_proc_stop_flag: Mutex::new(Arc::new(AtomicBool::new(false))),
             ext_guider:         Arc::new(Mutex::new(None)),
             img_cmds_sender,
@@ -130,6 +176,12 @@ impl Core {
         result.connect_indi_events();
         result.connect_1s_timer_event();
         result.start_taking_frames_restart_timer();
+        result.start_no_blob_check_timer();
+        result.start_dome_slaving_timer();
+        result.start_meridian_limit_timer();
+        result.start_weather_watchdog_timer();
+        result.start_mode_watchdog_timer();
+        result.start_focuser_temp_comp_timer();
         result
     }
 
@@ -240,14 +292,37 @@ impl Core {
     fn connect_indi_events(self: &Arc<Self>) {
         let self_ = Arc::clone(self);
         let img_cmds_sender = self.img_cmds_sender.clone();
-        self.indi.subscribe_events(move |event| {
+        self.indi.subscribe_events_in_group(indi::SubscriptionGroup::System, move |event| {
             let result = || -> anyhow::Result<()> {
                 match event {
+                    indi::Event::Message(message) => {
+                        log::debug!("indi: device={}, text={}", message.device_name, message.text);
+                    }
                     indi::Event::BlobStart(event) => {
                         let mut mode_data = self_.mode_data.write().unwrap();
+                        let is_mode_camera = mode_data.mode.cam_device()
+                            .map(|cam| cam.name == *event.device_name)
+                            .unwrap_or(false);
+                        if is_mode_camera {
+                            self_.blob_received.store(true, Ordering::Relaxed);
+                            self_.blob_reenabled.store(false, Ordering::Relaxed);
+                            self_.no_blob_wd.store(0, Ordering::Relaxed);
+                        }
+                        if is_mode_camera && matches!(
+                            mode_data.mode.get_type(),
+                            ModeType::SavingRawFrames|ModeType::LiveStacking
+                        ) {
+                            // Focuser is moved before next exposure is started
+                            if let Err(err) = self_.compensate_focuser_temperature() {
+                                log::error!("Error in Core::compensate_focuser_temperature: {}", err.to_string());
+                            }
+                        }
                         let result = mode_data.mode.notify_blob_start_event(&event)?;
                         self_.apply_change_result(result, &mut mode_data)?;
                     }
+                    indi::Event::BlobTimeOut(event) => {
+                        self_.process_indi_blob_timeout_event(&event)?;
+                    }
                     indi::Event::PropChange(prop_change) => {
                         if let indi::PropChange::Change {
                             value: indi::PropChangeValue{
@@ -263,6 +338,9 @@ impl Core {
                         } else {
                             self_.process_indi_prop_change_event(&prop_change)?;
                         }
+                        if *prop_change.prop_name == "GEOGRAPHIC_COORD" {
+                            self_.fill_site_from_devices_if_empty();
+                        }
                     },
                     _ => {}
                 }
@@ -298,11 +376,450 @@ impl Core {
         });
     }
 
+    fn start_no_blob_check_timer(self: &Arc<Self>) {
+        const NO_BLOB_GRACE_PERIOD: u16 = 15; // seconds
+        let self_ = Arc::clone(self);
+        self.timer.exec(1000, true, move || {
+            let prev = self_.no_blob_wd.fetch_update(
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+                |v| {
+                    if v == 0 {
+                        None
+                    } else if v == NO_BLOB_GRACE_PERIOD {
+                        Some(0)
+                    } else {
+                        Some(v+1)
+                    }
+                }
+            );
+            // Exposure is finished but image is not received.
+            // Usually it means BLOB is disabled for camera
+            if prev == Ok(NO_BLOB_GRACE_PERIOD) {
+                let result = self_.reenable_blob_and_restart_exposure();
+                self_.process_error(result, "Core::start_no_blob_check_timer");
+            }
+        });
+    }
+
+    fn reenable_blob_and_restart_exposure(self: &Arc<Self>) -> anyhow::Result<()> {
+        let mode_data = self.mode_data.read().unwrap();
+        let Some(cam_device) = mode_data.mode.cam_device() else { return Ok(()); };
+        let cam_name = cam_device.name.clone();
+        drop(mode_data);
+
+        if self.blob_reenabled.swap(true, Ordering::Relaxed) {
+            anyhow::bail!(
+                "Exposure of {} is finished but image is not received after BLOB re-enabling",
+                cam_name
+            );
+        }
+
+        log::warn!(
+            "Exposure of {} is finished but image is not received. Re-enabling BLOB and retrying...",
+            cam_name
+        );
+        self.indi.command_enable_blob(
+            &cam_name,
+            None,
+            indi::BlobEnable::Also,
+        )?;
+        self.restart_camera_exposure()
+    }
+
+    fn start_dome_slaving_timer(self: &Arc<Self>) {
+        let self_ = Arc::clone(self);
+        self.timer.exec(1000, true, move || {
+            if let Err(err) = self_.slave_dome() {
+                log::error!("Error in Core::slave_dome: {}", err.to_string());
+            }
+        });
+    }
+
+    fn slave_dome(&self) -> anyhow::Result<()> {
+        let options = self.options.read().unwrap();
+        if !options.dome.slaving
+        || options.dome.device.is_empty()
+        || options.mount.device.is_empty() {
+            return Ok(());
+        }
+        let dome = options.dome.device.clone();
+        let mount = options.mount.device.clone();
+        let threshold = options.dome.slave_threshold;
+        let latitude = degree_to_radian(options.site.latitude);
+        let longitude = degree_to_radian(options.site.longitude);
+        drop(options);
+
+        if self.indi.state() != indi::ConnState::Connected
+        || !self.indi.is_device_enabled(&dome).unwrap_or(false)
+        || !self.indi.is_device_enabled(&mount).unwrap_or(false)
+        || self.indi.dome_get_parked(&dome).unwrap_or(false)
+        || self.indi.mount_get_parked(&mount).unwrap_or(false) {
+            return Ok(());
+        }
+
+        // Dome is still moving to previous position
+        if self.indi.dome_get_azimuth_prop_state(&dome)? == indi::PropState::Busy {
+            return Ok(());
+        }
+
+        let (ra, dec) = self.indi.mount_get_eq_ra_and_dec(&mount)?;
+        let mount_crd = EqCoord {
+            ra:  hour_to_radian(ra),
+            dec: degree_to_radian(dec),
+        };
+        let mount_az = calc_dome_azimuth(
+            &mount_crd,
+            latitude,
+            longitude,
+            &chrono::Utc::now().naive_utc()
+        );
+        let dome_az = self.indi.dome_get_azimuth(&dome)?;
+        let diff = f64::rem_euclid(mount_az - dome_az + 180.0, 360.0) - 180.0;
+        if diff.abs() > threshold {
+            log::info!(
+                "Slaving dome {}: azimuth {:.1}° -> {:.1}°",
+                dome, dome_az, mount_az
+            );
+            self.indi.dome_set_azimuth(&dome, mount_az, true, None)?;
+        }
+        Ok(())
+    }
+
+    fn start_meridian_limit_timer(self: &Arc<Self>) {
+        let self_ = Arc::clone(self);
+        self.timer.exec(1000, true, move || {
+            if let Err(err) = self_.check_meridian_limit() {
+                log::error!("Error in Core::check_meridian_limit: {}", err.to_string());
+            }
+        });
+    }
+
+    fn check_meridian_limit(self: &Arc<Self>) -> anyhow::Result<()> {
+        let options = self.options.read().unwrap();
+        if !options.mount.merid_stop || options.mount.device.is_empty() {
+            return Ok(());
+        }
+        let mount = options.mount.device.clone();
+        let limit = options.mount.merid_limit;
+        let park = options.mount.merid_park;
+        let longitude = degree_to_radian(options.site.longitude);
+        drop(options);
+
+        if self.indi.state() != indi::ConnState::Connected
+        || !self.indi.is_device_enabled(&mount).unwrap_or(false)
+        || self.indi.mount_get_parked(&mount).unwrap_or(true)
+        || !self.indi.mount_get_tracking(&mount).unwrap_or(false) {
+            self.merid_limit_flag.store(false, Ordering::Relaxed);
+            return Ok(());
+        }
+
+        let ra = self.indi.mount_get_eq_ra(&mount)?;
+        let hour_angle = calc_hour_angle(
+            hour_to_radian(ra),
+            longitude,
+            &chrono::Utc::now().naive_utc()
+        );
+        let past_meridian = radian_to_degree(hour_angle);
+        let pier_side = if self.indi.mount_is_pier_side_supported(&mount).unwrap_or(false) {
+            self.indi.mount_get_pier_side(&mount)?
+        } else {
+            None
+        };
+        if !meridian_limit_is_reached(past_meridian, limit, pier_side) {
+            self.merid_limit_flag.store(false, Ordering::Relaxed);
+            return Ok(());
+        }
+        if self.merid_limit_flag.swap(true, Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        log::warn!(
+            "Mount {} is {:.1}° past meridian (limit is {:.1}°). Stopping tracking...",
+            mount, past_meridian, limit
+        );
+        self.abort_active_mode();
+        self.indi.mount_set_tracking(&mount, false, true, None)?;
+        if park {
+            log::info!("Parking mount {}...", mount);
+            self.indi.mount_set_parked(&mount, true, true, None)?;
+        }
+        self.subscribers.notify(Event::Warning(format!(
+            "Mount has passed the meridian limit ({:.1}°). Tracking is stopped{}",
+            limit,
+            if park { " and mount is parked" } else { "" }
+        )));
+        Ok(())
+    }
+
+    fn start_weather_watchdog_timer(self: &Arc<Self>) {
+        let self_ = Arc::clone(self);
+        self.timer.exec(1000, true, move || {
+            if let Err(err) = self_.check_weather_is_safe() {
+                log::error!("Error in Core::check_weather_is_safe: {}", err.to_string());
+            }
+        });
+    }
+
+    fn check_weather_is_safe(self: &Arc<Self>) -> anyhow::Result<()> {
+        let options = self.options.read().unwrap();
+        if !options.weather.monitoring || options.weather.device.is_empty() {
+            self.weather_flag.store(false, Ordering::Relaxed);
+            return Ok(());
+        }
+        let weather_opts = options.weather.clone();
+        let mount = options.mount.device.clone();
+        drop(options);
+
+        let device = &weather_opts.device;
+        if self.indi.state() != indi::ConnState::Connected
+        || !self.indi.is_device_enabled(device).unwrap_or(false) {
+            self.weather_flag.store(false, Ordering::Relaxed);
+            return Ok(());
+        }
+
+        let params = self.indi.weather_get_parameters(device).unwrap_or_default();
+        let alerts = self.indi.weather_get_alerts(device).unwrap_or_default();
+        let Some(reason) = get_weather_unsafe_reason(&params, &alerts, &weather_opts) else {
+            if self.weather_flag.swap(false, Ordering::Relaxed) {
+                log::info!("Weather device {} reports safe conditions", device);
+            }
+            return Ok(());
+        };
+        if self.weather_flag.swap(true, Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        log::warn!("Weather device {} reports unsafe conditions: {}", device, reason);
+        self.abort_active_mode();
+        let park =
+            weather_opts.park &&
+            !mount.is_empty() &&
+            self.indi.is_device_enabled(&mount).unwrap_or(false) &&
+            !self.indi.mount_get_parked(&mount).unwrap_or(true);
+        if park {
+            log::info!("Parking mount {}...", mount);
+            self.indi.mount_set_parked(&mount, true, true, None)?;
+        }
+        self.subscribers.notify(Event::Warning(format!(
+            "Unsafe weather conditions ({}). Active operation is aborted{}",
+            reason,
+            if park { " and mount is parked" } else { "" }
+        )));
+        Ok(())
+    }
+
+    fn start_mode_watchdog_timer(self: &Arc<Self>) {
+        let self_ = Arc::clone(self);
+        self.timer.exec(1000, true, move || {
+            self_.check_mode_is_stuck();
+        });
+    }
+
+    fn reset_mode_watchdog(&self) {
+        self.mode_wd_cnt.store(0, Ordering::Relaxed);
+        self.mode_wd_flag.store(false, Ordering::Relaxed);
+    }
+
+    /// Time without progress after which mode is considered as stuck
+    fn mode_watchdog_timeout(mode: &dyn Mode, base_timeout: u32) -> Option<u32> {
+        if mode.is_paused() {
+            return None;
+        }
+        let mode_timeout = match mode.get_type() {
+            ModeType::Waiting|
+            ModeType::OpeningImgFile =>
+                return None,
+            // mount slewing and plate solving can take a long time
+            ModeType::Goto|
+            ModeType::CapturePlatesolve|
+            ModeType::PolarAlignment|
+            ModeType::DitherCalibr|
+            ModeType::Targets|
+            ModeType::Sequence|
+            ModeType::FlatsSession =>
+                2 * base_timeout,
+            _ =>
+                base_timeout,
+        };
+        let exposure = mode.get_cur_exposure().unwrap_or(0.0);
+        Some(mode_timeout + exposure.ceil() as u32)
+    }
+
+    fn check_mode_is_stuck(self: &Arc<Self>) {
+        let options = self.options.read().unwrap();
+        let enabled = options.mode_wd.enabled;
+        let base_timeout = options.mode_wd.timeout;
+        let auto_abort = options.mode_wd.auto_abort;
+        drop(options);
+        if !enabled {
+            self.reset_mode_watchdog();
+            return;
+        }
+
+        let mode_data = self.mode_data.read().unwrap();
+        let Some(timeout) = Self::mode_watchdog_timeout(&*mode_data.mode, base_timeout) else {
+            drop(mode_data);
+            self.reset_mode_watchdog();
+            return;
+        };
+        let mode_type = mode_data.mode.get_type();
+        let progress_str = mode_data.mode.progress_string();
+        drop(mode_data);
+
+        let seconds = self.mode_wd_cnt.fetch_add(1, Ordering::Relaxed) + 1;
+        if seconds < timeout || self.mode_wd_flag.swap(true, Ordering::Relaxed) {
+            return;
+        }
+
+        log::warn!(
+            "Mode {:?} ({}) has no progress for {} seconds",
+            mode_type, progress_str, seconds
+        );
+        if auto_abort {
+            log::info!("Aborting stuck mode...");
+            self.abort_active_mode();
+            self.subscribers.notify(Event::Warning(format!(
+                "{} has no progress for {} seconds and was aborted",
+                progress_str, seconds
+            )));
+        } else {
+            self.subscribers.notify(Event::ModeStuck(mode_type, progress_str));
+        }
+    }
+
+    /// Takes site coordinates from GPS or mount device
+    /// if user didn't enter them manually
+    fn fill_site_from_devices_if_empty(&self) {
+        let site_is_empty = |site: &SiteOptions| {
+            site.latitude == 0.0 && site.longitude == 0.0
+        };
+        if !site_is_empty(&self.options.read().unwrap().site) {
+            return;
+        }
+        let devices = self.indi
+            .get_devices_list_by_interface(indi::DriverInterface::GPS)
+            .into_iter()
+            .chain(self.indi.get_devices_list_by_interface(indi::DriverInterface::TELESCOPE));
+        for device in devices {
+            let Ok((latitude, longitude, elevation)) = self.indi.mount_get_geo_coords(&device.name) else {
+                continue;
+            };
+            if latitude == 0.0 && longitude == 0.0 {
+                continue;
+            }
+            // INDI uses 0..360 range for longitude
+            let longitude = if longitude > 180.0 { longitude - 360.0 } else { longitude };
+            let mut options = self.options.write().unwrap();
+            if !site_is_empty(&options.site) {
+                return;
+            }
+            options.site.latitude = latitude;
+            options.site.longitude = longitude;
+            options.site.elevation = elevation;
+            drop(options);
+            log::info!(
+                "Site is updated from {}: latitude={}, longitude={}, elevation={:.0}m",
+                device.name,
+                indi::value_to_sexagesimal(latitude, true, 6),
+                indi::value_to_sexagesimal(longitude, true, 6),
+                elevation,
+            );
+            self.subscribers.notify(Event::SiteChanged);
+            return;
+        }
+    }
+
+    fn start_focuser_temp_comp_timer(self: &Arc<Self>) {
+        let self_ = Arc::clone(self);
+        self.timer.exec(1000, true, move || {
+            // Focuser is moved by timer only in idle mode. Other
+            // modes move it themselves or control focuser
+            if self_.mode_data().mode.get_type() != ModeType::Waiting {
+                return;
+            }
+            if let Err(err) = self_.compensate_focuser_temperature() {
+                log::error!("Error in Core::compensate_focuser_temperature: {}", err.to_string());
+            }
+        });
+    }
+
+    /// Moves focuser to compensate focus shift caused by temperature
+    /// change since last autofocus. Focuser is moved by timer in idle mode
+    /// or between frames while saving LIGHT frames or live stacking.
+    /// Waits until focuser reaches new position
+    fn compensate_focuser_temperature(&self) -> anyhow::Result<()> {
+        if self.indi.state() != indi::ConnState::Connected {
+            return Ok(());
+        }
+
+        let options = self.options.read().unwrap();
+        if !options.focuser.temp_comp
+        || options.focuser.device.is_empty()
+        || options.focuser.temp_coeff == 0.0 {
+            return Ok(());
+        }
+        let Some(ref_temp) = options.focuser.temp_ref else {
+            return Ok(());
+        };
+        let Some(temperature) = get_focuser_comp_temperature(&self.indi, &options) else {
+            return Ok(());
+        };
+        let f_options = options.focuser.clone();
+        drop(options);
+
+        let mut comp = self.foc_temp_comp.lock().unwrap();
+        if comp.ref_temp != Some(ref_temp) {
+            // new autofocus result
+            *comp = FocuserTempComp {
+                ref_temp: Some(ref_temp),
+                ..Default::default()
+            };
+        }
+        if let Some(last_move) = comp.last_move {
+            if last_move.elapsed() < FOC_TEMP_COMP_MIN_INTERVAL {
+                return Ok(());
+            }
+        }
+        let offset = ((temperature - ref_temp) * f_options.temp_coeff).round();
+        let steps = offset - comp.applied;
+        if steps.abs() < 1.0
+        || steps.abs() < (FOC_TEMP_COMP_HYSTERESIS * f_options.temp_coeff).abs() {
+            return Ok(());
+        }
+        let cur_pos = self.indi.focuser_get_abs_value(&f_options.device)?;
+        let new_pos = cur_pos + steps;
+        log::info!(
+            "Focuser temperature compensation: T={:.1}°C (ref. {:.1}°C), moving {} from {:.0} to {:.0} ({:+.0} steps)",
+            temperature, ref_temp, f_options.device, cur_pos, new_pos, steps
+        );
+        comp.last_move = Some(Instant::now());
+        focuser_set_abs_value_with_backlash(
+            &self.indi,
+            &f_options.device,
+            new_pos,
+            f_options.backlash_steps,
+            f_options.backlash_direction,
+            FOC_TEMP_COMP_MOVE_TIMEOUT
+        )?;
+        comp.applied = offset;
+        Ok(())
+    }
+
     fn process_indi_prop_change_event(
         self:        &Arc<Self>,
         prop_change: &indi::PropChangeEvent,
     ) -> anyhow::Result<()> {
         let mut mode_data = self.mode_data.write().unwrap();
+
+        // Busy property of device used by mode means that
+        // device is working so mode is not stuck
+        if let indi::PropChange::Change { new_state: indi::PropState::Busy, .. } = &prop_change.change {
+            if mode_data.mode.used_devices().contains(&prop_change.device_name.as_str()) {
+                self.reset_mode_watchdog();
+            }
+        }
+
         let result = mode_data.mode.notify_indi_prop_change(&prop_change)?;
         self.apply_change_result(result, &mut mode_data)?;
 
@@ -313,17 +830,46 @@ impl Core {
             && cur_device.name == *prop_change.device_name {
                 // exposure = 0.0 and state = busy means exposure has ended
                 // but still no blob received
-                if value.prop_value.to_f64().unwrap_or(0.0) == 0.0
-                && *new_state == indi::PropState::Busy {
+                let exp_value = value.prop_value.to_f64().unwrap_or(0.0);
+                if exp_value == 0.0 && *new_state == indi::PropState::Busy {
                     _ = self.exp_stuck_wd.compare_exchange(0, 1, Ordering::Relaxed, Ordering::Relaxed);
                 } else {
                     self.exp_stuck_wd.store(0, Ordering::Relaxed);
                 }
+
+                // exposure = 0.0 and state = ok without blob means
+                // image is not sent to us (BLOB is disabled for example)
+                if exp_value != 0.0 && *new_state == indi::PropState::Busy {
+                    self.blob_received.store(false, Ordering::Relaxed);
+                    self.no_blob_wd.store(0, Ordering::Relaxed);
+                } else if exp_value == 0.0
+                && *new_state == indi::PropState::Ok
+                && !self.blob_received.load(Ordering::Relaxed) {
+                    _ = self.no_blob_wd.compare_exchange(0, 1, Ordering::Relaxed, Ordering::Relaxed);
+                }
             }
         }
         Ok(())
     }
 
+    fn process_indi_blob_timeout_event(
+        self:  &Arc<Self>,
+        event: &indi::BlobTimeOutEvent,
+    ) -> anyhow::Result<()> {
+        let mode_data = self.mode_data.read().unwrap();
+        let Some(cam_device) = mode_data.mode.cam_device() else { return Ok(()); };
+        if cam_device.name != *event.device_name
+        || (!cam_device.prop.is_empty() && cam_device.prop != *event.prop_name) {
+            return Ok(());
+        }
+        drop(mode_data);
+
+        // Partially received image was discarded.
+        // Take same shot again
+        self.exp_stuck_wd.store(0, Ordering::Relaxed);
+        self.restart_camera_exposure()
+    }
+
     fn process_indi_blob_event(
         self:              &Arc<Self>,
         blob:              &Arc<indi::BlobPropValue>,
@@ -362,15 +908,23 @@ impl Core {
         let mut command_data = {
             let options = self.options.read().unwrap();
             let device = DeviceAndProp {
-                name: device_name.to_string(),
-                prop: device_prop.to_string(),
+                name:  device_name.to_string(),
+                prop:  device_prop.to_string(),
+                index: 0,
             };
 
             let calibr_params = Some(CalibrParams {
-                extract_dark:  options.calibr.dark_frame_en,
-                dark_lib_path: options.calibr.dark_library_path.clone(),
-                flat_fname:    None,
-                sar_hot_pixs:  options.calibr.hot_pixels,
+                extract_dark:    options.calibr.dark_frame_en,
+                dark_lib_path:   options.calibr.dark_library_path.clone(),
+                flat_fname:      None,
+                sar_hot_pixs:    options.calibr.hot_pixels,
+                dark_scaling:    options.calibr.allow_dark_scaling,
+                hot_pix_removal: options.calibr.hot_pixel_removal,
+                hot_pix_sigma:   options.calibr.hot_pixel_sigma,
+                dark_match_tol:  DarkMatchTolerance {
+                    temperature: options.calibr.dark_temp_tol,
+                    exposure:    options.calibr.dark_exp_tol,
+                },
             });
 
             let new_stop_flag = Arc::new(AtomicBool::new(false));
@@ -389,6 +943,7 @@ impl Core {
                 frame_options:   options.cam.frame.clone(),
                 quality_options: Some(options.quality.clone()),
                 live_stacking:   None,
+                airmass:         calc_mount_airmass(&self.indi, &options),
                 calibr_params,
             }
         };
@@ -424,6 +979,7 @@ impl Core {
             return;
         }
 
+        self.reset_mode_watchdog();
         self.subscribers.notify(
             Event::FrameProcessing(res.clone())
         );
@@ -472,6 +1028,7 @@ impl Core {
     ) -> anyhow::Result<()> {
         // abort previous mode
         let mut mode_data = self.mode_data.write().unwrap();
+        let prev_is_waiting = mode_data.mode.get_type() == ModeType::Waiting;
         mode_data.prev_mode = Some(std::mem::replace(
             &mut mode_data.mode,
             Box::new(WaitingMode)
@@ -479,6 +1036,11 @@ impl Core {
         mode_data.mode.abort()?;
         drop(mode_data);
 
+        // Nobody uses camera now so any active exposure is left after crash
+        if let (true, Some(cam_device)) = (prev_is_waiting, mode.cam_device()) {
+            clear_orphan_camera_exposure(&self.indi, cam_device)?;
+        }
+
         // init camera for mode
         self.init_cam_for_mode(&mode)?;
 
@@ -492,6 +1054,7 @@ impl Core {
         }
 
         // Start new mode
+        self.reset_mode_watchdog();
         mode_data.mode.start()?;
 
         let progress = mode_data.mode.progress();
@@ -514,10 +1077,17 @@ impl Core {
         let options = self.options.read().unwrap();
 
         let calibr_params = Some(CalibrParams {
-            extract_dark:  options.calibr.dark_frame_en,
-            dark_lib_path: options.calibr.dark_library_path.clone(),
-            flat_fname:    None,
-            sar_hot_pixs:  options.calibr.hot_pixels,
+            extract_dark:    options.calibr.dark_frame_en,
+            dark_lib_path:   options.calibr.dark_library_path.clone(),
+            flat_fname:      None,
+            sar_hot_pixs:    options.calibr.hot_pixels,
+            dark_scaling:    options.calibr.allow_dark_scaling,
+            hot_pix_removal: options.calibr.hot_pixel_removal,
+            hot_pix_sigma:   options.calibr.hot_pixel_sigma,
+            dark_match_tol:  DarkMatchTolerance {
+                temperature: options.calibr.dark_temp_tol,
+                exposure:    options.calibr.dark_exp_tol,
+            },
         });
 
         let command = FrameProcessCommandData {
@@ -533,6 +1103,7 @@ impl Core {
             frame_options:   options.cam.frame.clone(),
             quality_options: None,
             live_stacking:   None,
+            airmass:         None,
             calibr_params,
         };
 
@@ -609,11 +1180,16 @@ impl Core {
 
     pub fn start_mount_calibr(&self) -> anyhow::Result<()> {
         self.mode_data.write().unwrap().mode.abort()?;
-        let mode = MountCalibrMode::new(&self.indi, &self.options, None)?;
+        let mode = MountCalibrMode::new(&self.indi, &self.options, &self.subscribers, None)?;
         self.start_new_mode(mode, false, false)?;
         Ok(())
     }
 
+    pub fn is_saved_mount_calibr_valid(&self) -> bool {
+        let options = self.options.read().unwrap();
+        get_saved_mount_calibr(&self.indi, &options, options.cam.frame.binning).is_some()
+    }
+
     pub fn start_creating_dark_library(
         &self,
         dark_lib_mode: DarkLibMode,
@@ -682,6 +1258,28 @@ impl Core {
         Ok(())
     }
 
+    pub fn start_quick_platesolve(&self) -> anyhow::Result<()> {
+        let image = self.cur_frame.image.read().unwrap();
+        if image.is_empty() {
+            anyhow::bail!("Image is empty");
+        }
+        drop(image);
+        let image_info = self.cur_frame.info.read().unwrap();
+        let ResultImageInfo::LightInfo(light_frame_info) = &*image_info else {
+            anyhow::bail!("Image is not light frame");
+        };
+        let mode = CapturePlatesolveMode::new_from_cur_frame(
+            &self.options,
+            &self.indi,
+            &self.subscribers,
+            &self.cur_frame.image,
+            light_frame_info,
+        )?;
+        drop(image_info);
+        self.start_new_mode(mode, false, false)?;
+        Ok(())
+    }
+
     pub fn start_polar_alignment(&self) -> anyhow::Result<()> {
         let mode = PolarAlignMode::new(
             &self.indi,
@@ -692,6 +1290,30 @@ impl Core {
         Ok(())
     }
 
+    pub fn start_targets(&self, targets: &[TargetItem]) -> anyhow::Result<()> {
+        let mode = TargetsMode::new(&self.options, &self.indi, targets)?;
+        self.start_new_mode(mode, false, false)?;
+        Ok(())
+    }
+
+    pub fn start_sequence(&self) -> anyhow::Result<()> {
+        let mode = SequenceMode::new(&self.options, &self.indi)?;
+        self.start_new_mode(mode, false, false)?;
+        Ok(())
+    }
+
+    pub fn start_flats_session(&self) -> anyhow::Result<()> {
+        let mode = FlatsSessionMode::new(&self.options, &self.indi)?;
+        self.start_new_mode(mode, false, false)?;
+        Ok(())
+    }
+
+    pub fn start_video_stream(&self) -> anyhow::Result<()> {
+        let mode = VideoStreamMode::new(&self.indi, &self.options)?;
+        self.start_new_mode(mode, false, true)?;
+        Ok(())
+    }
+
     pub fn init_cam_telescope_data(&self) -> anyhow::Result<()> {
         if self.indi.state() != indi::ConnState::Connected {
             return Ok(());
@@ -752,6 +1374,25 @@ impl Core {
         Ok(())
     }
 
+    /// Aborts active mode and camera exposure and
+    /// requests camera properties from INDI server again
+    pub fn reset_camera(self: &Arc<Self>) -> anyhow::Result<()> {
+        let options = self.options.read().unwrap();
+        let Some(cam_device) = options.cam.device.clone() else {
+            anyhow::bail!("Camera is not selected");
+        };
+        drop(options);
+
+        self.abort_active_mode();
+        abort_camera_exposure(&self.indi, &cam_device)?;
+        self.indi.command_get_properties(Some(&cam_device.name), None)?;
+        self.exp_stuck_wd.store(0, Ordering::Relaxed);
+        self.no_blob_wd.store(0, Ordering::Relaxed);
+        self.blob_reenabled.store(false, Ordering::Relaxed);
+        log::info!("Camera {} is reset", cam_device.to_string());
+        Ok(())
+    }
+
     pub fn abort_active_mode(self: &Arc<Self>) {
         let mut mode_data = self.mode_data.write().unwrap();
         if mode_data.mode.get_type() == ModeType::Waiting {
@@ -777,6 +1418,98 @@ impl Core {
         drop(mode_data);
         self.subscribers.notify(Event::ModeChanged);
         self.exp_stuck_wd.store(0, Ordering::Relaxed);
+        self.no_blob_wd.store(0, Ordering::Relaxed);
+    }
+
+    pub fn is_emergency_shutdown_active(&self) -> bool {
+        self.shutdown_flag.load(Ordering::Relaxed)
+    }
+
+    /// Aborts active mode, parks mount, warms up camera and disconnects
+    /// INDI drivers. Slow steps are executed in separate thread. Error of
+    /// any step doesn't stop the sequence. Failed steps are reported
+    /// by `Event::Warning` at the end
+    pub fn emergency_shutdown(self: &Arc<Self>) {
+        if self.shutdown_flag.swap(true, Ordering::Relaxed) {
+            log::info!("Emergency shutdown is already in progress");
+            return;
+        }
+        log::info!("Emergency shutdown is started");
+        self.abort_active_mode();
+
+        let options = self.options.read().unwrap();
+        let mount = options.mount.device.clone();
+        let camera = options.cam.device.as_ref().map(|d| d.name.clone());
+        let disable_devices = !options.indi.remote;
+        drop(options);
+
+        let indi = Arc::clone(&self.indi);
+        let subscribers = Arc::clone(&self.subscribers);
+        let shutdown_flag = Arc::clone(&self.shutdown_flag);
+        std::thread::spawn(move || {
+            let mut failed_steps = Vec::new();
+            let mut exec_step = |name: &str, fun: &dyn Fn() -> anyhow::Result<()>| {
+                log::info!("Emergency shutdown: {}...", name);
+                match fun() {
+                    Ok(_) => log::info!("Emergency shutdown: {} done", name),
+                    Err(err) => {
+                        log::error!("Emergency shutdown: {} failed: {}", name, err);
+                        failed_steps.push(format!("{} ({})", name, err));
+                    }
+                }
+            };
+
+            if indi.state() == indi::ConnState::Connected {
+                if !mount.is_empty() && indi.is_device_enabled(&mount).unwrap_or(false) {
+                    exec_step("stopping mount", &|| {
+                        indi.mount_abort_motion(&mount)?;
+                        Ok(())
+                    });
+                    exec_step("parking mount", &|| {
+                        if !indi.mount_get_parked(&mount)? {
+                            let timeout = MAX_GOTO_TIME as u64 * 1000;
+                            indi.mount_set_parked(&mount, true, true, Some(timeout))?;
+                        }
+                        Ok(())
+                    });
+                }
+                if let Some(camera) = &camera {
+                    if indi.is_device_enabled(camera).unwrap_or(false)
+                    && indi.camera_is_cooler_supported(camera).unwrap_or(false) {
+                        exec_step("warming up camera", &|| {
+                            warm_up_camera(&indi, camera)
+                        });
+                        exec_step("switching cooler off", &|| {
+                            indi.camera_enable_cooler(camera, false, true, INDI_SET_PROP_TIMEOUT)?;
+                            Ok(())
+                        });
+                    }
+                }
+                if disable_devices {
+                    exec_step("disabling devices", &|| {
+                        indi.command_enable_all_devices(false, true, Some(2000))?;
+                        Ok(())
+                    });
+                }
+                exec_step("disconnecting INDI", &|| {
+                    indi.disconnect_and_wait()?;
+                    Ok(())
+                });
+            } else {
+                log::info!("Emergency shutdown: INDI is not connected");
+            }
+
+            shutdown_flag.store(false, Ordering::Relaxed);
+            if failed_steps.is_empty() {
+                log::info!("Emergency shutdown is finished");
+            } else {
+                log::error!("Emergency shutdown is finished with errors");
+                subscribers.notify(Event::Warning(format!(
+                    "Emergency shutdown is finished. Failed steps:\n{}",
+                    failed_steps.join("\n")
+                )));
+            }
+        });
     }
 
     pub fn continue_prev_mode(&self) -> anyhow::Result<()> {
@@ -785,6 +1518,11 @@ impl Core {
             anyhow::bail!("Aborted state is empty");
         };
         mode_data.mode = perv_mode;
+        self.reset_mode_watchdog();
+
+        // BLOB can be disabled after reconnect
+        self.init_cam_for_mode(&*mode_data.mode)?;
+
         mode_data.mode.continue_work()?;
         let progress = mode_data.mode.progress();
         let mode_type = mode_data.mode.get_type();
@@ -800,6 +1538,9 @@ impl Core {
         result:    NotifyResult,
         mode_data: &mut ModeData,
     ) -> anyhow::Result<()> {
+        if !matches!(result, NotifyResult::Empty) {
+            self.reset_mode_watchdog();
+        }
         let mut mode_changed = false;
         let mut progress_changed = false;
         let mut finished_progress_and_type = None;
@@ -848,7 +1589,7 @@ impl Core {
             NotifyResult::StartMountCalibr => {
                 mode_data.mode.abort()?;
                 let prev_mode = std::mem::replace(&mut mode_data.mode, Box::new(WaitingMode));
-                let mut mode = MountCalibrMode::new(&self.indi, &self.options, Some(prev_mode))?;
+                let mut mode = MountCalibrMode::new(&self.indi, &self.options, &self.subscribers, Some(prev_mode))?;
                 mode.start()?;
                 mode_data.mode = Box::new(mode);
                 mode_changed = true;
@@ -869,6 +1610,32 @@ impl Core {
                 mode_changed = true;
                 progress_changed = true;
             }
+            NotifyResult::StartTargetGoto(eq_coord) => {
+                self.start_target_goto_stage(mode_data, &eq_coord)?;
+                mode_changed = true;
+                progress_changed = true;
+            }
+            NotifyResult::StartTargetCapture(item) => {
+                self.start_target_capture_stage(mode_data, &item)?;
+                mode_changed = true;
+                progress_changed = true;
+            }
+            NotifyResult::StartSequenceStep { step, filter_changed } => {
+                self.start_sequence_step_stage(mode_data, &step, filter_changed)?;
+                mode_changed = true;
+                progress_changed = true;
+            }
+            NotifyResult::StartFlatsSessionStep { step, sky_flats } => {
+                self.start_flats_session_step_stage(mode_data, &step, sky_flats)?;
+                mode_changed = true;
+                progress_changed = true;
+            }
+            NotifyResult::StartRecenterAfterFlip(info) |
+            NotifyResult::StartMeridianFlip(info) => {
+                self.start_goto_frame_position_stage(mode_data, &info)?;
+                mode_changed = true;
+                progress_changed = true;
+            }
             _ => {}
         }
 
@@ -905,6 +1672,121 @@ impl Core {
         mode_data.mode = Box::new(mode);
         Ok(())
     }
+
+    fn start_target_goto_stage(
+        self:      &Arc<Self>,
+        mode_data: &mut ModeData,
+        eq_coord:  &EqCoord,
+    ) -> anyhow::Result<()> {
+        mode_data.mode.abort()?;
+        let prev_mode = std::mem::replace(&mut mode_data.mode, Box::new(WaitingMode));
+        let mut mode = GotoMode::new(
+            GotoDestination::Coord(eq_coord.clone()),
+            GotoConfig::GotoPlateSolveAndCorrect,
+            &self.options,
+            &self.indi,
+            &self.subscribers,
+        )?;
+        mode.set_next_mode(Some(prev_mode));
+        mode.start()?;
+        mode_data.mode = Box::new(mode);
+        Ok(())
+    }
+
+    /// Goto (with plate solving and correction) into position of frame
+    /// and continue current mode after that. Used to recenter last
+    /// frame before meridian flip
+    fn start_goto_frame_position_stage(
+        self:      &Arc<Self>,
+        mode_data: &mut ModeData,
+        info:      &Arc<LightFrameInfo>,
+    ) -> anyhow::Result<()> {
+        mode_data.mode.abort()?;
+        let prev_mode = std::mem::replace(&mut mode_data.mode, Box::new(WaitingMode));
+        let mut mode = GotoMode::new(
+            GotoDestination::Image {
+                image: Arc::clone(&self.cur_frame.image),
+                info:  Arc::clone(info),
+            },
+            GotoConfig::GotoPlateSolveAndCorrect,
+            &self.options,
+            &self.indi,
+            &self.subscribers,
+        )?;
+        mode.set_next_mode(Some(prev_mode));
+        mode.start()?;
+        mode_data.mode = Box::new(mode);
+        Ok(())
+    }
+
+    fn start_target_capture_stage(
+        self:      &Arc<Self>,
+        mode_data: &mut ModeData,
+        item:      &TargetItem,
+    ) -> anyhow::Result<()> {
+        mode_data.mode.abort()?;
+        let prev_mode = std::mem::replace(&mut mode_data.mode, Box::new(WaitingMode));
+        let mut mode = TackingPicturesMode::new(
+            &self.indi,
+            &self.subscribers,
+            CameraMode::SavingRawFrames,
+            &self.options
+        )?;
+        mode.set_target_item(item);
+        mode.set_guider(&self.ext_guider);
+        mode.set_ref_stars(&self.ref_stars);
+        mode.set_next_mode(Some(prev_mode));
+        mode.start()?;
+        mode_data.mode = Box::new(mode);
+        Ok(())
+    }
+
+    fn start_sequence_step_stage(
+        self:           &Arc<Self>,
+        mode_data:      &mut ModeData,
+        step:           &SequenceStep,
+        filter_changed: bool,
+    ) -> anyhow::Result<()> {
+        mode_data.mode.abort()?;
+        let prev_mode = std::mem::replace(&mut mode_data.mode, Box::new(WaitingMode));
+        let seq_progress = prev_mode.progress();
+        let mut mode = TackingPicturesMode::new(
+            &self.indi,
+            &self.subscribers,
+            CameraMode::SavingRawFrames,
+            &self.options
+        )?;
+        mode.set_sequence_step(step, filter_changed, seq_progress);
+        mode.set_guider(&self.ext_guider);
+        mode.set_ref_stars(&self.ref_stars);
+        mode.set_next_mode(Some(prev_mode));
+        mode.start()?;
+        mode_data.mode = Box::new(mode);
+        Ok(())
+    }
+
+    fn start_flats_session_step_stage(
+        self:      &Arc<Self>,
+        mode_data: &mut ModeData,
+        step:      &FlatsSessionStep,
+        sky_flats: bool,
+    ) -> anyhow::Result<()> {
+        // Flats session mode is not aborted here because
+        // it keeps flat panel turned on for all steps
+        let prev_mode = std::mem::replace(&mut mode_data.mode, Box::new(WaitingMode));
+        let seq_progress = prev_mode.progress();
+        let mut mode = TackingPicturesMode::new(
+            &self.indi,
+            &self.subscribers,
+            CameraMode::SavingRawFrames,
+            &self.options
+        )?;
+        mode.set_flats_session_step(step, sky_flats, seq_progress);
+        mode.set_next_mode(Some(prev_mode));
+        mode.start()?;
+        mode_data.mode = Box::new(mode);
+        Ok(())
+    }
 }
 
 impl Drop for Core {
@@ -953,11 +1835,17 @@ pub fn apply_camera_options_and_take_shot(
         let (width, height) = indi.camera_get_max_frame_size(&device.name, cam_ccd)?;
         let crop_width = frame.crop.translate(width);
         let crop_height = frame.crop.translate(height);
+        let (offset_x, offset_y) = frame.crop_offset;
+        let x = ((width - crop_width) / 2) as i32 + offset_x;
+        let y = ((height - crop_height) / 2) as i32 + offset_y;
+        // Keep frame origin even to preserve bayer pattern
+        let x = x.clamp(0, (width - crop_width) as i32) & !1;
+        let y = y.clamp(0, (height - crop_height) as i32) & !1;
         indi.camera_set_frame_size(
             &device.name,
             cam_ccd,
-            (width - crop_width) / 2,
-            (height - crop_height) / 2,
+            x as usize,
+            y as usize,
             crop_width,
             crop_height,
             true,
@@ -1004,9 +1892,18 @@ pub fn apply_camera_options_and_take_shot(
     // Offset
 
     if indi.camera_is_offset_supported(&device.name)? {
+        let offset_prop = indi.camera_get_offset_prop_value(&device.name)?;
+        let mut offset = frame.offset as f64;
+        if offset < offset_prop.min || offset > offset_prop.max {
+            log::warn!(
+                "Offset {} is out of camera range {}..{}",
+                offset, offset_prop.min, offset_prop.max
+            );
+            offset = offset.clamp(offset_prop.min, offset_prop.max);
+        }
         indi.camera_set_offset(
             &device.name,
-            frame.offset as f64,
+            offset,
             true,
             INDI_SET_PROP_TIMEOUT
         )?;
@@ -1023,6 +1920,18 @@ pub fn apply_camera_options_and_take_shot(
         )?;
     }
 
+    // Image compression. Cameras without CCD_COMPRESSION
+    // always send uncompressed images
+
+    if indi.camera_is_compression_supported(&device.name)? {
+        indi.camera_set_compression(
+            &device.name,
+            frame.compressed,
+            true,
+            INDI_SET_PROP_TIMEOUT
+        )?;
+    }
+
     // Capture format = RAW
 
     if indi.camera_is_capture_format_supported(&device.name)? {
@@ -1058,10 +1967,111 @@ pub fn abort_camera_exposure(
     indi:   &indi::Connection,
     device: &DeviceAndProp,
 ) -> anyhow::Result<()> {
-    indi.camera_abort_exposure(
-        &device.name,
-        indi::CamCcd::from_ccd_prop_name(&device.prop)
-    )?;
+    let cam_ccd = indi::CamCcd::from_ccd_prop_name(&device.prop);
+    // Some drivers have no abort property. Do nothing for them
+    if !indi.camera_is_abort_exposure_supported(&device.name, cam_ccd)? {
+        return Ok(());
+    }
+    indi.camera_abort_exposure(&device.name, cam_ccd)?;
     Ok(())
 }
 
+/// Aborts exposure that can be left after previous mode crash
+/// so nobody waits for its result
+pub fn clear_orphan_camera_exposure(
+    indi:   &indi::Connection,
+    device: &DeviceAndProp,
+) -> anyhow::Result<()> {
+    let cam_ccd = indi::CamCcd::from_ccd_prop_name(&device.prop);
+    let state = indi.camera_get_exposure_prop_state(&device.name, cam_ccd)?;
+    if state == indi::PropState::Busy {
+        log::warn!("Camera {} is still exposing. Aborting exposure", device.to_string());
+        abort_camera_exposure(indi, device)?;
+    }
+    Ok(())
+}
+
+/// Returns description of first parameter that exceeds threshold
+/// or `None` if weather is safe
+fn get_weather_unsafe_reason(
+    params:  &[(Arc<String>, f64)],
+    alerts:  &[Arc<String>],
+    options: &WeatherOptions,
+) -> Option<String> {
+    for (name, value) in params {
+        let max_value = match name.as_str() {
+            "WEATHER_CLOUD_COVER" =>
+                options.max_clouds,
+            "WEATHER_RAIN_HOUR"|"WEATHER_RAIN_RATE"|"WEATHER_PRECIP" =>
+                options.max_rain,
+            "WEATHER_WIND_SPEED"|"WEATHER_WIND_GUST" =>
+                options.max_wind,
+            _ =>
+                continue,
+        };
+        if *value > max_value {
+            return Some(format!("{} = {:.1} > {:.1}", name, value, max_value));
+        }
+    }
+    alerts.first().map(|name| format!("{} is in alert state", name))
+}
+
+/// Airmass at current mount pointing. Returns `None` if
+/// mount is not selected or its coordinates are not available
+fn calc_mount_airmass(indi: &indi::Connection, options: &Options) -> Option<f64> {
+    if options.mount.device.is_empty() {
+        return None;
+    }
+    let (ra, dec) = indi.mount_get_eq_ra_and_dec(&options.mount.device).ok()?;
+    let mount_crd = EqCoord {
+        ra:  hour_to_radian(ra),
+        dec: degree_to_radian(dec),
+    };
+    let latitude = degree_to_radian(options.site.latitude);
+    let longitude = degree_to_radian(options.site.longitude);
+    let utc_time = chrono::Utc::now().naive_utc();
+    let cvt = EqToSphereCvt::new(longitude, latitude, &utc_time);
+    let h_crd = HorizCoord::from_sphere_pt(&cvt.eq_to_sphere(&mount_crd));
+    Some(airmass(h_crd.alt))
+}
+
+/// Azimuth of dome slit (in degrees) for the mount's pointing.
+/// Offsets of telescope axis from dome center are not taken into account
+pub fn calc_dome_azimuth(
+    mount_crd: &EqCoord, // JNow
+    latitude:  f64,
+    longitude: f64,
+    utc_time:  &chrono::NaiveDateTime,
+) -> f64 {
+    let cvt = EqToSphereCvt::new(longitude, latitude, utc_time);
+    let h_crd = HorizCoord::from_sphere_pt(&cvt.eq_to_sphere(mount_crd));
+    f64::rem_euclid(radian_to_degree(h_crd.az), 360.0)
+}
+
+/// Hour angle in range `-PI..PI`. Positive value means
+/// coordinate is past meridian (western part of sky)
+pub fn calc_hour_angle(
+    ra:        f64, // JNow
+    longitude: f64,
+    utc_time:  &chrono::NaiveDateTime,
+) -> f64 {
+    use std::f64::consts::PI;
+    let lst = calc_sidereal_time(utc_time) + longitude;
+    f64::rem_euclid(lst - ra + PI, 2.0 * PI) - PI
+}
+
+/// Raises camera target temperature by steps to prevent
+/// condensation and thermal shock of sensor
+fn warm_up_camera(indi: &indi::Connection, camera: &str) -> anyhow::Result<()> {
+    if !indi.camera_is_temperature_supported(camera)? {
+        return Ok(());
+    }
+    let mut temperature = indi.camera_get_temperature_prop_value(camera)?.value;
+    while temperature < SHUTDOWN_WARMUP_TARGET {
+        temperature = f64::min(temperature + SHUTDOWN_WARMUP_STEP, SHUTDOWN_WARMUP_TARGET);
+        log::info!("Setting camera {} temperature to {:.1}°C", camera, temperature);
+        indi.camera_set_temperature(camera, temperature)?;
+        std::thread::sleep(SHUTDOWN_WARMUP_STEP_TIME);
+    }
+    Ok(())
+}
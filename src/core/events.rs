@@ -11,9 +11,13 @@ pub struct Progress {
 #[derive(Clone)]
 pub enum Event {
     Error(String),
+    Warning(String),
+    ModeStuck(ModeType, String), // mode type and progress string
     ModeContinued,
     CameraDeviceChanged(DeviceAndProp),
+    LiveViewGainChanged(f64),
     MountDeviceSelected(String),
+    SiteChanged,
     ModeChanged,
     Progress(Option<Progress>, ModeType),
     FrameProcessing(FrameProcessResult),
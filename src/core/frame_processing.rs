@@ -4,8 +4,8 @@ use bitflags::bitflags;
 use chrono::{DateTime, Local, Utc};
 
 use crate::{
-    core::{core::ModeType, utils::{FileNameArg, FileNameUtils}},
-    image::{histogram::*, image::*, info::*, io::*, preview::*, raw::*, simple_fits::{FitsReader, SeekNRead}, stacker::Stacker, stars_offset::*},
+    core::{core::ModeType, utils::{DarkMatchTolerance, FileNameArg, FileNameUtils, DARK_CURRENT_DOUBLING_TEMP}},
+    image::{histogram::*, image::*, info::*, io::*, preview::*, raw::*, simple_fits::{decompress_fits, is_fpack_format, FitsReader, SeekNRead}, stacker::Stacker, stars_offset::*},
     indi,
     options::*, utils::log_utils::*
 };
@@ -37,20 +37,36 @@ impl ResultImage {
 
 #[derive(Default, Debug)]
 pub struct CalibrParams {
-    pub extract_dark:  bool,
-    pub dark_lib_path: PathBuf,
-    pub flat_fname:    Option<PathBuf>,
+    pub extract_dark:    bool,
+    pub dark_lib_path:   PathBuf,
+    pub flat_fname:      Option<PathBuf>,
 
     /// search and remove hot pixles
-    pub sar_hot_pixs:  bool,
+    pub sar_hot_pixs:    bool,
+
+    /// scale nearest master dark if there is no exact one
+    pub dark_scaling:    bool,
+
+    /// remove hot pixels found in master dark
+    pub hot_pix_removal: bool,
+
+    /// sigma threshold for hot pixels in master dark
+    pub hot_pix_sigma:   f64,
+
+    /// tolerances of master dark selection if there is no exact one
+    pub dark_match_tol:  DarkMatchTolerance,
 }
 
 #[derive(Default)]
 pub struct CalibrData {
     /// Defect pixles found in current master dark
     dark_defect_pixels:  Option<BadPixels>,
+    dark_bpm_fname:      Option<PathBuf>,
     subtract_image:      Option<RawImage>,
     subtract_fname:      Option<PathBuf>,
+    scaled_dark:         Option<(f64, RawImage)>, // scale factor and scaled dark
+    bias_image:          Option<RawImage>, // zero point for dark scaling
+    bias_fname:          Option<PathBuf>,
     master_flat:         Option<RawImage>,
     master_flat_fname:   Option<PathBuf>,
     defect_pixels:       Option<BadPixels>,
@@ -60,8 +76,12 @@ pub struct CalibrData {
 impl CalibrData {
     pub fn clear(&mut self) {
         self.dark_defect_pixels = None;
+        self.dark_bpm_fname = None;
         self.subtract_image = None;
         self.subtract_fname = None;
+        self.scaled_dark = None;
+        self.bias_image = None;
+        self.bias_fname = None;
         self.master_flat = None;
         self.master_flat_fname = None;
         self.defect_pixels = None;
@@ -75,6 +95,7 @@ pub struct LiveStackingData {
     pub hist:     RwLock<Histogram>,
     pub info:     RwLock<ResultImageInfo>,
     pub time_cnt: Mutex<f64>,
+    pub rejected: Mutex<usize>, // frames rejected because of bad quality
 }
 
 impl LiveStackingData {
@@ -85,6 +106,7 @@ impl LiveStackingData {
             hist:     RwLock::new(Histogram::new()),
             info:     RwLock::new(ResultImageInfo::None),
             time_cnt: Mutex::new(0.0),
+            rejected: Mutex::new(0),
         }
     }
 
@@ -94,6 +116,7 @@ impl LiveStackingData {
         self.hist.write().unwrap().clear();
         *self.info.write().unwrap() = ResultImageInfo::None;
         *self.time_cnt.lock().unwrap() = 0.0;
+        *self.rejected.lock().unwrap() = 0;
     }
 }
 
@@ -109,12 +132,18 @@ bitflags! { pub struct ProcessImageFlags: u32 {
 pub enum ImageSource {
     Blob(Arc<indi::BlobPropValue>),
     FileName(PathBuf),
+    VideoFrame {
+        blob:   Arc<indi::BlobPropValue>,
+        width:  usize, // size of stream frame
+        height: usize,
+    },
 }
 
 impl ImageSource {
     fn type_hint(&self) -> &str {
         match self {
-            Self::Blob(blob) =>
+            Self::Blob(blob) |
+            Self::VideoFrame { blob, .. } =>
                 &blob.format,
             Self::FileName(fname) =>
                 fname.extension().unwrap_or_default().to_str().unwrap_or_default(),
@@ -136,6 +165,7 @@ pub struct FrameProcessCommandData {
     pub frame_options:   FrameOptions,
     pub quality_options: Option<QualityOptions>,
     pub live_stacking:   Option<LiveStackingParams>,
+    pub airmass:         Option<f64>, // at mount pointing when frame is received
 }
 
 pub struct Preview8BitImgData {
@@ -242,6 +272,24 @@ pub fn start_frame_processing_thread() -> (mpsc::Sender<FrameProcessCommand>, Jo
     (bg_comands_sender, thread)
 }
 
+/// Dark current scale factor for master dark taken with other exposure
+/// or temperature. Dark current is proportional to exposure and
+/// doubles every 6.5°C (typical value for CMOS sensors)
+fn calc_dark_scale_factor(dark: &RawImageInfo, light: &RawImageInfo) -> f64 {
+    let exp_factor = if dark.exposure > 0.0 && light.exposure > 0.0 {
+        light.exposure / dark.exposure
+    } else {
+        1.0
+    };
+    let temp_factor = match (dark.ccd_temp, light.ccd_temp) {
+        (Some(dark_temp), Some(light_temp)) =>
+            f64::powf(2.0, (light_temp - dark_temp) / DARK_CURRENT_DOUBLING_TEMP),
+        _ =>
+            1.0,
+    };
+    exp_factor * temp_factor
+}
+
 fn apply_calibr_data_and_remove_hot_pixels(
     params:    &Option<CalibrParams>,
     raw_image: &mut RawImage,
@@ -254,6 +302,7 @@ fn apply_calibr_data_and_remove_hot_pixels(
     let mut calibr_methods = CalibrMethods::empty();
 
     let fn_utils = FileNameUtils::default();
+    let mut scale_dark = false;
     let (defect_pixel_file, subtrack_fname, subtrack_method) =
         if params.extract_dark {
             let to_calibrate = FileNameArg::RawInfo(image_info);
@@ -262,6 +311,31 @@ fn apply_calibr_data_and_remove_hot_pixels(
                 &to_calibrate,
                 &params.dark_lib_path
             );
+            let subtrack_fname = if subtrack_method.contains(CalibrMethods::BY_DARK)
+            && !subtrack_fname.is_file() {
+                let matching = FileNameUtils::find_matching_master_dark(
+                    image_info,
+                    &params.dark_lib_path,
+                    &params.dark_match_tol
+                );
+                if let Some(matching) = matching {
+                    scale_dark = params.dark_scaling;
+                    matching
+                } else if params.dark_scaling {
+                    match FileNameUtils::find_nearest_master_dark(image_info, &params.dark_lib_path) {
+                        Some(nearest) => {
+                            scale_dark = true;
+                            nearest
+                        }
+                        None =>
+                            subtrack_fname,
+                    }
+                } else {
+                    subtrack_fname
+                }
+            } else {
+                subtrack_fname
+            };
             (Some(defect_pixel_file), Some(subtrack_fname), subtrack_method)
         } else {
             (None, None, CalibrMethods::empty())
@@ -295,8 +369,14 @@ fn apply_calibr_data_and_remove_hot_pixels(
 
     if calibr.subtract_fname != subtrack_fname {
         calibr.subtract_image = None;
+        calibr.scaled_dark = None;
         calibr.dark_defect_pixels = None;
-        if let Some(file_name) = &subtrack_fname { if file_name.is_file() {
+        if let Some(file_name) = &subtrack_fname { if !file_name.is_file() {
+            log::warn!(
+                "No acceptable master file found, expected {}",
+                file_name.to_str().unwrap_or_default()
+            );
+        } else {
             log::debug!(
                 "Loading master dark file {} ...",
                 file_name.to_str().unwrap_or_default()
@@ -310,18 +390,58 @@ fn apply_calibr_data_and_remove_hot_pixels(
                 ))?;
             tmr.log("loading master dark from file");
 
-            if subtrack_method.contains(CalibrMethods::BY_DARK) {
-                let tmr = TimeLogger::start();
-                let defect_pixels = subtract_image.find_hot_pixels_in_master_dark();
-                tmr.log("searching hot pixels in dark image");
-                log::debug!("hot pixels count = {}", defect_pixels.items.len());
-                calibr.dark_defect_pixels = Some(defect_pixels);
-                reload_flat = true;
-            }
-
             calibr.subtract_image = Some(subtract_image);
         }}
         calibr.subtract_fname = subtrack_fname.clone();
+        calibr.dark_bpm_fname = None;
+    }
+
+    // Load or build hot pixels map of master dark
+
+    let bpm_fname = match &subtrack_fname {
+        Some(file_name)
+        if params.hot_pix_removal
+        && subtrack_method.contains(CalibrMethods::BY_DARK)
+        && calibr.subtract_image.is_some() =>
+            Some(FileNameUtils::bad_pixel_map_file_name(file_name, params.hot_pix_sigma)),
+        _ =>
+            None,
+    };
+
+    if calibr.dark_bpm_fname != bpm_fname {
+        calibr.dark_defect_pixels = None;
+        if let (Some(bpm_fname), Some(dark_fname), Some(dark_image))
+        = (&bpm_fname, &subtrack_fname, &calibr.subtract_image) {
+            let modified = |file_name: &Path| {
+                file_name.metadata().and_then(|m| m.modified()).ok()
+            };
+            let map_is_actual =
+                bpm_fname.is_file() &&
+                modified(bpm_fname) >= modified(dark_fname);
+            let mut defect_pixels = BadPixels::default();
+            if map_is_actual {
+                log::debug!(
+                    "Loading hot pixels map {} ...",
+                    bpm_fname.to_str().unwrap_or_default()
+                );
+                defect_pixels.load_from_file(bpm_fname)?;
+            } else {
+                let tmr = TimeLogger::start();
+                defect_pixels = dark_image.build_bad_pixel_map(params.hot_pix_sigma);
+                tmr.log("building hot pixels map of master dark");
+                if let Err(err) = defect_pixels.save_to_file(bpm_fname) {
+                    log::error!(
+                        "Can't save hot pixels map {}: {}",
+                        bpm_fname.to_str().unwrap_or_default(),
+                        err.to_string()
+                    );
+                }
+            }
+            log::debug!("hot pixels count = {}", defect_pixels.items.len());
+            calibr.dark_defect_pixels = Some(defect_pixels);
+        }
+        calibr.dark_bpm_fname = bpm_fname;
+        reload_flat = true;
     }
 
     // Load master flat file
@@ -360,6 +480,62 @@ fn apply_calibr_data_and_remove_hot_pixels(
     // Apply master dark or bias image
 
     if let (Some(file_name), Some(dark_image)) = (&subtrack_fname, &calibr.subtract_image) {
+        let scale_factor = if scale_dark {
+            calc_dark_scale_factor(dark_image.info(), image_info)
+        } else {
+            1.0
+        };
+        let dark_image = if f64::abs(scale_factor - 1.0) > 0.01 {
+            let bias_fname = FileNameUtils::find_master_bias(image_info, &params.dark_lib_path);
+            if calibr.bias_fname != bias_fname {
+                calibr.bias_image = None;
+                calibr.scaled_dark = None;
+                if let Some(bias_fname) = &bias_fname {
+                    log::debug!(
+                        "Loading master bias file {} ...",
+                        bias_fname.to_str().unwrap_or_default()
+                    );
+                    let bias_image = load_raw_image_from_fits_file(bias_fname)
+                        .map_err(|e| anyhow::anyhow!(
+                            "Error '{}'\nwhen reading master bias '{}'",
+                            e.to_string(),
+                            bias_fname.to_str().unwrap_or_default()
+                        ))?;
+                    calibr.bias_image = Some(bias_image);
+                }
+                calibr.bias_fname = bias_fname;
+            }
+            let cached_factor = calibr.scaled_dark.as_ref().map(|(factor, _)| *factor);
+            if cached_factor.map(|f| f64::abs(f - scale_factor) > 0.01).unwrap_or(true) {
+                let zero_point = match &calibr.bias_fname {
+                    Some(bias_fname) => bias_fname.to_str().unwrap_or_default().to_string(),
+                    None             => format!("camera offset {}", dark_image.info().offset),
+                };
+                log::info!(
+                    "Scaling master dark {} by factor {:.3} (zero point: {})",
+                    file_name.to_str().unwrap_or_default(),
+                    scale_factor,
+                    zero_point
+                );
+                let tmr = TimeLogger::start();
+                let mut scaled_dark = dark_image.clone();
+                match scaled_dark.scale_dark(scale_factor, calibr.bias_image.as_ref()) {
+                    Ok(()) => {
+                        tmr.log("scaling master dark");
+                        calibr.scaled_dark = Some((scale_factor, scaled_dark));
+                    }
+                    Err(err) => {
+                        log::warn!("Master dark is used without scaling: {}", err);
+                        calibr.scaled_dark = None;
+                    }
+                }
+            }
+            calibr.scaled_dark.as_ref()
+                .map(|(_, scaled_dark)| scaled_dark)
+                .unwrap_or(dark_image)
+        } else {
+            dark_image
+        };
         let tmr = TimeLogger::start();
         raw_image.subtract_dark_or_bias(dark_image)
             .map_err(|err| anyhow::anyhow!(
@@ -405,7 +581,8 @@ fn apply_calibr_data_and_remove_hot_pixels(
     if !is_flat_file
     && params.sar_hot_pixs
     && calibr.defect_pixels.is_none()
-    && calibr.dark_defect_pixels.is_none() {
+    && calibr.dark_defect_pixels.is_none()
+    && !calibr_methods.contains(CalibrMethods::BY_DARK) {
         let tmr = TimeLogger::start();
         let hot_pixels = raw_image.find_hot_pixels_in_light();
         tmr.log("searching hot pixels in light image");
@@ -455,10 +632,14 @@ fn make_preview_image(
     }
 }
 
+/// Video stream frames are binned down to this size for preview
+const VIDEO_FRAME_MAX_SIZE: usize = 1280;
+
 enum ImageLoader<'a> {
     Fits(FitsReader, Box<dyn SeekNRead + 'a>),
     Tif(PathBuf),
     ByPixbuf(PathBuf),
+    VideoFrame(Option<RawImage>),
 }
 
 impl<'a> ImageLoader<'a> {
@@ -466,6 +647,8 @@ impl<'a> ImageLoader<'a> {
         match self {
             Self::Fits(reader, _) =>
                 find_mono_image_hdu_in_fits(reader).is_some(),
+            Self::VideoFrame(_) =>
+                true,
             _ =>
                 false,
         }
@@ -475,6 +658,8 @@ impl<'a> ImageLoader<'a> {
         match self {
             Self::Fits(reader, _) =>
                 find_color_image_hdu_in_fits(reader).is_some(),
+            Self::VideoFrame(_) =>
+                false,
             _ =>
                 true,
         }
@@ -484,6 +669,8 @@ impl<'a> ImageLoader<'a> {
         match self {
             Self::Fits(reader, stream) =>
                 load_raw_image_from_fits_reader(reader, stream),
+            Self::VideoFrame(raw_image) =>
+                raw_image.take().ok_or_else(|| anyhow::anyhow!("Video frame is already loaded")),
             _ =>
                 anyhow::bail!("Format not support raw images"),
         }
@@ -497,6 +684,8 @@ impl<'a> ImageLoader<'a> {
                 load_image_from_tif_file(image, file_name)?,
             Self::ByPixbuf(file_name) =>
                 load_image_by_pixbuf(image, file_name, 6000)?,
+            Self::VideoFrame(_) =>
+                anyhow::bail!("Video frame is raw image"),
         }
         Ok(())
     }
@@ -529,6 +718,8 @@ fn make_preview_image_impl(
         type_hint.eq_ignore_ascii_case(".fit") ||
         type_hint.eq_ignore_ascii_case(".fits");
 
+    let is_fpack_file = is_fpack_format(type_hint);
+
     let is_tif_file =
         type_hint.eq_ignore_ascii_case("tif");
 
@@ -537,9 +728,18 @@ fn make_preview_image_impl(
         type_hint.eq_ignore_ascii_case("jpeg") ||
         type_hint.eq_ignore_ascii_case("png");
 
-    let mut loader = if is_fits_file {
+    let mut loader = if let ImageSource::VideoFrame { blob, width, height } = &command.img_source {
+        let raw_image = load_raw_image_from_video_frame(
+            &blob.data,
+            *width,
+            *height,
+            VIDEO_FRAME_MAX_SIZE
+        )?;
+        ImageLoader::VideoFrame(Some(raw_image))
+    } else if is_fits_file {
         let mut stream: Box<dyn SeekNRead> = match &command.img_source {
-            ImageSource::Blob(blob) =>
+            ImageSource::Blob(blob) |
+            ImageSource::VideoFrame { blob, .. } =>
                 Box::new(Cursor::new(blob.data.as_slice())),
             ImageSource::FileName(file_name) => {
                 let file = std::fs::File::open(file_name)?;
@@ -548,6 +748,19 @@ fn make_preview_image_impl(
         };
         let reader = FitsReader::new(&mut stream)?;
         ImageLoader::Fits(reader, stream)
+    } else if is_fpack_file {
+        let tmr = TimeLogger::start();
+        let data = match &command.img_source {
+            ImageSource::Blob(blob) |
+            ImageSource::VideoFrame { blob, .. } =>
+                decompress_fits(&blob.data)?,
+            ImageSource::FileName(file_name) =>
+                decompress_fits(&std::fs::read(file_name)?)?,
+        };
+        tmr.log("Decompressing FITS");
+        let mut stream: Box<dyn SeekNRead> = Box::new(Cursor::new(data));
+        let reader = FitsReader::new(&mut stream)?;
+        ImageLoader::Fits(reader, stream)
     } else if is_tif_file {
         if let ImageSource::FileName(file_name) = &command.img_source {
             ImageLoader::Tif(file_name.clone())
@@ -561,7 +774,15 @@ fn make_preview_image_impl(
             unreachable!();
         }
     } else {
-        anyhow::bail!("Image format {} is not supported", type_hint);
+        let source = match &command.img_source {
+            ImageSource::Blob(_) |
+            ImageSource::VideoFrame { .. } =>
+                format!("{}.{}", command.camera.name, command.camera.prop),
+            ImageSource::FileName(file_name) =>
+                file_name.to_str().unwrap_or_default().to_string(),
+        };
+        log::error!("Image format '{}' from {} is not supported", type_hint, source);
+        anyhow::bail!("Image format '{}' from {} is not supported", type_hint, source);
     };
 
     let is_raw_image = loader.is_raw_image();
@@ -868,6 +1089,11 @@ fn make_preview_image_impl(
         .as_ref()
         .and_then(|qo| if qo.use_max_ovality { Some(qo.max_ovality) } else { None });
 
+    let star_detection = command.quality_options
+        .as_ref()
+        .map(|qo| qo.star_detection)
+        .unwrap_or_default();
+
     let is_bad_frame = if frame_type == FrameType::Lights {
         let mut ref_stars_lock = command.ref_stars.lock().unwrap();
 
@@ -884,6 +1110,7 @@ fn make_preview_image_impl(
             &image,
             max_stars_fwhm,
             max_stars_ovality,
+            &star_detection,
             ref_stars,
             true,
         );
@@ -892,6 +1119,7 @@ fn make_preview_image_impl(
         info.calibr_methods = raw_info.as_ref()
             .map(|i| i.calibr_methods)
             .unwrap_or(CalibrMethods::empty());
+        info.airmass = command.airmass.map(|v| v as f32);
         tmr.log("TOTAL LightImageInfo::from_image");
 
         if command.stop_flag.load(Ordering::Relaxed) {
@@ -901,9 +1129,11 @@ fn make_preview_image_impl(
 
         // Store reference stars for first good light frame
 
-        if command.flags.contains(ProcessImageFlags::CALC_STARS_OFFSET)
-        && info.stars.is_ok()
-        && ref_stars_lock.is_none() {
+        let is_ref_frame =
+            command.flags.contains(ProcessImageFlags::CALC_STARS_OFFSET) &&
+            info.stars.is_ok() &&
+            ref_stars_lock.is_none();
+        if is_ref_frame {
             *ref_stars_lock = Some(info.stars.items.iter()
                 .map(|star| Point {x: star.x, y: star.y })
                 .collect::<Vec<_>>());
@@ -934,9 +1164,29 @@ fn make_preview_image_impl(
 
         let bad_frame = !info.stars.fwhm_is_ok || !info.stars.ovality_is_ok;
 
+        // Reject bad frame from live stacking. Frame with reference
+        // stars is never rejected because it is used for alignment
+
+        let mut rejected_from_ls = false;
+        if let (Some(live_stacking), false) = (&command.live_stacking, is_ref_frame) {
+            let too_few_stars =
+                live_stacking.options.use_min_stars &&
+                info.stars.items.len() < live_stacking.options.min_stars;
+            if bad_frame || too_few_stars {
+                rejected_from_ls = true;
+                let mut rejected = live_stacking.data.rejected.lock().unwrap();
+                *rejected += 1;
+                log::info!(
+                    "Frame is rejected from live stacking (fwhm_ok={}, ovality_ok={}, stars={}). Total rejected: {}",
+                    info.stars.fwhm_is_ok, info.stars.ovality_is_ok,
+                    info.stars.items.len(), *rejected
+                );
+            }
+        }
+
         // Live stacking
 
-        if let (Some(live_stacking), false) = (&command.live_stacking, bad_frame) {
+        if let (Some(live_stacking), false) = (&command.live_stacking, rejected_from_ls) {
             // Translate/rotate image to reference image and add
             let offset = info.stars_offset.clone().unwrap_or_default();
             let mut stacker = live_stacking.data.stacker.write().unwrap();
@@ -948,7 +1198,8 @@ fn make_preview_image_impl(
                 -offset.y,
                 -offset.angle,
                 exposure,
-                live_stacking.options.remove_tracks
+                live_stacking.options.remove_tracks,
+                live_stacking.options.drizzle == Drizzle::X2,
             );
             tmr.log("ImageStacker::add");
             drop(stacker);
@@ -1004,6 +1255,7 @@ fn make_preview_image_impl(
                 &res_image,
                 max_stars_fwhm,
                 max_stars_ovality,
+                &star_detection,
                 None,
                 true,
             );
@@ -1083,6 +1335,7 @@ fn make_preview_image_impl(
                     let tmr = TimeLogger::start();
                     stacker.save_to_tiff(&file_path)?;
                     tmr.log("save live stacking result image");
+                    log::info!("Live stacking snapshot is saved to {}", file_path.to_str().unwrap_or_default());
                 }
             }
         }
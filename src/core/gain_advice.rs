@@ -0,0 +1,243 @@
+use crate::indi;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GainGoal {
+    MaxDynamicRange,
+    Unity,
+    LowReadNoise,
+}
+
+impl GainGoal {
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Self::MaxDynamicRange => "Max dynamic range",
+            Self::Unity           => "Unity gain",
+            Self::LowReadNoise    => "Low read noise",
+        }
+    }
+}
+
+struct SensorPoint {
+    gain:       f64,
+    e_per_adu:  f64,
+    read_noise: f64, // electrons
+}
+
+struct SensorData {
+    sensor:    &'static str,
+    cam_names: &'static [&'static str],
+    adc_bits:  u32,
+    full_well: f64, // electrons
+    offset:    i32,
+    points:    &'static [SensorPoint],
+}
+
+macro_rules! pt {
+    ($gain:expr, $e_per_adu:expr, $read_noise:expr) => {
+        SensorPoint { gain: $gain, e_per_adu: $e_per_adu, read_noise: $read_noise }
+    };
+}
+
+// Approximate values from manufacturers' charts
+const SENSORS: &[SensorData] = &[
+    SensorData {
+        sensor:    "IMX571",
+        cam_names: &["ASI2600", "ASI6200"],
+        adc_bits:  16,
+        full_well: 50_000.0,
+        offset:    50,
+        points:    &[pt!(0.0, 0.77, 3.5), pt!(99.0, 0.37, 3.0), pt!(100.0, 0.25, 1.5), pt!(200.0, 0.08, 1.2), pt!(300.0, 0.03, 1.0)],
+    },
+    SensorData {
+        sensor:    "IMX533",
+        cam_names: &["ASI533"],
+        adc_bits:  14,
+        full_well: 50_000.0,
+        offset:    50,
+        points:    &[pt!(0.0, 3.05, 3.7), pt!(99.0, 1.1, 3.2), pt!(100.0, 0.86, 1.5), pt!(200.0, 0.29, 1.3), pt!(300.0, 0.09, 1.0)],
+    },
+    SensorData {
+        sensor:    "IMX294",
+        cam_names: &["ASI294MC"],
+        adc_bits:  14,
+        full_well: 63_700.0,
+        offset:    30,
+        points:    &[pt!(0.0, 3.9, 7.3), pt!(119.0, 1.0, 6.2), pt!(120.0, 0.9, 1.8), pt!(200.0, 0.37, 1.5), pt!(300.0, 0.12, 1.2)],
+    },
+    SensorData {
+        sensor:    "IMX183",
+        cam_names: &["ASI183"],
+        adc_bits:  12,
+        full_well: 15_000.0,
+        offset:    10,
+        points:    &[pt!(0.0, 3.6, 3.0), pt!(111.0, 1.0, 2.2), pt!(200.0, 0.4, 1.9), pt!(300.0, 0.12, 1.6)],
+    },
+    SensorData {
+        sensor:    "MN34230",
+        cam_names: &["ASI1600"],
+        adc_bits:  12,
+        full_well: 20_000.0,
+        offset:    10,
+        points:    &[pt!(0.0, 5.0, 3.6), pt!(139.0, 1.0, 1.7), pt!(200.0, 0.5, 1.4), pt!(300.0, 0.2, 1.2)],
+    },
+];
+
+pub struct GainAdvice {
+    pub goal:       GainGoal,
+    pub gain:       f64,
+    pub offset:     Option<i32>,
+    pub read_noise: Option<f64>, // electrons
+    pub dyn_range:  Option<f64>, // stops
+    pub sensor:     Option<&'static str>,
+}
+
+impl GainAdvice {
+    pub fn to_string(&self) -> String {
+        let mut result = format!("{}: gain {:.0}", self.goal.to_str(), self.gain);
+        if let Some(offset) = self.offset {
+            result += &format!(", offset {}", offset);
+        }
+        let mut details = Vec::new();
+        if let Some(dyn_range) = self.dyn_range {
+            details.push(format!("DR {:.1} stops", dyn_range));
+        }
+        if let Some(read_noise) = self.read_noise {
+            details.push(format!("RN {:.1}e", read_noise));
+        }
+        if self.sensor.is_none() {
+            details.push("estimation".to_string());
+        }
+        if !details.is_empty() {
+            result += &format!(" ({})", details.join(", "));
+        }
+        result
+    }
+}
+
+fn find_sensor(device_name: &str) -> Option<&'static SensorData> {
+    let device_name = device_name.to_uppercase();
+    SENSORS.iter().find(|sensor|
+        sensor.cam_names.iter().any(|name| device_name.contains(name))
+    )
+}
+
+/// Converts raw camera gain (camera native units) into e-/ADU
+/// using per-sensor table. Values between table points are
+/// interpolated logarithmically because e-/ADU changes
+/// exponentially with gain. Returns `None` for unknown sensor
+pub fn gain_to_e_per_adu(device_name: &str, gain: f64) -> Option<f64> {
+    let sensor = find_sensor(device_name)?;
+    let points = sensor.points;
+    let first = points.first()?;
+    let last = points.last()?;
+    if gain <= first.gain {
+        return Some(first.e_per_adu);
+    }
+    if gain >= last.gain {
+        return Some(last.e_per_adu);
+    }
+    let (pt1, pt2) = points.windows(2)
+        .map(|pts| (&pts[0], &pts[1]))
+        .find(|(pt1, pt2)| gain >= pt1.gain && gain <= pt2.gain)?;
+    let part = (gain - pt1.gain) / (pt2.gain - pt1.gain);
+    let log_e_per_adu =
+        f64::ln(pt1.e_per_adu) +
+        part * (f64::ln(pt2.e_per_adu) - f64::ln(pt1.e_per_adu));
+    Some(f64::exp(log_e_per_adu))
+}
+
+/// Suggests camera gain (and offset if sensor is known) for `goal`.
+/// Gain is limited by range of INDI gain property
+pub fn suggest_gain(
+    device_name: &str,
+    gain_prop:   &indi::NumPropValue,
+    goal:        GainGoal,
+) -> GainAdvice {
+    let clamp = |gain: f64| gain.clamp(gain_prop.min, gain_prop.max);
+
+    let Some(sensor) = find_sensor(device_name) else {
+        // No sensor data. Just use common rules
+        let range = gain_prop.max - gain_prop.min;
+        let gain = match goal {
+            GainGoal::MaxDynamicRange => gain_prop.min,
+            GainGoal::Unity           => gain_prop.min + 0.25 * range,
+            GainGoal::LowReadNoise    => gain_prop.min + 0.5 * range,
+        };
+        return GainAdvice {
+            goal,
+            gain:       clamp(gain).round(),
+            offset:     None,
+            read_noise: None,
+            dyn_range:  None,
+            sensor:     None,
+        };
+    };
+
+    let adc_max = ((1_u32 << sensor.adc_bits) - 1) as f64;
+    let dyn_range = |pt: &SensorPoint| {
+        let full_well = f64::min(sensor.full_well, pt.e_per_adu * adc_max);
+        f64::log2(full_well / pt.read_noise)
+    };
+
+    let points: Vec<_> = sensor.points.iter()
+        .filter(|pt| pt.gain >= gain_prop.min && pt.gain <= gain_prop.max)
+        .collect();
+
+    let point = match goal {
+        GainGoal::MaxDynamicRange =>
+            points.iter()
+                .max_by(|pt1, pt2| dyn_range(pt1).total_cmp(&dyn_range(pt2)))
+                .copied(),
+        GainGoal::Unity =>
+            points.iter()
+                .min_by(|pt1, pt2| {
+                    let d1 = f64::abs(pt1.e_per_adu - 1.0);
+                    let d2 = f64::abs(pt2.e_per_adu - 1.0);
+                    d1.total_cmp(&d2)
+                })
+                .copied(),
+        GainGoal::LowReadNoise => {
+            // Lowest gain with read noise close to minimal one
+            // to not lose too much of dynamic range
+            let min_noise = points.iter()
+                .map(|pt| pt.read_noise)
+                .min_by(f64::total_cmp)
+                .unwrap_or(0.0);
+            points.iter()
+                .filter(|pt| pt.read_noise <= 1.3 * min_noise)
+                .min_by(|pt1, pt2| pt1.gain.total_cmp(&pt2.gain))
+                .copied()
+        }
+    };
+
+    let Some(point) = point else {
+        return GainAdvice {
+            goal,
+            gain:       clamp(gain_prop.min),
+            offset:     Some(sensor.offset),
+            read_noise: None,
+            dyn_range:  None,
+            sensor:     Some(sensor.sensor),
+        };
+    };
+
+    GainAdvice {
+        goal,
+        gain:       clamp(point.gain),
+        offset:     Some(sensor.offset),
+        read_noise: Some(point.read_noise),
+        dyn_range:  Some(dyn_range(point)),
+        sensor:     Some(sensor.sensor),
+    }
+}
+
+#[test]
+fn test_gain_to_e_per_adu() {
+    assert!(gain_to_e_per_adu("Unknown camera", 100.0).is_none());
+    let e_per_adu = gain_to_e_per_adu("ZWO CCD ASI2600MM Pro", 100.0).unwrap();
+    assert!(f64::abs(e_per_adu - 0.25) < 1e-6);
+    let e_per_adu = gain_to_e_per_adu("ZWO CCD ASI2600MM Pro", 1000.0).unwrap();
+    assert!(f64::abs(e_per_adu - 0.03) < 1e-6);
+    let e_per_adu = gain_to_e_per_adu("ZWO CCD ASI2600MM Pro", 150.0).unwrap();
+    assert!(e_per_adu < 0.25 && e_per_adu > 0.08);
+}
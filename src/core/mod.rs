@@ -6,9 +6,15 @@ pub mod consts;
 pub mod mode_darks_library;
 pub mod mode_goto;
 pub mod mode_polar_align;
+pub mod gain_advice;
+pub mod mode_targets;
+pub mod mode_sequence;
+pub mod mode_flats_session;
+pub mod pointing_model;
 
 mod mode_waiting;
 mod mode_tacking_pictures;
 mod mode_mount_calibration;
 mod utils;
-mod mode_capture_platesolve;
\ No newline at end of file
+mod mode_capture_platesolve;
+mod mode_video_stream;
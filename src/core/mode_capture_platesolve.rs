@@ -1,16 +1,31 @@
 use std::sync::{Arc, RwLock};
 
-use crate::{core::{consts::INDI_SET_PROP_TIMEOUT, core::*, frame_processing::*}, image::{image::*, stars::Stars}, indi, options::*, plate_solve::*, ui::sky_map::math::*};
+use crate::{core::{consts::*, core::*, frame_processing::*}, image::{image::*, info::LightFrameInfo, stars::Stars}, indi, options::*, plate_solve::*, ui::sky_map::math::*};
 
 use super::{events::*, utils::gain_to_value};
 
+// Distance between neighbour points of spiral search in parts of FOV
+const SPIRAL_STEP_FOV_PART: f64 = 0.7;
+
 enum State {
     None,
     Capturing,
     PlateSolve,
+    SpiralMoving,
+    SpiralReturning,
     Finished,
 }
 
+// Data for searching of field by spiral around start position
+// when platesolve fails
+struct SpiralSearch {
+    orig_crd:        EqCoord,
+    points:          Vec<EqCoord>,
+    cur_point:       usize,
+    failed:          bool,
+    move_seconds:    usize,
+}
+
 pub struct CapturePlatesolveMode {
     state:        State,
     indi:         Arc<indi::Connection>,
@@ -19,7 +34,11 @@ pub struct CapturePlatesolveMode {
     mount:        String,
     cam_opts:     CamOptions,
     ps_opts:      PlateSolverOptions,
+    telescope:    TelescopeOptions,
+    mount_opts:   MountOptions,
     plate_solver: PlateSolver,
+    cur_frame:    Option<(Arc<RwLock<Image>>, Arc<LightFrameInfo>)>, // for quick platesolve
+    spiral:       Option<SpiralSearch>,
 }
 
 impl CapturePlatesolveMode {
@@ -42,22 +61,68 @@ impl CapturePlatesolveMode {
             &camera,
             indi
         )?;
-        let plate_solver = PlateSolver::new(opts.plate_solver.solver);
+        let plate_solver = PlateSolver::new(&opts.plate_solver);
         Ok(Self {
             state:        State::None,
             indi:         Arc::clone(indi),
             subscribers:  Arc::clone(subscribers),
             mount:        opts.mount.device.clone(),
             ps_opts:      opts.plate_solver.clone(),
+            telescope:    opts.telescope.clone(),
+            mount_opts:   opts.mount.clone(),
+            cur_frame:    None,
+            spiral:       None,
             plate_solver,
             camera,
             cam_opts,
         })
     }
 
+    /// Platesolve already captured frame without capturing new one.
+    /// Uses stars found in frame if platesolver supports them as input
+    pub fn new_from_cur_frame(
+        options:     &Arc<RwLock<Options>>,
+        indi:        &Arc<indi::Connection>,
+        subscribers: &Arc<EventSubscriptions>,
+        image:       &Arc<RwLock<Image>>,
+        info:        &Arc<LightFrameInfo>,
+    ) -> anyhow::Result<Self> {
+        let opts = options.read().unwrap();
+        let plate_solver = PlateSolver::new(&opts.plate_solver);
+        Ok(Self {
+            state:        State::None,
+            indi:         Arc::clone(indi),
+            subscribers:  Arc::clone(subscribers),
+            camera:       opts.cam.device.clone().unwrap_or_default(),
+            mount:        opts.mount.device.clone(),
+            cam_opts:     opts.cam.clone(),
+            ps_opts:      opts.plate_solver.clone(),
+            telescope:    opts.telescope.clone(),
+            mount_opts:   opts.mount.clone(),
+            cur_frame:    Some((Arc::clone(image), Arc::clone(info))),
+            spiral:       None,
+            plate_solver,
+        })
+    }
+
+    fn plate_solve_cur_frame(&mut self) -> anyhow::Result<()> {
+        let Some((image, info)) = self.cur_frame.clone() else {
+            return Ok(());
+        };
+        if self.plate_solver.support_stars_as_input() {
+            log::debug!("Quick platesolve by {} stars", info.stars.items.len());
+            self.plate_solve_stars(&info.stars.items, info.width, info.height)?;
+        } else {
+            log::debug!("Quick platesolve by image");
+            self.plate_solve_image(&image)?;
+        }
+        Ok(())
+    }
+
     fn plate_solve_image(&mut self, image: &Arc<RwLock<Image>>) -> anyhow::Result<()> {
         let image = image.read().unwrap();
         let mut config = PlateSolveConfig::default();
+        config.eq_coord = self.spiral_point();
         config.time_out = self.ps_opts.timeout;
         config.blind_time_out = self.ps_opts.blind_timeout;
         self.plate_solver.start(&PlateSolverInData::Image(&image), &config)?;
@@ -72,6 +137,7 @@ impl CapturePlatesolveMode {
         img_height: usize
     ) -> anyhow::Result<()> {
         let mut config = PlateSolveConfig::default();
+        config.eq_coord = self.spiral_point();
         config.time_out = self.ps_opts.timeout;
         config.blind_time_out = self.ps_opts.blind_timeout;
         let stars_arg = PlateSolverInData::Stars{
@@ -87,7 +153,12 @@ impl CapturePlatesolveMode {
         let result = match self.plate_solver.get_result()? {
             PlateSolveResult::Waiting => return Ok(false),
             PlateSolveResult::Done(result) => result,
-            PlateSolveResult::Failed => anyhow::bail!("Can't platesolve image")
+            PlateSolveResult::Failed => {
+                if self.start_next_spiral_point()? {
+                    return Ok(false);
+                }
+                anyhow::bail!("Can't platesolve image");
+            }
         };
 
         result.print_to_log();
@@ -99,6 +170,12 @@ impl CapturePlatesolveMode {
         self.subscribers.notify(
             Event::PlateSolve(event)
         );
+
+        // Quick platesolve only reports result and never syncs mount
+        if self.cur_frame.is_some() {
+            return Ok(true);
+        }
+
         self.indi.set_after_coord_set_action(
             &self.mount,
             indi::AfterCoordSetAction::Sync,
@@ -114,8 +191,167 @@ impl CapturePlatesolveMode {
             INDI_SET_PROP_TIMEOUT
         )?;
 
+        // Mount is synced now so it can return to start position accurately
+        if let Some(spiral) = &self.spiral {
+            log::info!("Spiral search: field found at point {}", spiral.cur_point);
+            let orig_crd = spiral.orig_crd;
+            self.start_spiral_return(&orig_crd, false)?;
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    /// Hint position for plate solver during spiral search
+    fn spiral_point(&self) -> Option<EqCoord> {
+        let spiral = self.spiral.as_ref()?;
+        spiral.points.get(spiral.cur_point.checked_sub(1)?).copied()
+    }
+
+    /// Moves mount to next point of spiral search.
+    /// Returns `false` if spiral search is not used or all points are done
+    fn start_next_spiral_point(&mut self) -> anyhow::Result<bool> {
+        if !self.ps_opts.spiral_search
+        || self.cur_frame.is_some()
+        || self.mount.is_empty() {
+            return Ok(false);
+        }
+        if self.spiral.is_none() {
+            let (ra, dec) = self.indi.mount_get_eq_ra_and_dec(&self.mount)?;
+            let orig_crd = EqCoord {
+                ra:  hour_to_radian(ra),
+                dec: degree_to_radian(dec),
+            };
+            let step = calc_spiral_step(&self.indi, &self.camera, &self.cam_opts, &self.telescope)?;
+            let max_radius = degree_to_radian(self.ps_opts.spiral_max_radius);
+            let points = spiral_search_points(&orig_crd, step, max_radius);
+            log::info!(
+                "Platesolve failed. Starting spiral search with step {:.2}° and {} points",
+                radian_to_degree(step), points.len()
+            );
+            self.spiral = Some(SpiralSearch {
+                orig_crd,
+                points,
+                cur_point:       0,
+                failed:          false,
+                move_seconds:    0,
+            });
+        }
+        let spiral = self.spiral.as_mut().unwrap();
+        let Some(crd) = spiral.points.get(spiral.cur_point).copied() else {
+            log::error!("Spiral search failed. Returning mount to start position");
+            let orig_crd = spiral.orig_crd;
+            self.start_spiral_return(&orig_crd, true)?;
+            return Ok(true);
+        };
+        spiral.cur_point += 1;
+        log::info!("Spiral search: moving to point {}/{} {:?}", spiral.cur_point, spiral.points.len(), crd);
+        self.start_mount_move(&crd)?;
+        self.state = State::SpiralMoving;
         Ok(true)
     }
+
+    fn start_spiral_return(&mut self, orig_crd: &EqCoord, failed: bool) -> anyhow::Result<()> {
+        if let Some(spiral) = &mut self.spiral {
+            spiral.failed = failed;
+        }
+        self.start_mount_move(orig_crd)?;
+        self.state = State::SpiralReturning;
+        Ok(())
+    }
+
+    fn start_mount_move(&mut self, crd: &EqCoord) -> anyhow::Result<()> {
+        self.indi.set_after_coord_set_action(
+            &self.mount,
+            indi::AfterCoordSetAction::Track,
+            true,
+            INDI_SET_PROP_TIMEOUT
+        )?;
+        self.indi.mount_set_eq_coord(
+            &self.mount,
+            radian_to_hour(crd.ra),
+            radian_to_degree(crd.dec),
+            true,
+            None
+        )?;
+        if let Some(spiral) = &mut self.spiral {
+            spiral.move_seconds = 0;
+        }
+        self.indi.mount_reset_coords_samples(&self.mount);
+        Ok(())
+    }
+
+    /// Returns `true` if mount has reached destination and settled
+    fn check_mount_move_finished(&mut self) -> anyhow::Result<bool> {
+        let crd_prop_state = self.indi.mount_get_eq_coord_prop_state(&self.mount)?;
+        let Some(spiral) = &mut self.spiral else {
+            return Ok(true);
+        };
+        if crd_prop_state == indi::PropState::Ok {
+            let is_settled = self.indi.mount_coords_are_stable(
+                &self.mount,
+                self.mount_opts.settle_tol,
+                self.mount_opts.settle_samples
+            )?;
+            return Ok(is_settled);
+        }
+        spiral.move_seconds += 1;
+        if spiral.move_seconds > MAX_GOTO_TIME {
+            anyhow::bail!("Telescope is moving too long time (> {}s)", MAX_GOTO_TIME);
+        }
+        Ok(false)
+    }
+}
+
+/// Distance between neighbour points of spiral search
+/// calculated from field of view of camera
+pub(super) fn calc_spiral_step(
+    indi:      &indi::Connection,
+    camera:    &DeviceAndProp,
+    cam_opts:  &CamOptions,
+    telescope: &TelescopeOptions,
+) -> anyhow::Result<f64> {
+    let focal_len = telescope.real_focal_length();
+    if focal_len <= 0.1 {
+        anyhow::bail!("Wrong telescope focal lenght");
+    }
+    let cam_ccd = indi::CamCcd::from_ccd_prop_name(&camera.prop);
+    let (sensor_width, sensor_height) = indi.camera_get_max_frame_size(&camera.name, cam_ccd)?;
+    let (pixel_width_um, pixel_height_um) = indi.camera_get_pixel_size_um(&camera.name, cam_ccd)?;
+    let (width_mm, height_mm) = cam_opts.calc_active_zone_mm(
+        sensor_width, sensor_height,
+        pixel_width_um, pixel_height_um
+    );
+    let fov = f64::atan2(f64::min(width_mm, height_mm), focal_len);
+    Ok(SPIRAL_STEP_FOV_PART * fov)
+}
+
+/// Points of square spiral around `center` with `step` distance
+/// between neighbours. All points are inside `max_radius`
+pub(super) fn spiral_search_points(center: &EqCoord, step: f64, max_radius: f64) -> Vec<EqCoord> {
+    let mut result = Vec::new();
+    if step <= 0.0 {
+        return result;
+    }
+    let max_ring = (max_radius / step) as i32;
+    for ring in 1..=max_ring {
+        // start at right-bottom corner and go counterclockwise
+        let mut offsets = Vec::new();
+        for y in (-ring+1)..=ring { offsets.push((ring, y)); }
+        for x in (-ring..ring).rev() { offsets.push((x, ring)); }
+        for y in (-ring..ring).rev() { offsets.push((-ring, y)); }
+        for x in (-ring+1)..=ring { offsets.push((x, -ring)); }
+        for (x, y) in offsets {
+            let dec = center.dec + y as f64 * step;
+            if dec.abs() > 0.5 * std::f64::consts::PI {
+                continue;
+            }
+            let cos_dec = f64::max(f64::cos(dec), 0.01);
+            let ra = (center.ra + x as f64 * step / cos_dec).rem_euclid(2.0 * std::f64::consts::PI);
+            result.push(EqCoord { ra, dec });
+        }
+    }
+    result
 }
 
 impl Mode for CapturePlatesolveMode {
@@ -127,32 +363,63 @@ impl Mode for CapturePlatesolveMode {
         match self.state {
             State::Capturing =>
                 "Capturing image".to_string(),
+            State::PlateSolve if self.spiral.is_some() =>
+                "Spiral search: platesolving...".to_string(),
             State::PlateSolve =>
                 "Platesolving...".to_string(),
+            State::SpiralMoving =>
+                "Spiral search: moving mount".to_string(),
+            State::SpiralReturning =>
+                "Returning to start position".to_string(),
+            State::None|State::Finished if self.cur_frame.is_some() =>
+                "Quick platesolve".to_string(),
             State::None|State::Finished =>
                 "Capture, platesolve & sync".to_string(),
         }
     }
 
     fn progress(&self) -> Option<Progress> {
+        if let Some(spiral) = &self.spiral {
+            return Some(Progress {
+                cur:   spiral.cur_point,
+                total: spiral.points.len(),
+            });
+        }
         let stage = match self.state {
             State::None       => 0,
             State::Capturing  => 0,
             State::PlateSolve => 1,
             State::Finished   => 2,
+            State::SpiralMoving|State::SpiralReturning => 1,
         };
         Some(Progress { cur: stage, total: 2 })
     }
 
     fn cam_device(&self) -> Option<&DeviceAndProp> {
+        if self.cur_frame.is_some() {
+            return None;
+        }
         Some(&self.camera)
     }
 
+    fn used_devices(&self) -> Vec<&str> {
+        vec![&self.camera.name, &self.mount]
+    }
+
     fn get_cur_exposure(&self) -> Option<f64> {
+        if self.cur_frame.is_some() {
+            return None;
+        }
         Some(self.cam_opts.frame.exp_main)
     }
 
     fn start(&mut self) -> anyhow::Result<()> {
+        if self.cur_frame.is_some() {
+            self.plate_solve_cur_frame()?;
+            self.state = State::PlateSolve;
+            return Ok(());
+        }
+        self.spiral = None;
         log::debug!("Tacking picture for plate solve with {:?}", &self.cam_opts.frame);
         apply_camera_options_and_take_shot(&self.indi, &self.camera, &self.cam_opts.frame)?;
         self.state = State::Capturing;
@@ -160,8 +427,10 @@ impl Mode for CapturePlatesolveMode {
     }
 
     fn abort(&mut self) -> anyhow::Result<()> {
-        _ = abort_camera_exposure(&self.indi, &self.camera);
-        _ = self.indi.mount_abort_motion(&self.mount);
+        if self.cur_frame.is_none() {
+            _ = abort_camera_exposure(&self.indi, &self.camera);
+            _ = self.indi.mount_abort_motion(&self.mount);
+        }
         self.state = State::None;
         Ok(())
     }
@@ -196,9 +465,51 @@ impl Mode for CapturePlatesolveMode {
                     self.state = State::Finished;
                     return Ok(NotifyResult::Finished { next_mode: None });
                 }
+                if !matches!(self.state, State::PlateSolve) {
+                    return Ok(NotifyResult::ProgressChanges);
+                }
+            }
+            State::SpiralMoving => {
+                if self.check_mount_move_finished()? {
+                    log::debug!("Tacking picture for spiral search with {:?}", &self.cam_opts.frame);
+                    apply_camera_options_and_take_shot(&self.indi, &self.camera, &self.cam_opts.frame)?;
+                    self.state = State::Capturing;
+                    return Ok(NotifyResult::ProgressChanges);
+                }
+            }
+            State::SpiralReturning => {
+                if self.check_mount_move_finished()? {
+                    if self.spiral.as_ref().map(|spiral| spiral.failed).unwrap_or(false) {
+                        anyhow::bail!("Can't platesolve image (spiral search failed)");
+                    }
+                    self.state = State::Finished;
+                    return Ok(NotifyResult::Finished { next_mode: None });
+                }
             }
             _ => {},
         }
         Ok(NotifyResult::Empty)
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_spiral_search_points() {
+    let center = EqCoord { ra: 1.0, dec: 0.0 };
+    let step = degree_to_radian(1.0);
+
+    let points = spiral_search_points(&center, step, degree_to_radian(0.5));
+    assert!(points.is_empty());
+
+    let points = spiral_search_points(&center, step, degree_to_radian(2.5));
+    assert_eq!(points.len(), 8 + 16);
+    for (i, pt) in points.iter().enumerate() {
+        let dist = radian_to_degree(EqCoord::angle_between(&center, pt));
+        let max_dist = if i < 8 { 1.5 } else { 2.9 };
+        assert!(dist > 0.9 && dist < max_dist);
+    }
+
+    // no points behind pole
+    let center = EqCoord { ra: 1.0, dec: degree_to_radian(89.0) };
+    let points = spiral_search_points(&center, step, degree_to_radian(2.5));
+    assert!(points.iter().all(|pt| pt.dec <= 0.5 * std::f64::consts::PI));
+}
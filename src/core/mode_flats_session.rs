@@ -0,0 +1,279 @@
+use std::{path::Path, sync::{Arc, RwLock}};
+use crate::{indi, options::*};
+use super::{core::*, events::Progress, mode_tacking_pictures::sun_alt_if_not_suitable_for_sky_flats, utils::FlatPanelData};
+
+const MAX_FILTER_CHANGE_TIME: usize = 60; // seconds
+
+/// Loads flats session steps from CSV or text file. Every line
+/// contains filter slot (empty for no filter change) and frames count
+/// separated by comma, semicolon or tab.
+/// Lines beginning with `#` and header line are ignored
+pub fn load_flats_session_from_file(file_name: &Path) -> anyhow::Result<Vec<FlatsSessionStep>> {
+    let text = std::fs::read_to_string(file_name)?;
+    parse_flats_session(&text)
+}
+
+fn parse_flats_session(text: &str) -> anyhow::Result<Vec<FlatsSessionStep>> {
+    let mut result = Vec::new();
+    let mut first_line = true;
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let is_first_line = first_line;
+        first_line = false;
+
+        let line_num = idx + 1;
+        let separator = if line.contains(';') {
+            ';'
+        } else if line.contains('\t') {
+            '\t'
+        } else {
+            ','
+        };
+        let fields: Vec<_> = line.split(separator).map(str::trim).collect();
+        if fields.len() < 2 {
+            anyhow::bail!("Line {}: filter slot and count expected", line_num);
+        }
+
+        let count = fields[1].parse::<usize>().ok().filter(|v| *v != 0);
+        if is_first_line && count.is_none() {
+            // header
+            continue;
+        }
+        let filter_slot = if !fields[0].is_empty() {
+            let slot = fields[0].parse::<usize>().ok().filter(|v| *v != 0);
+            let Some(slot) = slot else {
+                anyhow::bail!("Line {}: wrong filter slot {}", line_num, fields[0]);
+            };
+            Some(slot)
+        } else {
+            None
+        };
+        let Some(count) = count else {
+            anyhow::bail!("Line {}: wrong frames count {}", line_num, fields[1]);
+        };
+
+        result.push(FlatsSessionStep { filter_slot, count });
+    }
+
+    if result.is_empty() {
+        anyhow::bail!("Flats session is empty");
+    }
+
+    Ok(result)
+}
+
+#[derive(PartialEq)]
+enum State {
+    Undefined,
+    WaitingForSky(f64), // current altitude of the Sun
+    ChangingFilter,
+    WaitingForCapture,
+}
+
+/// Saves FLAT frames for every filter of list. Flat panel is
+/// used if it is enabled in calibration options. Otherwise sky
+/// flats are taken when the Sun is inside altitude range from
+/// options. Exposure is found and corrected by ADU target loop
+pub struct FlatsSessionMode {
+    indi:           Arc<indi::Connection>,
+    options:        Arc<RwLock<Options>>,
+    filter_wheel:   String,
+    steps:          Vec<FlatsSessionStep>,
+    use_panel:      bool,
+    flat_panel:     Option<FlatPanelData>,
+    index:          usize,
+    state:          State,
+    filter_seconds: usize,
+}
+
+impl FlatsSessionMode {
+    pub fn new(
+        options: &Arc<RwLock<Options>>,
+        indi:    &Arc<indi::Connection>,
+    ) -> anyhow::Result<Self> {
+        let opts = options.read().unwrap();
+        if opts.cam.device.is_none() {
+            anyhow::bail!("Camera is not selected");
+        }
+        let steps: Vec<_> = opts.flats.steps
+            .iter()
+            .filter(|step| step.count != 0)
+            .cloned()
+            .collect();
+        if steps.is_empty() {
+            anyhow::bail!("Flats session is empty");
+        }
+        if opts.flats.sky_sun_alt_min >= opts.flats.sky_sun_alt_max {
+            anyhow::bail!("Wrong range of the Sun altitude for sky flats");
+        }
+        let mut filter_wheel = opts.sequence.filter_wheel.clone();
+        let filters_used = steps.iter().any(|step| step.filter_slot.is_some());
+        if filters_used && filter_wheel.is_empty() {
+            // Take first filter wheel if it is not selected explicitly
+            let wheels = indi.get_devices_list_by_interface(indi::DriverInterface::FILTER);
+            let Some(wheel) = wheels.first() else {
+                anyhow::bail!("Filter wheel is not found");
+            };
+            filter_wheel = wheel.name.to_string();
+        }
+
+        Ok(Self {
+            indi:           Arc::clone(indi),
+            options:        Arc::clone(options),
+            filter_wheel,
+            use_panel:      opts.calibr.flat_panel,
+            flat_panel:     None,
+            index:          0,
+            state:          State::Undefined,
+            filter_seconds: 0,
+            steps,
+        })
+    }
+
+    fn release_flat_panel(&mut self) -> anyhow::Result<()> {
+        let Some(flat_panel) = self.flat_panel.take() else {
+            return Ok(());
+        };
+        flat_panel.release(&self.indi)
+    }
+
+    fn start_cur_step(&mut self) -> anyhow::Result<NotifyResult> {
+        let step = self.steps[self.index].clone();
+        log::info!(
+            "Starting flats session step {}/{}: filter={:?}, count={}",
+            self.index + 1, self.steps.len(),
+            step.filter_slot, step.count
+        );
+        self.state = State::WaitingForCapture;
+        Ok(NotifyResult::StartFlatsSessionStep { step, sky_flats: !self.use_panel })
+    }
+}
+
+impl Mode for FlatsSessionMode {
+    fn get_type(&self) -> ModeType {
+        ModeType::FlatsSession
+    }
+
+    fn progress_string(&self) -> String {
+        if let State::WaitingForSky(sun_alt) = self.state {
+            return format!("Waiting for sky flats (Sun altitude {:.1}°)...", sun_alt);
+        }
+        match self.steps.get(self.index) {
+            Some(step) => {
+                let filter_str = step.filter_slot
+                    .map(|slot| format!(", filter #{}", slot))
+                    .unwrap_or_default();
+                format!(
+                    "Flats session: step {}/{}{}",
+                    self.index + 1, self.steps.len(), filter_str
+                )
+            }
+            None =>
+                "Flats session".to_string(),
+        }
+    }
+
+    fn can_be_stopped(&self) -> bool {
+        true
+    }
+
+    fn progress(&self) -> Option<Progress> {
+        let frames_done = self.steps.iter().take(self.index).map(|step| step.count).sum();
+        let frames_total = self.steps.iter().map(|step| step.count).sum();
+        Some(Progress {
+            cur: frames_done,
+            total: frames_total,
+        })
+    }
+
+    fn start(&mut self) -> anyhow::Result<()> {
+        self.state = State::Undefined;
+        if self.use_panel && self.flat_panel.is_none() {
+            let brightness = self.options.read().unwrap().calibr.flat_panel_bright;
+            self.flat_panel = Some(FlatPanelData::prepare(&self.indi, brightness)?);
+        }
+        Ok(())
+    }
+
+    fn abort(&mut self) -> anyhow::Result<()> {
+        self.release_flat_panel()
+    }
+
+    fn notify_timer_1s(&mut self) -> anyhow::Result<NotifyResult> {
+        match self.state {
+            State::Undefined | State::WaitingForSky(_) => {
+                let Some(step) = self.steps.get(self.index) else {
+                    log::info!("Flats session is finished");
+                    self.release_flat_panel()?;
+                    return Ok(NotifyResult::Finished { next_mode: None });
+                };
+                if !self.use_panel {
+                    if let Some(sun_alt) = sun_alt_if_not_suitable_for_sky_flats(&self.options.read().unwrap()) {
+                        if self.state == State::Undefined {
+                            log::info!("Waiting for the Sun altitude suitable for sky flats...");
+                        }
+                        self.state = State::WaitingForSky(sun_alt);
+                        return Ok(NotifyResult::ProgressChanges);
+                    }
+                }
+                let Some(slot) = step.filter_slot else {
+                    return self.start_cur_step();
+                };
+                if self.indi.filterwheel_get_current_slot(&self.filter_wheel)? == slot {
+                    return self.start_cur_step();
+                }
+                log::info!("Changing filter wheel {} slot to {}...", self.filter_wheel, slot);
+                self.indi.filterwheel_set_slot(&self.filter_wheel, slot, true, None)?;
+                self.filter_seconds = 0;
+                self.state = State::ChangingFilter;
+                Ok(NotifyResult::ProgressChanges)
+            }
+
+            State::ChangingFilter => {
+                let slot = self.steps[self.index].filter_slot.unwrap_or_default();
+                if self.indi.filterwheel_get_current_slot(&self.filter_wheel)? == slot {
+                    return self.start_cur_step();
+                }
+                self.filter_seconds += 1;
+                if self.filter_seconds > MAX_FILTER_CHANGE_TIME {
+                    anyhow::bail!(
+                        "Filter wheel slot change time out (> {} seconds)!",
+                        MAX_FILTER_CHANGE_TIME
+                    );
+                }
+                Ok(NotifyResult::Empty)
+            }
+
+            State::WaitingForCapture => {
+                self.index += 1;
+                self.state = State::Undefined;
+                Ok(NotifyResult::ProgressChanges)
+            }
+        }
+    }
+}
+
+#[test]
+fn test_parse_flats_session() {
+    let steps = parse_flats_session(
+        "# comment\n\
+         Filter;Count\n\
+         1;20\n\
+         ;15\n"
+    ).unwrap();
+    assert!(steps.len() == 2);
+    assert!(steps[0].filter_slot == Some(1));
+    assert!(steps[0].count == 20);
+    assert!(steps[1].filter_slot.is_none());
+    assert!(steps[1].count == 15);
+
+    let steps = parse_flats_session("2,30\n3,30").unwrap();
+    assert!(steps.len() == 2);
+
+    assert!(parse_flats_session("1").is_err());
+    assert!(parse_flats_session("1,10\n1,0").is_err());
+    assert!(parse_flats_session("x,10\n").is_err());
+}
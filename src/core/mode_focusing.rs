@@ -27,6 +27,101 @@ pub enum FocusingStateEvent {
     Result { value: f64 }
 }
 
+#[derive(Clone, Debug)]
+pub struct FocusCurveFit {
+    pub coeffs:   SquareCoeffs,
+    pub min_pos:  f64,
+    pub r2:       f64,
+    pub rejected: usize,
+}
+
+const FOCUS_FIT_OUTLIER_K: f64 = 3.0;
+
+/// Fits parabola into focus curve samples (focuser position, stars HFD or FWHM).
+/// Points which are too far from the curve are rejected one by one.
+/// Returns `None` if there are too few samples or if curve has no minimum
+pub fn fit_focus_curve(samples: &[(f64, f64)]) -> Option<FocusCurveFit> {
+    if samples.len() < 3 { return None; }
+
+    // Center and normalize positions to avoid precision
+    // problems with big focuser values
+    let x_center = samples.iter().map(|(x, _)| x).sum::<f64>() / samples.len() as f64;
+    let x_scale = samples.iter()
+        .map(|(x, _)| f64::abs(x - x_center))
+        .max_by(cmp_f64)
+        .filter(|v| *v > 0.0)?;
+
+    let mut points: Vec<_> = samples.iter()
+        .map(|(x, y)| ((x - x_center) / x_scale, *y))
+        .collect();
+
+    let max_rejected = samples.len() / 4;
+    let mut rejected = 0;
+    let coeffs = loop {
+        let x: Vec<_> = points.iter().map(|(x, _)| *x).collect();
+        let y: Vec<_> = points.iter().map(|(_, y)| *y).collect();
+        let coeffs = square_ls(&x, &y)?;
+        if rejected >= max_rejected || points.len() <= 4 {
+            break coeffs;
+        }
+
+        let mut residuals: Vec<_> = points.iter()
+            .map(|(x, y)| f64::abs(y - coeffs.calc(*x)))
+            .collect();
+        let (worst_idx, worst_residual) = residuals.iter()
+            .copied()
+            .enumerate()
+            .max_by(|(_, r1), (_, r2)| cmp_f64(r1, r2))?;
+
+        // Robust sigma estimation via median absolute residual
+        residuals.sort_by(cmp_f64);
+        let sigma = 1.4826 * residuals[residuals.len() / 2];
+        let min_sigma = 1e-6 * y.iter().map(|v| f64::abs(*v)).sum::<f64>() / y.len() as f64;
+        if worst_residual <= FOCUS_FIT_OUTLIER_K * f64::max(sigma, min_sigma) {
+            break coeffs;
+        }
+        points.remove(worst_idx);
+        rejected += 1;
+    };
+
+    if coeffs.a2 <= 0.0 { return None; }
+    let min_x = parabola_extremum(&coeffs)?;
+
+    let y_mean = points.iter().map(|(_, y)| y).sum::<f64>() / points.len() as f64;
+    let ss_tot = points.iter().map(|(_, y)| (y - y_mean).powi(2)).sum::<f64>();
+    let ss_res = points.iter().map(|(x, y)| (y - coeffs.calc(*x)).powi(2)).sum::<f64>();
+    let r2 = if ss_tot > 0.0 { 1.0 - ss_res / ss_tot } else { 0.0 };
+
+    // Back to focuser positions
+    let a2 = coeffs.a2 / (x_scale * x_scale);
+    let a1 = coeffs.a1 / x_scale - 2.0 * a2 * x_center;
+    let a0 = coeffs.a0 - coeffs.a1 * x_center / x_scale + a2 * x_center * x_center;
+
+    Some(FocusCurveFit {
+        coeffs:  SquareCoeffs { a2, a1, a0 },
+        min_pos: x_center + min_x * x_scale,
+        r2,
+        rejected,
+    })
+}
+
+/// Checks result of `fit_focus_curve`. Fit is rejected
+/// if curve has no minimum or if its R² is less than `min_r2`
+pub fn check_focus_curve_fit(
+    fit:    Option<FocusCurveFit>,
+    min_r2: f64,
+) -> anyhow::Result<FocusCurveFit> {
+    let Some(fit) = fit else {
+        anyhow::bail!("Wrong focuser curve result");
+    };
+    if fit.r2 < min_r2 {
+        log::error!("focus fit too poor (R²={:.3} < {:.3})", fit.r2, min_r2);
+        anyhow::bail!("Focus curve fit is too poor (R²={:.3})", fit.r2);
+    }
+    log::info!("Focus curve fit: R²={:.3}, rejected points: {}", fit.r2, fit.rejected);
+    Ok(fit)
+}
+
 #[derive(PartialEq, Debug)]
 enum Stage {
     Undef,
@@ -36,6 +131,7 @@ enum Stage {
 
 pub struct FocusingMode {
     indi:        Arc<indi::Connection>,
+    options:     Arc<RwLock<Options>>,
     subscribers: Arc<EventSubscriptions>,
     state:       FocusingState,
     camera:      DeviceAndProp,
@@ -96,6 +192,7 @@ impl FocusingMode {
 
         Ok(FocusingMode {
             indi:        Arc::clone(indi),
+            options:     Arc::clone(options),
             subscribers: Arc::clone(subscribers),
             state:       FocusingState::Undefined,
             f_options:   opts.focuser.clone(),
@@ -134,29 +231,68 @@ impl FocusingMode {
         &mut self,
         first_time: bool
     ) -> anyhow::Result<()> {
+        if first_time {
+            // Samples must be taken in direction of focuser approach
+            // to avoid backlash between them
+            self.to_go.make_contiguous().sort_by(|p1, p2| {
+                match self.f_options.backlash_direction {
+                    FocuserDirection::Increasing => cmp_f64(p1, p2),
+                    FocuserDirection::Decreasing => cmp_f64(p2, p1),
+                }
+            });
+        }
         let Some(pos) = self.to_go.pop_front() else {
             return Ok(());
         };
-        if !first_time {
-            log::debug!("Setting focuser value: {}", pos);
-            self.indi.focuser_set_abs_value(&self.f_options.device, pos, true, None)?;
-            self.state = FocusingState::WaitingPosition(pos);
+        self.set_focuser_pos(pos, false)?;
+        Ok(())
+    }
+
+    fn set_focuser_pos(&mut self, target_pos: f64, is_result: bool) -> anyhow::Result<()> {
+        // Autofocus step is used for backlash compensation
+        // if it is not set in options
+        let backlash_steps = if self.f_options.backlash_steps >= 1.0 {
+            self.f_options.backlash_steps
         } else {
-            let mut anti_backlash_pos = pos - self.f_options.step;
-            let cur_pos = self.indi.focuser_get_abs_value(&self.f_options.device)?;
-            if f64::abs(anti_backlash_pos - cur_pos) < 1.0 {
-                anti_backlash_pos -= 1.0;
-            }
-            log::debug!("Setting focuser value for avoiding backlash: {}", pos);
+            self.f_options.step
+        };
+        let cur_pos = self.indi.focuser_get_abs_value(&self.f_options.device)?;
+        let positions = focuser_positions_with_backlash(
+            cur_pos,
+            target_pos,
+            backlash_steps,
+            self.f_options.backlash_direction
+        );
+        if let [anti_backlash_pos, _] = positions.as_slice() {
+            let anti_backlash_pos = *anti_backlash_pos;
+            log::debug!("Setting focuser value for avoiding backlash: {}", anti_backlash_pos);
             self.indi.focuser_set_abs_value(&self.f_options.device, anti_backlash_pos, true, None)?;
-            self.state = FocusingState::WaitingPositionAntiBacklash{
-                anti_backlash_pos,
-                target_pos: pos
+            self.state = if is_result {
+                FocusingState::WaitingResultPosAntiBacklash { anti_backlash_pos, target_pos }
+            } else {
+                FocusingState::WaitingPositionAntiBacklash { anti_backlash_pos, target_pos }
+            };
+        } else {
+            log::debug!("Setting focuser value: {}", target_pos);
+            self.indi.focuser_set_abs_value(&self.f_options.device, target_pos, true, None)?;
+            self.state = if is_result {
+                FocusingState::WaitingResultPos(target_pos)
+            } else {
+                FocusingState::WaitingPosition(target_pos)
             };
         }
         Ok(())
     }
 
+    fn save_temperature_reference(&self) {
+        let mut options = self.options.write().unwrap();
+        let temperature = get_focuser_comp_temperature(&self.indi, &options);
+        if let Some(temperature) = temperature {
+            log::info!("Focuser reference temperature = {:.1}°C", temperature);
+        }
+        options.focuser.temp_ref = temperature;
+    }
+
     fn process_light_frame_info(
         &mut self,
         info: &LightFrameInfo,
@@ -207,40 +343,24 @@ impl FocusingMode {
                         ok, self.try_cnt, self.samples.len(),
                     );
 
-                    let mut x = Vec::new();
-                    let mut y = Vec::new();
-                    for sample in &self.samples {
-                        x.push(sample.focus_pos);
-                        y.push(sample.stars_fwhm as f64);
-                    }
-                    let coeffs = square_ls(&x, &y)
-                        .ok_or_else(|| anyhow::anyhow!("Can't find focus function"))?;
-
-                    log::debug!("Calculated coefficients = {:?}", coeffs);
-                    if coeffs.a2 <= 0.0 {
-                        let event_data = FocusingResultData {
-                            samples: self.samples.clone(),
-                            coeffs: Some(coeffs.clone()),
-                            result: None,
-                        };
-                        self.subscribers.notify(Event::Focusing(
-                            FocusingStateEvent::Data(event_data)
-                        ));
-                        anyhow::bail!("Wrong focuser curve result");
-                    }
-                    let extr = parabola_extremum(&coeffs)
-                        .ok_or_else(|| anyhow::anyhow!("Can't find focus extremum"))?;
-
-                    log::debug!("Calculated extremum = {}", extr);
+                    let points: Vec<_> = self.samples
+                        .iter()
+                        .map(|s| (s.focus_pos, s.stars_fwhm as f64))
+                        .collect();
+                    let fit = fit_focus_curve(&points);
 
+                    log::debug!("Calculated focus curve fit = {:?}", fit);
                     let event_data = FocusingResultData {
                         samples: self.samples.clone(),
-                        coeffs: Some(coeffs.clone()),
-                        result: Some(extr),
+                        coeffs: fit.as_ref().map(|fit| fit.coeffs.clone()),
+                        result: fit.as_ref().map(|fit| fit.min_pos),
                     };
                     self.subscribers.notify(Event::Focusing(
                         FocusingStateEvent::Data(event_data)
                     ));
+                    let fit = check_focus_curve_fit(fit, self.f_options.min_fit_r2)?;
+                    let extr = fit.min_pos;
+
                     let focuser_info = self.indi.focuser_get_abs_value_prop_info(&self.f_options.device)?;
                     if extr < focuser_info.min || extr > focuser_info.max {
                         anyhow::bail!(
@@ -284,22 +404,8 @@ impl FocusingMode {
 
                     self.result_pos = Some(result_pos);
 
-                    // for anti-backlash
-                    let anti_backlash_pos = result_pos - self.f_options.step;
-                    log::debug!(
-                        "Set RESULT focuser value for anti backlash {}",
-                        anti_backlash_pos
-                    );
-                    self.indi.focuser_set_abs_value(
-                        &self.f_options.device,
-                        anti_backlash_pos,
-                        true,
-                        None
-                    )?;
-                    self.state = FocusingState::WaitingResultPosAntiBacklash {
-                        anti_backlash_pos,
-                        target_pos: result_pos
-                    };
+                    self.set_focuser_pos(result_pos, true)?;
+                    self.save_temperature_reference();
                     let result_event = FocusingStateEvent::Result { value: result_pos };
                     self.subscribers.notify(Event::Focusing(result_event));
                 } else {
@@ -340,6 +446,10 @@ impl Mode for FocusingMode {
         Some(&self.camera)
     }
 
+    fn used_devices(&self) -> Vec<&str> {
+        vec![&self.camera.name, &self.f_options.device]
+    }
+
     fn progress(&self) -> Option<Progress> {
         Some(Progress {
             cur: self.samples.len(),
@@ -437,3 +547,75 @@ impl Mode for FocusingMode {
         }
     }
 }
+
+#[cfg(test)]
+fn make_v_curve(center: f64, step: f64, noise: &[f64]) -> Vec<(f64, f64)> {
+    noise.iter().enumerate().map(|(i, n)| {
+        let pos = center + (i as f64 - (noise.len() / 2) as f64) * step;
+        let hfd = 2.0 + 3.0 * ((pos - center) / (5.0 * step)).powi(2);
+        (pos, hfd + n)
+    }).collect()
+}
+
+#[test]
+fn test_fit_focus_curve() {
+    // ideal curve
+    let samples = make_v_curve(30000.0, 500.0, &[0.0; 11]);
+    let fit = fit_focus_curve(&samples).unwrap();
+    assert!(f64::abs(fit.min_pos - 30000.0) < 1.0);
+    assert!(fit.r2 > 0.999);
+    assert!(fit.rejected == 0);
+    assert!(f64::abs(fit.coeffs.calc(30000.0) - 2.0) < 1e-6);
+
+    // noisy curve
+    let noise = [0.05, -0.08, 0.03, 0.07, -0.04, 0.02, -0.06, 0.05, -0.03, 0.08, -0.05];
+    let samples = make_v_curve(12345.0, 100.0, &noise);
+    let fit = fit_focus_curve(&samples).unwrap();
+    assert!(f64::abs(fit.min_pos - 12345.0) < 20.0);
+    assert!(fit.r2 > 0.95);
+
+    // noisy curve with outlier (bad seeing or passing cloud)
+    let mut noise = noise;
+    noise[3] += 4.0;
+    let samples = make_v_curve(12345.0, 100.0, &noise);
+    let fit = fit_focus_curve(&samples).unwrap();
+    assert!(fit.rejected >= 1);
+    assert!(f64::abs(fit.min_pos - 12345.0) < 20.0);
+    assert!(fit.r2 > 0.95);
+
+    // pure noise
+    let samples = make_v_curve(1000.0, 10.0, &[0.0; 9])
+        .into_iter()
+        .enumerate()
+        .map(|(i, (x, _))| (x, if i % 2 == 0 { 3.0 } else { 3.5 }))
+        .collect::<Vec<_>>();
+    assert!(fit_focus_curve(&samples).is_none());
+
+    // inverted curve
+    let samples: Vec<_> = make_v_curve(500.0, 10.0, &[0.0; 7])
+        .into_iter()
+        .map(|(x, y)| (x, 10.0 - y))
+        .collect();
+    assert!(fit_focus_curve(&samples).is_none());
+
+    assert!(fit_focus_curve(&[(1.0, 2.0), (2.0, 1.0)]).is_none());
+}
+
+#[test]
+fn test_check_focus_curve_fit() {
+    // good curve is accepted
+    let samples = make_v_curve(30000.0, 500.0, &[0.0; 11]);
+    let fit = check_focus_curve_fit(fit_focus_curve(&samples), 0.8).unwrap();
+    assert!(f64::abs(fit.min_pos - 30000.0) < 1.0);
+
+    // curve has minimum but it is too noisy (bad seeing)
+    let noise: Vec<_> = (0..11).map(|i| if i % 2 == 0 { 1.2 } else { -1.2 }).collect();
+    let samples = make_v_curve(30000.0, 500.0, &noise);
+    let fit = fit_focus_curve(&samples).unwrap();
+    assert!(fit.coeffs.a2 > 0.0);
+    assert!(fit.r2 < 0.8);
+    assert!(check_focus_curve_fit(Some(fit), 0.8).is_err());
+
+    // no minimum
+    assert!(check_focus_curve_fit(None, 0.8).is_err());
+}
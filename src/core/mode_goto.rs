@@ -1,9 +1,8 @@
 use std::sync::{Arc, RwLock};
 use crate::{core::{consts::*, events::*, frame_processing::*}, image::{image::Image, info::LightFrameInfo, stars::Stars}, indi::{self, value_to_sexagesimal}, options::*, plate_solve::*, ui::sky_map::math::*};
-use super::{core::*, events::EventSubscriptions, utils::*};
+use super::{core::*, events::EventSubscriptions, mode_capture_platesolve::{calc_spiral_step, spiral_search_points}, utils::*};
 
 const MAX_MOUNT_UNPARK_TIME: usize = 20; // seconds
-const AFTER_GOTO_WAIT_TIME: usize = 3; // seconds
 
 #[derive(PartialEq)]
 enum State {
@@ -38,17 +37,25 @@ pub struct GotoMode {
     destination:     GotoDestination,
     config:          GotoConfig,
     eq_coord:        EqCoord,
+    mount_coord:     EqCoord, // coordinate sent to mount (corrected by pointing model)
     camera:          Option<DeviceAndProp>,
     cam_opts:        Option<CamOptions>,
     ps_opts:         PlateSolverOptions,
+    telescope:       TelescopeOptions,
     mount:           String,
     indi:            Arc<indi::Connection>,
     subscribers:     Arc<EventSubscriptions>,
     plate_solver:    Option<PlateSolver>,
     unpark_seconds:  usize,
     goto_seconds:    usize,
-    goto_ok_seconds: usize,
+    mount_opts:      MountOptions,
     extra_stages:    usize,
+    next_mode:       Option<ModeBox>,
+    options:         Arc<RwLock<Options>>,
+    corrections:     usize,
+    residual:        Option<f64>, // distance to destination after last platesolve, in radian
+    spiral_crd:      Option<EqCoord>, // spiral step position after failed platesolve
+    spiral_done:     bool,
 }
 
 impl GotoMode {
@@ -74,7 +81,7 @@ impl GotoMode {
                 &camera,
                 indi
             )?;
-            let plate_solver = PlateSolver::new(opts.plate_solver.solver);
+            let plate_solver = PlateSolver::new(&opts.plate_solver);
 
             (Some(camera), Some(cam_opts), Some(plate_solver))
         } else {
@@ -85,14 +92,22 @@ impl GotoMode {
             state:           State::None,
             config,
             eq_coord:        EqCoord::default(),
+            mount_coord:     EqCoord::default(),
             ps_opts:         opts.plate_solver.clone(),
+            telescope:       opts.telescope.clone(),
             mount:           opts.mount.device.clone(),
             indi:            Arc::clone(indi),
             subscribers:     Arc::clone(subscribers),
             unpark_seconds:  0,
             goto_seconds:    0,
-            goto_ok_seconds: 0,
+            mount_opts:      opts.mount.clone(),
             extra_stages:    0,
+            next_mode:       None,
+            options:         Arc::clone(options),
+            corrections:     0,
+            residual:        None,
+            spiral_crd:      None,
+            spiral_done:     false,
             plate_solver,
             destination,
             camera,
@@ -100,6 +115,10 @@ impl GotoMode {
         })
     }
 
+    pub fn set_next_mode(&mut self, next_mode: Option<ModeBox>) {
+        self.next_mode = next_mode;
+    }
+
     fn start_goto(&mut self) -> anyhow::Result<()> {
         if self.indi.mount_get_parked(&self.mount)? {
             self.start_unpark_telescope()?;
@@ -111,7 +130,7 @@ impl GotoMode {
     }
 
     fn start_unpark_telescope(&mut self) -> anyhow::Result<()> {
-        log::debug!("Unparking mount...");
+        log::info!("Mount {} is parked. Unparking it because goto requires slewing...", self.mount);
         self.indi.mount_set_parked(
             &self.mount,
             false,
@@ -129,6 +148,26 @@ impl GotoMode {
             indi::value_to_sexagesimal(self.eq_coord.ra, true, 9),
             indi::value_to_sexagesimal(self.eq_coord.dec, true, 8)
         );
+        self.mount_coord = self.eq_coord;
+        if self.mount_opts.use_pt_model {
+            let options = self.options.read().unwrap();
+            let corrected = options.mount
+                .pointing_model(&self.mount)
+                .and_then(|model| model.correct(&self.eq_coord));
+            drop(options);
+            if let Some(corrected) = corrected {
+                log::debug!(
+                    "Pointing model correction = {:.1}\", mount coordinate = {:?}",
+                    radian_to_degree(EqCoord::angle_between(&self.eq_coord, &corrected)) * 3600.0,
+                    corrected
+                );
+                self.mount_coord = corrected;
+            }
+        }
+        self.start_mount_move()
+    }
+
+    fn start_mount_move(&mut self) -> anyhow::Result<()> {
         self.indi.set_after_coord_set_action(
             &self.mount,
             indi::AfterCoordSetAction::Track,
@@ -138,16 +177,60 @@ impl GotoMode {
 
         self.indi.mount_set_eq_coord(
             &self.mount,
-            radian_to_hour(self.eq_coord.ra),
-            radian_to_degree(self.eq_coord.dec),
+            radian_to_hour(self.mount_coord.ra),
+            radian_to_degree(self.mount_coord.dec),
             true,
             None
         )?;
         self.goto_seconds = 0;
-        self.goto_ok_seconds = 0;
+        self.indi.mount_reset_coords_samples(&self.mount);
         Ok(())
     }
 
+    /// Moves mount one step of spiral around current position if platesolve
+    /// fails during centering. Returns `false` if the step is already done
+    fn start_spiral_step(&mut self) -> anyhow::Result<bool> {
+        if self.spiral_done {
+            return Ok(false);
+        }
+        let (Some(camera), Some(cam_opts)) = (&self.camera, &self.cam_opts) else {
+            return Ok(false);
+        };
+        let step = calc_spiral_step(&self.indi, camera, cam_opts, &self.telescope)?;
+        let points = spiral_search_points(&self.mount_coord, step, 1.5 * step);
+        let Some(crd) = points.first().copied() else {
+            return Ok(false);
+        };
+        log::info!("Platesolve failed. Moving mount by spiral step to {:?}", crd);
+        self.spiral_done = true;
+        self.spiral_crd = Some(crd);
+        self.mount_coord = crd;
+        self.start_mount_move()?;
+        self.state = State::Goto;
+        Ok(true)
+    }
+
+    /// Returns `true` if mount is close enough to destination
+    /// or max number of corrections is reached
+    fn is_centered(&self) -> bool {
+        let Some(residual) = self.residual else {
+            return false;
+        };
+        let residual_arcsec = radian_to_degree(residual) * 3600.0;
+        if residual_arcsec <= self.ps_opts.center_tol {
+            log::info!("Destination is reached after {} correction(s)", self.corrections);
+            return true;
+        }
+        if self.corrections >= self.ps_opts.center_iters {
+            log::warn!(
+                "Destination is not reached after {} correction(s). Residual = {:.1}\"",
+                self.corrections, residual_arcsec
+            );
+            return true;
+        }
+        false
+    }
+
     fn start_take_picture(&mut self) -> anyhow::Result<()> {
         let cam_opts = self.cam_opts.as_ref().unwrap();
         let camera = self.camera.as_ref().unwrap();
@@ -161,7 +244,7 @@ impl GotoMode {
         let plate_solver = self.plate_solver.as_mut().unwrap();
         let image = image.read().unwrap();
         let mut config = PlateSolveConfig::default();
-        config.eq_coord = Some(self.eq_coord.clone());
+        config.eq_coord = Some(self.spiral_crd.unwrap_or(self.eq_coord));
         config.time_out = self.ps_opts.timeout;
         config.blind_time_out = self.ps_opts.blind_timeout;
         plate_solver.start(&PlateSolverInData::Image(&image), &config)?;
@@ -177,7 +260,7 @@ impl GotoMode {
     ) -> anyhow::Result<()> {
         let plate_solver = self.plate_solver.as_mut().unwrap();
         let mut config = PlateSolveConfig::default();
-        config.eq_coord = Some(self.eq_coord.clone());
+        config.eq_coord = Some(self.spiral_crd.unwrap_or(self.eq_coord));
         config.time_out = self.ps_opts.timeout;
         config.blind_time_out = self.ps_opts.blind_timeout;
         let stars_arg = PlateSolverInData::Stars{
@@ -189,17 +272,28 @@ impl GotoMode {
         Ok(())
     }
 
+    fn correction_action(&self) -> ProcessPlateSolverResultAction {
+        if self.mount_opts.use_pt_model {
+            ProcessPlateSolverResultAction::AddModelPoint
+        } else {
+            ProcessPlateSolverResultAction::Sync
+        }
+    }
+
     fn try_process_plate_solving_result(
         &mut self,
         action: ProcessPlateSolverResultAction,
     ) -> anyhow::Result<bool> {
         let plate_solver = self.plate_solver.as_mut().unwrap();
-        let camera = self.camera.as_ref().unwrap();
-
         let result = match plate_solver.get_result()? {
             PlateSolveResult::Waiting => return Ok(false),
             PlateSolveResult::Done(result) => result,
-            PlateSolveResult::Failed => anyhow::bail!("Can't platesolve image")
+            PlateSolveResult::Failed => {
+                if self.state != State::ImagePlateSolving && self.start_spiral_step()? {
+                    return Ok(false);
+                }
+                anyhow::bail!("Can't platesolve image")
+            }
         };
 
         log::debug!(
@@ -212,6 +306,7 @@ impl GotoMode {
             radian_to_degree(result.height),
         );
 
+        let camera = self.camera.as_ref().unwrap();
         let event = PlateSolverEvent {
             cam_name: camera.name.clone(),
             result: result.clone(),
@@ -220,6 +315,15 @@ impl GotoMode {
             Event::PlateSolve(event)
         );
 
+        if self.state != State::ImagePlateSolving {
+            let residual = EqCoord::angle_between(&self.eq_coord, &result.crd_now);
+            log::info!(
+                "Residual distance to destination after goto = {:.1}\"",
+                radian_to_degree(residual) * 3600.0
+            );
+            self.residual = Some(residual);
+        }
+
         match action {
             ProcessPlateSolverResultAction::Sync => {
                 self.indi.set_after_coord_set_action(
@@ -237,6 +341,15 @@ impl GotoMode {
                     INDI_SET_PROP_TIMEOUT
                 )?;
             }
+            ProcessPlateSolverResultAction::AddModelPoint => {
+                let mut options = self.options.write().unwrap();
+                let model = options.mount.pointing_model_mut(&self.mount);
+                model.add_point(&self.mount_coord, &result.crd_now);
+                log::info!(
+                    "Point is added into pointing model of {}. Total points: {}",
+                    self.mount, model.points.len()
+                );
+            }
             ProcessPlateSolverResultAction::SetEqCoord => {
                 self.eq_coord = result.crd_now.clone();
             }
@@ -263,8 +376,16 @@ impl Mode for GotoMode {
                 "Tacking picture".to_string(),
             State::PlateSolving =>
                 "Plate solving".to_string(),
-            State::CorrectMount =>
-                "Mount correction".to_string(),
+            State::CorrectMount => {
+                let mut result = format!(
+                    "Mount correction {}/{}",
+                    self.corrections, self.ps_opts.center_iters
+                );
+                if let Some(residual) = self.residual {
+                    result += &format!(" (residual {:.1}\")", radian_to_degree(residual) * 3600.0);
+                }
+                result
+            }
             State::TackingFinalPicture =>
                 "Tacking final picture".to_string(),
             State::FinalPlateSolving =>
@@ -312,11 +433,24 @@ impl Mode for GotoMode {
         }
     }
 
+    fn used_devices(&self) -> Vec<&str> {
+        let mut result = vec![self.mount.as_str()];
+        if let Some(camera) = self.cam_device() {
+            result.push(&camera.name);
+        }
+        result
+    }
+
     fn get_cur_exposure(&self) -> Option<f64> {
         self.cam_opts.as_ref().map(|cam_opts| cam_opts.frame.exposure())
     }
 
     fn start(&mut self) -> anyhow::Result<()> {
+        self.corrections = 0;
+        self.residual = None;
+        self.spiral_crd = None;
+        self.spiral_done = false;
+
         match &self.destination {
             GotoDestination::Coord(coord) => {
                 self.extra_stages = 0;
@@ -362,6 +496,10 @@ impl Mode for GotoMode {
         Ok(())
     }
 
+    fn take_next_mode(&mut self) -> Option<ModeBox> {
+        self.next_mode.take()
+    }
+
     fn notify_timer_1s(&mut self) -> anyhow::Result<NotifyResult> {
         match self.state {
             State::Unparking => {
@@ -382,17 +520,21 @@ impl Mode for GotoMode {
             State::Goto | State::CorrectMount => {
                 let crd_prop_state = self.indi.mount_get_eq_coord_prop_state(&self.mount)?;
                 if crd_prop_state == indi::PropState::Ok {
-                    self.goto_ok_seconds += 1;
-                    if self.goto_ok_seconds >= AFTER_GOTO_WAIT_TIME {
+                    let is_settled = self.indi.mount_coords_are_stable(
+                        &self.mount,
+                        self.mount_opts.settle_tol,
+                        self.mount_opts.settle_samples
+                    )?;
+                    if is_settled {
                         check_telescope_is_at_desired_position(
                             &self.indi,
                             &self.mount,
-                            &self.eq_coord,
+                            &self.mount_coord,
                             0.5
                         )?;
                         if self.state == State::Goto {
                             if self.config == GotoConfig::OnlyGoto {
-                                return Ok(NotifyResult::Finished { next_mode: None });
+                                return Ok(NotifyResult::Finished { next_mode: self.next_mode.take() });
                             }
                             self.start_take_picture()?;
                             self.state = State::TackingPicture;
@@ -420,24 +562,23 @@ impl Mode for GotoMode {
                 }
             }
 
-            State::PlateSolving => {
+            State::PlateSolving | State::FinalPlateSolving => {
                 let ok = self.try_process_plate_solving_result(
-                    ProcessPlateSolverResultAction::Sync
+                    self.correction_action()
                 )?;
                 if ok {
+                    self.spiral_crd = None;
+                    if self.is_centered() {
+                        self.state = State::Finished;
+                        return Ok(NotifyResult::Finished { next_mode: self.next_mode.take() })
+                    }
+                    self.corrections += 1;
                     self.start_goto_coord()?;
                     self.state = State::CorrectMount;
                     return Ok(NotifyResult::ProgressChanges)
                 }
-            }
-
-            State::FinalPlateSolving => {
-                let ok = self.try_process_plate_solving_result(
-                    ProcessPlateSolverResultAction::Sync
-                )?;
-                if ok {
-                    self.state = State::Finished;
-                    return Ok(NotifyResult::Finished { next_mode: None })
+                if !matches!(self.state, State::PlateSolving|State::FinalPlateSolving) {
+                    return Ok(NotifyResult::ProgressChanges);
                 }
             }
 
@@ -483,5 +624,6 @@ impl Mode for GotoMode {
 
 enum ProcessPlateSolverResultAction {
     Sync,
+    AddModelPoint,
     SetEqCoord,
 }
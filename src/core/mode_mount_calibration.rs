@@ -1,4 +1,4 @@
-use std::{sync::{Arc, RwLock}, f64::consts::PI};
+use std::{sync::{Arc, RwLock}, f64::consts::PI, hash::{Hash, Hasher}, collections::hash_map::DefaultHasher};
 use itertools::Itertools;
 use crate::{
     image::{info::*, stars::*, stars_offset::*}, indi, options::*, utils::math::*
@@ -8,6 +8,10 @@ use super::{consts::INDI_SET_PROP_TIMEOUT, core::*, events::*, frame_processing:
 pub const DITHER_CALIBR_ATTEMPTS_CNT: usize = 11;
 pub const DITHER_CALIBR_SPEED: f64 = 1.0;
 
+const BAD_FRAMES_TO_INCREASE: usize = 2; // bad frames in a row before increasing exposure or gain
+const MAX_BAD_FRAMES_CNT: usize = 8; // bad frames in a row before aborting calibration
+const MAX_EXPOSURE_MULT: f64 = 4.0; // limit for exposure increasing
+
 #[derive(Debug, Default, Clone)]
 pub struct MountMoveCalibrRes {
     move_x_ra: f64,
@@ -18,10 +22,18 @@ pub struct MountMoveCalibrRes {
 
 impl MountMoveCalibrRes {
     pub fn is_ok(&self) -> bool {
-        self.move_x_ra != 0.0 ||
-        self.move_y_ra != 0.0 ||
-        self.move_x_dec != 0.0 ||
-        self.move_y_dec != 0.0
+        let all_finite =
+            self.move_x_ra.is_finite() &&
+            self.move_y_ra.is_finite() &&
+            self.move_x_dec.is_finite() &&
+            self.move_y_dec.is_finite();
+
+        all_finite && (
+            self.move_x_ra != 0.0 ||
+            self.move_y_ra != 0.0 ||
+            self.move_x_dec != 0.0 ||
+            self.move_y_dec != 0.0
+        )
     }
 
     pub fn calc(&self, x0: f64, y0: f64) -> Option<(f64, f64)> {
@@ -37,10 +49,81 @@ impl MountMoveCalibrRes {
         let t_dec = calc_t(self.move_x_dec, self.move_y_dec, self.move_x_ra, self.move_y_ra)?;
         Some((t_ra, t_dec))
     }
+
+    pub fn from_saved(saved: &SavedMountCalibr) -> Self {
+        Self {
+            move_x_ra:  saved.move_x_ra,
+            move_y_ra:  saved.move_y_ra,
+            move_x_dec: saved.move_x_dec,
+            move_y_dec: saved.move_y_dec,
+        }
+    }
+
+    pub fn to_saved(&self, key: u64) -> SavedMountCalibr {
+        SavedMountCalibr {
+            key,
+            move_x_ra:  self.move_x_ra,
+            move_y_ra:  self.move_y_ra,
+            move_x_dec: self.move_x_dec,
+            move_y_dec: self.move_y_dec,
+        }
+    }
+
+    /// Distances along RA and DEC axes (in pixels) for offset of image
+    pub fn calc_axes_distance(&self, x0: f64, y0: f64) -> Option<(f64, f64)> {
+        let (t_ra, t_dec) = self.calc(x0, y0)?;
+        let ra_speed = f64::hypot(self.move_x_ra, self.move_y_ra);
+        let dec_speed = f64::hypot(self.move_x_dec, self.move_y_dec);
+        Some((t_ra * ra_speed, t_dec * dec_speed))
+    }
+}
+
+/// Key for saved mount calibration. Calibration is in pixels of binned
+/// image so it depends on mount, camera, focal length, pixel size and binning
+pub fn mount_calibr_key(
+    indi:    &indi::Connection,
+    options: &Options,
+    binning: Binning,
+) -> Option<u64> {
+    let camera = options.cam.device.as_ref()?;
+    if options.mount.device.is_empty() {
+        return None;
+    }
+    let cam_ccd = indi::CamCcd::from_ccd_prop_name(&camera.prop);
+    let (pix_size_x, pix_size_y) = indi.camera_get_pixel_size_um(&camera.name, cam_ccd).ok()?;
+    let mut hasher = DefaultHasher::new();
+    options.mount.device.hash(&mut hasher);
+    camera.name.hash(&mut hasher);
+    camera.prop.hash(&mut hasher);
+    options.telescope.real_focal_length().to_bits().hash(&mut hasher);
+    pix_size_x.to_bits().hash(&mut hasher);
+    pix_size_y.to_bits().hash(&mut hasher);
+    binning.to_str().hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+/// Saved mount calibration if it is valid for current equipment
+pub fn get_saved_mount_calibr(
+    indi:    &indi::Connection,
+    options: &Options,
+    binning: Binning,
+) -> Option<MountMoveCalibrRes> {
+    let saved = options.guiding.main_cam.saved_calibr.as_ref()?;
+    let key = mount_calibr_key(indi, options, binning)?;
+    if saved.key != key {
+        return None;
+    }
+    let result = MountMoveCalibrRes::from_saved(saved);
+    if !result.is_ok() {
+        return None;
+    }
+    Some(result)
 }
 
 pub struct MountCalibrMode {
     indi:              Arc<indi::Connection>,
+    options:           Arc<RwLock<Options>>,
+    subscribers:       Arc<EventSubscriptions>,
     state:             DitherCalibrState,
     axis:              DitherCalibrAxis,
     cam_opts:          CamOptions,
@@ -55,15 +138,17 @@ pub struct MountCalibrMode {
     cur_timed_guide_s: f64,
     cur_timed_guide_w: f64,
     cur_timed_guide_e: f64,
-    cur_ra:            f64,
-    cur_dec:           f64,
+    mount_opts:        MountOptions,
     image_width:       usize,
     image_height:      usize,
     move_period:       f64,
     result:            MountMoveCalibrRes,
     next_mode:         Option<Box<dyn Mode + Sync + Send>>,
     can_change_g_rate: bool,
+    orig_guide_rate:   Option<(f64, f64)>, // to restore after calibration
     calibr_speed:      f64,
+    bad_frames_cnt:    usize,
+    orig_exposure:     f64,
 }
 
 #[derive(PartialEq)]
@@ -87,9 +172,10 @@ struct DitherCalibrAtempt {
 
 impl MountCalibrMode {
     pub fn new(
-        indi:      &Arc<indi::Connection>,
-        options:   &Arc<RwLock<Options>>,
-        next_mode: Option<Box<dyn Mode + Sync + Send>>,
+        indi:        &Arc<indi::Connection>,
+        options:     &Arc<RwLock<Options>>,
+        subscribers: &Arc<EventSubscriptions>,
+        next_mode:   Option<Box<dyn Mode + Sync + Send>>,
     ) -> anyhow::Result<Self> {
         let opts = options.read().unwrap();
         let Some(cam_device) = &opts.cam.device else {
@@ -104,8 +190,11 @@ impl MountCalibrMode {
             &cam_device,
             indi
         )?;
+        let orig_exposure = cam_opts.frame.exp_main;
         Ok(Self {
             indi:              Arc::clone(indi),
+            options:           Arc::clone(options),
+            subscribers:       Arc::clone(subscribers),
             state:             DitherCalibrState::Undefined,
             axis:              DitherCalibrAxis::Undefined,
             cam_opts,
@@ -120,15 +209,17 @@ impl MountCalibrMode {
             cur_timed_guide_s: 0.0,
             cur_timed_guide_w: 0.0,
             cur_timed_guide_e: 0.0,
-            cur_ra:            0.0,
-            cur_dec:           0.0,
+            mount_opts:        opts.mount.clone(),
             image_width:       0,
             image_height:      0,
             move_period:       0.0,
             result:            MountMoveCalibrRes::default(),
             next_mode,
             can_change_g_rate: false,
+            orig_guide_rate:   None,
             calibr_speed:      0.0,
+            bad_frames_cnt:    0,
+            orig_exposure,
         })
     }
 
@@ -141,6 +232,10 @@ impl MountCalibrMode {
             self.indi.mount_get_guide_rate_prop_data(&self.mount_device)?.permition == indi::PropPermition::RW;
 
         if self.can_change_g_rate {
+            // Guide rate is already changed if it is not first axis
+            if self.orig_guide_rate.is_none() {
+                self.orig_guide_rate = Some(self.indi.mount_get_guide_rate(&self.mount_device)?);
+            }
             self.calibr_speed = DITHER_CALIBR_SPEED;
         } else if guid_rate_supported {
             self.calibr_speed = self.indi.mount_get_guide_rate(&self.mount_device)?.0;
@@ -181,7 +276,13 @@ impl MountCalibrMode {
             }
         }
 
-        // TODO: check result is not empty
+        // Original coordinates are restored in `abort` after error
+        if result.is_empty() {
+            anyhow::bail!(
+                "Mount calibration failed: no stars detected or matched \
+                between frames, check focus and exposure"
+            );
+        }
 
         let dist_max = result.iter().map(|r|r.dist).max_by(cmp_f64).unwrap_or(0.0);
         let min_dist = 0.5 * dist_max;
@@ -209,9 +310,14 @@ impl MountCalibrMode {
             DitherCalibrAxis::Dec => {
                 self.result.move_x_dec = move_x;
                 self.result.move_y_dec = move_y;
+                if !self.result.is_ok() {
+                    anyhow::bail!("Mount calibration failed: mount doesn't move or stars are not detected");
+                }
                 if let Some(next_mode) = &mut self.next_mode {
                     next_mode.set_or_correct_value(&mut self.result);
                 }
+                self.save_result();
+                self.restore_guide_rate()?;
                 self.restore_orig_coords()?;
                 self.state = DitherCalibrState::WaitForOrigCoords;
             }
@@ -220,6 +326,30 @@ impl MountCalibrMode {
         Ok(())
     }
 
+    fn save_result(&self) {
+        let mut options = self.options.write().unwrap();
+        let key = mount_calibr_key(&self.indi, &options, self.cam_opts.frame.binning);
+        options.guiding.main_cam.saved_calibr = key.map(|key| self.result.to_saved(key));
+        if options.guiding.main_cam.saved_calibr.is_some() {
+            log::info!("Mount calibration is saved: {:?}", self.result);
+        }
+    }
+
+    fn restore_guide_rate(&mut self) -> anyhow::Result<()> {
+        let Some((rate_ns, rate_we)) = self.orig_guide_rate.take() else {
+            return Ok(());
+        };
+        self.indi.mount_set_guide_rate(
+            &self.mount_device,
+            rate_ns,
+            rate_we,
+            true,
+            INDI_SET_PROP_TIMEOUT
+        )?;
+        log::info!("Guide rate of mount is restored to NS={}, WE={}", rate_ns, rate_we);
+        Ok(())
+    }
+
     fn restore_orig_coords(&self) -> anyhow::Result<()> {
         self.indi.set_after_coord_set_action(
             &self.mount_device,
@@ -235,6 +365,7 @@ impl MountCalibrMode {
             true,
             None
         )?;
+        self.indi.mount_reset_coords_samples(&self.mount_device);
         Ok(())
     }
 
@@ -244,6 +375,7 @@ impl MountCalibrMode {
     ) -> anyhow::Result<NotifyResult> {
         let mut result = NotifyResult::Empty;
         if info.stars.fwhm_is_ok && info.stars.ovality_is_ok {
+            self.bad_frames_cnt = 0;
             if self.image_width == 0 || self.image_height == 0 {
                 self.image_width = info.width;
                 self.image_height = info.height;
@@ -293,10 +425,51 @@ impl MountCalibrMode {
                 self.state = DitherCalibrState::WaitForSlew;
             }
         } else {
-            apply_camera_options_and_take_shot(&self.indi, &self.camera, &self.cam_opts.frame)?;
+            self.process_bad_frame()?;
         }
         Ok(result)
     }
+
+    /// Increases exposure (or gain if exposure is already maximal)
+    /// when there are no good stars in several frames in a row
+    fn process_bad_frame(&mut self) -> anyhow::Result<()> {
+        self.bad_frames_cnt += 1;
+        if self.bad_frames_cnt >= MAX_BAD_FRAMES_CNT {
+            anyhow::bail!(
+                "Mount calibration failed: no good stars in {} frames.\n\
+                Check focus or increase exposure and gain for calibration",
+                self.bad_frames_cnt
+            );
+        }
+
+        if self.bad_frames_cnt % BAD_FRAMES_TO_INCREASE == 0 {
+            let frame = &mut self.cam_opts.frame;
+            let max_exposure = self.orig_exposure * MAX_EXPOSURE_MULT;
+            let mut warning = None;
+            if frame.exp_main < max_exposure {
+                frame.exp_main = f64::min(2.0 * frame.exp_main, max_exposure);
+                warning = Some(format!(
+                    "Not enough stars for mount calibration. Exposure is increased to {:.1}s",
+                    frame.exp_main
+                ));
+            } else if let Ok(gain_prop) = self.indi.camera_get_gain_prop_value(&self.camera.name) {
+                if frame.gain < gain_prop.max {
+                    frame.gain = f64::min(frame.gain + 0.5 * (gain_prop.max - frame.gain), gain_prop.max);
+                    warning = Some(format!(
+                        "Not enough stars for mount calibration. Gain is increased to {:.0}",
+                        frame.gain
+                    ));
+                }
+            }
+            if let Some(warning) = warning {
+                log::warn!("{}", warning);
+                self.subscribers.notify(Event::Warning(warning));
+            }
+        }
+
+        apply_camera_options_and_take_shot(&self.indi, &self.camera, &self.cam_opts.frame)?;
+        Ok(())
+    }
 }
 
 impl Mode for MountCalibrMode {
@@ -305,17 +478,23 @@ impl Mode for MountCalibrMode {
     }
 
     fn progress_string(&self) -> String {
+        let caption = if self.next_mode.is_some() {
+            "Calibrating before guiding"
+        } else {
+            "Mount calibration"
+        };
         match self.axis {
             DitherCalibrAxis::Undefined =>
-                "Mount calibration".to_string(),
+                caption.to_string(),
             DitherCalibrAxis::Ra =>
-                "Mount calibration (RA)".to_string(),
+                format!("{} (RA)", caption),
             DitherCalibrAxis::Dec =>
-                "Mount calibration (DEC)".to_string(),
+                format!("{} (DEC)", caption),
         }
     }
 
     fn abort(&mut self) -> anyhow::Result<()> {
+        self.restore_guide_rate()?;
         self.restore_orig_coords()?;
         Ok(())
     }
@@ -328,6 +507,10 @@ impl Mode for MountCalibrMode {
         Some(&self.camera)
     }
 
+    fn used_devices(&self) -> Vec<&str> {
+        vec![&self.camera.name, &self.mount_device]
+    }
+
     fn get_cur_exposure(&self) -> Option<f64> {
         Some(self.cam_opts.frame.exposure())
     }
@@ -359,14 +542,37 @@ impl Mode for MountCalibrMode {
         }
     }
 
+    fn notify_timer_1s(&mut self) -> anyhow::Result<NotifyResult> {
+        if self.state != DitherCalibrState::WaitForOrigCoords {
+            return Ok(NotifyResult::Empty);
+        }
+        let state_is_ok = self.indi.mount_get_eq_coord_prop_state(&self.mount_device)? == indi::PropState::Ok;
+        let (cur_ra, cur_dec) = self.indi.mount_get_eq_ra_and_dec(&self.mount_device)?;
+        let coord_is_near =
+            f64::abs(cur_ra-self.start_ra) < 0.001
+            && f64::abs(cur_dec-self.start_dec) < 0.001;
+        if !state_is_ok && !coord_is_near {
+            return Ok(NotifyResult::Empty);
+        }
+        let is_settled = self.indi.mount_coords_are_stable(
+            &self.mount_device,
+            self.mount_opts.settle_tol,
+            self.mount_opts.settle_samples
+        )?;
+        if !is_settled {
+            return Ok(NotifyResult::Empty);
+        }
+        Ok(NotifyResult::Finished {
+            next_mode: self.next_mode.take()
+        })
+    }
+
     fn notify_indi_prop_change(
         &mut self,
         prop_change: &indi::PropChangeEvent
     ) -> anyhow::Result<NotifyResult> {
-        let mut result = NotifyResult::Empty;
-
         if *prop_change.device_name != self.mount_device {
-            return Ok(result);
+            return Ok(NotifyResult::Empty);
         }
         match self.state {
             DitherCalibrState::WaitForSlew => {
@@ -389,29 +595,8 @@ impl Mode for MountCalibrMode {
                 }
             }
 
-            DitherCalibrState::WaitForOrigCoords => {
-                if let ("EQUATORIAL_EOD_COORD", indi::PropChange::Change { value, new_state, .. })
-                = (prop_change.prop_name.as_str(), &prop_change.change) {
-                    match value.elem_name.as_str() {
-                        "RA" => self.cur_ra = value.prop_value.to_f64()?,
-                        "DEC" => self.cur_dec = value.prop_value.to_f64()?,
-                        _ => {},
-                    }
-                    let state_is_ok = *new_state == indi::PropState::Ok;
-                    let coord_is_near =
-                        f64::abs(self.cur_ra-self.start_ra) < 0.001
-                        && f64::abs(self.cur_dec-self.start_dec) < 0.001;
-                    if state_is_ok || coord_is_near {
-                        // TODO: add delay before got next mode
-                        result = NotifyResult::Finished {
-                            next_mode: self.next_mode.take()
-                        };
-                    }
-                }
-            }
-
             _ => {},
         }
-        Ok(result)
+        Ok(NotifyResult::Empty)
     }
 }
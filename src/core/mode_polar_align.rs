@@ -18,6 +18,16 @@ struct PolarAlignment {
     measurements: Vec<PolarAlignmentMeasure>,
     pole:         Option<HorizCoord>,
     mount_pole:   Option<HorizCoord>,
+    latitude:     f64, // in radians
+    longitude:    f64, // in radians
+}
+
+/// Angle difference in range -PI..PI
+fn angle_diff(a1: f64, a2: f64) -> f64 {
+    let mut diff = (a1 - a2) % (2.0 * PI);
+    if diff > PI { diff -= 2.0 * PI; }
+    if diff < -PI { diff += 2.0 * PI; }
+    diff
 }
 
 impl PolarAlignment {
@@ -26,6 +36,8 @@ impl PolarAlignment {
             measurements: Vec::new(),
             pole:         None,
             mount_pole:   None,
+            latitude:     0.0,
+            longitude:    0.0,
         }
     }
 
@@ -38,6 +50,8 @@ impl PolarAlignment {
 
         let latitude = degree_to_radian(latitude);
         let longitude = degree_to_radian(longitude);
+        self.latitude = latitude;
+        self.longitude = longitude;
 
         let horiz_crd = |m: &PolarAlignmentMeasure| -> HorizCoord {
             let cvt = EqToSphereCvt::new(longitude, latitude, &m.utc_time);
@@ -51,30 +65,91 @@ impl PolarAlignment {
         let vec1 = &pt2 - &pt1;
         let vec2 = &pt3 - &pt2;
 
+        // Visible pole is the south one in southern hemisphere
+        let pole_dec = if latitude < 0.0 { -0.5 * PI } else { 0.5 * PI };
+        let cvt = EqToSphereCvt::new(longitude, latitude, &Utc::now().naive_utc());
+        let celestial_pole_crd = cvt.eq_to_sphere(&EqCoord { ra: 0.0, dec: pole_dec });
+        let celestial_pole = HorizCoord::from_sphere_pt(&celestial_pole_crd);
+
+        // Direction of rotation axis depends on direction of mount
+        // movement and hemisphere. Take one pointing to visible pole
         let mut mount_pole_crd = &vec1 * &vec2;
         mount_pole_crd.normalize();
+        let dot =
+            mount_pole_crd.x * celestial_pole_crd.x +
+            mount_pole_crd.y * celestial_pole_crd.y +
+            mount_pole_crd.z * celestial_pole_crd.z;
+        if dot < 0.0 {
+            mount_pole_crd.x = -mount_pole_crd.x;
+            mount_pole_crd.y = -mount_pole_crd.y;
+            mount_pole_crd.z = -mount_pole_crd.z;
+        }
         let mount_pole = HorizCoord::from_sphere_pt(&mount_pole_crd);
 
-        let cvt = EqToSphereCvt::new(longitude, latitude, &Utc::now().naive_utc());
-        let celestial_pole = HorizCoord::from_sphere_pt(&cvt.eq_to_sphere(&EqCoord { ra: 0.0, dec: 0.5 * PI }));
-
         self.pole = Some(celestial_pole);
         self.mount_pole = Some(mount_pole);
     }
 
+    /// Corrects mount pole by shift of last measured point during
+    /// adjusting of mount with alt and az knobs. Mount doesn't rotate
+    /// around polar axis at this time so shift of pointing is
+    /// approximately equal to shift of mount pole
+    fn refine_mount_pole(&mut self, coord: &EqCoord) {
+        let (Some(mount_pole), Some(last)) = (&mut self.mount_pole, self.measurements.last()) else {
+            return;
+        };
+        // Same time for both points to exclude rotation of sky
+        let cvt = EqToSphereCvt::new(self.longitude, self.latitude, &Utc::now().naive_utc());
+        let last_crd = HorizCoord::from_sphere_pt(&cvt.eq_to_sphere(&last.coord));
+        let new_crd = HorizCoord::from_sphere_pt(&cvt.eq_to_sphere(coord));
+        mount_pole.alt += new_crd.alt - last_crd.alt;
+        mount_pole.az += angle_diff(new_crd.az, last_crd.az);
+        self.measurements.last_mut().unwrap().coord = *coord;
+    }
+
     fn pole_error(&self) -> Option<HorizCoord> {
         let (Some(pole), Some(mnt_pole)) = (&self.pole, &self.mount_pole) else { return None; };
 
         Some(HorizCoord {
             alt: mnt_pole.alt - pole.alt,
-            az: mnt_pole.az - pole.az,
+            az: angle_diff(mnt_pole.az, pole.az),
         })
     }
 }
 
-///////////////////////////////////////////////////////////////////////////////
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PolarAlignAdjDir {
+    Up,
+    Down,
+    Left,
+    Right,
+}
 
-const AFTER_GOTO_WAIT_TIME: usize = 3; // seconds
+/// Required adjustment of mount polar axis
+#[derive(Clone)]
+pub struct PolarAlignResult {
+    pub error:      HorizCoord, // mount pole minus celestial pole
+    pub alt_arcmin: f64,
+    pub az_arcmin:  f64,
+    pub alt_dir:    PolarAlignAdjDir,
+    pub az_dir:     PolarAlignAdjDir,
+}
+
+impl PolarAlignResult {
+    fn from_error(error: HorizCoord) -> Self {
+        // Azimuth grows to the right when looking at visible
+        // pole in both hemispheres so no sign change for it
+        Self {
+            alt_arcmin: f64::abs(radian_to_degree(error.alt) * 60.0),
+            az_arcmin:  f64::abs(radian_to_degree(error.az) * 60.0),
+            alt_dir:    if error.alt < 0.0 { PolarAlignAdjDir::Up } else { PolarAlignAdjDir::Down },
+            az_dir:     if error.az < 0.0 { PolarAlignAdjDir::Right } else { PolarAlignAdjDir::Left },
+            error,
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////
 
 enum State {
     Undefined,
@@ -93,7 +168,7 @@ enum Step {
 
 #[derive(Clone)]
 pub enum PolarAlignmentEvent {
-    Error(HorizCoord),
+    Result(PolarAlignResult),
 }
 
 pub struct PolarAlignMode {
@@ -110,7 +185,7 @@ pub struct PolarAlignMode {
     ps_opts:      PlateSolverOptions,
     plate_solver: PlateSolver,
     goto_time:    usize,
-    goto_ok_cnt:  usize,
+    mount_opts:   MountOptions,
     goto_pos:     EqCoord,
     alignment:    PolarAlignment,
 }
@@ -137,7 +212,7 @@ impl PolarAlignMode {
             indi
         )?;
 
-        let plate_solver = PlateSolver::new(opts.plate_solver.solver);
+        let plate_solver = PlateSolver::new(&opts.plate_solver);
 
         Ok(Self{
             state:       State::Undefined,
@@ -152,7 +227,7 @@ impl PolarAlignMode {
             ps_opts:     opts.plate_solver.clone(),
             alignment:   PolarAlignment::new(),
             goto_time:   0,
-            goto_ok_cnt: 0,
+            mount_opts:  opts.mount.clone(),
             goto_pos:    Default::default(),
             cam_opts,
             plate_solver
@@ -245,6 +320,8 @@ impl PolarAlignMode {
                 self.state = State::Capture;
             }
             Step::Corr => {
+                self.alignment.refine_mount_pole(&result.crd_now);
+                self.notify_error()?;
                 self.start_capture()?;
                 self.state = State::Capture;
             }
@@ -281,7 +358,7 @@ impl PolarAlignMode {
             None
         )?;
         self.goto_time = 0;
-        self.goto_ok_cnt = 0;
+        self.indi.mount_reset_coords_samples(&self.mount);
         Ok(())
     }
 
@@ -289,8 +366,14 @@ impl PolarAlignMode {
         let Some(error) = self.alignment.pole_error() else {
             anyhow::bail!("Mount pole is not calculated!");
         };
-        self.subscribers.notify(Event::PolarAlignment(PolarAlignmentEvent::Error(
-            error
+        let result = PolarAlignResult::from_error(error);
+        log::info!(
+            "Polar alignment error: alt = {:.1}' ({:?}), az = {:.1}' ({:?})",
+            result.alt_arcmin, result.alt_dir,
+            result.az_arcmin, result.az_dir
+        );
+        self.subscribers.notify(Event::PolarAlignment(PolarAlignmentEvent::Result(
+            result
         )));
         Ok(())
     }
@@ -339,6 +422,10 @@ impl Mode for PolarAlignMode {
         Some(&self.camera)
     }
 
+    fn used_devices(&self) -> Vec<&str> {
+        vec![&self.camera.name, &self.mount]
+    }
+
     fn get_cur_exposure(&self) -> Option<f64> {
         Some(self.cam_opts.frame.exp_main)
     }
@@ -386,8 +473,12 @@ impl Mode for PolarAlignMode {
 
             State::Goto => {
                 if self.indi.mount_get_eq_coord_prop_state(&self.mount)? == indi::PropState::Ok {
-                    self.goto_ok_cnt += 1;
-                    if self.goto_ok_cnt >= AFTER_GOTO_WAIT_TIME {
+                    let is_settled = self.indi.mount_coords_are_stable(
+                        &self.mount,
+                        self.mount_opts.settle_tol,
+                        self.mount_opts.settle_samples
+                    )?;
+                    if is_settled {
                         check_telescope_is_at_desired_position(
                             &self.indi,
                             &self.mount,
@@ -0,0 +1,276 @@
+use std::{path::Path, sync::{Arc, RwLock}};
+use crate::{indi, options::*};
+use super::{core::*, events::Progress};
+
+const MAX_FILTER_CHANGE_TIME: usize = 60; // seconds
+
+/// Loads sequence steps from CSV or text file. Every line
+/// contains filter slot (empty for no filter change), frames count,
+/// exposure (seconds) and optionally gain and binning (1..4)
+/// separated by comma, semicolon or tab.
+/// Lines beginning with `#` and header line are ignored
+pub fn load_sequence_from_file(file_name: &Path) -> anyhow::Result<Vec<SequenceStep>> {
+    let text = std::fs::read_to_string(file_name)?;
+    parse_sequence(&text)
+}
+
+fn parse_sequence(text: &str) -> anyhow::Result<Vec<SequenceStep>> {
+    let mut result = Vec::new();
+    let mut first_line = true;
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let is_first_line = first_line;
+        first_line = false;
+
+        let line_num = idx + 1;
+        let separator = if line.contains(';') {
+            ';'
+        } else if line.contains('\t') {
+            '\t'
+        } else {
+            ','
+        };
+        let fields: Vec<_> = line.split(separator).map(str::trim).collect();
+        if fields.len() < 3 {
+            anyhow::bail!(
+                "Line {}: filter slot, count and exposure expected",
+                line_num
+            );
+        }
+
+        let count = fields[1].parse::<usize>().ok().filter(|v| *v != 0);
+        if is_first_line && count.is_none() {
+            // header
+            continue;
+        }
+        let filter_slot = if !fields[0].is_empty() {
+            let slot = fields[0].parse::<usize>().ok().filter(|v| *v != 0);
+            let Some(slot) = slot else {
+                anyhow::bail!("Line {}: wrong filter slot {}", line_num, fields[0]);
+            };
+            Some(slot)
+        } else {
+            None
+        };
+        let Some(count) = count else {
+            anyhow::bail!("Line {}: wrong frames count {}", line_num, fields[1]);
+        };
+        let exposure = fields[2].replace(',', ".").parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite() && *v > 0.0);
+        let Some(exposure) = exposure else {
+            anyhow::bail!("Line {}: wrong exposure value {}", line_num, fields[2]);
+        };
+        let gain_str = fields.get(3).copied().unwrap_or_default();
+        let gain = if !gain_str.is_empty() {
+            let gain = gain_str.replace(',', ".").parse::<f64>()
+                .ok()
+                .filter(|v| v.is_finite() && *v >= 0.0);
+            let Some(gain) = gain else {
+                anyhow::bail!("Line {}: wrong gain value {}", line_num, gain_str);
+            };
+            Some(gain)
+        } else {
+            None
+        };
+        let bin_str = fields.get(4).copied().unwrap_or_default();
+        let binning = match bin_str {
+            "" | "1" => Binning::Orig,
+            "2"      => Binning::Bin2,
+            "3"      => Binning::Bin3,
+            "4"      => Binning::Bin4,
+            _ => anyhow::bail!("Line {}: wrong binning {}", line_num, bin_str),
+        };
+
+        result.push(SequenceStep {
+            filter_slot,
+            count,
+            exposure,
+            gain,
+            binning,
+        });
+    }
+
+    if result.is_empty() {
+        anyhow::bail!("Sequence is empty");
+    }
+
+    Ok(result)
+}
+
+#[derive(PartialEq)]
+enum State {
+    Undefined,
+    ChangingFilter,
+    WaitingForCapture,
+}
+
+pub struct SequenceMode {
+    indi:           Arc<indi::Connection>,
+    filter_wheel:   String,
+    steps:          Vec<SequenceStep>,
+    index:          usize,
+    state:          State,
+    filter_seconds: usize,
+}
+
+impl SequenceMode {
+    pub fn new(
+        options: &Arc<RwLock<Options>>,
+        indi:    &Arc<indi::Connection>,
+    ) -> anyhow::Result<Self> {
+        let opts = options.read().unwrap();
+        if opts.cam.device.is_none() {
+            anyhow::bail!("Camera is not selected");
+        }
+        let steps: Vec<_> = opts.sequence.steps
+            .iter()
+            .filter(|step| step.count != 0)
+            .cloned()
+            .collect();
+        if steps.is_empty() {
+            anyhow::bail!("Sequence is empty");
+        }
+        let mut filter_wheel = opts.sequence.filter_wheel.clone();
+        let filters_used = steps.iter().any(|step| step.filter_slot.is_some());
+        if filters_used && filter_wheel.is_empty() {
+            // Take first filter wheel if it is not selected explicitly
+            let wheels = indi.get_devices_list_by_interface(indi::DriverInterface::FILTER);
+            let Some(wheel) = wheels.first() else {
+                anyhow::bail!("Filter wheel is not found");
+            };
+            filter_wheel = wheel.name.to_string();
+        }
+
+        Ok(Self {
+            indi:           Arc::clone(indi),
+            filter_wheel,
+            index:          0,
+            state:          State::Undefined,
+            filter_seconds: 0,
+            steps,
+        })
+    }
+
+    fn start_cur_step(&mut self, filter_changed: bool) -> anyhow::Result<NotifyResult> {
+        let step = self.steps[self.index].clone();
+        log::info!(
+            "Starting sequence step {}/{}: filter={:?}, count={}, exposure={}s",
+            self.index + 1, self.steps.len(),
+            step.filter_slot, step.count, step.exposure
+        );
+        self.state = State::WaitingForCapture;
+        Ok(NotifyResult::StartSequenceStep { step, filter_changed })
+    }
+}
+
+impl Mode for SequenceMode {
+    fn get_type(&self) -> ModeType {
+        ModeType::Sequence
+    }
+
+    fn progress_string(&self) -> String {
+        match self.steps.get(self.index) {
+            Some(step) => {
+                let filter_str = step.filter_slot
+                    .map(|slot| format!(", filter #{}", slot))
+                    .unwrap_or_default();
+                format!(
+                    "Sequence: step {}/{}{}",
+                    self.index + 1, self.steps.len(), filter_str
+                )
+            }
+            None =>
+                "Sequence".to_string(),
+        }
+    }
+
+    fn can_be_stopped(&self) -> bool {
+        true
+    }
+
+    fn progress(&self) -> Option<Progress> {
+        let frames_done = self.steps.iter().take(self.index).map(|step| step.count).sum();
+        let frames_total = self.steps.iter().map(|step| step.count).sum();
+        Some(Progress {
+            cur: frames_done,
+            total: frames_total,
+        })
+    }
+
+    fn start(&mut self) -> anyhow::Result<()> {
+        self.state = State::Undefined;
+        Ok(())
+    }
+
+    fn notify_timer_1s(&mut self) -> anyhow::Result<NotifyResult> {
+        match self.state {
+            State::Undefined => {
+                let Some(step) = self.steps.get(self.index) else {
+                    log::info!("Sequence is finished");
+                    return Ok(NotifyResult::Finished { next_mode: None });
+                };
+                let Some(slot) = step.filter_slot else {
+                    return self.start_cur_step(false);
+                };
+                if self.indi.filterwheel_get_current_slot(&self.filter_wheel)? == slot {
+                    return self.start_cur_step(false);
+                }
+                log::info!("Changing filter wheel {} slot to {}...", self.filter_wheel, slot);
+                self.indi.filterwheel_set_slot(&self.filter_wheel, slot, true, None)?;
+                self.filter_seconds = 0;
+                self.state = State::ChangingFilter;
+                Ok(NotifyResult::ProgressChanges)
+            }
+
+            State::ChangingFilter => {
+                let slot = self.steps[self.index].filter_slot.unwrap_or_default();
+                if self.indi.filterwheel_get_current_slot(&self.filter_wheel)? == slot {
+                    return self.start_cur_step(true);
+                }
+                self.filter_seconds += 1;
+                if self.filter_seconds > MAX_FILTER_CHANGE_TIME {
+                    anyhow::bail!(
+                        "Filter wheel slot change time out (> {} seconds)!",
+                        MAX_FILTER_CHANGE_TIME
+                    );
+                }
+                Ok(NotifyResult::Empty)
+            }
+
+            State::WaitingForCapture => {
+                self.index += 1;
+                self.state = State::Undefined;
+                Ok(NotifyResult::ProgressChanges)
+            }
+        }
+    }
+}
+
+#[test]
+fn test_parse_sequence() {
+    let steps = parse_sequence(
+        "# comment\n\
+         Filter;Count;Exposure;Gain;Bin\n\
+         1;10;60;100;2\n\
+         ;5;30,5\n"
+    ).unwrap();
+    assert!(steps.len() == 2);
+    assert!(steps[0].filter_slot == Some(1));
+    assert!(steps[0].gain == Some(100.0));
+    assert!(steps[0].binning == Binning::Bin2);
+    assert!(steps[1].filter_slot.is_none());
+    assert!(steps[1].exposure == 30.5);
+    assert!(steps[1].gain.is_none());
+    assert!(steps[1].binning == Binning::Orig);
+
+    let steps = parse_sequence("2,20,120,,1").unwrap();
+    assert!(steps.len() == 1);
+
+    assert!(parse_sequence("1,10").is_err());
+    assert!(parse_sequence("1,10,60\n1,0,60").is_err());
+    assert!(parse_sequence("1,10,60,0,5").is_err());
+}
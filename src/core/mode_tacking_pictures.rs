@@ -1,26 +1,63 @@
 use core::f64;
 use std::{
-    any::Any, path::PathBuf, sync::{atomic::AtomicBool, Arc, Mutex, RwLock}
+    any::Any, borrow::Cow, io::Cursor, path::{Path, PathBuf}, sync::{atomic::AtomicBool, Arc, Mutex, RwLock}, time::{Duration, Instant}
 };
 
 use chrono::Utc;
 
 use crate::{
-    core::{consts::INDI_SET_PROP_TIMEOUT, utils::FileNameArg},
-    guiding::external_guider::*,
-    image::{histogram::*, info::LightFrameInfo, raw::{FrameType, RawStacker, RawImage, RawImageInfo}, stars_offset::*},
+    core::{consts::{AFTER_MOUNT_MOVE_WAIT_TIME, INDI_SET_PROP_TIMEOUT}, utils::FileNameArg},
+    guiding::{external_guider::*, guide_log::*},
+    image::{histogram::*, info::LightFrameInfo, raw::{FrameType, RawStacker, RawImage, RawImageInfo}, simple_fits::{decompress_fits, is_fpack_format, FitsReader, FitsWriter, Header}, stars_offset::*},
     indi,
     options::*,
+    plate_solve::*,
+    sky_math::{math::*, solar_system::mini_sun},
     utils::io_utils::*,
     TimeLogger
 };
-use super::{core::*, events::*, frame_processing::*, mode_darks_library::MasterFileCreationProgramItem, mode_mount_calibration::*, utils::FileNameUtils};
+use super::{core::*, events::*, frame_processing::*, mode_darks_library::MasterFileCreationProgramItem, mode_mount_calibration::*, mode_targets::TargetItem, utils::{FileNameUtils, FlatPanelData}};
 
 const MAX_TIMED_GUIDE: f64 = 20.0; // in seconds
 
+// Large drift is considered as real one after
+// such number of frames in a row
+const MAX_SUCCESSIVE_OUTLIERS: usize = 2;
+
+// Automatic meridian flip starts at least such
+// distance before meridian stop limit
+const FLIP_BEFORE_MERID_STOP: f64 = 1.0; // degrees
+
+const SIDEREAL_RATE: f64 = 15.041 / 3600.0; // degrees per second
+
+/// Altitude of the Sun in degrees
+pub fn calc_sun_altitude(
+    latitude:  f64, // in radians
+    longitude: f64, // in radians
+    utc_time:  &chrono::NaiveDateTime,
+) -> f64 {
+    let cvt = EqToSphereCvt::new(longitude, latitude, utc_time);
+    let sun_crd = mini_sun(calc_julian_centuries(utc_time));
+    let h_crd = HorizCoord::from_sphere_pt(&cvt.eq_to_sphere(&sun_crd));
+    radian_to_degree(h_crd.alt)
+}
+
+/// Returns the Sun altitude if it is out of range for sky flats
+pub fn sun_alt_if_not_suitable_for_sky_flats(options: &Options) -> Option<f64> {
+    let latitude = degree_to_radian(options.site.latitude);
+    let longitude = degree_to_radian(options.site.longitude);
+    let sun_alt = calc_sun_altitude(latitude, longitude, &Utc::now().naive_utc());
+    if sun_alt < options.flats.sky_sun_alt_min || sun_alt > options.flats.sky_sun_alt_max {
+        Some(sun_alt)
+    } else {
+        None
+    }
+}
+
 // Guider data for guiding by main camera
 struct SimpleGuider {
     mnt_calibr:        Option<MountMoveCalibrRes>,
+    calibr_binning:    Option<Binning>,
     dither_x:          f64,
     dither_y:          f64,
     cur_timed_guide_n: f64,
@@ -28,12 +65,17 @@ struct SimpleGuider {
     cur_timed_guide_w: f64,
     cur_timed_guide_e: f64,
     dither_exp_sum:    f64,
+    residual_ra:       f64, // not applied short pulses, in ms
+    residual_dec:      f64, // not applied short pulses, in ms
+    outliers_cnt:      usize,
+    guide_log:         Option<GuideLog>,
 }
 
 impl SimpleGuider {
     fn new() -> Self {
         Self {
             mnt_calibr: None,
+            calibr_binning: None,
             dither_x: 0.0,
             dither_y: 0.0,
             cur_timed_guide_n: 0.0,
@@ -41,6 +83,23 @@ impl SimpleGuider {
             cur_timed_guide_w: 0.0,
             cur_timed_guide_e: 0.0,
             dither_exp_sum:    0.0,
+            residual_ra:       0.0,
+            residual_dec:      0.0,
+            outliers_cnt:      0,
+            guide_log:         None,
+        }
+    }
+
+    fn write_log_frame(&mut self, offset: Option<(f64, f64)>, ra_pulse: f64, dec_pulse: f64) {
+        let (Some(guide_log), Some((dx, dy))) = (&mut self.guide_log, offset) else {
+            return;
+        };
+        let (ra_dist, dec_dist) = self.mnt_calibr.as_ref()
+            .and_then(|calibr| calibr.calc_axes_distance(dx, dy))
+            .unwrap_or((0.0, 0.0));
+        let item = GuideLogItem { dx, dy, ra_dist, dec_dist, ra_pulse, dec_pulse };
+        if let Err(err) = guide_log.add_frame(&item) {
+            log::error!("Can't write guiding log: {}", err.to_string());
         }
     }
 }
@@ -61,9 +120,22 @@ enum State {
     FrameToSkip,
     Common,
     CameraOffsetCalculation,
+    FlatExposureSearch,
     WaitingForMountCalibration,
     InternalMountCorrection,
     ExternalDithering,
+    SimpleDithering,
+    LowDiskSpace,
+    ShotDelay,
+    WaitingForDarkness,
+    WaitingForMeridianFlip,
+}
+
+// Pause before next shot. See `FrameOptions::delay`
+// and `FrameOptions::settle_delay`
+struct ShotDelay {
+    end_time: Instant,
+    settle:   bool,
 }
 
 // Guider data for guiding by external program
@@ -72,11 +144,51 @@ struct ExtGuiderData {
     ext_guider:     Arc<Mutex<Option<Box<dyn ExternalGuider + Send>>>>,
 }
 
+// Data for dithering by shifting of cropped frame
+#[derive(Default)]
+struct FrameOffsetDither {
+    exp_sum: f64,
+}
+
+// Data for dithering by random guide pulses without mount calibration
+#[derive(Default)]
+struct SimpleDither {
+    exp_sum:   f64,
+    wait_time: usize, // seconds left until mount is settled
+}
+
 struct RefocusData {
-    exp_sum:  f64,
-    min_temp: Option<f64>,
-    max_temp: Option<f64>,
-    fwhm:     Vec<f32>,
+    exp_sum:      f64,
+    min_temp:     Option<f64>,
+    max_temp:     Option<f64>,
+    fwhm:         Vec<f32>,
+    cur_exp_time: f64, // time from start of current exposure
+}
+
+impl RefocusData {
+    fn clear(&mut self) {
+        self.exp_sum = 0.0;
+        self.min_temp = None;
+        self.max_temp = None;
+        self.fwhm.clear();
+    }
+}
+
+// Data for recentering of target after meridian flip
+#[derive(Default)]
+struct FlipRecenterData {
+    pier_side:     Option<indi::PierSide>,
+    pre_flip_info: Option<Arc<LightFrameInfo>>,
+}
+
+// Plate solving of saved light frame for writing WCS into its FITS file
+struct FramePlateSolve {
+    solver:      PlateSolver,
+    in_progress: bool,
+    frame_time:  Option<chrono::DateTime<Utc>>, // time of frame being solved
+    img_width:   usize,
+    img_height:  usize,
+    file_name:   Option<PathBuf>, // saved FITS file of frame being solved
 }
 
 #[derive(Default)]
@@ -86,6 +198,12 @@ struct Flags {
     use_raw_stacker:    bool,
     save_master_file:   bool,
     save_defect_pixels: bool,
+    flat_exp_found:     bool,
+    flats_session_step: bool,
+    sky_flats:          bool, // exposure is corrected after every frame
+    dither_settle:      bool, // mount correction is dithering
+    filter_changed:     bool, // settle before first frame of sequence step
+    shot_postponed:     bool, // exposure is not started at blob start because of meridian flip
 }
 
 #[derive(Default, Debug)]
@@ -101,6 +219,79 @@ struct CamOffsetCalc {
     high_values: Vec<(u16, f32)>,
 }
 
+#[derive(Debug, PartialEq)]
+enum FlatExpSearchResult {
+    NextShot(f64), // exposure for next test shot
+    Found(f64),
+    Failed(String),
+}
+
+/// Bisection of flat exposure (in logarithmic scale)
+/// until median of frame is in target window
+struct FlatExpSearch {
+    min_exp:   f64,
+    max_exp:   f64,
+    cur_exp:   f64,
+    target:    f64, // %
+    tolerance: f64, // %
+    iteration: usize,
+}
+
+impl FlatExpSearch {
+    const MIN_EXPOSURE: f64 = 0.0001;
+    const MAX_EXPOSURE: f64 = 60.0;
+    const MAX_ITERATIONS: usize = 16;
+
+    fn new(start_exp: f64, target: f64, tolerance: f64) -> Self {
+        Self {
+            min_exp:   Self::MIN_EXPOSURE,
+            max_exp:   Self::MAX_EXPOSURE,
+            cur_exp:   start_exp.clamp(Self::MIN_EXPOSURE, Self::MAX_EXPOSURE),
+            target,
+            tolerance,
+            iteration: 0,
+        }
+    }
+
+    /// `median` is median of test shot with `cur_exp` exposure in % of max value
+    fn process_median(&mut self, median: f64) -> FlatExpSearchResult {
+        self.iteration += 1;
+        if f64::abs(median - self.target) <= self.tolerance {
+            return FlatExpSearchResult::Found(self.cur_exp);
+        }
+        if median < self.target {
+            self.min_exp = self.cur_exp;
+        } else {
+            self.max_exp = self.cur_exp;
+        }
+        if self.iteration >= Self::MAX_ITERATIONS
+        || self.max_exp / self.min_exp < 1.001 {
+            return FlatExpSearchResult::Failed(format!(
+                "Can't find exposure for flat frames after {} test shots \
+                (last exposure = {:.4}s, median = {:.1}%, target = {:.1}±{:.1}%). \
+                Check brightness of light source",
+                self.iteration, self.cur_exp, median, self.target, self.tolerance
+            ));
+        }
+        self.cur_exp = f64::sqrt(self.min_exp * self.max_exp);
+        FlatExpSearchResult::NextShot(self.cur_exp)
+    }
+
+    /// Exposure for next sky flat frame if frame with `exposure`
+    /// has `median` (in %). Returns `None` if sky brightness
+    /// requires exposure out of allowed range
+    fn correct_sky_flat_exposure(exposure: f64, median: f64, target: f64) -> Option<f64> {
+        if median <= 0.0 {
+            return None;
+        }
+        let result = exposure * target / median;
+        if !(Self::MIN_EXPOSURE..=Self::MAX_EXPOSURE).contains(&result) {
+            return None;
+        }
+        Some(result)
+    }
+}
+
 pub struct TackingPicturesMode {
     cam_mode:        CameraMode,
     state:           State,
@@ -119,6 +310,8 @@ pub struct TackingPicturesMode {
     cur_exposure:    f64,
     simple_guider:   Option<SimpleGuider>,
     guider:          Option<ExtGuiderData>,
+    frame_dither:    FrameOffsetDither,
+    simple_dither:   SimpleDither,
     live_stacking:   Option<Arc<LiveStackingData>>,
     refocus:         RefocusData,
     flags:           Flags,
@@ -126,7 +319,21 @@ pub struct TackingPicturesMode {
     out_file_names:  OutFileNames,
     camera_offset:   Option<u16>,
     cam_offset_calc: Option<CamOffsetCalc>,
+    flat_exp_search: Option<FlatExpSearch>,
+    flat_panel:      Option<FlatPanelData>,
+    sky_flat_median: Option<f64>,
+    target_name:     Option<String>,
+    seq_progress:    Option<Progress>, // frames before current sequence step and total
     next_mode:       Option<ModeBox>,
+    disk_check_cnt:  usize,
+    disk_check_time: Option<Instant>, // time of last free space check while saving frames
+    shot_delay:      Option<ShotDelay>,
+    dark_start_time: Option<chrono::NaiveDateTime>,
+    flip_recenter:   Option<FlipRecenterData>,
+    auto_flip:       bool,
+    auto_flip_done:  bool,
+    flip_info:       Option<Arc<LightFrameInfo>>, // frame before meridian flip
+    frame_ps:        Option<FramePlateSolve>,
 }
 
 impl TackingPicturesMode {
@@ -152,10 +359,11 @@ impl TackingPicturesMode {
         };
 
         let refocus = RefocusData {
-            exp_sum:  0.0,
-            min_temp: None,
-            max_temp: None,
-            fwhm:     Vec::new(),
+            exp_sum:      0.0,
+            min_temp:     None,
+            max_temp:     None,
+            fwhm:         Vec::new(),
+            cur_exp_time: 0.0,
         };
 
         let mut cam_options = opts.cam.clone();
@@ -191,11 +399,27 @@ impl TackingPicturesMode {
             cur_exposure:    0.0,
             simple_guider:   None,
             guider:          None,
+            frame_dither:    FrameOffsetDither::default(),
+            simple_dither:   SimpleDither::default(),
             live_stacking:   None,
             out_file_names:  OutFileNames::default(),
             camera_offset:   None,
             cam_offset_calc: None,
+            flat_exp_search: None,
+            flat_panel:      None,
+            sky_flat_median: None,
+            target_name:     None,
+            seq_progress:    None,
             next_mode:       None,
+            disk_check_cnt:  0,
+            disk_check_time: None,
+            shot_delay:      None,
+            dark_start_time: None,
+            flip_recenter:   None,
+            auto_flip:       false,
+            auto_flip_done:  false,
+            flip_info:       None,
+            frame_ps:        None,
             flags:           Flags::default(),
             fname_utils:     FileNameUtils::default(),
             refocus,
@@ -234,6 +458,44 @@ impl TackingPicturesMode {
         self.cam_options.frame.crop = item.crop;
     }
 
+    pub fn set_target_item(&mut self, item: &TargetItem) {
+        self.progress = Some(Progress {cur: 0, total: item.count});
+        self.cam_options.frame.frame_type = FrameType::Lights;
+        self.cam_options.frame.exp_main = item.exposure;
+        self.target_name = Some(item.name.clone());
+    }
+
+    pub fn set_sequence_step(
+        &mut self,
+        step:           &SequenceStep,
+        filter_changed: bool,
+        seq_progress:   Option<Progress>,
+    ) {
+        self.progress = Some(Progress {cur: 0, total: step.count});
+        self.seq_progress = seq_progress;
+        self.flags.filter_changed = filter_changed;
+        self.cam_options.frame.frame_type = FrameType::Lights;
+        self.cam_options.frame.exp_main = step.exposure;
+        self.cam_options.frame.binning = step.binning;
+        if let Some(gain) = step.gain {
+            self.cam_options.frame.gain = gain;
+        }
+    }
+
+    /// Step of flats session. Flat panel is controlled by flats session mode
+    pub fn set_flats_session_step(
+        &mut self,
+        step:         &FlatsSessionStep,
+        sky_flats:    bool,
+        seq_progress: Option<Progress>,
+    ) {
+        self.progress = Some(Progress {cur: 0, total: step.count});
+        self.seq_progress = seq_progress;
+        self.cam_options.frame.frame_type = FrameType::Flats;
+        self.flags.flats_session_step = true;
+        self.flags.sky_flats = sky_flats;
+    }
+
     pub fn set_next_mode(&mut self, next_mode: Option<ModeBox>) {
         self.next_mode = next_mode;
     }
@@ -283,6 +545,25 @@ impl TackingPicturesMode {
             return Ok(());
         }
 
+        if self.cam_mode == CameraMode::SavingRawFrames
+        && self.cam_options.frame.frame_type == FrameType::Flats
+        && self.flat_exp_search.is_none() {
+            let options = self.options.read().unwrap();
+            if options.calibr.flat_auto_exp || self.flags.flats_session_step {
+                let search = FlatExpSearch::new(
+                    self.cam_options.frame.exposure(),
+                    options.calibr.flat_adu_target,
+                    options.calibr.flat_adu_tolerance,
+                );
+                drop(options);
+                let exposure = search.cur_exp;
+                self.flat_exp_search = Some(search);
+                self.start_flat_exp_search_shot(exposure)?;
+                self.state = State::FlatExposureSearch;
+                return Ok(());
+            }
+        }
+
         if self.cam_mode == CameraMode::SavingRawFrames
         && self.cam_options.frame.frame_type == FrameType::Flats
         && self.cam_options.frame.offset != 0
@@ -310,10 +591,36 @@ impl TackingPicturesMode {
         }
         apply_camera_options_and_take_shot(&self.indi, &self.device, &self.cam_options.frame)?;
         self.cur_exposure = self.cam_options.frame.exposure();
+        self.refocus.cur_exp_time = 0.0;
         self.state = State::Common;
         Ok(())
     }
 
+    fn is_flat_panel_used(&self) -> bool {
+        self.cam_mode == CameraMode::SavingRawFrames &&
+        self.cam_options.frame.frame_type == FrameType::Flats &&
+        !self.flags.flats_session_step &&
+        self.options.read().unwrap().calibr.flat_panel
+    }
+
+    /// Closes dust cap and turns on flat panel. Missing devices are skipped
+    fn prepare_flat_panel(&mut self) -> anyhow::Result<()> {
+        if self.flat_panel.is_some() {
+            return Ok(());
+        }
+        let brightness = self.options.read().unwrap().calibr.flat_panel_bright;
+        self.flat_panel = Some(FlatPanelData::prepare(&self.indi, brightness)?);
+        Ok(())
+    }
+
+    /// Turns off flat panel and opens dust cap if it was open before flat frames
+    fn release_flat_panel(&mut self) -> anyhow::Result<()> {
+        let Some(flat_panel) = self.flat_panel.take() else {
+            return Ok(());
+        };
+        flat_panel.release(&self.indi)
+    }
+
     fn start_first_shot_that_will_be_skipped(&mut self) -> anyhow::Result<()> {
         let mut frame_opts = self.cam_options.frame.clone();
         const MAX_EXP: f64 = 1.0;
@@ -335,16 +642,67 @@ impl TackingPicturesMode {
         Ok(())
     }
 
+    fn start_flat_exp_search_shot(&mut self, exposure: f64) -> anyhow::Result<()> {
+        let mut frame_opts = self.cam_options.frame.clone();
+        frame_opts.set_exposure(exposure);
+        apply_camera_options_and_take_shot(&self.indi, &self.device, &frame_opts)?;
+        self.cur_exposure = exposure;
+        Ok(())
+    }
+
     const MIN_EXPOSURE_FOR_DELAYED_CAPTURE_START: f64 = 3.0;
 
     fn have_to_start_new_exposure_at_blob_start(&mut self) -> bool {
         self.cam_mode != CameraMode::SingleShot &&
-        self.cam_options.frame.exposure() >= Self::MIN_EXPOSURE_FOR_DELAYED_CAPTURE_START
+        self.cam_options.frame.exposure() >= Self::MIN_EXPOSURE_FOR_DELAYED_CAPTURE_START &&
+        self.shot_delay_time(false) == 0.0 &&
+        !self.flags.sky_flats // exposure is corrected after frame processing
     }
 
     fn have_to_start_new_exposure_at_processing_end(&mut self) -> bool {
-        self.cam_mode != CameraMode::SingleShot &&
-        self.cam_options.frame.exposure() < Self::MIN_EXPOSURE_FOR_DELAYED_CAPTURE_START
+        self.cam_mode != CameraMode::SingleShot && (
+            self.cam_options.frame.exposure() < Self::MIN_EXPOSURE_FOR_DELAYED_CAPTURE_START ||
+            self.shot_delay_time(false) != 0.0 ||
+            self.flags.sky_flats
+        )
+    }
+
+    /// Inter-frame delay or settle delay (after dithering or filter change)
+    /// in seconds. Delays are used only while saving frames or live stacking
+    fn shot_delay_time(&self, settle: bool) -> f64 {
+        if !matches!(self.cam_mode, CameraMode::SavingRawFrames|CameraMode::LiveStacking) {
+            return 0.0;
+        }
+        let frame = &self.cam_options.frame;
+        let delay = if settle { frame.settle_delay } else { frame.delay };
+        delay.max(0.0)
+    }
+
+    /// Starts next shot right now or after delay if it is defined
+    fn start_or_continue_after_delay(&mut self, settle: bool) -> anyhow::Result<()> {
+        let delay = self.shot_delay_time(settle);
+        if delay == 0.0 {
+            return self.start_or_continue();
+        }
+        log::debug!("{} delay {:.1}s before next shot", if settle { "Settle" } else { "Inter-frame" }, delay);
+        self.shot_delay = Some(ShotDelay {
+            end_time: Instant::now() + Duration::from_secs_f64(delay),
+            settle,
+        });
+        self.state = State::ShotDelay;
+        Ok(())
+    }
+
+    fn continue_after_shot_delay(&mut self) -> anyhow::Result<NotifyResult> {
+        let Some(shot_delay) = &self.shot_delay else {
+            return Ok(NotifyResult::Empty);
+        };
+        if Instant::now() < shot_delay.end_time {
+            return Ok(NotifyResult::ProgressChanges); // for countdown
+        }
+        self.shot_delay = None;
+        self.start_or_continue()?;
+        Ok(NotifyResult::ProgressChanges)
     }
 
     fn generate_output_file_names(&mut self) -> anyhow::Result<()> {
@@ -386,6 +744,12 @@ impl TackingPicturesMode {
             let save_dir = self.fname_utils.raw_file_dest_dir(time, &self.cam_options);
             let mut path = PathBuf::new();
             path.push(&options.raw_frames.out_path);
+            if let Some(target_name) = &self.target_name {
+                let target_dir: String = target_name.chars()
+                    .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+                    .collect();
+                path.push(&target_dir);
+            }
             path.push(&save_dir);
             self.out_file_names.raw_files_dir = get_free_folder_name(&path);
         }
@@ -395,6 +759,162 @@ impl TackingPicturesMode {
         Ok(())
     }
 
+    fn process_light_frame_info_and_recenter_after_flip(
+        &mut self,
+        info: &Arc<LightFrameInfo>,
+    ) -> anyhow::Result<NotifyResult> {
+        let Some(flip_recenter) = &mut self.flip_recenter else {
+            return Ok(NotifyResult::Empty);
+        };
+        let Some(pier_side) = self.indi.mount_get_pier_side(&self.mount_device)? else {
+            return Ok(NotifyResult::Empty);
+        };
+        let prev_pier_side = flip_recenter.pier_side.replace(pier_side);
+        if prev_pier_side.is_none() || prev_pier_side == Some(pier_side) {
+            flip_recenter.pre_flip_info = Some(Arc::clone(info));
+            return Ok(NotifyResult::Empty);
+        }
+
+        log::info!(
+            "Mount pier side changed from {:?} to {:?}",
+            prev_pier_side.unwrap(), pier_side
+        );
+
+        let Some(pre_flip_info) = flip_recenter.pre_flip_info.take() else {
+            return Ok(NotifyResult::Empty);
+        };
+
+        self.reset_main_cam_guiding_after_flip();
+
+        log::info!("Recentering target at position before meridian flip...");
+        Ok(NotifyResult::StartRecenterAfterFlip(pre_flip_info))
+    }
+
+    fn flip_limit(&self) -> f64 {
+        let options = self.options.read().unwrap();
+        let mut flip_limit = options.mount.flip_limit;
+        // Flip must happen before mount is stopped at meridian limit
+        if options.mount.merid_stop {
+            flip_limit = flip_limit.min(options.mount.merid_limit - FLIP_BEFORE_MERID_STOP);
+        }
+        flip_limit
+    }
+
+    fn mount_past_meridian(&self) -> anyhow::Result<f64> {
+        let longitude = degree_to_radian(self.options.read().unwrap().site.longitude);
+        let ra = self.indi.mount_get_eq_ra(&self.mount_device)?;
+        let hour_angle = calc_hour_angle(
+            hour_to_radian(ra),
+            longitude,
+            &Utc::now().naive_utc()
+        );
+        Ok(radian_to_degree(hour_angle))
+    }
+
+    /// Exposure started now would be finished after mount passes flip limit
+    fn exposure_crosses_flip_limit(&self) -> bool {
+        if !self.auto_flip || self.auto_flip_done {
+            return false;
+        }
+        let Ok(past_meridian) = self.mount_past_meridian() else {
+            return false;
+        };
+        let exposure = self.cam_options.frame.exposure();
+        past_meridian + exposure * SIDEREAL_RATE >= self.flip_limit()
+    }
+
+    fn process_light_frame_info_and_meridian_flip(
+        &mut self,
+        info: &Arc<LightFrameInfo>,
+    ) -> anyhow::Result<NotifyResult> {
+        if !self.auto_flip || self.auto_flip_done {
+            return Ok(NotifyResult::Empty);
+        }
+
+        let flip_limit = self.flip_limit();
+        let past_meridian = self.mount_past_meridian()?;
+        if past_meridian < flip_limit {
+            if self.exposure_crosses_flip_limit() {
+                // Next frame would be exposed during passing of flip limit.
+                // It is not taken and meridian flip starts when limit is reached
+                log::info!(
+                    "Next exposure crosses flip limit {:.1}° (mount is {:.1}° past meridian). \
+                    Waiting for meridian flip...",
+                    flip_limit, past_meridian
+                );
+                abort_camera_exposure(&self.indi, &self.device)?;
+                self.flags.shot_postponed = false;
+                self.flip_info = Some(Arc::clone(info));
+                self.state = State::WaitingForMeridianFlip;
+                return Ok(NotifyResult::ProgressChanges);
+            }
+            return Ok(NotifyResult::Empty);
+        }
+
+        self.start_meridian_flip(info, past_meridian, flip_limit)
+    }
+
+    fn continue_after_flip_limit(&mut self) -> anyhow::Result<NotifyResult> {
+        let flip_limit = self.flip_limit();
+        let past_meridian = self.mount_past_meridian()?;
+        if past_meridian < flip_limit {
+            return Ok(NotifyResult::ProgressChanges); // for countdown
+        }
+        let Some(info) = self.flip_info.take() else {
+            return Ok(NotifyResult::Empty);
+        };
+        self.state = State::Common;
+        let res = self.start_meridian_flip(&info, past_meridian, flip_limit)?;
+        if matches!(&res, NotifyResult::Empty) {
+            self.start_or_continue()?;
+            return Ok(NotifyResult::ProgressChanges);
+        }
+        Ok(res)
+    }
+
+    fn start_meridian_flip(
+        &mut self,
+        info:          &Arc<LightFrameInfo>,
+        past_meridian: f64,
+        flip_limit:    f64,
+    ) -> anyhow::Result<NotifyResult> {
+        self.auto_flip_done = true;
+
+        // Mount at east side of pier points to western part of sky
+        let pier_side_supported = self.indi
+            .mount_is_pier_side_supported(&self.mount_device)
+            .unwrap_or(false);
+        if pier_side_supported
+        && self.indi.mount_get_pier_side(&self.mount_device)? == Some(indi::PierSide::East) {
+            log::info!("Mount is already at correct pier side. Meridian flip is not required");
+            return Ok(NotifyResult::Empty);
+        }
+
+        log::info!(
+            "Mount is {:.1}° past meridian (flip limit is {:.1}°). Starting meridian flip...",
+            past_meridian, flip_limit
+        );
+
+        // Goto mode recenters target itself
+        if let Some(flip_recenter) = &mut self.flip_recenter {
+            *flip_recenter = FlipRecenterData::default();
+        }
+
+        self.reset_main_cam_guiding_after_flip();
+
+        Ok(NotifyResult::StartMeridianFlip(Arc::clone(info)))
+    }
+
+    fn reset_main_cam_guiding_after_flip(&mut self) {
+        // Star positions and mount directions are not
+        // actual for main camera guiding after flip
+        self.simple_guider = None;
+        if let Some(ref_stars) = &mut self.ref_stars {
+            let mut ref_stars = ref_stars.lock().unwrap();
+            *ref_stars = None;
+        }
+    }
+
     fn process_light_frame_info_and_refocus(
         &mut self,
         info: &LightFrameInfo
@@ -418,41 +938,18 @@ impl TackingPicturesMode {
             return Ok(NotifyResult::Empty);
         };
 
-        // Update min and max temperature
-        let temperature = self.indi.focuser_get_temperature(&focuser_options.device)?;
-        if !temperature.is_nan() && !temperature.is_infinite() {
-            self.refocus.min_temp = self.refocus.min_temp
-                .map(|v| f64::min(v, temperature))
-                .or_else(|| Some(temperature));
-            self.refocus.max_temp = self.refocus.max_temp
-                .map(|v| f64::max(v, temperature))
-                .or_else(|| Some(temperature));
-        }
+        let focuser_options = focuser_options.clone();
+
+        self.update_refocus_temperature(&focuser_options)?;
 
         if !self.indi.is_device_enabled(&focuser_options.device).unwrap_or(false) {
             return Ok(NotifyResult::Empty);
         }
 
-        let mut have_to_refocus = false;
-
-        // Periodically
-        if focuser_options.periodically
-        && focuser_options.period_minutes != 0 {
-            let max_exp_sum = (focuser_options.period_minutes * 60) as f64;
-            if self.refocus.exp_sum >= max_exp_sum {
-                have_to_refocus = true;
-            }
-        }
-
-        // When temperature changed
-        if focuser_options.on_temp_change
-        && focuser_options.max_temp_change > 0.0 {
-            if let (Some(min), Some(max)) = (self.refocus.min_temp, self.refocus.max_temp) {
-                if max - min > focuser_options.max_temp_change {
-                    have_to_refocus = true;
-                }
-            }
-        }
+        let mut have_to_refocus = self.have_to_refocus_by_time_or_temp(
+            &focuser_options,
+            self.refocus.exp_sum
+        );
 
         // On FWHM increase
         if focuser_options.on_fwhm_change
@@ -475,16 +972,109 @@ impl TackingPicturesMode {
         }
 
         if have_to_refocus {
-            self.refocus.exp_sum = 0.0;
-            self.refocus.min_temp = None;
-            self.refocus.max_temp = None;
-            self.refocus.fwhm.clear();
+            self.refocus.clear();
             return Ok(NotifyResult::StartFocusing);
         }
 
         Ok(NotifyResult::Empty)
     }
 
+    fn update_refocus_temperature(
+        &mut self,
+        focuser_options: &FocuserOptions
+    ) -> anyhow::Result<()> {
+        let temperature = self.indi.focuser_get_temperature(&focuser_options.device)?;
+        if !temperature.is_nan() && !temperature.is_infinite() {
+            self.refocus.min_temp = self.refocus.min_temp
+                .map(|v| f64::min(v, temperature))
+                .or_else(|| Some(temperature));
+            self.refocus.max_temp = self.refocus.max_temp
+                .map(|v| f64::max(v, temperature))
+                .or_else(|| Some(temperature));
+        }
+        Ok(())
+    }
+
+    fn have_to_refocus_by_time_or_temp(
+        &self,
+        focuser_options: &FocuserOptions,
+        exp_sum:         f64,
+    ) -> bool {
+        // Periodically
+        if focuser_options.periodically
+        && focuser_options.period_minutes != 0 {
+            let max_exp_sum = (focuser_options.period_minutes * 60) as f64;
+            if exp_sum >= max_exp_sum {
+                return true;
+            }
+        }
+
+        // When temperature changed
+        if focuser_options.on_temp_change
+        && focuser_options.max_temp_change > 0.0 {
+            if let (Some(min), Some(max)) = (self.refocus.min_temp, self.refocus.max_temp) {
+                if max - min > focuser_options.max_temp_change {
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Minimal rest of exposure time when exposure can be interrupted for refocus
+    const MIN_EXP_REST_TO_INTERRUPT: f64 = 10.0;
+
+    fn check_refocus_during_exposure(&mut self) -> anyhow::Result<NotifyResult> {
+        if self.state != State::Common {
+            return Ok(NotifyResult::Empty);
+        }
+        let use_focus =
+            self.cam_mode == CameraMode::LiveStacking ||
+            self.cam_mode == CameraMode::SavingRawFrames;
+        if !use_focus {
+            return Ok(NotifyResult::Empty);
+        }
+        let Some(focuser_options) = &self.focus_options else {
+            return Ok(NotifyResult::Empty);
+        };
+        if !focuser_options.interrupt_exp {
+            return Ok(NotifyResult::Empty);
+        }
+
+        self.refocus.cur_exp_time += 1.0;
+
+        // Don't interrupt exposure which is almost finished
+        if self.cur_exposure - self.refocus.cur_exp_time < Self::MIN_EXP_REST_TO_INTERRUPT {
+            return Ok(NotifyResult::Empty);
+        }
+
+        let focuser_options = focuser_options.clone();
+        if !self.indi.is_device_enabled(&focuser_options.device).unwrap_or(false) {
+            return Ok(NotifyResult::Empty);
+        }
+
+        self.update_refocus_temperature(&focuser_options)?;
+
+        let have_to_refocus = self.have_to_refocus_by_time_or_temp(
+            &focuser_options,
+            self.refocus.exp_sum + self.refocus.cur_exp_time
+        );
+        if !have_to_refocus {
+            return Ok(NotifyResult::Empty);
+        }
+
+        // Current exposure will be aborted and discarded. After focusing
+        // the mode is continued and same frame is taken again
+        log::info!(
+            "Interrupting exposure at {:.0}s of {:.0}s for refocus",
+            self.refocus.cur_exp_time, self.cur_exposure
+        );
+        self.refocus.clear();
+        self.refocus.cur_exp_time = 0.0;
+        Ok(NotifyResult::StartFocusing)
+    }
+
     fn process_light_frame_info_and_dither_by_main_camera(
         &mut self,
         info: &LightFrameInfo
@@ -498,14 +1088,59 @@ impl TackingPicturesMode {
 
         let guider_data = self.simple_guider.get_or_insert_with(|| SimpleGuider::new());
         if guider_options.is_used() && mount_device_active {
-            if guider_data.mnt_calibr.is_none() { // mount moving calibration
+            // Calibration is made in pixels of binned image so
+            // it is invalid for other binning
+            let need_calibr = match &guider_data.mnt_calibr {
+                Some(mnt_calibr) =>
+                    !mnt_calibr.is_ok() ||
+                    guider_data.calibr_binning != Some(self.cam_options.frame.binning),
+                None =>
+                    true,
+            };
+            let saved_calibr = if need_calibr {
+                get_saved_mount_calibr(
+                    &self.indi,
+                    &self.options.read().unwrap(),
+                    self.cam_options.frame.binning
+                )
+            } else {
+                None
+            };
+            if let Some(saved_calibr) = saved_calibr {
+                log::info!("Saved mount calibration is used: {:?}", saved_calibr);
+                guider_data.mnt_calibr = Some(saved_calibr);
+                guider_data.calibr_binning = Some(self.cam_options.frame.binning);
+            } else if need_calibr { // mount moving calibration
+                if !guider_options.main_cam.auto_calibr {
+                    anyhow::bail!("Mount is not calibrated for guiding by main camera");
+                }
+                guider_data.mnt_calibr = None;
                 self.abort()?;
                 self.state = State::WaitingForMountCalibration;
                 return Ok(NotifyResult::Empty);
             }
         }
 
+        // PHD2 compatible guiding log
+        if guider_options.main_cam.save_log
+        && self.flags.save_raw_files
+        && guider_data.guide_log.is_none() {
+            let cam_ccd = indi::CamCcd::from_ccd_prop_name(&self.device.prop);
+            let focal_len = self.options.read().unwrap().telescope.real_focal_length();
+            let binning = self.cam_options.frame.binning.get_ratio();
+            let pixel_scale = self.indi
+                .camera_get_pixel_size_um(&self.device.name, cam_ccd)
+                .ok()
+                .filter(|_| focal_len > 0.0)
+                .map(|(pixel_size, _)| 206.265 * pixel_size * binning as f64 / focal_len);
+            match GuideLog::new(&self.out_file_names.raw_files_dir, pixel_scale, focal_len, binning) {
+                Ok(guide_log) => guider_data.guide_log = Some(guide_log),
+                Err(err) => log::error!("Can't create guiding log: {}", err.to_string()),
+            }
+        }
+
         let mut move_offset = None;
+        let mut log_offset = None;
         let mut prev_dither_x = 0_f64;
         let mut prev_dither_y = 0_f64;
         let mut dithering_flag = false;
@@ -522,6 +1157,12 @@ impl TackingPicturesMode {
                 guider_data.dither_x = guider_options.main_cam.dith_dist as f64 * (rng.gen::<f64>() - 0.5);
                 guider_data.dither_y = guider_options.main_cam.dith_dist as f64 * (rng.gen::<f64>() - 0.5);
                 log::debug!("dithering position = {}px,{}px", guider_data.dither_x, guider_data.dither_y);
+                if let Some(guide_log) = &mut guider_data.guide_log {
+                    _ = guide_log.add_dither(
+                        guider_data.dither_x - prev_dither_x,
+                        guider_data.dither_y - prev_dither_y
+                    );
+                }
                 dithering_flag = true;
             }
         }
@@ -534,13 +1175,29 @@ impl TackingPicturesMode {
             offset_y -= guider_data.dither_y;
             let diff_dist = f64::sqrt(offset_x * offset_x + offset_y * offset_y);
             log::debug!("diff_dist = {}px", diff_dist);
-            if diff_dist > guider_options.main_cam.max_error || dithering_flag {
-                move_offset = Some((-offset_x, -offset_y));
-                log::debug!(
-                    "diff_dist > guid_options.max_error ({} > {}), start mount correction",
-                    diff_dist,
-                    guider_options.main_cam.max_error
+            log_offset = Some((offset_x, offset_y));
+            let max_correction = guider_options.main_cam.max_correction;
+            let is_outlier =
+                max_correction != 0.0 &&
+                diff_dist > max_correction &&
+                !dithering_flag &&
+                guider_data.outliers_cnt < MAX_SUCCESSIVE_OUTLIERS;
+            if is_outlier {
+                guider_data.outliers_cnt += 1;
+                log::warn!(
+                    "Stars drift {:.1}px is greater than max correction {:.1}px. Skipped as outlier",
+                    diff_dist, max_correction
                 );
+            } else {
+                guider_data.outliers_cnt = 0;
+                if diff_dist > guider_options.main_cam.max_error || dithering_flag {
+                    move_offset = Some((-offset_x, -offset_y));
+                    log::debug!(
+                        "diff_dist > guid_options.max_error ({} > {}), start mount correction",
+                        diff_dist,
+                        guider_options.main_cam.max_error
+                    );
+                }
             }
         } else if dithering_flag {
             move_offset = Some((
@@ -553,6 +1210,37 @@ impl TackingPicturesMode {
         if let (Some((offset_x, offset_y)), Some(mnt_calibr)) = (move_offset, &guider_data.mnt_calibr) {
             if mnt_calibr.is_ok() {
                 if let Some((mut ra, mut dec)) = mnt_calibr.calc(offset_x, offset_y) {
+                    let (max_dec, max_ra) = self.indi.mount_get_timed_guide_max(&self.mount_device)?;
+                    let max_dec = f64::min(MAX_TIMED_GUIDE * 1000.0, max_dec);
+                    let max_ra = f64::min(MAX_TIMED_GUIDE * 1000.0, max_ra);
+                    ra = 1000.0 * ra + guider_data.residual_ra;
+                    dec = 1000.0 * dec + guider_data.residual_dec;
+                    if ra > max_ra { ra = max_ra; }
+                    if ra < -max_ra { ra = -max_ra; }
+                    if dec > max_dec { dec = max_dec; }
+                    if dec < -max_dec { dec = -max_dec; }
+
+                    // Mount ignores too short pulses so keep them for next corrections
+                    let min_pulse = guider_options.main_cam.min_pulse;
+                    guider_data.residual_ra = 0.0;
+                    guider_data.residual_dec = 0.0;
+                    if ra.abs() < min_pulse {
+                        guider_data.residual_ra = ra;
+                        ra = 0.0;
+                    }
+                    if dec.abs() < min_pulse {
+                        guider_data.residual_dec = dec;
+                        dec = 0.0;
+                    }
+                    guider_data.write_log_frame(log_offset, ra, dec);
+                    if ra == 0.0 && dec == 0.0 {
+                        log::debug!(
+                            "Guide pulses are too short, residual NS = {:.0}ms, WE = {:.0}ms",
+                            guider_data.residual_dec, guider_data.residual_ra
+                        );
+                        return Ok(NotifyResult::Empty);
+                    }
+
                     guider_data.cur_timed_guide_n = 0.0;
                     guider_data.cur_timed_guide_s = 0.0;
                     guider_data.cur_timed_guide_w = 0.0;
@@ -570,24 +1258,108 @@ impl TackingPicturesMode {
                             INDI_SET_PROP_TIMEOUT
                         )?;
                     }
-                    let (max_dec, max_ra) = self.indi.mount_get_timed_guide_max(&self.mount_device)?;
-                    let max_dec = f64::min(MAX_TIMED_GUIDE * 1000.0, max_dec);
-                    let max_ra = f64::min(MAX_TIMED_GUIDE * 1000.0, max_ra);
-                    ra *= 1000.0;
-                    dec *= 1000.0;
-                    if ra > max_ra { ra = max_ra; }
-                    if ra < -max_ra { ra = -max_ra; }
-                    if dec > max_dec { dec = max_dec; }
-                    if dec < -max_dec { dec = -max_dec; }
-                    log::debug!("Timed guide, NS = {:.2}s, WE = {:.2}s", dec, ra);
+                    log::debug!("Timed guide, NS = {:.0}ms, WE = {:.0}ms", dec, ra);
                     self.indi.mount_timed_guide(&self.mount_device, dec, ra)?;
+                    self.flags.dither_settle = dithering_flag;
                     self.state = State::InternalMountCorrection;
                     return Ok(NotifyResult::ProgressChanges);
                 }
             }
         }
 
-        Ok(NotifyResult::Empty)
+        guider_data.write_log_frame(log_offset, 0.0, 0.0);
+        Ok(NotifyResult::Empty)
+    }
+
+    fn process_light_frame_info_and_dither_by_frame_offset(
+        &mut self,
+        info: &LightFrameInfo
+    ) -> anyhow::Result<NotifyResult> {
+        let guider_options = self.guider_options.as_ref().unwrap();
+        if guider_options.dith_period == 0 {
+            return Ok(NotifyResult::Empty);
+        }
+
+        // No room for shifting if frame is not cropped
+        if self.cam_options.frame.crop == Crop::None {
+            return Ok(NotifyResult::Empty);
+        }
+
+        self.frame_dither.exp_sum += info.exposure;
+        if self.frame_dither.exp_sum <= (guider_options.dith_period * 60) as f64 {
+            return Ok(NotifyResult::Empty);
+        }
+        self.frame_dither.exp_sum = 0.0;
+
+        use rand::prelude::*;
+        let mut rng = rand::thread_rng();
+        let dist = guider_options.frame_offset.dith_dist as f64;
+        let mut rand_offset = || {
+            let value = (dist * (rng.gen::<f64>() - 0.5)).round() as i32;
+            value & !1 // keep even to preserve bayer pattern
+        };
+        let offset = (rand_offset(), rand_offset());
+        self.cam_options.frame.crop_offset = offset;
+        log::info!("Dithering by frame offset. New offset = {}px,{}px", offset.0, offset.1);
+
+        Ok(NotifyResult::ProgressChanges)
+    }
+
+    fn process_light_frame_info_and_simple_dither(
+        &mut self,
+        info: &LightFrameInfo
+    ) -> anyhow::Result<NotifyResult> {
+        let mount_device_active = self.indi.is_device_enabled(&self.mount_device).unwrap_or(false);
+        if !mount_device_active {
+            return Ok(NotifyResult::Empty);
+        }
+
+        let guider_options = self.guider_options.as_ref().unwrap();
+        if guider_options.dith_period == 0 {
+            return Ok(NotifyResult::Empty);
+        }
+
+        self.simple_dither.exp_sum += info.exposure;
+        if self.simple_dither.exp_sum <= (guider_options.dith_period * 60) as f64 {
+            return Ok(NotifyResult::Empty);
+        }
+        self.simple_dither.exp_sum = 0.0;
+
+        let (max_dec, max_ra) = self.indi.mount_get_timed_guide_max(&self.mount_device)?;
+        let max_dec = f64::min(MAX_TIMED_GUIDE * 1000.0, max_dec);
+        let max_ra = f64::min(MAX_TIMED_GUIDE * 1000.0, max_ra);
+
+        use rand::prelude::*;
+        let mut rng = rand::thread_rng();
+        let pulse = guider_options.simple_dith.pulse;
+        let mut rand_pulse = |max: f64| {
+            let value = (pulse * (2.0 * rng.gen::<f64>() - 1.0)).round();
+            value.clamp(-max, max)
+        };
+        let dec = rand_pulse(max_dec);
+        let ra = rand_pulse(max_ra);
+
+        self.abort()?;
+        log::info!("Simple dithering. Timed guide, NS = {:.0}ms, WE = {:.0}ms", dec, ra);
+        self.indi.mount_timed_guide(&self.mount_device, dec, ra)?;
+
+        // Wait for end of guide pulses and for mount to settle
+        let pulse_time = (f64::max(dec.abs(), ra.abs()) / 1000.0).ceil() as usize;
+        let settle_time = self.shot_delay_time(true).ceil() as usize;
+        self.simple_dither.wait_time = pulse_time + AFTER_MOUNT_MOVE_WAIT_TIME + settle_time;
+        self.state = State::SimpleDithering;
+
+        Ok(NotifyResult::ProgressChanges)
+    }
+
+    fn continue_after_simple_dithering(&mut self) -> anyhow::Result<NotifyResult> {
+        if self.simple_dither.wait_time != 0 {
+            self.simple_dither.wait_time -= 1;
+            return Ok(NotifyResult::Empty);
+        }
+        self.flags.skip_frame_done = false;
+        self.start_or_continue()?;
+        Ok(NotifyResult::ProgressChanges)
     }
 
     fn process_light_frame_info_and_dither_by_ext_guider(
@@ -654,9 +1426,16 @@ impl TackingPicturesMode {
             }
         }
 
+        if self.state == State::FlatExposureSearch && self.flags.flat_exp_found {
+            self.start_or_continue()?;
+            return Ok(NotifyResult::ProgressChanges);
+        }
+
         if self.state != State::Common
         && self.state != State::WaitingForMountCalibration
-        && self.state != State::InternalMountCorrection {
+        && self.state != State::WaitingForMeridianFlip
+        && self.state != State::InternalMountCorrection
+        && self.state != State::SimpleDithering {
             return Ok(NotifyResult::Empty);
         }
 
@@ -681,6 +1460,42 @@ impl TackingPicturesMode {
             }
         }
 
+        // Counter of rejected frames is shown in progress string
+        if self.cam_mode == CameraMode::LiveStacking
+        && matches!(result, NotifyResult::Empty) {
+            result = NotifyResult::ProgressChanges;
+        }
+
+        // Check conditions to stop saving light frames
+        if !is_last_frame && frame_is_ok {
+            if let Some(reason) = self.check_stop_conditions() {
+                log::info!("Stop condition is reached: {}", reason);
+                abort_camera_exposure(&self.indi, &self.device)?;
+                self.subscribers.notify(Event::Warning(format!(
+                    "Saving frames is stopped: {}", reason
+                )));
+                result = NotifyResult::Finished {
+                    next_mode: self.next_mode.take()
+                };
+                is_last_frame = true;
+            }
+        }
+
+        // Sky is too bright or too dark for flats now so flats session
+        // is stopped. The Sun leaves altitude range until next twilight
+        if !is_last_frame && self.flags.sky_flats {
+            let sun_alt = sun_alt_if_not_suitable_for_sky_flats(&self.options.read().unwrap());
+            if let Some(sun_alt) = sun_alt {
+                log::info!("The Sun altitude {:.1}° is out of range for sky flats", sun_alt);
+                abort_camera_exposure(&self.indi, &self.device)?;
+                self.subscribers.notify(Event::Warning(format!(
+                    "Sky flats are stopped: the Sun altitude {:.1}° is out of range", sun_alt
+                )));
+                result = NotifyResult::Finished { next_mode: None };
+                is_last_frame = true;
+            }
+        }
+
         // Save master file
         if is_last_frame && self.flags.save_master_file {
             self.save_master_file()?;
@@ -710,7 +1525,42 @@ impl TackingPicturesMode {
         }
 
         let finished = matches!(result, NotifyResult::Finished {..});
-        if !finished && self.have_to_start_new_exposure_at_processing_end() {
+        if finished {
+            self.release_flat_panel()?;
+        }
+        // Next frame is taken after meridian flip
+        if !finished && self.state == State::WaitingForMeridianFlip {
+            return Ok(NotifyResult::ProgressChanges);
+        }
+        if !finished && self.is_time_to_check_disk_space() && self.is_disk_space_low() {
+            abort_camera_exposure(&self.indi, &self.device)?;
+            self.flags.skip_frame_done = false; // will skip first frame when continue
+            self.state = State::LowDiskSpace;
+            self.disk_check_cnt = 0;
+            self.subscribers.notify(Event::Error(format!(
+                "Free disk space is less than {:.1} GB. Saving frames is paused",
+                self.options.read().unwrap().raw_frames.min_free_space
+            )));
+            return Ok(NotifyResult::ProgressChanges);
+        }
+
+        let start_at_processing_end =
+            self.have_to_start_new_exposure_at_processing_end() ||
+            std::mem::take(&mut self.flags.shot_postponed);
+
+        // Sky brightness is changing so exposure of
+        // sky flats is corrected after every frame
+        if let (false, true, Some(median)) = (finished, frame_is_ok, self.sky_flat_median.take()) {
+            self.correct_sky_flat_exposure(raw_image_info.exposure, median)?;
+        }
+
+        if !finished
+        && self.state != State::SimpleDithering
+        && start_at_processing_end {
+            if self.state == State::Common && self.shot_delay_time(false) != 0.0 {
+                self.start_or_continue_after_delay(false)?;
+                return Ok(NotifyResult::ProgressChanges);
+            }
             apply_camera_options_and_take_shot(&self.indi, &self.device, &self.cam_options.frame)?;
             self.cur_exposure = self.cam_options.frame.exposure();
         }
@@ -720,12 +1570,239 @@ impl TackingPicturesMode {
 
     const MAX_OFFSET_CALC_STEPS: usize = 8;
 
+    fn correct_sky_flat_exposure(&mut self, exposure: f64, median: f64) -> anyhow::Result<()> {
+        let options = self.options.read().unwrap();
+        let target = options.calibr.flat_adu_target;
+        let tolerance = options.calibr.flat_adu_tolerance;
+        drop(options);
+        if f64::abs(median - target) <= 0.5 * tolerance {
+            return Ok(());
+        }
+        let Some(new_exposure) = FlatExpSearch::correct_sky_flat_exposure(exposure, median, target) else {
+            anyhow::bail!(
+                "Sky brightness is out of range for flat frames \
+                (exposure = {:.4}s, median = {:.1}%)",
+                exposure, median
+            );
+        };
+        log::info!(
+            "Sky flat median = {:.1}%. Exposure is changed {:.4}s -> {:.4}s",
+            median, exposure, new_exposure
+        );
+        self.cam_options.frame.set_exposure(new_exposure);
+        Ok(())
+    }
+
+    const DISK_CHECK_PERIOD: usize = 10; // in seconds
+
+    /// Free disk space is checked not more often than once per `DISK_CHECK_PERIOD`
+    fn is_time_to_check_disk_space(&mut self) -> bool {
+        let period = Duration::from_secs(Self::DISK_CHECK_PERIOD as u64);
+        if let Some(time) = self.disk_check_time {
+            if time.elapsed() < period {
+                return false;
+            }
+        }
+        self.disk_check_time = Some(Instant::now());
+        true
+    }
+
+    /// Checks free space of disk for raw frames. Returns `true`
+    /// if it is less than minimum from options
+    fn is_disk_space_low(&self) -> bool {
+        if !self.flags.save_raw_files {
+            return false;
+        }
+        let options = self.options.read().unwrap();
+        if !options.raw_frames.pause_low_disk {
+            return false;
+        }
+        let min_free_space = (options.raw_frames.min_free_space * 1024.0 * 1024.0 * 1024.0) as u64;
+        drop(options);
+        match get_free_disk_space(&self.out_file_names.raw_files_dir) {
+            Ok(free_space) => {
+                log::debug!("Free disk space = {} MB", free_space / (1024 * 1024));
+                free_space < min_free_space
+            }
+            Err(err) => {
+                log::error!("Can't get free disk space: {}", err.to_string());
+                false
+            }
+        }
+    }
+
+    fn continue_if_disk_space_freed(&mut self) -> anyhow::Result<NotifyResult> {
+        self.disk_check_cnt += 1;
+        if self.disk_check_cnt < Self::DISK_CHECK_PERIOD {
+            return Ok(NotifyResult::Empty);
+        }
+        self.disk_check_cnt = 0;
+        if self.is_disk_space_low() {
+            return Ok(NotifyResult::Empty);
+        }
+        log::info!("Disk space is enough now. Continue saving frames");
+        self.start_or_continue()?;
+        Ok(NotifyResult::ProgressChanges)
+    }
+
+    const DAWN_SUN_ALT: f64 = -18.0; // in degrees
+
+    fn stop_conditions_used(&self) -> bool {
+        self.cam_mode == CameraMode::SavingRawFrames &&
+        self.cam_options.frame.frame_type == FrameType::Lights
+    }
+
+    /// Checks altitude of mount pointing and sun position.
+    /// Returns description of reached stop condition
+    fn check_stop_conditions(&self) -> Option<String> {
+        if !self.stop_conditions_used() {
+            return None;
+        }
+        let options = self.options.read().unwrap();
+        let raw_opts = &options.raw_frames;
+        if !raw_opts.stop_by_alt && !raw_opts.stop_at_dawn {
+            return None;
+        }
+        let latitude = degree_to_radian(options.site.latitude);
+        let longitude = degree_to_radian(options.site.longitude);
+        let utc_time = Utc::now().naive_utc();
+        let cvt = EqToSphereCvt::new(longitude, latitude, &utc_time);
+
+        if raw_opts.stop_by_alt && !self.mount_device.is_empty() {
+            match self.indi.mount_get_eq_ra_and_dec(&self.mount_device) {
+                Ok((ra, dec)) => {
+                    let crd = EqCoord {
+                        ra:  hour_to_radian(ra),
+                        dec: degree_to_radian(dec),
+                    };
+                    let h_crd = HorizCoord::from_sphere_pt(&cvt.eq_to_sphere(&crd));
+                    let alt = radian_to_degree(h_crd.alt);
+                    if alt < raw_opts.stop_alt {
+                        return Some(format!(
+                            "altitude {:.1}° is less than {:.0}°",
+                            alt, raw_opts.stop_alt
+                        ));
+                    }
+                }
+                Err(err) => {
+                    log::error!("Can't get mount coordinates: {}", err.to_string());
+                }
+            }
+        }
+
+        if raw_opts.stop_at_dawn {
+            let cur_sun_alt = calc_sun_altitude(latitude, longitude, &utc_time);
+            let next_time = utc_time + chrono::Duration::minutes(1);
+            let next_sun_alt = calc_sun_altitude(latitude, longitude, &next_time);
+            let sun_is_rising = next_sun_alt > cur_sun_alt;
+            if sun_is_rising && cur_sun_alt > Self::DAWN_SUN_ALT {
+                return Some("astronomical dawn".to_string());
+            }
+        }
+
+        None
+    }
+
+    /// Returns time when the Sun drops below altitude from options
+    /// or `None` if it is already dark
+    fn calc_dark_start_time(&self) -> anyhow::Result<Option<chrono::NaiveDateTime>> {
+        let options = self.options.read().unwrap();
+        let latitude = degree_to_radian(options.site.latitude);
+        let longitude = degree_to_radian(options.site.longitude);
+        let dark_sun_alt = options.raw_frames.dark_sun_alt;
+        drop(options);
+
+        let utc_time = Utc::now().naive_utc();
+        for minute in 0..=24*60 {
+            let time = utc_time + chrono::Duration::minutes(minute);
+            if calc_sun_altitude(latitude, longitude, &time) < dark_sun_alt {
+                return Ok(if minute == 0 { None } else { Some(time) });
+            }
+        }
+        anyhow::bail!(
+            "The Sun doesn't drop below {:.0}° during next 24 hours",
+            dark_sun_alt
+        );
+    }
+
+    fn continue_if_dark(&mut self) -> anyhow::Result<NotifyResult> {
+        let Some(dark_start_time) = self.dark_start_time else {
+            return Ok(NotifyResult::Empty);
+        };
+        if Utc::now().naive_utc() < dark_start_time {
+            return Ok(NotifyResult::ProgressChanges); // for countdown
+        }
+        log::info!("Darkness is reached. Start saving frames");
+        self.dark_start_time = None;
+        self.start_or_continue()?;
+        Ok(NotifyResult::ProgressChanges)
+    }
+
+    const LIVE_AG_DEAD_ZONE: f64 = 0.15;
+    const LIVE_AG_SMOOTH: f64 = 0.5;
+
+    /// Auto gain for live view. Used only in live view mode
+    /// so it never changes gain of frames for calibrated sequences
+    fn correct_live_view_gain(&mut self, hist: &Histogram) -> anyhow::Result<()> {
+        let ag_options = self.options.read().unwrap().cam.live_ag.clone();
+        if !ag_options.enabled || hist.max == 0 {
+            return Ok(());
+        }
+        let chan = if hist.g.is_some() { &hist.g } else { &hist.l };
+        let Some(chan) = chan else { return Ok(()); };
+        let median = chan.median() as f64;
+        let target = hist.max as f64 * ag_options.target / 100.0;
+        if median <= 0.0 || target <= 0.0 {
+            return Ok(());
+        }
+
+        // dead zone to avoid flicker of image brightness
+        let ratio = target / median;
+        if f64::abs(ratio - 1.0) < Self::LIVE_AG_DEAD_ZONE {
+            return Ok(());
+        }
+        let ratio = ratio.clamp(0.5, 2.0).powf(Self::LIVE_AG_SMOOTH);
+
+        let mut min_gain = ag_options.min_gain;
+        let mut max_gain = ag_options.max_gain;
+        if let Ok(gain_prop) = self.indi.camera_get_gain_prop_value(&self.device.name) {
+            min_gain = f64::max(min_gain, gain_prop.min);
+            max_gain = f64::min(max_gain, gain_prop.max);
+        }
+        if min_gain > max_gain {
+            return Ok(());
+        }
+
+        let cur_gain = self.cam_options.frame.gain;
+        let new_gain = (f64::max(cur_gain, 1.0) * ratio).round().clamp(min_gain, max_gain);
+        if new_gain == cur_gain {
+            return Ok(());
+        }
+
+        log::debug!(
+            "Live view auto gain: median={:.0}, target={:.0}, gain {} -> {}",
+            median, target, cur_gain, new_gain
+        );
+
+        // New gain will be applied for next exposure
+        // (camera options are taken from common options in live view mode)
+        self.options.write().unwrap().cam.frame.gain = new_gain;
+        self.cam_options.frame.gain = new_gain;
+        self.subscribers.notify(Event::LiveViewGainChanged(new_gain));
+        Ok(())
+    }
+
     fn process_raw_histogram(
         &mut self,
         hist: &Arc<RwLock<Histogram>>
     ) -> anyhow::Result<NotifyResult> {
         let mut result = NotifyResult::Empty;
 
+        if self.cam_mode == CameraMode::LiveView {
+            let hist = hist.read().unwrap();
+            self.correct_live_view_gain(&hist)?;
+        }
+
         if let (State::CameraOffsetCalculation, Some(offset_calc))
         = (&self.state, &mut self.cam_offset_calc) {
             let hist = hist.read().unwrap();
@@ -766,6 +1843,41 @@ impl TackingPicturesMode {
             }
         }
 
+        if let (State::FlatExposureSearch, Some(search))
+        = (&self.state, &mut self.flat_exp_search) {
+            let hist = hist.read().unwrap();
+            let chan = if hist.g.is_some() { &hist.g } else { &hist.l };
+            let Some(chan) = chan else {
+                anyhow::bail!("Can't calculate median of test flat frame");
+            };
+            let median = 100.0 * chan.median() as f64 / hist.max.max(1) as f64;
+            let test_exp = search.cur_exp;
+            drop(hist);
+            log::debug!("Flat exposure search: exposure={:.4}s, median={:.1}%", test_exp, median);
+            match search.process_median(median) {
+                FlatExpSearchResult::NextShot(exposure) => {
+                    self.start_flat_exp_search_shot(exposure)?;
+                }
+                FlatExpSearchResult::Found(exposure) => {
+                    log::info!("Exposure for flat frames found: {:.4}s", exposure);
+                    self.cam_options.frame.set_exposure(exposure);
+                    self.flags.flat_exp_found = true;
+                    self.generate_output_file_names()?; // names depend on exposure
+                    result = NotifyResult::ProgressChanges;
+                }
+                FlatExpSearchResult::Failed(text) => {
+                    anyhow::bail!(text);
+                }
+            }
+        }
+
+        if self.state == State::Common && self.flags.sky_flats {
+            let hist = hist.read().unwrap();
+            let chan = if hist.g.is_some() { &hist.g } else { &hist.l };
+            self.sky_flat_median = chan.as_ref()
+                .map(|chan| 100.0 * chan.median() as f64 / hist.max.max(1) as f64);
+        }
+
         Ok(result)
     }
 
@@ -810,22 +1922,159 @@ impl TackingPicturesMode {
                     self.out_file_names.raw_files_dir.to_str().unwrap_or_default()
                 ))?;
         }
-        let mut file_ext = blob.format.as_str().trim();
-        while file_ext.starts_with('.') { file_ext = &file_ext[1..]; }
+        let save_format = self.options.read().unwrap().raw_frames.save_format;
+        let mut blob_ext = blob.format.as_str().trim();
+        while blob_ext.starts_with('.') { blob_ext = &blob_ext[1..]; }
+        let is_fits_blob =
+            is_fpack_format(&blob.format) ||
+            blob_ext.eq_ignore_ascii_case("fits") ||
+            blob_ext.eq_ignore_ascii_case("fit");
+        let save_as_fits = save_format == RawSaveFormat::Fits && is_fits_blob;
+        if save_format == RawSaveFormat::Fits && !is_fits_blob {
+            log::warn!("Frame of {} format can't be saved as FITS. Saving as is", blob.format);
+        }
+        let file_ext = if save_as_fits { "fits" } else { blob_ext };
         let fn_mask = format!("{}_${{num}}.{}", prefix, file_ext);
         let mut fn_gen = self.fn_gen.lock().unwrap();
         let file_name = fn_gen.generate(&self.out_file_names.raw_files_dir, &fn_mask);
         drop(fn_gen);
 
         let tmr = TimeLogger::start();
-        std::fs::write(&file_name, blob.data.as_slice())
-            .map_err(|e| anyhow::anyhow!(
-                "Error '{}'\nwhen saving file '{}'",
-                e.to_string(),
-                file_name.to_str().unwrap_or_default()
-            ))?;
+        let save_result = if save_as_fits {
+            Self::save_blob_as_fits(&file_name, blob, raw_image_info)
+        } else {
+            std::fs::write(&file_name, blob.data.as_slice())
+                .map_err(anyhow::Error::from)
+        };
+        save_result.map_err(|e| anyhow::anyhow!(
+            "Error '{}'\nwhen saving file '{}'",
+            e.to_string(),
+            file_name.to_str().unwrap_or_default()
+        ))?;
         tmr.log("Saving raw image");
 
+        // WCS will be written into file after plate solving is finished
+        if let (true, Some(frame_ps)) = (save_as_fits, &mut self.frame_ps) {
+            if frame_ps.in_progress
+            && frame_ps.file_name.is_none()
+            && frame_ps.frame_time == raw_image_info.time {
+                frame_ps.file_name = Some(file_name.clone());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Starts plate solving of light frame if writing of WCS into
+    /// saved FITS files is enabled and previous solving is finished.
+    /// Only solvers which support stars as input are used
+    fn start_frame_plate_solve(&mut self, info: &LightFrameInfo) -> anyhow::Result<()> {
+        if self.cam_mode != CameraMode::SavingRawFrames
+        || self.cam_options.frame.frame_type != FrameType::Lights
+        || !self.flags.save_raw_files {
+            return Ok(());
+        }
+        let options = self.options.read().unwrap();
+        if !options.raw_frames.save_wcs
+        || options.raw_frames.save_format != RawSaveFormat::Fits {
+            return Ok(());
+        }
+        if self.frame_ps.is_none() {
+            let solver = PlateSolver::new(&options.plate_solver);
+            if !solver.support_stars_as_input() {
+                log::warn!("Plate solver doesn't support stars as input. WCS will not be written");
+            }
+            self.frame_ps = Some(FramePlateSolve {
+                solver,
+                in_progress: false,
+                frame_time:  None,
+                img_width:   0,
+                img_height:  0,
+                file_name:   None,
+            });
+        }
+        let mut config = PlateSolveConfig::default();
+        config.time_out = options.plate_solver.timeout;
+        config.blind_time_out = options.plate_solver.blind_timeout;
+        drop(options);
+
+        let Some(frame_ps) = &mut self.frame_ps else { return Ok(()); };
+        if frame_ps.in_progress
+        || !frame_ps.solver.support_stars_as_input()
+        || info.stars.items.is_empty() {
+            return Ok(());
+        }
+        if !self.mount_device.is_empty() {
+            if let Ok((ra, dec)) = self.indi.mount_get_eq_ra_and_dec(&self.mount_device) {
+                config.eq_coord = Some(crate::ui::sky_map::math::EqCoord {
+                    ra:  hour_to_radian(ra),
+                    dec: degree_to_radian(dec),
+                });
+            }
+        }
+        let stars_arg = PlateSolverInData::Stars {
+            stars:      &info.stars.items,
+            img_width:  info.width,
+            img_height: info.height,
+        };
+        frame_ps.solver.start(&stars_arg, &config)?;
+        frame_ps.in_progress = true;
+        frame_ps.frame_time = info.time;
+        frame_ps.img_width = info.width;
+        frame_ps.img_height = info.height;
+        frame_ps.file_name = None;
+        Ok(())
+    }
+
+    /// Writes WCS into saved FITS file when plate solving is finished
+    fn check_frame_plate_solve(&mut self) -> anyhow::Result<()> {
+        let Some(frame_ps) = &mut self.frame_ps else { return Ok(()); };
+        if !frame_ps.in_progress {
+            return Ok(());
+        }
+        let result = match frame_ps.solver.get_result()? {
+            PlateSolveResult::Waiting => return Ok(()),
+            PlateSolveResult::Done(result) => Some(result),
+            PlateSolveResult::Failed => None,
+        };
+        frame_ps.in_progress = false;
+        let Some(file_name) = frame_ps.file_name.take() else {
+            return Ok(());
+        };
+        let Some(result) = result else {
+            log::warn!("Can't plate solve {}. WCS is not written", file_name.display());
+            return Ok(());
+        };
+        let mut hdu = Header::new();
+        result.add_wcs_to_fits_header(&mut hdu, frame_ps.img_width, frame_ps.img_height);
+        let data = std::fs::read(&file_name)?;
+        let data = FitsWriter::new().add_values_to_header(&data, &hdu)?;
+        std::fs::write(&file_name, data)?;
+        log::info!("WCS is written into {}", file_name.display());
+        Ok(())
+    }
+
+    /// Saves FITS (or decompressed fpack) BLOB keeping original header
+    /// cards of camera driver. Only missing keywords are added to header
+    fn save_blob_as_fits(
+        file_name:      &Path,
+        blob:           &indi::BlobPropValue,
+        raw_image_info: &RawImageInfo,
+    ) -> anyhow::Result<()> {
+        let data = if is_fpack_format(&blob.format) {
+            Cow::Owned(decompress_fits(&blob.data)?)
+        } else {
+            Cow::Borrowed(blob.data.as_slice())
+        };
+        let reader = FitsReader::new(&mut Cursor::new(data.as_ref()))?;
+        let Some(orig_hdu) = reader.headers.first() else {
+            anyhow::bail!("FITS header is not found");
+        };
+        let mut hdu = Header::new();
+        raw_image_info.fill_fits_header(&mut hdu);
+        hdu.remove_existing_in(orig_hdu);
+        let data = FitsWriter::new().add_values_to_header(&data, &hdu)?;
+        std::fs::write(file_name, data)?;
         Ok(())
     }
 
@@ -894,7 +2143,7 @@ impl TackingPicturesMode {
 
     fn process_light_frame_info(
         &mut self,
-        info: &LightFrameInfo,
+        info: &Arc<LightFrameInfo>,
     ) -> anyhow::Result<NotifyResult> {
         if !info.stars.is_ok() {
             return Ok(NotifyResult::Empty);
@@ -904,6 +2153,20 @@ impl TackingPicturesMode {
             return Ok(NotifyResult::Empty);
         }
 
+        if let Err(err) = self.start_frame_plate_solve(info) {
+            log::error!("Can't start plate solving of frame: {}", err.to_string());
+        }
+
+        let res = self.process_light_frame_info_and_meridian_flip(info)?;
+        if matches!(&res, NotifyResult::Empty) == false {
+            return Ok(res);
+        }
+
+        let res = self.process_light_frame_info_and_recenter_after_flip(info)?;
+        if matches!(&res, NotifyResult::Empty) == false {
+            return Ok(res);
+        }
+
         let res = self.process_light_frame_info_and_refocus(info)?;
         if matches!(&res, NotifyResult::Empty) == false {
             return Ok(res);
@@ -918,6 +2181,10 @@ impl TackingPicturesMode {
                     self.process_light_frame_info_and_dither_by_main_camera(info)?,
                 GuidingMode::External =>
                     self.process_light_frame_info_and_dither_by_ext_guider(info)?,
+                GuidingMode::FrameOffset =>
+                    self.process_light_frame_info_and_dither_by_frame_offset(info)?,
+                GuidingMode::SimpleDither =>
+                    self.process_light_frame_info_and_simple_dither(info)?,
             };
             if matches!(&res, NotifyResult::Empty) == false { return Ok(res); }
         }
@@ -972,16 +2239,48 @@ impl Mode for TackingPicturesMode {
         Some(&self.device)
     }
 
+    fn is_paused(&self) -> bool {
+        self.state == State::LowDiskSpace
+    }
+
     fn progress_string(&self) -> String {
         let mut mode_str = match (&self.state, &self.cam_mode) {
             (State::FrameToSkip, _) =>
                 "First frame (will be skipped)".to_string(),
             (State::InternalMountCorrection, _) =>
                 "Mount position correction".to_string(),
-            (State::ExternalDithering, _) =>
+            (State::ExternalDithering|State::SimpleDithering, _) =>
                 "Dithering".to_string(),
             (State::CameraOffsetCalculation, _) =>
                 "Camera calibration...".to_string(),
+            (State::FlatExposureSearch, _) =>
+                "Flat exposure calculation...".to_string(),
+            (State::LowDiskSpace, _) =>
+                "Paused (low disk space)".to_string(),
+            (State::WaitingForMeridianFlip, _) =>
+                "Waiting for meridian flip...".to_string(),
+            (State::ShotDelay, _) => {
+                let secs_left = self.shot_delay
+                    .as_ref()
+                    .map(|delay| delay.end_time.saturating_duration_since(Instant::now()).as_secs_f64().ceil())
+                    .unwrap_or(0.0);
+                let settle = self.shot_delay.as_ref().map(|delay| delay.settle).unwrap_or(false);
+                if settle {
+                    format!("Settling ({:.0}s)...", secs_left)
+                } else {
+                    format!("Delay before next frame ({:.0}s)...", secs_left)
+                }
+            },
+            (State::WaitingForDarkness, _) => {
+                let secs_left = self.dark_start_time
+                    .map(|time| (time - Utc::now().naive_utc()).num_seconds())
+                    .unwrap_or(0)
+                    .max(0);
+                format!(
+                    "Waiting for darkness ({}:{:02}:{:02})",
+                    secs_left / 3600, (secs_left / 60) % 60, secs_left % 60
+                )
+            },
             (_, CameraMode::SingleShot) =>
                 "Taking shot".to_string(),
             (_, CameraMode::LiveView) =>
@@ -1003,8 +2302,16 @@ impl Mode for TackingPicturesMode {
                     "Creating master bias ({})",
                     self.get_dark_or_bias_creation_short_info()
                 ),
-            (_, CameraMode::LiveStacking) =>
-                "Live stacking".to_string(),
+            (_, CameraMode::LiveStacking) => {
+                let rejected = self.live_stacking.as_ref()
+                    .map(|ls| *ls.rejected.lock().unwrap())
+                    .unwrap_or(0);
+                if rejected != 0 {
+                    format!("Live stacking ({} rejected)", rejected)
+                } else {
+                    "Live stacking".to_string()
+                }
+            },
         };
         let mut extra_modes = Vec::new();
         if matches!(self.cam_mode, CameraMode::SavingRawFrames|CameraMode::LiveStacking)
@@ -1014,13 +2321,24 @@ impl Mode for TackingPicturesMode {
                 if focus_options.on_fwhm_change
                 || focus_options.on_temp_change
                 || focus_options.periodically {
-                    extra_modes.push("F");
+                    extra_modes.push("F".to_string());
                 }
             }
             if let Some(guid_options) = &self.guider_options {
-                extra_modes.push("G");
-                if guid_options.dith_period != 0 {
-                    extra_modes.push("D");
+                match guid_options.mode {
+                    GuidingMode::FrameOffset => if guid_options.dith_period != 0 {
+                        let (x, y) = self.cam_options.frame.crop_offset;
+                        extra_modes.push(format!("D ({:+},{:+})", x, y));
+                    }
+                    GuidingMode::SimpleDither => if guid_options.dith_period != 0 {
+                        extra_modes.push("D".to_string());
+                    }
+                    _ => {
+                        extra_modes.push("G".to_string());
+                        if guid_options.dith_period != 0 {
+                            extra_modes.push("D".to_string());
+                        }
+                    }
                 }
             }
         }
@@ -1028,11 +2346,32 @@ impl Mode for TackingPicturesMode {
             mode_str += " ";
             mode_str += &extra_modes.join(" + ");
         }
+        if self.stop_conditions_used() && self.state == State::Common {
+            let options = self.options.read().unwrap();
+            let mut conditions = Vec::new();
+            if options.raw_frames.stop_by_alt && !self.mount_device.is_empty() {
+                conditions.push(format!("alt < {:.0}°", options.raw_frames.stop_alt));
+            }
+            if options.raw_frames.stop_at_dawn {
+                conditions.push("dawn".to_string());
+            }
+            if !conditions.is_empty() {
+                mode_str += &format!(" (until {})", conditions.join(", "));
+            }
+        }
         mode_str
     }
 
     fn progress(&self) -> Option<Progress> {
-        self.progress.clone()
+        match (&self.progress, &self.seq_progress) {
+            (Some(progress), Some(seq_progress)) =>
+                Some(Progress {
+                    cur:   seq_progress.cur + progress.cur,
+                    total: seq_progress.total,
+                }),
+            _ =>
+                self.progress.clone(),
+        }
     }
 
     fn take_next_mode(&mut self) -> Option<ModeBox> {
@@ -1060,6 +2399,8 @@ impl Mode for TackingPicturesMode {
     }
 
     fn can_be_continued_after_stop(&self) -> bool {
+        // flat panel of flats session is turned off after stop
+        !self.flags.flats_session_step &&
         matches!(
             &self.cam_mode,
             CameraMode::SavingRawFrames|
@@ -1101,6 +2442,25 @@ impl Mode for TackingPicturesMode {
             *ref_stars = None;
         }
 
+        let recenter_after_flip =
+            self.cam_mode == CameraMode::SavingRawFrames &&
+            self.cam_options.frame.frame_type == FrameType::Lights &&
+            self.options.read().unwrap().mount.flip_recenter &&
+            !self.mount_device.is_empty() &&
+            self.indi.mount_is_pier_side_supported(&self.mount_device).unwrap_or(false);
+        self.flip_recenter = if recenter_after_flip {
+            Some(FlipRecenterData::default())
+        } else {
+            None
+        };
+
+        self.auto_flip =
+            self.cam_mode == CameraMode::SavingRawFrames &&
+            self.cam_options.frame.frame_type == FrameType::Lights &&
+            self.options.read().unwrap().mount.auto_flip &&
+            !self.mount_device.is_empty();
+        self.auto_flip_done = false;
+
         self.fname_utils.init(&self.indi, &self.device);
         self.generate_output_file_names()?;
 
@@ -1108,13 +2468,39 @@ impl Mode for TackingPicturesMode {
             self.raw_stacker.clear();
         }
 
-        self.start_or_continue()?;
+        if self.is_flat_panel_used() {
+            self.prepare_flat_panel()?;
+        }
+
+        let wait_for_dark =
+            self.stop_conditions_used() &&
+            self.options.read().unwrap().raw_frames.wait_for_dark;
+        if wait_for_dark {
+            self.dark_start_time = self.calc_dark_start_time()?;
+            if self.dark_start_time.is_some() {
+                self.state = State::WaitingForDarkness;
+                return Ok(());
+            }
+        }
+
+        if std::mem::take(&mut self.flags.filter_changed) {
+            self.start_or_continue_after_delay(true)?;
+        } else {
+            self.start_or_continue()?;
+        }
         Ok(())
     }
 
     fn abort(&mut self) -> anyhow::Result<()> {
         abort_camera_exposure(&self.indi, &self.device)?;
         self.flags.skip_frame_done = false; // will skip first frame when continue
+        self.release_flat_panel()?;
+        if self.flags.flats_session_step {
+            // flats session mode turns off flat panel
+            if let Some(next_mode) = &mut self.next_mode {
+                next_mode.abort()?;
+            }
+        }
         Ok(())
     }
 
@@ -1130,6 +2516,9 @@ impl Mode for TackingPicturesMode {
             let mut options = self.options.write().unwrap();
             options.cam.frame = self.cam_options.frame.clone();
         }
+        if self.is_flat_panel_used() {
+            self.prepare_flat_panel()?;
+        }
         self.start_or_continue()?;
         Ok(())
     }
@@ -1138,6 +2527,7 @@ impl Mode for TackingPicturesMode {
         if let Some(value) = value.downcast_mut::<MountMoveCalibrRes>() {
             let dith_data = self.simple_guider.get_or_insert_with(|| SimpleGuider::new());
             dith_data.mnt_calibr = Some(value.clone());
+            dith_data.calibr_binning = Some(self.cam_options.frame.binning);
             log::debug!("New mount calibration set: {:?}", dith_data.mnt_calibr);
         }
     }
@@ -1159,9 +2549,15 @@ impl Mode for TackingPicturesMode {
         }
 
         if self.have_to_start_new_exposure_at_blob_start() {
-            apply_camera_options_and_take_shot(&self.indi, &self.device, &self.cam_options.frame)?;
-            self.cur_exposure = self.cam_options.frame.exposure();
+            if self.exposure_crosses_flip_limit() {
+                // Meridian flip is checked after frame processing
+                self.flags.shot_postponed = true;
+            } else {
+                apply_camera_options_and_take_shot(&self.indi, &self.device, &self.cam_options.frame)?;
+                self.cur_exposure = self.cam_options.frame.exposure();
+            }
         }
+        self.refocus.cur_exp_time = 0.0;
 
         Ok(NotifyResult::Empty)
     }
@@ -1260,9 +2656,14 @@ impl Mode for TackingPicturesMode {
                 && guid_data.cur_timed_guide_w == 0.0
                 && guid_data.cur_timed_guide_e == 0.0 {
                     self.indi.mount_abort_motion(&self.mount_device)?;
-                    apply_camera_options_and_take_shot(&self.indi, &self.device, &self.cam_options.frame)?;
-                    self.cur_exposure = self.cam_options.frame.exposure();
-                    self.state = State::Common;
+                    if std::mem::take(&mut self.flags.dither_settle)
+                    && self.shot_delay_time(true) != 0.0 {
+                        self.start_or_continue_after_delay(true)?;
+                    } else {
+                        apply_camera_options_and_take_shot(&self.indi, &self.device, &self.cam_options.frame)?;
+                        self.cur_exposure = self.cam_options.frame.exposure();
+                        self.state = State::Common;
+                    }
                     result = NotifyResult::ProgressChanges;
                 }
             }
@@ -1270,6 +2671,28 @@ impl Mode for TackingPicturesMode {
         Ok(result)
     }
 
+    fn notify_timer_1s(&mut self) -> anyhow::Result<NotifyResult> {
+        if let Err(err) = self.check_frame_plate_solve() {
+            log::error!("Can't write WCS into frame file: {}", err.to_string());
+        }
+        if self.state == State::LowDiskSpace {
+            return self.continue_if_disk_space_freed();
+        }
+        if self.state == State::ShotDelay {
+            return self.continue_after_shot_delay();
+        }
+        if self.state == State::WaitingForDarkness {
+            return self.continue_if_dark();
+        }
+        if self.state == State::WaitingForMeridianFlip {
+            return self.continue_after_flip_limit();
+        }
+        if self.state == State::SimpleDithering {
+            return self.continue_after_simple_dithering();
+        }
+        self.check_refocus_during_exposure()
+    }
+
     fn notify_guider_event(
         &mut self,
         event: ExtGuiderEvent
@@ -1280,7 +2703,7 @@ impl Mode for TackingPicturesMode {
                 match event {
                     ExtGuiderEvent::DitheringFinished => {
                         self.flags.skip_frame_done = false;
-                        self.start_or_continue()?;
+                        self.start_or_continue_after_delay(true)?;
                         return Ok(NotifyResult::ProgressChanges);
                     }
                     ExtGuiderEvent::Error(error) =>
@@ -1292,3 +2715,44 @@ impl Mode for TackingPicturesMode {
         Ok(NotifyResult::Empty)
     }
 }
+
+#[test]
+fn test_flat_exp_search() {
+    // Linear sensor: median is 20% at 1s exposure
+    let median_for_exp = |exp: f64| f64::min(20.0 * exp, 100.0);
+
+    let mut search = FlatExpSearch::new(1.0, 50.0, 5.0);
+    let mut exposure = search.cur_exp;
+    let mut found = None;
+    for _ in 0..FlatExpSearch::MAX_ITERATIONS {
+        match search.process_median(median_for_exp(exposure)) {
+            FlatExpSearchResult::NextShot(exp) => exposure = exp,
+            FlatExpSearchResult::Found(exp) => { found = Some(exp); break; }
+            FlatExpSearchResult::Failed(text) => panic!("{}", text),
+        }
+    }
+    let found = found.unwrap();
+    assert!(f64::abs(median_for_exp(found) - 50.0) <= 5.0);
+
+    // Light source is too dark
+    let mut search = FlatExpSearch::new(1.0, 50.0, 5.0);
+    let mut result = FlatExpSearchResult::NextShot(1.0);
+    for _ in 0..FlatExpSearch::MAX_ITERATIONS {
+        result = search.process_median(1.0);
+        if !matches!(result, FlatExpSearchResult::NextShot(_)) { break; }
+    }
+    assert!(matches!(result, FlatExpSearchResult::Failed(_)));
+}
+
+#[test]
+fn test_correct_sky_flat_exposure() {
+    // Sky is getting darker: twice longer exposure is needed
+    let exposure = FlatExpSearch::correct_sky_flat_exposure(2.0, 25.0, 50.0);
+    assert!(exposure == Some(4.0));
+
+    // Sky is too dark for maximum exposure
+    let exposure = FlatExpSearch::correct_sky_flat_exposure(40.0, 10.0, 50.0);
+    assert!(exposure.is_none());
+
+    assert!(FlatExpSearch::correct_sky_flat_exposure(1.0, 0.0, 50.0).is_none());
+}
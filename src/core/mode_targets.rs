@@ -0,0 +1,270 @@
+use std::{path::Path, sync::{Arc, RwLock}};
+use chrono::Utc;
+use crate::{indi, options::*, ui::sky_map::math::*};
+use super::{core::*, events::Progress};
+
+#[derive(Clone)]
+pub struct TargetItem {
+    pub name:     String,
+    pub eq_coord: EqCoord, // J2000
+    pub exposure: f64,
+    pub count:    usize,
+}
+
+/// Loads target list from CSV or text file. Every line
+/// contains name, RA (hours), Dec (degrees), exposure (seconds)
+/// and frames count separated by comma, semicolon or tab.
+/// Lines beginning with `#` and header line are ignored
+pub fn load_targets_from_file(file_name: &Path) -> anyhow::Result<Vec<TargetItem>> {
+    let text = std::fs::read_to_string(file_name)?;
+    parse_targets(&text)
+}
+
+fn parse_targets(text: &str) -> anyhow::Result<Vec<TargetItem>> {
+    let mut result = Vec::new();
+    let mut first_line = true;
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let is_first_line = first_line;
+        first_line = false;
+
+        let line_num = idx + 1;
+        let separator = if line.contains(';') {
+            ';'
+        } else if line.contains('\t') {
+            '\t'
+        } else {
+            ','
+        };
+        let fields: Vec<_> = line.split(separator).map(str::trim).collect();
+        if fields.len() < 5 {
+            anyhow::bail!(
+                "Line {}: name, RA, Dec, exposure and count expected",
+                line_num
+            );
+        }
+
+        let ra = indi::parse_coord_value(fields[1], 0.0, 24.0);
+        let dec = indi::parse_coord_value(fields[2], -90.0, 90.0);
+        if is_first_line && ra.is_none() && dec.is_none() {
+            // header
+            continue;
+        }
+        let Some(ra) = ra else {
+            anyhow::bail!("Line {}: wrong RA value {}", line_num, fields[1]);
+        };
+        let Some(dec) = dec else {
+            anyhow::bail!("Line {}: wrong Dec value {}", line_num, fields[2]);
+        };
+        let exposure = fields[3].replace(',', ".").parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite() && *v > 0.0);
+        let Some(exposure) = exposure else {
+            anyhow::bail!("Line {}: wrong exposure value {}", line_num, fields[3]);
+        };
+        let count = fields[4].parse::<usize>().ok().filter(|v| *v != 0);
+        let Some(count) = count else {
+            anyhow::bail!("Line {}: wrong frames count {}", line_num, fields[4]);
+        };
+        let name = if !fields[0].is_empty() {
+            fields[0].to_string()
+        } else {
+            format!("Target{}", result.len() + 1)
+        };
+
+        result.push(TargetItem {
+            name,
+            eq_coord: EqCoord {
+                ra:  hour_to_radian(ra),
+                dec: degree_to_radian(dec),
+            },
+            exposure,
+            count,
+        });
+    }
+
+    if result.is_empty() {
+        anyhow::bail!("Target list is empty");
+    }
+
+    Ok(result)
+}
+
+#[derive(PartialEq)]
+enum State {
+    Undefined,
+    WaitingForGoto,
+    WaitingForCapture,
+}
+
+pub struct TargetsMode {
+    indi:      Arc<indi::Connection>,
+    targets:   Vec<TargetItem>,
+    min_alt:   Option<f64>,
+    latitude:  f64,
+    longitude: f64,
+    index:     usize,
+    state:     State,
+    dome:      Option<String>, // dome to park after last target
+}
+
+impl TargetsMode {
+    pub fn new(
+        options: &Arc<RwLock<Options>>,
+        indi:    &Arc<indi::Connection>,
+        targets: &[TargetItem],
+    ) -> anyhow::Result<Self> {
+        let opts = options.read().unwrap();
+        if opts.cam.device.is_none() {
+            anyhow::bail!("Camera is not selected");
+        }
+        if opts.mount.device.is_empty() {
+            anyhow::bail!("Mount is not selected");
+        }
+        if targets.is_empty() {
+            anyhow::bail!("Target list is empty");
+        }
+
+        let min_alt = if opts.targets.skip_low {
+            Some(degree_to_radian(opts.targets.min_alt))
+        } else {
+            None
+        };
+
+        let dome = if opts.dome.park_at_end && !opts.dome.device.is_empty() {
+            Some(opts.dome.device.clone())
+        } else {
+            None
+        };
+
+        Ok(Self {
+            indi:      Arc::clone(indi),
+            targets:   targets.to_vec(),
+            latitude:  degree_to_radian(opts.site.latitude),
+            longitude: degree_to_radian(opts.site.longitude),
+            index:     0,
+            state:     State::Undefined,
+            min_alt,
+            dome,
+        })
+    }
+
+    fn park_dome(&self) -> anyhow::Result<()> {
+        let Some(dome) = &self.dome else { return Ok(()); };
+        log::info!("Target list is finished. Parking dome {}...", dome);
+        self.indi.dome_set_parked(dome, true, true, None)?;
+        if self.indi.dome_is_shutter_supported(dome)? {
+            log::info!("Closing shutter of dome {}...", dome);
+            self.indi.dome_close_shutter(dome, true, None)?;
+        }
+        Ok(())
+    }
+
+    fn is_target_low(&self, target: &TargetItem, crd: &EqCoord) -> bool {
+        let Some(min_alt) = self.min_alt else { return false; };
+        let time = Utc::now().naive_utc();
+        let cvt = EqToSphereCvt::new(self.longitude, self.latitude, &time);
+        let h_crd = HorizCoord::from_sphere_pt(&cvt.eq_to_sphere(crd));
+        if h_crd.alt < min_alt {
+            log::info!(
+                "Target {} is skipped: altitude {:.1}° is below {:.1}°",
+                target.name,
+                radian_to_degree(h_crd.alt),
+                radian_to_degree(min_alt),
+            );
+            return true;
+        }
+        false
+    }
+}
+
+impl Mode for TargetsMode {
+    fn get_type(&self) -> ModeType {
+        ModeType::Targets
+    }
+
+    fn progress_string(&self) -> String {
+        match self.targets.get(self.index) {
+            Some(target) => format!("Target list: {}", target.name),
+            None => "Target list".to_string(),
+        }
+    }
+
+    fn can_be_stopped(&self) -> bool {
+        true
+    }
+
+    fn progress(&self) -> Option<Progress> {
+        Some(Progress {
+            cur: self.index,
+            total: self.targets.len(),
+        })
+    }
+
+    fn start(&mut self) -> anyhow::Result<()> {
+        self.state = State::Undefined;
+        Ok(())
+    }
+
+    fn notify_timer_1s(&mut self) -> anyhow::Result<NotifyResult> {
+        match self.state {
+            State::Undefined => {
+                let Some(target) = self.targets.get(self.index) else {
+                    self.park_dome()?;
+                    return Ok(NotifyResult::Finished { next_mode: None });
+                };
+
+                let j2000 = j2000_time();
+                let time = Utc::now().naive_utc();
+                let epoch_cvt = EpochCvt::new(&j2000, &time);
+                let crd = epoch_cvt.convert_eq(&target.eq_coord);
+
+                if self.is_target_low(target, &crd) {
+                    self.index += 1;
+                    return Ok(NotifyResult::ProgressChanges);
+                }
+
+                log::info!("Starting target {}", target.name);
+                self.state = State::WaitingForGoto;
+                Ok(NotifyResult::StartTargetGoto(crd))
+            }
+
+            State::WaitingForGoto => {
+                self.state = State::WaitingForCapture;
+                let target = self.targets[self.index].clone();
+                Ok(NotifyResult::StartTargetCapture(target))
+            }
+
+            State::WaitingForCapture => {
+                self.index += 1;
+                self.state = State::Undefined;
+                Ok(NotifyResult::ProgressChanges)
+            }
+        }
+    }
+}
+
+#[test]
+fn test_parse_targets() {
+    let targets = parse_targets(
+        "# comment\n\
+         Name;RA;Dec;Exposure;Count\n\
+         M31;00:42:44;+41:16:09;60;10\n\
+         M42;5.588;-5,39;30,5;20\n"
+    ).unwrap();
+    assert!(targets.len() == 2);
+    assert!(targets[0].name == "M31");
+    assert!(f64::abs(targets[0].eq_coord.dec - degree_to_radian(41.269167)) < 1e-6);
+    assert!(targets[1].exposure == 30.5);
+    assert!(targets[1].count == 20);
+
+    let targets = parse_targets("M45,3.79,24.1,120,5").unwrap();
+    assert!(targets.len() == 1);
+
+    assert!(parse_targets("M31,25:00:00,41.2,60,10").is_err());
+    assert!(parse_targets("M31,0.7,41.2,60").is_err());
+    assert!(parse_targets("M31,0.7,41.2,60,10\nM32,0.7,95.0,60,10").is_err());
+}
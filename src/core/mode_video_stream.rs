@@ -0,0 +1,129 @@
+use std::sync::{Arc, RwLock};
+
+use crate::{indi, options::*};
+
+use super::{consts::*, core::*, frame_processing::*};
+
+/// Video stream of camera for fast framing and focusing.
+/// Frames are shown in preview only and never saved
+pub struct VideoStreamMode {
+    indi:       Arc<indi::Connection>,
+    device:     DeviceAndProp,
+    exposure:   f64,
+    gain:       f64,
+    frame_size: Option<(usize, usize)>,
+    frames_cnt: usize, // frames received during last second
+    fps:        usize,
+}
+
+impl VideoStreamMode {
+    pub fn new(
+        indi:    &Arc<indi::Connection>,
+        options: &Arc<RwLock<Options>>,
+    ) -> anyhow::Result<Self> {
+        let opts = options.read().unwrap();
+        let Some(camera) = &opts.cam.device else {
+            anyhow::bail!("Camera is not selected");
+        };
+
+        // Stream frames always come via primary CCD BLOB
+        let device = DeviceAndProp {
+            name:  camera.name.clone(),
+            prop:  "CCD1".to_string(),
+            index: 0,
+        };
+
+        Ok(Self {
+            indi:       Arc::clone(indi),
+            exposure:   opts.cam.frame.exp_main,
+            gain:       opts.cam.frame.gain,
+            frame_size: None,
+            frames_cnt: 0,
+            fps:        0,
+            device,
+        })
+    }
+}
+
+impl Mode for VideoStreamMode {
+    fn get_type(&self) -> ModeType {
+        ModeType::VideoStream
+    }
+
+    fn progress_string(&self) -> String {
+        format!("Video stream ({} fps)", self.fps)
+    }
+
+    fn cam_device(&self) -> Option<&DeviceAndProp> {
+        Some(&self.device)
+    }
+
+    fn start(&mut self) -> anyhow::Result<()> {
+        let cam_name = &self.device.name;
+        if !self.indi.camera_is_video_stream_supported(cam_name)? {
+            anyhow::bail!("Camera {} doesn't support video streaming", cam_name);
+        }
+        if self.indi.camera_is_gain_supported(cam_name)? {
+            self.indi.camera_set_gain(cam_name, self.gain, false, INDI_SET_PROP_TIMEOUT)?;
+        }
+        self.indi.camera_set_stream_exposure(cam_name, self.exposure, false, INDI_SET_PROP_TIMEOUT)?;
+        self.indi.camera_set_raw_stream_encoder(cam_name, false, INDI_SET_PROP_TIMEOUT)?;
+        self.frame_size = self.indi.camera_get_stream_frame_size(cam_name).ok();
+        self.indi.camera_start_video_stream(cam_name, true, INDI_SET_PROP_TIMEOUT)?;
+        Ok(())
+    }
+
+    fn abort(&mut self) -> anyhow::Result<()> {
+        if self.indi.camera_is_video_stream_supported(&self.device.name).unwrap_or(false) {
+            self.indi.camera_stop_video_stream(&self.device.name, true, INDI_SET_PROP_TIMEOUT)?;
+        }
+        Ok(())
+    }
+
+    fn complete_img_process_params(&self, cmd: &mut FrameProcessCommandData) {
+        // Video frames are shown as is, without calibration and quality checks
+        cmd.calibr_params = None;
+        cmd.quality_options = None;
+        cmd.frame_options.offset = 0; // offset of camera is not applicable to 8-bit stream
+
+        let ImageSource::Blob(blob) = &cmd.img_source else { return; };
+        if !blob.format.trim().eq_ignore_ascii_case(".stream") {
+            return;
+        }
+        let frame_size = self.indi
+            .camera_get_stream_frame_size(&self.device.name)
+            .ok()
+            .or(self.frame_size);
+        let Some((width, height)) = frame_size else {
+            log::error!(
+                "Size of video stream frame of {} is unknown",
+                self.device.name
+            );
+            return;
+        };
+        cmd.img_source = ImageSource::VideoFrame {
+            blob: Arc::clone(blob),
+            width,
+            height,
+        };
+    }
+
+    fn notify_about_frame_processing_result(
+        &mut self,
+        fp_result: &FrameProcessResult
+    ) -> anyhow::Result<NotifyResult> {
+        if let FrameProcessResultData::PreviewFrame(_) = &fp_result.data {
+            self.frames_cnt += 1;
+        }
+        Ok(NotifyResult::Empty)
+    }
+
+    fn notify_timer_1s(&mut self) -> anyhow::Result<NotifyResult> {
+        let fps = std::mem::take(&mut self.frames_cnt);
+        if fps == self.fps {
+            return Ok(NotifyResult::Empty);
+        }
+        self.fps = fps;
+        Ok(NotifyResult::ProgressChanges)
+    }
+}
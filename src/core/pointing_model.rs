@@ -0,0 +1,206 @@
+use serde::{Deserialize, Serialize};
+use crate::ui::sky_map::math::*;
+
+/// Max number of sync points in model. Oldest points are removed first
+const MAX_POINTS: usize = 50;
+
+/// Only points closer than this distance to goto destination are used
+const MAX_POINT_DIST: f64 = 60.0; // in degrees
+
+/// Max deviation of model scale from 1.0. Model with bigger
+/// deviation is treated as invalid and only offset is applied
+const MAX_SCALE_DEVIATION: f64 = 0.1;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct SyncPoint {
+    pub cmd_ra:     f64, // commanded coordinate, in radian
+    pub cmd_dec:    f64, // commanded coordinate, in radian
+    pub solved_ra:  f64, // plate solved coordinate, in radian
+    pub solved_dec: f64, // plate solved coordinate, in radian
+}
+
+/// Software pointing model for mounts without internal alignment model.
+/// Built from (commanded, plate solved) coordinate pairs and stored
+/// for every mount device separately
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct PointingModel {
+    pub device: String,
+    pub points: Vec<SyncPoint>,
+}
+
+impl PointingModel {
+    pub fn new(device: &str) -> Self {
+        Self {
+            device: device.to_string(),
+            points: Vec::new(),
+        }
+    }
+
+    pub fn add_point(&mut self, commanded: &EqCoord, solved: &EqCoord) {
+        self.points.push(SyncPoint {
+            cmd_ra:     commanded.ra,
+            cmd_dec:    commanded.dec,
+            solved_ra:  solved.ra,
+            solved_dec: solved.dec,
+        });
+        if self.points.len() > MAX_POINTS {
+            let to_remove = self.points.len() - MAX_POINTS;
+            self.points.drain(..to_remove);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+
+    /// Returns coordinate to be sent to mount to point it at `dest`.
+    /// Offset, scale and rotation are fitted by least squares in tangent
+    /// plane with center in `dest`. Returns `None` if there are no
+    /// points near destination
+    pub fn correct(&self, dest: &EqCoord) -> Option<EqCoord> {
+        let max_dist = degree_to_radian(MAX_POINT_DIST);
+        let pairs: Vec<_> = self.points.iter()
+            .filter_map(|pt| {
+                let cmd = EqCoord { ra: pt.cmd_ra, dec: pt.cmd_dec };
+                let solved = EqCoord { ra: pt.solved_ra, dec: pt.solved_dec };
+                if EqCoord::angle_between(dest, &cmd) > max_dist
+                || EqCoord::angle_between(dest, &solved) > max_dist {
+                    return None;
+                }
+                let cmd = project_to_tangent_plane(dest, &cmd)?;
+                let solved = project_to_tangent_plane(dest, &solved)?;
+                Some((cmd, solved))
+            })
+            .collect();
+        if pairs.is_empty() {
+            return None;
+        }
+
+        // solved = a * cmd + b, where a and b are complex numbers
+        // (a is scale and rotation, b is offset)
+
+        let cnt = pairs.len() as f64;
+        let cmd_x = pairs.iter().map(|(c, _)| c.0).sum::<f64>() / cnt;
+        let cmd_y = pairs.iter().map(|(c, _)| c.1).sum::<f64>() / cnt;
+        let sol_x = pairs.iter().map(|(_, s)| s.0).sum::<f64>() / cnt;
+        let sol_y = pairs.iter().map(|(_, s)| s.1).sum::<f64>() / cnt;
+
+        let mut num_re = 0.0;
+        let mut num_im = 0.0;
+        let mut den = 0.0;
+        for (c, s) in &pairs {
+            let (cx, cy) = (c.0 - cmd_x, c.1 - cmd_y);
+            let (sx, sy) = (s.0 - sol_x, s.1 - sol_y);
+            num_re += sx * cx + sy * cy;
+            num_im += sy * cx - sx * cy;
+            den += cx * cx + cy * cy;
+        }
+
+        let (mut a_re, mut a_im) = (1.0, 0.0);
+        if pairs.len() >= 2 && den > 1e-12 {
+            let (re, im) = (num_re / den, num_im / den);
+            let scale = f64::sqrt(re * re + im * im);
+            if f64::abs(scale - 1.0) <= MAX_SCALE_DEVIATION {
+                (a_re, a_im) = (re, im);
+            } else {
+                log::warn!("Pointing model scale {:.3} is out of range. Only offset is used", scale);
+            }
+        }
+
+        let b_re = sol_x - (a_re * cmd_x - a_im * cmd_y);
+        let b_im = sol_y - (a_re * cmd_y + a_im * cmd_x);
+
+        // dest is center of tangent plane so a * cmd + b = 0 => cmd = -b / a
+        let a_sqr = a_re * a_re + a_im * a_im;
+        let x = -(b_re * a_re + b_im * a_im) / a_sqr;
+        let y = -(b_im * a_re - b_re * a_im) / a_sqr;
+
+        Some(unproject_from_tangent_plane(dest, x, y))
+    }
+}
+
+fn project_to_tangent_plane(center: &EqCoord, crd: &EqCoord) -> Option<(f64, f64)> {
+    let d_ra = crd.ra - center.ra;
+    let cos_c =
+        f64::sin(center.dec) * f64::sin(crd.dec) +
+        f64::cos(center.dec) * f64::cos(crd.dec) * f64::cos(d_ra);
+    if cos_c <= 0.0 {
+        return None;
+    }
+    let x = f64::cos(crd.dec) * f64::sin(d_ra) / cos_c;
+    let y = (
+        f64::cos(center.dec) * f64::sin(crd.dec) -
+        f64::sin(center.dec) * f64::cos(crd.dec) * f64::cos(d_ra)
+    ) / cos_c;
+    Some((x, y))
+}
+
+fn unproject_from_tangent_plane(center: &EqCoord, x: f64, y: f64) -> EqCoord {
+    let rho = f64::sqrt(x * x + y * y);
+    if rho == 0.0 {
+        return *center;
+    }
+    let c = f64::atan(rho);
+    let dec = f64::asin(
+        f64::cos(c) * f64::sin(center.dec) +
+        y * f64::sin(c) * f64::cos(center.dec) / rho
+    );
+    let mut ra = center.ra + f64::atan2(
+        x * f64::sin(c),
+        rho * f64::cos(center.dec) * f64::cos(c) - y * f64::sin(center.dec) * f64::sin(c)
+    );
+    ra = ra.rem_euclid(2.0 * std::f64::consts::PI);
+    EqCoord { ra, dec }
+}
+
+#[test]
+fn test_pointing_model() {
+    let dest = EqCoord { ra: hour_to_radian(5.5), dec: degree_to_radian(30.0) };
+
+    // empty model
+    let mut model = PointingModel::new("Mount");
+    assert!(model.correct(&dest).is_none());
+
+    // mount points with known offset, rotation and scale
+    let angle = degree_to_radian(0.5);
+    let scale = 1.003;
+    let (off_x, off_y) = (degree_to_radian(0.3), degree_to_radian(-0.2));
+    let mount_err = |x: f64, y: f64| {
+        let xr = scale * (x * f64::cos(angle) - y * f64::sin(angle)) + off_x;
+        let yr = scale * (x * f64::sin(angle) + y * f64::cos(angle)) + off_y;
+        (xr, yr)
+    };
+    for (dx, dy) in [(-5.0, -5.0), (5.0, -3.0), (4.0, 6.0), (-6.0, 4.0), (0.0, 1.0)] {
+        let (x, y) = (degree_to_radian(dx), degree_to_radian(dy));
+        let (sx, sy) = mount_err(x, y);
+        let cmd = unproject_from_tangent_plane(&dest, x, y);
+        let solved = unproject_from_tangent_plane(&dest, sx, sy);
+        model.add_point(&cmd, &solved);
+    }
+    let corrected = model.correct(&dest).unwrap();
+    let (cx, cy) = project_to_tangent_plane(&dest, &corrected).unwrap();
+    let (rx, ry) = mount_err(cx, cy);
+    let residual = unproject_from_tangent_plane(&dest, rx, ry);
+    assert!(radian_to_degree(EqCoord::angle_between(&residual, &dest)) * 3600.0 < 1.0);
+
+    // single point gives only offset
+    model.clear();
+    let cmd = EqCoord { ra: dest.ra, dec: dest.dec };
+    let solved = EqCoord { ra: dest.ra, dec: dest.dec + degree_to_radian(0.5) };
+    model.add_point(&cmd, &solved);
+    let corrected = model.correct(&dest).unwrap();
+    assert!(f64::abs(radian_to_degree(corrected.dec - dest.dec) + 0.5) < 0.001);
+    assert!(f64::abs(corrected.ra - dest.ra) < 1e-6);
+
+    // far points are ignored
+    let far = EqCoord { ra: dest.ra + degree_to_radian(180.0), dec: -dest.dec };
+    assert!(model.correct(&far).is_none());
+
+    // number of points is limited
+    for _ in 0..2*MAX_POINTS {
+        model.add_point(&cmd, &solved);
+    }
+    assert_eq!(model.points.len(), MAX_POINTS);
+}
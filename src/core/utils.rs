@@ -2,7 +2,11 @@ use std::{path::{Path, PathBuf}, sync::Arc};
 
 use chrono::{DateTime, Utc};
 
-use crate::{image::raw::*, indi, options::*, ui::sky_map::math::{degree_to_radian, hour_to_radian, radian_to_degree, EqCoord}};
+use crate::{core::consts::INDI_SET_PROP_TIMEOUT, image::raw::*, indi, options::*, ui::sky_map::math::{degree_to_radian, hour_to_radian, radian_to_degree, EqCoord}};
+
+/// Dark current doubles every such number of °C
+/// (typical value for CMOS sensors)
+pub const DARK_CURRENT_DOUBLING_TEMP: f64 = 6.5;
 
 pub enum FileNameArg<'a> {
     Options(&'a CamOptions),
@@ -25,6 +29,55 @@ impl<'a> FileNameArg<'a> {
     }
 }
 
+struct MasterDarkItem {
+    file_name:   PathBuf,
+    exposure:    f64,
+    temperature: Option<f64>,
+}
+
+/// Tolerances of master dark automatic selection.
+/// Gain, offset, size and binning must be same
+#[derive(Debug, Clone)]
+pub struct DarkMatchTolerance {
+    pub temperature: f64, // °C
+    pub exposure:    f64, // %
+}
+
+impl Default for DarkMatchTolerance {
+    fn default() -> Self {
+        Self {
+            temperature: 2.0,
+            exposure:    10.0,
+        }
+    }
+}
+
+impl DarkMatchTolerance {
+    pub fn is_acceptable(
+        &self,
+        dark_exp:   f64,
+        dark_temp:  Option<f64>,
+        light_exp:  f64,
+        light_temp: Option<f64>,
+    ) -> bool {
+        if dark_exp <= 0.0 || light_exp <= 0.0 {
+            return false;
+        }
+        let exp_diff = 100.0 * f64::abs(dark_exp - light_exp) / light_exp;
+        if exp_diff > self.exposure + 1e-6 {
+            return false;
+        }
+        match (dark_temp, light_temp) {
+            (Some(dark_temp), Some(light_temp)) =>
+                f64::abs(dark_temp - light_temp) <= self.temperature + 1e-6,
+            (None, None) =>
+                true,
+            _ =>
+                false,
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct FileNameUtils {
     device: DeviceAndProp,
@@ -192,6 +245,138 @@ impl FileNameUtils {
         (master_fname, master_calibr_method)
     }
 
+    /// Searches master dark in dark library with same gain, offset, size and
+    /// binning as `info` but closest exposure and temperature
+    pub fn find_nearest_master_dark(
+        info:          &RawImageInfo,
+        dark_lib_path: &Path,
+    ) -> Option<PathBuf> {
+        Self::master_darks_with_same_params(info, dark_lib_path)
+            .into_iter()
+            .filter(|dark| dark.exposure > 0.0 && info.exposure > 0.0)
+            .map(|dark| (Self::master_dark_distance(&dark, info), dark.file_name))
+            .min_by(|(dist1, _), (dist2, _)| f64::total_cmp(dist1, dist2))
+            .map(|(_, path)| path)
+    }
+
+    /// Searches master dark in dark library with same gain, offset, size and
+    /// binning as `info`, temperature and exposure within `tolerance`.
+    /// Closest one is returned if there are several acceptable master darks
+    pub fn find_matching_master_dark(
+        info:          &RawImageInfo,
+        dark_lib_path: &Path,
+        tolerance:     &DarkMatchTolerance,
+    ) -> Option<PathBuf> {
+        Self::master_darks_with_same_params(info, dark_lib_path)
+            .into_iter()
+            .filter(|dark| tolerance.is_acceptable(
+                dark.exposure, dark.temperature,
+                info.exposure, info.ccd_temp
+            ))
+            .map(|dark| (Self::master_dark_distance(&dark, info), dark.file_name))
+            .min_by(|(dist1, _), (dist2, _)| f64::total_cmp(dist1, dist2))
+            .map(|(_, path)| path)
+    }
+
+    /// Searches master bias in dark library with same gain, offset, size and
+    /// binning as `info`. Bias with closest temperature is used if there are several
+    pub fn find_master_bias(
+        info:          &RawImageInfo,
+        dark_lib_path: &Path,
+    ) -> Option<PathBuf> {
+        let mut dir = PathBuf::from(dark_lib_path);
+        dir.push(&info.camera);
+        let mut prefix = format!(
+            "{}_g{}_offs{}_{}x{}",
+            Self::type_part_of_file_name(FrameType::Biases),
+            info.gain, info.offset, info.width, info.height
+        );
+        if info.bin != 1 {
+            prefix += "_";
+            prefix += &Self::bin_to_str(info.bin as i32);
+        }
+        let mut best: Option<(f64, PathBuf)> = None;
+        for entry in std::fs::read_dir(&dir).ok()?.flatten() {
+            let path = entry.path();
+            let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else { continue; };
+            let Some(rest) = file_name
+                .strip_prefix(&prefix)
+                .and_then(|n| n.strip_suffix(".fit")) else { continue; };
+            let temperature = if rest.is_empty() {
+                None
+            } else if let Some(temp_str) = rest.strip_prefix('_').and_then(|t| t.strip_suffix('C')) {
+                let Ok(temperature) = temp_str.parse::<f64>() else { continue; };
+                Some(temperature)
+            } else {
+                continue;
+            };
+            let dist = match (temperature, info.ccd_temp) {
+                (Some(t1), Some(t2)) => f64::abs(t1 - t2),
+                _ => 0.0,
+            };
+            if best.as_ref().map(|(best_dist, _)| dist < *best_dist).unwrap_or(true) {
+                best = Some((dist, path));
+            }
+        }
+        best.map(|(_, path)| path)
+    }
+
+    /// Distance in terms of dark current ratio
+    fn master_dark_distance(dark: &MasterDarkItem, info: &RawImageInfo) -> f64 {
+        let exp_dist = if dark.exposure > 0.0 && info.exposure > 0.0 {
+            f64::abs(f64::ln(dark.exposure / info.exposure))
+        } else {
+            0.0
+        };
+        let temp_dist = match (dark.temperature, info.ccd_temp) {
+            (Some(t1), Some(t2)) =>
+                f64::abs(t1 - t2) * std::f64::consts::LN_2 / DARK_CURRENT_DOUBLING_TEMP,
+            _ =>
+                0.0,
+        };
+        exp_dist + temp_dist
+    }
+
+    fn master_darks_with_same_params(
+        info:          &RawImageInfo,
+        dark_lib_path: &Path,
+    ) -> Vec<MasterDarkItem> {
+        let mut dir = PathBuf::from(dark_lib_path);
+        dir.push(&info.camera);
+        let mut same_part = format!(
+            "_g{}_offs{}_{}x{}",
+            info.gain, info.offset, info.width, info.height
+        );
+        if info.bin != 1 {
+            same_part += "_";
+            same_part += &Self::bin_to_str(info.bin as i32);
+        }
+        let prefix = format!("{}_", Self::type_part_of_file_name(FrameType::Darks));
+
+        let Ok(dir_iter) = std::fs::read_dir(&dir) else { return Vec::new(); };
+        let mut result = Vec::new();
+        for entry in dir_iter.flatten() {
+            let path = entry.path();
+            let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else { continue; };
+            let Some(name) = file_name
+                .strip_prefix(&prefix)
+                .and_then(|n| n.strip_suffix(".fit")) else { continue; };
+            let Some((exp_str, rest)) = name.split_once('_') else { continue; };
+            let Some(rest) = rest.strip_prefix(&same_part[1..]) else { continue; };
+            let temperature = if rest.is_empty() {
+                None
+            } else if let Some(temp_str) = rest.strip_prefix('_').and_then(|t| t.strip_suffix('C')) {
+                let Ok(temperature) = temp_str.parse::<f64>() else { continue; };
+                Some(temperature)
+            } else {
+                continue;
+            };
+            let Some(exposure) = Self::exp_from_str(exp_str) else { continue; };
+            result.push(MasterDarkItem { file_name: path, exposure, temperature });
+        }
+        result
+    }
+
     fn master_file_name_impl(
         date:        Option<DateTime<Utc>>,
         frame_type:  FrameType,
@@ -236,6 +421,16 @@ impl FileNameUtils {
         result
     }
 
+    /// File name of hot pixels map built from master dark.
+    /// Map is stored alongside master dark in darks library
+    pub fn bad_pixel_map_file_name(master_dark_fname: &Path, sigma_k: f64) -> PathBuf {
+        let stem = master_dark_fname
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default();
+        master_dark_fname.with_file_name(format!("{}_hot_pixels_s{:.1}.txt", stem, sigma_k))
+    }
+
     fn defect_pixels_file_name_impl(
         img_width:  usize,
         img_height: usize,
@@ -309,6 +504,18 @@ impl FileNameUtils {
         }
     }
 
+    fn exp_from_str(text: &str) -> Option<f64> {
+        if let Some(us) = text.strip_suffix("us") {
+            us.parse::<f64>().ok().map(|v| v / 1_000_000.0)
+        } else if let Some(ms) = text.strip_suffix("ms") {
+            ms.parse::<f64>().ok().map(|v| v / 1_000.0)
+        } else if let Some(s) = text.strip_suffix('s') {
+            s.parse::<f64>().ok()
+        } else {
+            None
+        }
+    }
+
     fn temperature_to_str(temperature: f64) -> String {
         format!("{:+.0}C", temperature)
     }
@@ -340,6 +547,147 @@ pub fn gain_to_value(
     }
 }
 
+/// Temperature used for focuser temperature compensation. Focuser
+/// temperature sensor is used if present. Camera temperature is
+/// used as fallback only if camera cooler is disabled
+pub fn get_focuser_comp_temperature(
+    indi:    &indi::Connection,
+    options: &Options,
+) -> Option<f64> {
+    let temperature = indi.focuser_get_temperature(&options.focuser.device)
+        .ok()
+        .or_else(|| {
+            if options.cam.ctrl.enable_cooler { return None; }
+            let camera = options.cam.device.as_ref()?;
+            indi.camera_get_temperature_prop_value(&camera.name)
+                .ok()
+                .map(|prop| prop.value)
+        })?;
+    if !temperature.is_finite() {
+        return None;
+    }
+    Some(temperature)
+}
+
+/// Returns focuser positions to be set one by one to reach `target_pos`.
+/// If moving from `cur_pos` to `target_pos` is opposite to `direction`
+/// focuser overshoots target by `backlash_steps` and then approaches
+/// it from consistent direction. Returns empty list if focuser is
+/// already at target position
+pub fn focuser_positions_with_backlash(
+    cur_pos:        f64,
+    target_pos:     f64,
+    backlash_steps: f64,
+    direction:      FocuserDirection,
+) -> Vec<f64> {
+    if f64::abs(target_pos - cur_pos) < 1.0 {
+        return Vec::new();
+    }
+    let reversal = match direction {
+        FocuserDirection::Increasing => target_pos < cur_pos,
+        FocuserDirection::Decreasing => target_pos > cur_pos,
+    };
+    if !reversal || backlash_steps < 1.0 {
+        return vec![target_pos];
+    }
+    let overshoot_pos = match direction {
+        FocuserDirection::Increasing => target_pos - backlash_steps,
+        FocuserDirection::Decreasing => target_pos + backlash_steps,
+    };
+    vec![overshoot_pos, target_pos]
+}
+
+/// Moves focuser to `target_pos` with backlash compensation
+/// (see `focuser_positions_with_backlash`). Waits until focuser
+/// reaches every position
+pub fn focuser_set_abs_value_with_backlash(
+    indi:           &indi::Connection,
+    device:         &str,
+    target_pos:     f64,
+    backlash_steps: f64,
+    direction:      FocuserDirection,
+    timeout_ms:     Option<u64>,
+) -> anyhow::Result<()> {
+    let cur_pos = indi.focuser_get_abs_value(device)?;
+    let positions = focuser_positions_with_backlash(cur_pos, target_pos, backlash_steps, direction);
+    for pos in positions {
+        log::debug!("Setting focuser value: {}", pos);
+        indi.focuser_set_abs_value(device, pos, true, timeout_ms)?;
+        let cur_pos = indi.focuser_get_abs_value(device)?;
+        if f64::abs(cur_pos - pos) >= 1.0 {
+            anyhow::bail!("Focuser {} hasn't reached position {:.0}", device, pos);
+        }
+    }
+    Ok(())
+}
+
+/// Dust cap and flat panel which are used while taking flat frames
+pub struct FlatPanelData {
+    dustcap:  Option<String>,
+    lightbox: Option<String>,
+    open_cap: bool, // cap was open before flat frames
+}
+
+impl FlatPanelData {
+    /// Closes first active dust cap and turns on first active flat panel.
+    /// Missing devices are skipped
+    pub fn prepare(indi: &indi::Connection, brightness: f64) -> anyhow::Result<Self> {
+        let find_device = |iface| {
+            indi.get_devices_list_by_interface(iface)
+                .iter()
+                .map(|dev| dev.name.to_string())
+                .find(|name| indi.is_device_enabled(name).unwrap_or(false))
+        };
+        let dustcap = find_device(indi::DriverInterface::DUSTCAP);
+        let lightbox = find_device(indi::DriverInterface::LIGHTBOX);
+
+        let mut open_cap = false;
+        if let Some(dustcap) = &dustcap {
+            open_cap = !indi.dustcap_is_parked(dustcap).unwrap_or(true);
+            log::info!("Closing dust cap {}...", dustcap);
+            indi.dustcap_park(dustcap, true, INDI_SET_PROP_TIMEOUT)?;
+        } else {
+            log::info!("No dust cap device found. Closing of cap is skipped");
+        }
+
+        if let Some(lightbox) = &lightbox {
+            log::info!("Turning on flat panel {} with brightness {}...", lightbox, brightness);
+            indi.lightbox_enable(lightbox, true, true, INDI_SET_PROP_TIMEOUT)?;
+            if indi.lightbox_is_brightness_supported(lightbox)? {
+                indi.lightbox_set_brightness(lightbox, brightness, true, INDI_SET_PROP_TIMEOUT)?;
+            }
+        } else {
+            log::info!("No flat panel device found. Turning on of panel is skipped");
+        }
+
+        Ok(Self { dustcap, lightbox, open_cap })
+    }
+
+    /// Turns off flat panel and opens dust cap if it was open before flat frames
+    pub fn release(&self, indi: &indi::Connection) -> anyhow::Result<()> {
+        if let Some(lightbox) = &self.lightbox {
+            log::info!("Turning off flat panel {}...", lightbox);
+            indi.lightbox_enable(lightbox, false, true, INDI_SET_PROP_TIMEOUT)?;
+        }
+        if let (Some(dustcap), true) = (&self.dustcap, self.open_cap) {
+            log::info!("Opening dust cap {}...", dustcap);
+            indi.dustcap_unpark(dustcap, true, INDI_SET_PROP_TIMEOUT)?;
+        }
+        Ok(())
+    }
+}
+
+/// Mount has to be stopped at meridian limit only if it is still at
+/// pre-flip pier side (mount at west side of pier points to eastern part
+/// of sky). If pier side is unknown mount is stopped too
+pub fn meridian_limit_is_reached(
+    past_meridian: f64,
+    limit:         f64,
+    pier_side:     Option<indi::PierSide>,
+) -> bool {
+    past_meridian >= limit && pier_side != Some(indi::PierSide::East)
+}
+
 pub fn check_telescope_is_at_desired_position(
     indi:                &indi::Connection,
     mount_dev:           &str,
@@ -356,4 +704,63 @@ pub fn check_telescope_is_at_desired_position(
         anyhow::bail!("Tepescope position is too far from desired one");
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_dark_match_tolerance() {
+    let tol = DarkMatchTolerance { temperature: 2.0, exposure: 10.0 };
+
+    // exact
+    assert!(tol.is_acceptable(60.0, Some(-10.0), 60.0, Some(-10.0)));
+
+    // within tolerance
+    assert!(tol.is_acceptable(55.0, Some(-10.0), 60.0, Some(-8.4)));
+    assert!(tol.is_acceptable(66.0, Some(-12.0), 60.0, Some(-10.0)));
+
+    // exposure is out of tolerance
+    assert!(!tol.is_acceptable(67.0, Some(-10.0), 60.0, Some(-10.0)));
+    assert!(!tol.is_acceptable(30.0, Some(-10.0), 60.0, Some(-10.0)));
+
+    // temperature is out of tolerance
+    assert!(!tol.is_acceptable(60.0, Some(-10.0), 60.0, Some(-7.5)));
+
+    // temperature is unknown for one of frames
+    assert!(tol.is_acceptable(60.0, None, 60.0, None));
+    assert!(!tol.is_acceptable(60.0, None, 60.0, Some(-10.0)));
+    assert!(!tol.is_acceptable(60.0, Some(-10.0), 60.0, None));
+}
+
+#[test]
+fn test_focuser_positions_with_backlash() {
+    use FocuserDirection::*;
+
+    // same direction
+    assert_eq!(focuser_positions_with_backlash(1000.0, 1500.0, 100.0, Increasing), vec![1500.0]);
+    assert_eq!(focuser_positions_with_backlash(1500.0, 1000.0, 100.0, Decreasing), vec![1000.0]);
+
+    // reversing
+    assert_eq!(focuser_positions_with_backlash(1500.0, 1000.0, 100.0, Increasing), vec![900.0, 1000.0]);
+    assert_eq!(focuser_positions_with_backlash(1000.0, 1500.0, 100.0, Decreasing), vec![1600.0, 1500.0]);
+
+    // no backlash compensation
+    assert_eq!(focuser_positions_with_backlash(1500.0, 1000.0, 0.0, Increasing), vec![1000.0]);
+
+    // already at target
+    assert!(focuser_positions_with_backlash(1000.0, 1000.0, 100.0, Increasing).is_empty());
+}
+
+#[test]
+fn test_meridian_limit_is_reached() {
+    use indi::PierSide::*;
+
+    // before limit
+    assert!(!meridian_limit_is_reached(3.0, 5.0, Some(West)));
+    assert!(!meridian_limit_is_reached(3.0, 5.0, None));
+
+    // pre-flip pier side or unknown pier side
+    assert!(meridian_limit_is_reached(5.0, 5.0, Some(West)));
+    assert!(meridian_limit_is_reached(7.0, 5.0, None));
+
+    // mount is already flipped
+    assert!(!meridian_limit_is_reached(7.0, 5.0, Some(East)));
+}
@@ -0,0 +1,96 @@
+use std::{fs::File, io::{BufWriter, Write}, path::Path, time::Instant};
+use chrono::prelude::*;
+
+/// Guiding log in PHD2 format. Allows to analyze guiding
+/// of main camera by existing PHD2 log viewers
+pub struct GuideLog {
+    writer:    BufWriter<File>,
+    start:     Instant,
+    frame_num: usize,
+}
+
+pub struct GuideLogItem {
+    pub dx:        f64, // in pixels
+    pub dy:        f64, // in pixels
+    pub ra_dist:   f64, // in pixels
+    pub dec_dist:  f64, // in pixels
+    pub ra_pulse:  f64, // in ms, positive - west
+    pub dec_pulse: f64, // in ms, positive - north
+}
+
+impl GuideLog {
+    const TIME_FMT: &'static str = "%Y-%m-%d %H:%M:%S";
+
+    pub fn new(
+        dir:         &Path,
+        pixel_scale: Option<f64>, // arcsec per pixel
+        focal_len:   f64,         // in mm
+        binning:     usize,
+    ) -> anyhow::Result<Self> {
+        if !dir.is_dir() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let now = Local::now();
+        let file_name = format!("PHD2_GuideLog_{}.txt", now.format("%Y-%m-%d_%H%M%S"));
+        let file = File::create(dir.join(file_name))?;
+        let mut writer = BufWriter::new(file);
+        let now_str = now.format(Self::TIME_FMT);
+        writeln!(writer, "PHD2 version 2.6.13 [AstraLite], Log version 2.5. Log enabled at {}", now_str)?;
+        writeln!(writer)?;
+        writeln!(writer, "Guiding Begins at {}", now_str)?;
+        writeln!(
+            writer,
+            "Pixel scale = {:.2} arc-sec/px, Binning = {}, Focal length = {:.0} mm",
+            pixel_scale.unwrap_or(1.0), binning, focal_len
+        )?;
+        writeln!(
+            writer,
+            "Frame,Time,mount,dx,dy,RARawDistance,DECRawDistance,RAGuideDistance,DECGuideDistance,\
+            RADuration,RADirection,DECDuration,DECDirection,XStep,YStep,StarMass,SNR,ErrorCode"
+        )?;
+        writer.flush()?;
+        Ok(Self {
+            writer,
+            start:     Instant::now(),
+            frame_num: 0,
+        })
+    }
+
+    pub fn add_frame(&mut self, item: &GuideLogItem) -> anyhow::Result<()> {
+        self.frame_num += 1;
+        let ra_dir = if item.ra_pulse > 0.0 { "W" } else if item.ra_pulse < 0.0 { "E" } else { "" };
+        let dec_dir = if item.dec_pulse > 0.0 { "N" } else if item.dec_pulse < 0.0 { "S" } else { "" };
+        let ra_guide_dist = if item.ra_pulse != 0.0 { item.ra_dist } else { 0.0 };
+        let dec_guide_dist = if item.dec_pulse != 0.0 { item.dec_dist } else { 0.0 };
+        writeln!(
+            self.writer,
+            "{},{:.3},\"Mount\",{:.3},{:.3},{:.3},{:.3},{:.3},{:.3},{:.0},{},{:.0},{},,,0,0.00,0",
+            self.frame_num,
+            self.start.elapsed().as_secs_f64(),
+            item.dx, item.dy,
+            item.ra_dist, item.dec_dist,
+            ra_guide_dist, dec_guide_dist,
+            item.ra_pulse.abs(), ra_dir,
+            item.dec_pulse.abs(), dec_dir,
+        )?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    pub fn add_dither(&mut self, dx: f64, dy: f64) -> anyhow::Result<()> {
+        writeln!(self.writer, "INFO: DITHER by {:.3}, {:.3}", dx, dy)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+impl Drop for GuideLog {
+    fn drop(&mut self) {
+        _ = writeln!(
+            self.writer,
+            "Guiding Ends at {}",
+            Local::now().format(Self::TIME_FMT)
+        );
+        _ = self.writer.flush();
+    }
+}
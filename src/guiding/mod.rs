@@ -1,4 +1,5 @@
 pub mod external_guider;
+pub mod guide_log;
 pub mod phd2_conn;
 pub mod phd2_guider;
 
@@ -34,6 +34,7 @@ pub struct LightFrameInfo {
     pub exposure:       f64,
     pub raw_noise:      Option<f32>,
     pub noise:          f32,
+    pub snr:            Option<f32>, // median stars peak over noise
     pub background:     i32,
     pub bg_percent:     f32,
     pub max_value:      u16,
@@ -41,6 +42,7 @@ pub struct LightFrameInfo {
     pub stars_offset:   Option<Offset>,
     pub offset_is_ok:   bool,
     pub calibr_methods: CalibrMethods,
+    pub airmass:        Option<f32>, // NaN if object is below horizon
 }
 
 impl LightFrameInfo {
@@ -48,11 +50,12 @@ impl LightFrameInfo {
         image:                &Image,
         max_stars_fwhm:       Option<f32>,
         max_stars_ovality:    Option<f32>,
+        star_detection:       &StarDetectionOptions,
         stars_pos_for_offset: Option<&Vec<Point>>,
         mt:                   bool,
     ) -> Self {
         let max_value = image.max_value();
-        let overexposured_bord = (90 * max_value as u32 / 100) as u16;
+        let overexposured_bord = star_detection.overexposured_bord(max_value);
         let mono_layer = if image.is_color() { &image.g } else { &image.l };
 
         // Noise
@@ -80,11 +83,14 @@ impl LightFrameInfo {
             max_value,
             max_stars_fwhm,
             max_stars_ovality,
+            star_detection,
             mt
         );
 
         tmr.log("searching stars");
 
+        let snr = Self::calc_snr(&stars_info.items, noise);
+
         // Offset by reference stars
 
         let (stars_offset, offset_is_ok) = if let (Some(starts_for_offset), true, true) =
@@ -113,6 +119,7 @@ impl LightFrameInfo {
             exposure: 0.0,
             raw_noise: None,
             noise,
+            snr,
             background,
             bg_percent: (100.0 * background as f64 / image.max_value() as f64) as f32,
             max_value,
@@ -120,7 +127,66 @@ impl LightFrameInfo {
             stars_offset,
             offset_is_ok,
             calibr_methods: CalibrMethods::empty(),
+            airmass:        None,
+        }
+    }
+
+    fn calc_snr(stars: &Stars, noise: f32) -> Option<f32> {
+        if stars.is_empty() || noise <= 0.0 {
+            return None;
+        }
+        let mut signals: Vec<_> = stars.iter()
+            .map(|s| s.max_value.saturating_sub(s.background))
+            .collect();
+        let pos = signals.len() / 2;
+        let signal = *signals.select_nth_unstable(pos).1;
+        Some(signal as f32 / noise)
+    }
+
+    /// Frame quality score in range 0..100. Weighted average of sub-scores:
+    ///
+    /// * FWHM: `(10 - fwhm) / (10 - 1.5)` (FWHM in px; 1.5 and less is the best, 10 and more is the worst)
+    /// * ovality: `1 - ovality / 1.5`
+    /// * stars count: `log10(1 + count) / log10(1 + 500)` (500 stars and more is the best)
+    /// * background: `1 - bg_percent / 50`
+    ///
+    /// Every sub-score is clamped to 0..1. Sub-score is 0 if FWHM
+    /// or ovality can't be calculated
+    pub fn quality_score(
+        &self,
+        w_fwhm:    f32,
+        w_ovality: f32,
+        w_stars:   f32,
+        w_bg:      f32,
+    ) -> f32 {
+        const BEST_FWHM: f32 = 1.5;
+        const WORST_FWHM: f32 = 10.0;
+        const WORST_OVALITY: f32 = 1.5;
+        const BEST_STARS_CNT: f32 = 500.0;
+        const WORST_BG_PERCENT: f32 = 50.0;
+
+        let fwhm_score = self.stars.fwhm
+            .map(|fwhm| (WORST_FWHM - fwhm) / (WORST_FWHM - BEST_FWHM))
+            .unwrap_or(0.0);
+        let ovality_score = self.stars.ovality
+            .map(|ovality| 1.0 - ovality / WORST_OVALITY)
+            .unwrap_or(0.0);
+        let stars_score =
+            f32::log10(1.0 + self.stars.items.len() as f32) /
+            f32::log10(1.0 + BEST_STARS_CNT);
+        let bg_score = 1.0 - self.bg_percent / WORST_BG_PERCENT;
+
+        let weights = [w_fwhm, w_ovality, w_stars, w_bg].map(|w| w.max(0.0));
+        let scores = [fwhm_score, ovality_score, stars_score, bg_score];
+        let weights_sum: f32 = weights.iter().sum();
+        if weights_sum == 0.0 {
+            return 0.0;
         }
+        let score_sum: f32 = weights.iter()
+            .zip(scores)
+            .map(|(w, s)| w * s.clamp(0.0, 1.0))
+            .sum();
+        100.0 * score_sum / weights_sum
     }
 }
 
@@ -176,3 +242,56 @@ impl RawImageStat {
         }
     }
 }
+
+#[test]
+fn test_quality_score_order() {
+    let frame_info = |fwhm: Option<f32>, ovality: f32, stars_cnt: usize| LightFrameInfo {
+        time:           None,
+        width:          1000,
+        height:         1000,
+        exposure:       60.0,
+        raw_noise:      None,
+        noise:          10.0,
+        snr:            None,
+        background:     1000,
+        bg_percent:     5.0,
+        max_value:      u16::MAX,
+        stars:          StarsInfo {
+            items:         vec![Star::default(); stars_cnt],
+            fwhm,
+            fwhm_angular:  None,
+            fwhm_is_ok:    true,
+            ovality:       Some(ovality),
+            ovality_is_ok: true,
+            hfd:           None,
+            tilt:          StarsTilt::default(),
+        },
+        stars_offset:   None,
+        offset_is_ok:   true,
+        calibr_methods: CalibrMethods::empty(),
+        airmass:        None,
+    };
+
+    let sharp = frame_info(Some(2.0), 0.2, 300);
+    let bloated = frame_info(Some(6.0), 0.2, 300);
+    let no_fwhm = frame_info(None, 0.2, 300);
+
+    let sharp_score = sharp.quality_score(1.0, 1.0, 1.0, 1.0);
+    let bloated_score = bloated.quality_score(1.0, 1.0, 1.0, 1.0);
+    let no_fwhm_score = no_fwhm.quality_score(1.0, 1.0, 1.0, 1.0);
+    assert!(sharp_score > bloated_score);
+    assert!(bloated_score > no_fwhm_score);
+    assert!((0.0..=100.0).contains(&sharp_score));
+    assert!((0.0..=100.0).contains(&no_fwhm_score));
+
+    // Best values of all sub-scores
+    let best = frame_info(Some(1.0), 0.0, 1000);
+    let best_score = best.quality_score(1.0, 1.0, 1.0, 0.0);
+    assert!((best_score - 100.0).abs() < 0.001);
+
+    // Only FWHM is taken into account
+    let fwhm_score = bloated.quality_score(1.0, 0.0, 0.0, 0.0);
+    assert!((fwhm_score - 100.0 * (10.0 - 6.0) / (10.0 - 1.5)).abs() < 0.001);
+
+    assert_eq!(sharp.quality_score(0.0, 0.0, 0.0, 0.0), 0.0);
+}
@@ -39,6 +39,7 @@ pub fn load_raw_image_from_fits_reader(
     let frame_str    = image_hdu.get_str("FRAME");
     let time_str     = image_hdu.get_str("DATE-OBS").unwrap_or_default();
     let camera       = image_hdu.get_str("INSTRUME").unwrap_or_default().to_string();
+    let object       = image_hdu.get_str("OBJECT").map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
     let ccd_temp     = image_hdu.get_f64("CCD-TEMP");
     let focal_len    = image_hdu.get_f64("FOCALLEN");
     let pixel_size_x = image_hdu.get_f64("PIXSIZE1");
@@ -64,7 +65,7 @@ pub fn load_raw_image_from_fits_reader(
     let info = RawImageInfo {
         time, width, height, gain, offset, cfa, bin,
         max_value, frame_type, exposure, integr_time,
-        camera, ccd_temp, focal_len,
+        camera, object, ccd_temp, focal_len,
         pixel_size_x, pixel_size_y,
         calibr_methods: CalibrMethods::empty(),
     };
@@ -86,6 +87,78 @@ pub fn load_raw_image_from_fits_file(file_name: &Path) -> anyhow::Result<RawImag
     load_raw_image_from_fits_stream(&mut file)
 }
 
+/// Converts frame of video stream (INDI RAW stream encoder) into monochrome
+/// raw image. Frame is binned if its longest side is greater than `max_size`
+pub fn load_raw_image_from_video_frame(
+    data:     &[u8],
+    width:    usize,
+    height:   usize,
+    max_size: usize,
+) -> anyhow::Result<RawImage> {
+    // Stream can be binned by camera, so real frame size
+    // and bytes per pixel are found from data length
+    let Some((width, height, bpp)) = (1..=4_usize)
+        .cartesian_product([1, 2, 3, 6])
+        .map(|(bin, bpp)| (width / bin, height / bin, bpp))
+        .find(|(w, h, bpp)| *w != 0 && *h != 0 && w * h * bpp == data.len())
+    else {
+        anyhow::bail!(
+            "Video frame size ({} bytes) doesn't correspond to stream frame {}x{}",
+            data.len(), width, height
+        );
+    };
+
+    let u16_le = |bytes: &[u8]| u16::from_le_bytes([bytes[0], bytes[1]]) as u32;
+    let pixel = |idx: usize| -> u32 {
+        let bytes = &data[idx * bpp .. (idx + 1) * bpp];
+        match bpp {
+            1 => bytes[0] as u32,
+            2 => u16_le(bytes),
+            3 => (bytes[0] as u32 + 2 * bytes[1] as u32 + bytes[2] as u32) / 4,
+            _ => (u16_le(&bytes[0..]) + 2 * u16_le(&bytes[2..]) + u16_le(&bytes[4..])) / 4,
+        }
+    };
+    let max_value = if matches!(bpp, 1|3) { u8::MAX as u16 } else { u16::MAX };
+
+    let bin = usize::max(width, height).div_ceil(max_size.max(1)).max(1);
+    let res_width = width / bin;
+    let res_height = height / bin;
+    let mut result = Vec::with_capacity(res_width * res_height);
+    for (y, x) in (0..res_height).cartesian_product(0..res_width) {
+        let mut sum = 0_u32;
+        for dy in 0..bin {
+            let row_start = (y * bin + dy) * width + x * bin;
+            for idx in row_start..row_start+bin {
+                sum += pixel(idx);
+            }
+        }
+        result.push((sum / (bin * bin) as u32) as u16);
+    }
+
+    let info = RawImageInfo {
+        time:           Some(Utc::now()),
+        width:          res_width,
+        height:         res_height,
+        gain:           0,
+        offset:         0,
+        max_value,
+        cfa:            CfaType::None,
+        bin:            bin as u8,
+        frame_type:     FrameType::Lights,
+        exposure:       0.0,
+        integr_time:    None,
+        camera:         String::new(),
+        object:         None,
+        ccd_temp:       None,
+        focal_len:      None,
+        pixel_size_x:   None,
+        pixel_size_y:   None,
+        calibr_methods: CalibrMethods::empty(),
+    };
+
+    Ok(RawImage::new(info, result, CfaType::None.get_array()))
+}
+
 ///////////////////////////////////////////////////////////////////////////////
 
 // Image layer
@@ -325,4 +398,23 @@ pub fn load_image_by_pixbuf(
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_load_raw_image_from_video_frame() {
+    // 16-bit frame 8x4 binned by camera 2x2
+    let data = (0..8_u16).flat_map(|v| (v * 1000).to_le_bytes()).collect::<Vec<_>>();
+    let image = load_raw_image_from_video_frame(&data, 8, 4, 100).unwrap();
+    assert_eq!(image.info().width, 4);
+    assert_eq!(image.info().height, 2);
+    assert_eq!(image.info().max_value, u16::MAX);
+    assert_eq!(image.as_slice()[5], 5000);
+
+    // 8-bit frame 2x2 binned for preview
+    let image = load_raw_image_from_video_frame(&[10, 20, 30, 40], 2, 2, 1).unwrap();
+    assert_eq!(image.info().width, 1);
+    assert_eq!(image.info().max_value, 255);
+    assert_eq!(image.as_slice(), &[25]);
+
+    assert!(load_raw_image_from_video_frame(&[0; 7], 2, 2, 100).is_err());
+}
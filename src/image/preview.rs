@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use itertools::{izip, Itertools};
+use rayon::prelude::*;
 
 use crate::{
     utils::math::linear_interpolate, PreviewColorMode, PreviewScale
@@ -253,14 +254,15 @@ fn to_grb_bytes(
     let b_table = create_gamma_table(levels.b.dark, levels.b.light, params.gamma, wb[2]);
     let l_table = create_gamma_table(levels.l.dark, levels.l.light, params.gamma, 1.0);
     let (result_width, result_height) = params.get_preview_img_size(image.width(), image.height());
+    let mt = image.width() * image.height() >= MIN_PIXELS_FOR_MT;
 
     let rgb_bytes = if image.is_color() && params.color == PreviewColorMode::Rgb {
         match (params.scale, reduct_ratio) {
             (PreviewScale::CenterAndCorners, _) => to_grb_bytes_corners_rgb(image, &r_table, &g_table, &b_table, result_width, result_height),
-            (_, 1) => to_grb_bytes_no_reduct_rgb(image, &r_table, &g_table, &b_table, image.width(), image.height()),
-            (_, 2) => to_grb_bytes_reduct2_rgb  (image, &r_table, &g_table, &b_table, image.width(), image.height()),
-            (_, 3) => to_grb_bytes_reduct3_rgb  (image, &r_table, &g_table, &b_table, image.width(), image.height()),
-            (_, 4) => to_grb_bytes_reduct4_rgb  (image, &r_table, &g_table, &b_table, image.width(), image.height()),
+            (_, 1) => to_grb_bytes_no_reduct_rgb(image, &r_table, &g_table, &b_table, image.width(), image.height(), mt),
+            (_, 2) => to_grb_bytes_reduct2_rgb  (image, &r_table, &g_table, &b_table, image.width(), image.height(), mt),
+            (_, 3) => to_grb_bytes_reduct3_rgb  (image, &r_table, &g_table, &b_table, image.width(), image.height(), mt),
+            (_, 4) => to_grb_bytes_reduct4_rgb  (image, &r_table, &g_table, &b_table, image.width(), image.height(), mt),
             _ => panic!("Wrong reduct_ratio ({})", reduct_ratio),
         }
     } else {
@@ -273,10 +275,10 @@ fn to_grb_bytes(
         };
         match (params.scale, reduct_ratio) {
             (PreviewScale::CenterAndCorners, _) => to_grb_bytes_corners_mono(layer, &table, result_width, result_height),
-            (_, 1) => to_grb_bytes_no_reduct_mono(layer, &table, image.width(), image.height()),
-            (_, 2) => to_grb_bytes_reduct2_mono  (layer, &table, image.width(), image.height()),
-            (_, 3) => to_grb_bytes_reduct3_mono  (layer, &table, image.width(), image.height()),
-            (_, 4) => to_grb_bytes_reduct4_mono  (layer, &table, image.width(), image.height()),
+            (_, 1) => to_grb_bytes_no_reduct_mono(layer, &table, image.width(), image.height(), mt),
+            (_, 2) => to_grb_bytes_reduct2_mono  (layer, &table, image.width(), image.height(), mt),
+            (_, 3) => to_grb_bytes_reduct3_mono  (layer, &table, image.width(), image.height(), mt),
+            (_, 4) => to_grb_bytes_reduct4_mono  (layer, &table, image.width(), image.height(), mt),
             _ => panic!("Wrong reduct_ratio ({})", reduct_ratio),
         }
     };
@@ -411,26 +413,57 @@ fn to_grb_bytes_corners_mono(
     rgb_bytes
 }
 
+/// Preview of smaller images is created in one thread
+/// because multithreading overhead is bigger than profit
+const MIN_PIXELS_FOR_MT: usize = 1_000_000;
+
+/// Calls `fun` for each row of RGB result (`width` is in pixels).
+/// Rows are processed in parallel if `mt` is true
+fn process_rows(
+    rgb_bytes: &mut [u8],
+    width:     usize,
+    mt:        bool,
+    fun:       impl Fn(usize, &mut [u8]) + Sync + Send,
+) {
+    const MIN_ROWS_PER_TASK: usize = 16;
+    if width == 0 {
+        return;
+    }
+    if mt {
+        rgb_bytes
+            .par_chunks_exact_mut(3 * width)
+            .enumerate()
+            .with_min_len(MIN_ROWS_PER_TASK)
+            .for_each(|(y, row)| fun(y, row));
+    } else {
+        rgb_bytes
+            .chunks_exact_mut(3 * width)
+            .enumerate()
+            .for_each(|(y, row)| fun(y, row));
+    }
+}
+
 fn to_grb_bytes_no_reduct_rgb(
     image:   &Image,
     r_table: &[u8],
     g_table: &[u8],
     b_table: &[u8],
     width:   usize,
-    height:  usize
+    height:  usize,
+    mt:      bool,
 ) -> Vec<u8> {
-    let mut rgb_bytes = Vec::with_capacity(3 * width * height);
-    for row in 0..height {
+    let mut rgb_bytes = vec![0_u8; 3 * width * height];
+    process_rows(&mut rgb_bytes, width, mt, |row, dst_row| {
         let r_iter = image.r.row(row).iter();
         let g_iter = image.g.row(row).iter();
         let b_iter = image.b.row(row).iter();
-        for (r, g, b) in
-        izip!(r_iter, g_iter, b_iter) {
-            rgb_bytes.push(r_table[*r as usize]);
-            rgb_bytes.push(g_table[*g as usize]);
-            rgb_bytes.push(b_table[*b as usize]);
+        for (r, g, b, dst) in
+        izip!(r_iter, g_iter, b_iter, dst_row.chunks_exact_mut(3)) {
+            dst[0] = r_table[*r as usize];
+            dst[1] = g_table[*g as usize];
+            dst[2] = b_table[*b as usize];
         }
-    }
+    });
     rgb_bytes
 }
 
@@ -438,17 +471,18 @@ fn to_grb_bytes_no_reduct_mono(
     layer:  &ImageLayer<u16>,
     table:  &[u8],
     width:  usize,
-    height: usize
+    height: usize,
+    mt:     bool,
 ) -> Vec<u8> {
-    let mut rgb_bytes = Vec::with_capacity(3 * width * height);
-    for row in 0..height {
-        for l in layer.row(row).iter() {
+    let mut rgb_bytes = vec![0_u8; 3 * width * height];
+    process_rows(&mut rgb_bytes, width, mt, |row, dst_row| {
+        for (l, dst) in izip!(layer.row(row).iter(), dst_row.chunks_exact_mut(3)) {
             let l = table[*l as usize];
-            rgb_bytes.push(l);
-            rgb_bytes.push(l);
-            rgb_bytes.push(l);
+            dst[0] = l;
+            dst[1] = l;
+            dst[2] = l;
         }
-    }
+    });
     rgb_bytes
 }
 
@@ -458,19 +492,20 @@ fn to_grb_bytes_reduct2_rgb(
     g_table: &[u8],
     b_table: &[u8],
     width:   usize,
-    height:  usize
+    height:  usize,
+    mt:      bool,
 ) -> Vec<u8> {
     let width = width / 2;
     let height = height / 2;
-    let mut rgb_bytes = Vec::with_capacity(3 * width * height);
-    for y in 0..height {
+    let mut rgb_bytes = vec![0_u8; 3 * width * height];
+    process_rows(&mut rgb_bytes, width, mt, |y, dst_row| {
         let mut r0 = image.r.row(2*y).as_ptr();
         let mut r1 = image.r.row(2*y+1).as_ptr();
         let mut g0 = image.g.row(2*y).as_ptr();
         let mut g1 = image.g.row(2*y+1).as_ptr();
         let mut b0 = image.b.row(2*y).as_ptr();
         let mut b1 = image.b.row(2*y+1).as_ptr();
-        for _ in 0..width {
+        for dst in dst_row.chunks_exact_mut(3) {
             let r = unsafe {(
                 *r0 as u32 + *r0.offset(1) as u32 +
                 *r1 as u32 + *r1.offset(1) as u32 + 2
@@ -483,9 +518,9 @@ fn to_grb_bytes_reduct2_rgb(
                 *b0 as u32 + *b0.offset(1) as u32 +
                 *b1 as u32 + *b1.offset(1) as u32 + 2
             ) / 4};
-            rgb_bytes.push(r_table[r as usize]);
-            rgb_bytes.push(g_table[g as usize]);
-            rgb_bytes.push(b_table[b as usize]);
+            dst[0] = r_table[r as usize];
+            dst[1] = g_table[g as usize];
+            dst[2] = b_table[b as usize];
             r0 = r0.wrapping_offset(2);
             r1 = r1.wrapping_offset(2);
             g0 = g0.wrapping_offset(2);
@@ -493,7 +528,7 @@ fn to_grb_bytes_reduct2_rgb(
             b0 = b0.wrapping_offset(2);
             b1 = b1.wrapping_offset(2);
         }
-    }
+    });
     rgb_bytes
 }
 
@@ -501,27 +536,28 @@ fn to_grb_bytes_reduct2_mono(
     layer:  &ImageLayer<u16>,
     table:  &[u8],
     width:  usize,
-    height: usize
+    height: usize,
+    mt:     bool,
 ) -> Vec<u8> {
     let width = width / 2;
     let height = height / 2;
-    let mut rgb_bytes = Vec::with_capacity(3 * width * height);
-    for y in 0..height {
+    let mut rgb_bytes = vec![0_u8; 3 * width * height];
+    process_rows(&mut rgb_bytes, width, mt, |y, dst_row| {
         let mut l0 = layer.row(2*y).as_ptr();
         let mut l1 = layer.row(2*y+1).as_ptr();
-        for _ in 0..width {
+        for dst in dst_row.chunks_exact_mut(3) {
             let l = unsafe {(
                 *l0 as u32 + *l0.offset(1) as u32 +
                 *l1 as u32 + *l1.offset(1) as u32 + 2
             ) / 4};
             let l = table[l as usize];
-            rgb_bytes.push(l);
-            rgb_bytes.push(l);
-            rgb_bytes.push(l);
+            dst[0] = l;
+            dst[1] = l;
+            dst[2] = l;
             l0 = l0.wrapping_offset(2);
             l1 = l1.wrapping_offset(2);
         }
-    }
+    });
     rgb_bytes
 }
 
@@ -531,12 +567,13 @@ fn to_grb_bytes_reduct3_rgb(
     g_table: &[u8],
     b_table: &[u8],
     width:   usize,
-    height:  usize
+    height:  usize,
+    mt:      bool,
 ) -> Vec<u8> {
     let width = width / 3;
     let height = height / 3;
-    let mut rgb_bytes = Vec::with_capacity(3 * width * height);
-    for y in 0..height {
+    let mut rgb_bytes = vec![0_u8; 3 * width * height];
+    process_rows(&mut rgb_bytes, width, mt, |y, dst_row| {
         let mut r0 = image.r.row(3*y).as_ptr();
         let mut r1 = image.r.row(3*y+1).as_ptr();
         let mut r2 = image.r.row(3*y+2).as_ptr();
@@ -546,7 +583,7 @@ fn to_grb_bytes_reduct3_rgb(
         let mut b0 = image.b.row(3*y).as_ptr();
         let mut b1 = image.b.row(3*y+1).as_ptr();
         let mut b2 = image.b.row(3*y+2).as_ptr();
-        for _ in 0..width {
+        for dst in dst_row.chunks_exact_mut(3) {
             let r = unsafe {(
                 *r0 as u32 + *r0.offset(1) as u32 + *r0.offset(2) as u32 +
                 *r1 as u32 + *r1.offset(1) as u32 + *r1.offset(2) as u32 +
@@ -562,9 +599,9 @@ fn to_grb_bytes_reduct3_rgb(
                 *b1 as u32 + *b1.offset(1) as u32 + *b1.offset(2) as u32 +
                 *b2 as u32 + *b2.offset(1) as u32 + *b2.offset(2) as u32 + 4
             ) / 9};
-            rgb_bytes.push(r_table[r as usize]);
-            rgb_bytes.push(g_table[g as usize]);
-            rgb_bytes.push(b_table[b as usize]);
+            dst[0] = r_table[r as usize];
+            dst[1] = g_table[g as usize];
+            dst[2] = b_table[b as usize];
             r0 = r0.wrapping_offset(3);
             r1 = r1.wrapping_offset(3);
             r2 = r2.wrapping_offset(3);
@@ -575,7 +612,7 @@ fn to_grb_bytes_reduct3_rgb(
             b1 = b1.wrapping_offset(3);
             b2 = b2.wrapping_offset(3);
         }
-    }
+    });
     rgb_bytes
 }
 
@@ -583,30 +620,31 @@ fn to_grb_bytes_reduct3_mono(
     layer:  &ImageLayer<u16>,
     table:  &[u8],
     width:  usize,
-    height: usize
+    height: usize,
+    mt:     bool,
 ) -> Vec<u8> {
     let width = width / 3;
     let height = height / 3;
-    let mut rgb_bytes = Vec::with_capacity(3 * width * height);
-    for y in 0..height {
+    let mut rgb_bytes = vec![0_u8; 3 * width * height];
+    process_rows(&mut rgb_bytes, width, mt, |y, dst_row| {
         let mut l0 = layer.row(3*y).as_ptr();
         let mut l1 = layer.row(3*y+1).as_ptr();
         let mut l2 = layer.row(3*y+2).as_ptr();
-        for _ in 0..width {
+        for dst in dst_row.chunks_exact_mut(3) {
             let l = unsafe {(
                 *l0 as u32 + *l0.offset(1) as u32 + *l0.offset(2) as u32 +
                 *l1 as u32 + *l1.offset(1) as u32 + *l1.offset(2) as u32 +
                 *l2 as u32 + *l2.offset(1) as u32 + *l2.offset(2) as u32 + 4
             ) / 9};
             let l = table[l as usize];
-            rgb_bytes.push(l);
-            rgb_bytes.push(l);
-            rgb_bytes.push(l);
+            dst[0] = l;
+            dst[1] = l;
+            dst[2] = l;
             l0 = l0.wrapping_offset(3);
             l1 = l1.wrapping_offset(3);
             l2 = l2.wrapping_offset(3);
         }
-    }
+    });
     rgb_bytes
 }
 
@@ -616,12 +654,13 @@ fn to_grb_bytes_reduct4_rgb(
     g_table: &[u8],
     b_table: &[u8],
     width:   usize,
-    height:  usize
+    height:  usize,
+    mt:      bool,
 ) -> Vec<u8> {
     let width = width / 4;
     let height = height / 4;
-    let mut rgb_bytes = Vec::with_capacity(3 * width * height);
-    for y in 0..height {
+    let mut rgb_bytes = vec![0_u8; 3 * width * height];
+    process_rows(&mut rgb_bytes, width, mt, |y, dst_row| {
         let mut r0 = image.r.row(4*y).as_ptr();
         let mut r1 = image.r.row(4*y+1).as_ptr();
         let mut r2 = image.r.row(4*y+2).as_ptr();
@@ -634,7 +673,7 @@ fn to_grb_bytes_reduct4_rgb(
         let mut b1 = image.b.row(4*y+1).as_ptr();
         let mut b2 = image.b.row(4*y+2).as_ptr();
         let mut b3 = image.b.row(4*y+3).as_ptr();
-        for _ in 0..width {
+        for dst in dst_row.chunks_exact_mut(3) {
             let r = unsafe {(
                 *r0 as u32 + *r0.offset(1) as u32 + *r0.offset(2) as u32 + *r0.offset(3) as u32 +
                 *r1 as u32 + *r1.offset(1) as u32 + *r1.offset(2) as u32 + *r1.offset(3) as u32 +
@@ -653,9 +692,9 @@ fn to_grb_bytes_reduct4_rgb(
                 *b2 as u32 + *b2.offset(1) as u32 + *b2.offset(2) as u32 + *b2.offset(3) as u32 +
                 *b3 as u32 + *b3.offset(1) as u32 + *b3.offset(2) as u32 + *b3.offset(3) as u32 + 8
             ) / 16};
-            rgb_bytes.push(r_table[r as usize]);
-            rgb_bytes.push(g_table[g as usize]);
-            rgb_bytes.push(b_table[b as usize]);
+            dst[0] = r_table[r as usize];
+            dst[1] = g_table[g as usize];
+            dst[2] = b_table[b as usize];
             r0 = r0.wrapping_offset(4);
             r1 = r1.wrapping_offset(4);
             r2 = r2.wrapping_offset(4);
@@ -669,7 +708,7 @@ fn to_grb_bytes_reduct4_rgb(
             b2 = b2.wrapping_offset(4);
             b3 = b3.wrapping_offset(4);
         }
-    }
+    });
     rgb_bytes
 }
 
@@ -677,17 +716,18 @@ fn to_grb_bytes_reduct4_mono(
     layer:  &ImageLayer<u16>,
     table:  &[u8],
     width:  usize,
-    height: usize
+    height: usize,
+    mt:     bool,
 ) -> Vec<u8> {
     let width = width / 4;
     let height = height / 4;
-    let mut rgb_bytes = Vec::with_capacity(3 * width * height);
-    for y in 0..height {
+    let mut rgb_bytes = vec![0_u8; 3 * width * height];
+    process_rows(&mut rgb_bytes, width, mt, |y, dst_row| {
         let mut l0 = layer.row(4*y).as_ptr();
         let mut l1 = layer.row(4*y+1).as_ptr();
         let mut l2 = layer.row(4*y+2).as_ptr();
         let mut l3 = layer.row(4*y+3).as_ptr();
-        for _ in 0..width {
+        for dst in dst_row.chunks_exact_mut(3) {
             let l = unsafe {(
                 *l0 as u32 + *l0.offset(1) as u32 + *l0.offset(2) as u32 + *l0.offset(3) as u32 +
                 *l1 as u32 + *l1.offset(1) as u32 + *l1.offset(2) as u32 + *l1.offset(3) as u32 +
@@ -695,14 +735,61 @@ fn to_grb_bytes_reduct4_mono(
                 *l3 as u32 + *l3.offset(1) as u32 + *l3.offset(2) as u32 + *l3.offset(3) as u32 + 8
             ) / 16};
             let l = table[l as usize];
-            rgb_bytes.push(l);
-            rgb_bytes.push(l);
-            rgb_bytes.push(l);
+            dst[0] = l;
+            dst[1] = l;
+            dst[2] = l;
             l0 = l0.wrapping_offset(4);
             l1 = l1.wrapping_offset(4);
             l2 = l2.wrapping_offset(4);
             l3 = l3.wrapping_offset(4);
         }
-    }
+    });
     rgb_bytes
 }
+
+#[test]
+fn test_preview_serial_and_parallel_are_equal() {
+    const WIDTH: usize = 1203;
+    const HEIGHT: usize = 801;
+    let mut image = Image::new_empty();
+    image.make_color(WIDTH, HEIGHT, 0, u16::MAX);
+    for (i, (r, g, b)) in izip!(
+        image.r.as_slice_mut(),
+        image.g.as_slice_mut(),
+        image.b.as_slice_mut()
+    ).enumerate() {
+        *r = (i * 7919 % 65536) as u16;
+        *g = (i * 104729 % 65536) as u16;
+        *b = (i * 1299709 % 65536) as u16;
+    }
+    let r_table = create_gamma_table(100.0, 60000.0, 2.0, 1.2);
+    let g_table = create_gamma_table(200.0, 50000.0, 2.0, 1.0);
+    let b_table = create_gamma_table(300.0, 40000.0, 2.0, 1.5);
+
+    type RgbFun = fn(&Image, &[u8], &[u8], &[u8], usize, usize, bool) -> Vec<u8>;
+    type MonoFun = fn(&ImageLayer<u16>, &[u8], usize, usize, bool) -> Vec<u8>;
+    let rgb_funs: [(&str, RgbFun); 4] = [
+        ("no_reduct_rgb", to_grb_bytes_no_reduct_rgb),
+        ("reduct2_rgb",   to_grb_bytes_reduct2_rgb),
+        ("reduct3_rgb",   to_grb_bytes_reduct3_rgb),
+        ("reduct4_rgb",   to_grb_bytes_reduct4_rgb),
+    ];
+    let mono_funs: [(&str, MonoFun); 4] = [
+        ("no_reduct_mono", to_grb_bytes_no_reduct_mono),
+        ("reduct2_mono",   to_grb_bytes_reduct2_mono),
+        ("reduct3_mono",   to_grb_bytes_reduct3_mono),
+        ("reduct4_mono",   to_grb_bytes_reduct4_mono),
+    ];
+
+    let compare = |name: &str, fun: &dyn Fn(bool) -> Vec<u8>| {
+        let serial = fun(false);
+        let parallel = fun(true);
+        assert!(serial == parallel, "{}", name);
+    };
+    for (name, fun) in rgb_funs {
+        compare(name, &|mt| fun(&image, &r_table, &g_table, &b_table, WIDTH, HEIGHT, mt));
+    }
+    for (name, fun) in mono_funs {
+        compare(name, &|mt| fun(&image.g, &g_table, WIDTH, HEIGHT, mt));
+    }
+}
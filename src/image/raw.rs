@@ -149,6 +149,7 @@ pub struct RawImageInfo {
     pub exposure:       f64,
     pub integr_time:    Option<f64>, // for master files
     pub camera:         String,
+    pub object:         Option<String>, // target name
     pub ccd_temp:       Option<f64>,
     pub focal_len:      Option<f64>,
     pub pixel_size_x:   Option<f64>, // um
@@ -156,6 +157,34 @@ pub struct RawImageInfo {
     pub calibr_methods: CalibrMethods,
 }
 
+impl RawImageInfo {
+    pub fn fill_fits_header(&self, hdu: &mut Header) {
+        hdu.set_f64("EXPTIME",  self.exposure);
+        if let Some(integr_exp) = self.integr_time {
+            hdu.set_f64("TOTALEXP", integr_exp);
+        }
+        hdu.set_str("ROWORDER", "TOP-DOWN");
+        hdu.set_str("FRAME",    self.frame_type.to_str());
+        hdu.set_i64("XBINNING", self.bin as i64);
+        hdu.set_i64("YBINNING", self.bin as i64);
+        hdu.set_i64("GAIN",     self.gain as i64);
+        hdu.set_i64("OFFSET",   self.offset as i64);
+        hdu.set_str("INSTRUME", &self.camera);
+        if let Some(object) = &self.object {
+            hdu.set_str("OBJECT", object);
+        }
+        if let Some(bayer) = self.cfa.to_str() {
+            hdu.set_str("BAYERPAT", bayer);
+        }
+        if let Some(ccd_temp) = self.ccd_temp {
+            hdu.set_f64("CCD-TEMP", ccd_temp);
+        }
+        if let Some(time) = &self.time {
+            hdu.set_str("DATE-OBS", &time.format("%Y-%m-%dT%H:%M:%S%.3f").to_string());
+        }
+    }
+}
+
 pub struct RawImage {
     info:    RawImageInfo,
     data:    Vec<u16>,
@@ -184,23 +213,7 @@ impl RawImage {
         let mut file = File::create(file_name)?;
         let writer = FitsWriter::new();
         let mut hdu = Header::new_2d(self.info.width, self.info.height);
-        hdu.set_f64("EXPTIME",  self.info.exposure);
-        if let Some(integr_exp) = self.info.integr_time {
-            hdu.set_f64("TOTALEXP", integr_exp);
-        }
-        hdu.set_str("ROWORDER", "TOP-DOWN");
-        hdu.set_str("FRAME",    self.info.frame_type.to_str());
-        hdu.set_i64("XBINNING", self.info.bin as i64);
-        hdu.set_i64("YBINNING", self.info.bin as i64);
-        hdu.set_i64("GAIN",     self.info.gain as i64);
-        hdu.set_i64("OFFSET",   self.info.offset as i64);
-        hdu.set_str("INSTRUME", &self.info.camera);
-        if let Some(bayer) = self.info.cfa.to_str() {
-            hdu.set_str("BAYERPAT", bayer);
-        }
-        if let Some(ccd_temp) = self.info.ccd_temp {
-            hdu.set_f64("CCD-TEMP", ccd_temp);
-        }
+        self.info.fill_fits_header(&mut hdu);
         writer.write_header_and_data_u16(&mut file, &hdu, &self.data)?;
         Ok(())
     }
@@ -349,6 +362,53 @@ impl RawImage {
         BadPixels{ items: pixels }
     }
 
+    /// Builds bad pixels map from master dark. Pixel is treated as hot
+    /// if its value is above median + `sigma_k`·σ of pixels of the same
+    /// CFA color. σ is estimated via median absolute deviation to be
+    /// insensitive to hot pixels themselves
+    pub fn build_bad_pixel_map(&self, sigma_k: f64) -> BadPixels {
+        let colors: &[CfaColor] = if self.info.cfa == CfaType::None {
+            &[CfaColor::None]
+        } else {
+            &[CfaColor::R, CfaColor::G, CfaColor::B]
+        };
+        let mut items = Vec::new();
+        for &color in colors {
+            let color_pixels = || self.data
+                .par_chunks_exact(self.info.width)
+                .enumerate()
+                .flat_map_iter(move |(y, row)| {
+                    let cfa_row = self.cfa_row(y);
+                    row.iter()
+                        .enumerate()
+                        .filter(move |(x, _)| cfa_row[x % cfa_row.len()] == color)
+                        .map(move |(x, v)| (x, y, *v))
+                });
+
+            let mut values: Vec<_> = color_pixels().map(|(_, _, v)| v).collect();
+            if values.is_empty() { continue; }
+            let pos = values.len() / 2;
+            let median = *values.select_nth_unstable(pos).1;
+            let mut deviations: Vec<_> = values.iter().map(|v| v.abs_diff(median)).collect();
+            drop(values);
+            let mad = *deviations.select_nth_unstable(pos).1;
+            let sigma = 1.4826 * f64::max(mad as f64, 1.0);
+            let border = median as f64 + sigma_k * sigma;
+
+            let hot_pixels: Vec<_> = color_pixels()
+                .filter(|(_, _, v)| *v as f64 > border)
+                .map(|(x, y, _)| BadPixel { x: x as isize, y: y as isize })
+                .collect();
+            log::debug!(
+                "{:?}: median={}, sigma={:.1}, hot pixels count={}",
+                color, median, sigma, hot_pixels.len()
+            );
+            items.extend(hot_pixels);
+        }
+
+        BadPixels { items }
+    }
+
     pub fn find_hot_pixels_in_light(&self) -> Vec<BadPixel> {
         let process_color = |color: CfaColor, x_step: usize, y_step: usize, result: &mut Vec<BadPixel>| {
             let cfa_arr = self.info.cfa.get_array();
@@ -464,17 +524,18 @@ impl RawImage {
         }
 
         let mut fixed_pixels = Vec::new();
+        let mut neighbors = Vec::new();
         for _step in 0..3 {
             fixed_pixels.clear();
             for (px, py) in &pixels_to_fix {
                 let bad_pixel_color = self.cfa_get(*px, *py);
-                let mut cnt = 0_u32;
-                let mut sum = 0_u32;
                 let range = match bad_pixel_color {
                     Some(CfaColor::G)|
                     Some(CfaColor::None) => 1,
                     _                    => 2
                 };
+                // median of neighbors with same color
+                neighbors.clear();
                 for dy in -range..=range {
                     let y = *py + dy;
                     for dx in -range..=range {
@@ -482,14 +543,20 @@ impl RawImage {
                         if self.cfa_get(x, y) == bad_pixel_color
                         && !pixels_to_fix.contains(&(x, y)) {
                             if let Some(v) = self.get(x, y) {
-                                sum += v as u32;
-                                cnt += 1;
+                                neighbors.push(v);
                             }
                         }
                     }
                 }
-                if cnt != 0 {
-                    self.set(*px, *py, (sum / cnt) as u16);
+                if !neighbors.is_empty() {
+                    neighbors.sort_unstable();
+                    let mid = neighbors.len() / 2;
+                    let median = if neighbors.len() % 2 == 0 {
+                        ((neighbors[mid-1] as u32 + neighbors[mid] as u32) / 2) as u16
+                    } else {
+                        neighbors[mid]
+                    };
+                    self.set(*px, *py, median);
                     fixed_pixels.push((*px, *py));
                 }
             }
@@ -535,6 +602,33 @@ impl RawImage {
         Ok(())
     }
 
+    /// Scales dark current of master dark by `factor`.
+    /// Master bias is used as zero point if defined.
+    /// Camera offset is used as zero point otherwise.
+    /// Returns error if there is no zero point for scaling
+    pub fn scale_dark(&mut self, factor: f64, bias: Option<&RawImage>) -> anyhow::Result<()> {
+        if self.data.is_empty() {
+            return Ok(());
+        }
+        if let Some(bias) = bias {
+            self.check_master_frame_is_compatible(bias, &[FrameType::Biases])?;
+            for (v, b) in self.data.iter_mut().zip(&bias.data) {
+                let b = *b as f64;
+                let value = b + factor * (*v as f64 - b);
+                *v = value.round().clamp(0.0, u16::MAX as f64) as u16;
+            }
+        } else if self.info.offset > 0 {
+            let pedestal = self.info.offset as f64;
+            for v in &mut self.data {
+                let value = pedestal + factor * (*v as f64 - pedestal);
+                *v = value.round().clamp(0.0, u16::MAX as f64) as u16;
+            }
+        } else {
+            anyhow::bail!("No master bias or camera offset to use as zero point");
+        }
+        Ok(())
+    }
+
     pub fn subtract_dark_or_bias(&mut self, dark: &RawImage) -> anyhow::Result<()> {
         self.check_master_frame_is_compatible(dark, &[FrameType::Darks, FrameType::Biases])?;
         debug_assert!(self.data.len() == dark.data.len());
@@ -558,21 +652,59 @@ impl RawImage {
         Ok(())
     }
 
+    /// Divides frame by master flat normalized to unity mean
+    /// (separately for every CFA color). Pixels where flat is too
+    /// dark (less than `MIN_FLAT_PART` of mean) are left as is
     pub fn apply_flat(&mut self, flat: &RawImage) -> anyhow::Result<()> {
+        const MIN_FLAT_PART: i64 = 20; // 1/20 = 5% of mean
         self.check_master_frame_is_compatible(flat, &[FrameType::Flats])?;
         debug_assert!(self.data.len() == flat.data.len());
         let zero = self.info.offset as i64;
         let flat_zero = flat.info.offset as i64;
-        self.data.par_iter_mut().zip(flat.data.par_iter()).for_each(|(s, f)| {
-            let flat_value = *f as i64 - flat_zero;
-            let mut value = *s as i64;
-            value -= zero;
-            value = value * u16::MAX as i64 / flat_value;
-            value += zero;
-            if value < 0 { value = 0; }
-            if value > u16::MAX as i64 { value = u16::MAX as i64; }
-            *s = value as u16;
-        });
+        let color_index = |c: CfaColor| -> usize {
+            match c {
+                CfaColor::None => 0,
+                CfaColor::R    => 1,
+                CfaColor::G    => 2,
+                CfaColor::B    => 3,
+            }
+        };
+        let mut sums = [0_i64; 4];
+        let mut counts = [0_i64; 4];
+        for y in 0..flat.info.height {
+            let cfa_row = flat.cfa_row(y);
+            for (v, c) in flat.row(y).iter().zip(cfa_row.iter().cycle()) {
+                let idx = color_index(*c);
+                sums[idx] += (*v as i64 - flat_zero).max(0);
+                counts[idx] += 1;
+            }
+        }
+        let means: [i64; 4] = std::array::from_fn(|i|
+            if counts[i] != 0 { sums[i] / counts[i] } else { 0 }
+        );
+        let width = self.info.width;
+        let cfa_arr = self.cfa_arr;
+        self.data
+            .par_chunks_exact_mut(width)
+            .zip(flat.data.par_chunks_exact(width))
+            .enumerate()
+            .for_each(|(y, (s_row, f_row))| {
+                let cfa_row = cfa_arr[y % cfa_arr.len()];
+                for ((s, f), c) in s_row.iter_mut().zip(f_row).zip(cfa_row.iter().cycle()) {
+                    let flat_mean = means[color_index(*c)];
+                    let flat_value = *f as i64 - flat_zero;
+                    if flat_value <= 0 || flat_value * MIN_FLAT_PART < flat_mean {
+                        continue;
+                    }
+                    let mut value = *s as i64;
+                    value -= zero;
+                    value = value * flat_mean / flat_value;
+                    value += zero;
+                    if value < 0 { value = 0; }
+                    if value > u16::MAX as i64 { value = u16::MAX as i64; }
+                    *s = value as u16;
+                }
+            });
         Ok(())
     }
 
@@ -620,6 +752,7 @@ impl RawImage {
                     CfaColor::G => g_max,
                     CfaColor::B => b_max,
                 };
+                if max <= 0 { continue; }
                 let val = *v as i64 - zero;
                 let normalized: i64 = (u16::MAX as i64 * val) / max;
                 let normalized = normalized.max(0).min(u16::MAX as i64);
@@ -742,6 +875,10 @@ impl RawImage {
     }
 
     fn demosaic_linear(&self, mt: bool, result: &mut Image) {
+        // multithreading has no sense for small images
+        const MIN_PIXELS_FOR_MT: usize = 1_000_000;
+        let mt = mt && self.info.width * self.info.height >= MIN_PIXELS_FOR_MT;
+
         result.make_color(
             self.info.width,
             self.info.height,
@@ -1080,3 +1217,123 @@ impl<'a> Iterator for RawRectIterator<'a> {
         }
     }
 }
+
+#[test]
+fn test_scale_dark_by_bias() {
+    const WIDTH: usize = 16;
+    const HEIGHT: usize = 8;
+    const DARK_EXP: f64 = 300.0;
+    const LIGHT_EXP: f64 = 120.0;
+    const SKY_LEVEL: f64 = 500.0;
+    let make_image = |frame_type, exposure, fun: &dyn Fn(usize) -> f64| {
+        let info = RawImageInfo {
+            time:           None,
+            width:          WIDTH,
+            height:         HEIGHT,
+            gain:           100,
+            offset:         0,
+            max_value:      u16::MAX,
+            cfa:            CfaType::None,
+            bin:            1,
+            frame_type,
+            exposure,
+            integr_time:    None,
+            camera:         String::new(),
+            object:         None,
+            ccd_temp:       None,
+            focal_len:      None,
+            pixel_size_x:   None,
+            pixel_size_y:   None,
+            calibr_methods: CalibrMethods::empty(),
+        };
+        let data = (0..WIDTH * HEIGHT).map(|i| fun(i).round() as u16).collect();
+        RawImage::new(info, data, CfaType::None.get_array())
+    };
+
+    // Non-uniform bias and dark current with some hot pixels
+    let bias_level = |i: usize| 1000.0 + (i % 7) as f64 * 10.0;
+    let dark_current = |i: usize| if i % 13 == 0 { 2.0 } else { 0.1 }; // per second
+
+    let bias = make_image(FrameType::Biases, 0.0, &bias_level);
+    let mut dark = make_image(
+        FrameType::Darks, DARK_EXP,
+        &|i| bias_level(i) + DARK_EXP * dark_current(i)
+    );
+    let mut light = make_image(
+        FrameType::Lights, LIGHT_EXP,
+        &|i| bias_level(i) + LIGHT_EXP * dark_current(i) + SKY_LEVEL
+    );
+
+    // No zero point to scale dark current relative to
+    let mut dark_wo_bias = dark.clone();
+    assert!(dark_wo_bias.scale_dark(LIGHT_EXP / DARK_EXP, None).is_err());
+    assert!(dark_wo_bias.as_slice() == dark.as_slice());
+    dark_wo_bias.info.offset = 1000;
+    assert!(dark_wo_bias.scale_dark(LIGHT_EXP / DARK_EXP, None).is_ok());
+
+    dark.scale_dark(LIGHT_EXP / DARK_EXP, Some(&bias)).unwrap();
+    for (i, v) in dark.as_slice().iter().enumerate() {
+        let thermal = *v as f64 - bias_level(i);
+        assert!((thermal - LIGHT_EXP * dark_current(i)).abs() <= 1.0, "i={}", i);
+    }
+
+    // Only sky signal must remain after subtraction
+    light.subtract_dark_or_bias(&dark).unwrap();
+    let first = light.as_slice()[0] as i32;
+    assert!(light.as_slice().iter().all(|v| (*v as i32 - first).abs() <= 1));
+}
+
+#[test]
+fn test_apply_flat() {
+    const WIDTH: usize = 32;
+    const HEIGHT: usize = 16;
+    const SKY_LEVEL: f64 = 1000.0;
+    let make_image = |frame_type, fun: &dyn Fn(usize, usize) -> f64| {
+        let info = RawImageInfo {
+            time:           None,
+            width:          WIDTH,
+            height:         HEIGHT,
+            gain:           100,
+            offset:         0,
+            max_value:      u16::MAX,
+            cfa:            CfaType::None,
+            bin:            1,
+            frame_type,
+            exposure:       1.0,
+            integr_time:    None,
+            camera:         String::new(),
+            object:         None,
+            ccd_temp:       None,
+            focal_len:      None,
+            pixel_size_x:   None,
+            pixel_size_y:   None,
+            calibr_methods: CalibrMethods::empty(),
+        };
+        let data = (0..WIDTH * HEIGHT)
+            .map(|i| fun(i % WIDTH, i / WIDTH).round() as u16)
+            .collect();
+        RawImage::new(info, data, CfaType::None.get_array())
+    };
+
+    // Vignetting from 50% to 100% and fully dark first column
+    let vignetting = |x: usize, _y: usize| {
+        if x == 0 { 0.0 } else { 0.5 + 0.5 * x as f64 / (WIDTH - 1) as f64 }
+    };
+    let mut flat = make_image(FrameType::Flats, &|x, y| 30000.0 * vignetting(x, y));
+    flat.normalize_flat();
+    let mut light = make_image(FrameType::Lights, &|x, y| SKY_LEVEL * vignetting(x, y).max(0.25));
+    light.apply_flat(&flat).unwrap();
+
+    let flat_mean = (1..WIDTH)
+        .map(|x| vignetting(x, 0))
+        .sum::<f64>() / WIDTH as f64;
+    for y in 0..HEIGHT {
+        let row = light.row(y);
+        // dark region of flat is left as is
+        assert_eq!(row[0], (0.25 * SKY_LEVEL) as u16);
+        for &v in &row[1..] {
+            let expected = SKY_LEVEL * flat_mean;
+            assert!((v as f64 - expected).abs() < 0.01 * expected, "{} {}", v, expected);
+        }
+    }
+}
@@ -92,6 +92,13 @@ impl Header {
         self.bitpix
     }
 
+    /// Removes values which are present in `other` header
+    pub fn remove_existing_in(&mut self, other: &Header) {
+        self.values.retain(|item|
+            !other.values.iter().any(|other| other.name.eq_ignore_ascii_case(&item.name))
+        );
+    }
+
     pub fn get_i64(&self, key: &str) -> Option<i64> {
         Self::get_value_impl(&self.values, key)
     }
@@ -150,7 +157,7 @@ impl FitsReader {
                 }
             };
 
-            let size = hdu.bytes_len;
+            let size = hdu.bytes_len.div_ceil(2880) * 2880;
             result.push(hdu);
             stream.seek(SeekFrom::Current(size as i64))?;
         }
@@ -195,7 +202,7 @@ impl FitsReader {
         let bitpix: i8 = Header::get_value_impl(&values, "BITPIX").unwrap_or(DEFAULT_BITPIX as i8);
 
         let mut dims = Vec::new();
-        let mut data_len = 1_usize;
+        let mut data_len = if ndim != 0 { 1_usize } else { 0_usize };
         for idx in 1 ..= ndim {
             let key = format!("NAXIS{}", idx);
             let dim: usize = Header::get_value_impl(&values, &key).unwrap_or(1);
@@ -316,19 +323,23 @@ impl FitsWriter {
         Ok(())
     }
 
+    fn value_to_card(item: &Value) -> String {
+        let mut line = format!("{:8}= ", item.name);
+        if item.value.starts_with("'") {
+            line.push_str(&format!("{:<20}", item.value))
+        } else {
+            line.push_str(&format!("{:>20}", item.value))
+        }
+        line.push_str(" / ");
+        line.push_str(item.comment.as_ref().unwrap_or(&item.name));
+        while line.len() < 80 { line.push(' '); }
+        while line.len() > 80 { line.pop(); }
+        line
+    }
+
     pub fn write_header(&self, stream: &mut dyn SeekNWrite, hdu: &Header) -> Result<()> {
         for item in &hdu.values {
-            let mut line = format!("{:8}= ", item.name);
-            if item.value.starts_with("'") {
-                line.push_str(&format!("{:<20}", item.value))
-            } else {
-                line.push_str(&format!("{:>20}", item.value))
-            }
-            line.push_str(" / ");
-            line.push_str(item.comment.as_ref().unwrap_or(&item.name));
-            while line.len() < 80 { line.push(' '); }
-            while line.len() > 80 { line.pop(); }
-            stream.write_all(line.as_bytes())?;
+            stream.write_all(Self::value_to_card(item).as_bytes())?;
         }
         write!(stream, "{:80}", "END")?;
         let lines_written = hdu.values.len() + 1;
@@ -339,6 +350,53 @@ impl FitsWriter {
         Ok(())
     }
 
+    /// Returns FITS file data with `hdu` values written into primary
+    /// header. Existing values with same names are replaced. Other
+    /// header cards and data are kept as is
+    pub fn add_values_to_header(&self, data: &[u8], hdu: &Header) -> Result<Vec<u8>> {
+        const CARD_LEN: usize = 80;
+        const BLOCK_LEN: usize = 2880;
+        let mut cards = Vec::new();
+        let mut header_len = None;
+        for (idx, card) in data.chunks_exact(CARD_LEN).enumerate() {
+            if card.starts_with(b"END") && card[3..].iter().all(|b| *b == b' ') {
+                header_len = Some(((idx + 1) * CARD_LEN).div_ceil(BLOCK_LEN) * BLOCK_LEN);
+                break;
+            }
+            cards.push(card);
+        }
+        let Some(header_len) = header_len.filter(|len| *len <= data.len()) else {
+            return Err(invalid_data("End of FITS header is not found"));
+        };
+
+        let mut result = Vec::with_capacity(data.len() + BLOCK_LEN);
+        let mut added = vec![false; hdu.values.len()];
+        for card in cards {
+            let key = String::from_utf8_lossy(&card[..8]);
+            let key = key.trim();
+            let new_value = hdu.values.iter()
+                .position(|item| item.name.eq_ignore_ascii_case(key));
+            if let Some(idx) = new_value {
+                result.extend_from_slice(Self::value_to_card(&hdu.values[idx]).as_bytes());
+                added[idx] = true;
+            } else {
+                result.extend_from_slice(card);
+            }
+        }
+        for (item, added) in izip!(&hdu.values, added) {
+            if !added {
+                result.extend_from_slice(Self::value_to_card(item).as_bytes());
+            }
+        }
+        result.extend_from_slice(format!("{:80}", "END").as_bytes());
+        let written = result.len() % BLOCK_LEN;
+        if written != 0 {
+            result.resize(result.len() + BLOCK_LEN - written, b' ');
+        }
+        result.extend_from_slice(&data[header_len..]);
+        Ok(result)
+    }
+
     fn write_data(
         &self,
         bitpix: i8,
@@ -451,3 +509,430 @@ impl FitsWriter {
     }
 
 }
+
+///////////////////////////////////////////////////////////////////////////////
+
+// Tile compressed images (fpack, .fits.fz)
+
+pub fn is_fpack_format(format: &str) -> bool {
+    let format = format.trim().trim_start_matches('.');
+    format.eq_ignore_ascii_case("fits.fz") ||
+    format.eq_ignore_ascii_case("fit.fz") ||
+    format.eq_ignore_ascii_case("fz")
+}
+
+/// Converts tile compressed FITS (only RICE_1 for integer images is
+/// supported) into plain uncompressed FITS
+pub fn decompress_fits(data: &[u8]) -> Result<Vec<u8>> {
+    let mut stream = Cursor::new(data);
+    let reader = FitsReader::new(&mut stream)?;
+    let hdu = reader.headers.iter()
+        .find(|hdu| hdu.get_str("ZIMAGE") == Some("T"))
+        .ok_or_else(|| invalid_data("No tile compressed image found"))?;
+
+    let cmp_type = hdu.get_str("ZCMPTYPE").unwrap_or_default();
+    if !cmp_type.eq_ignore_ascii_case("RICE_1") {
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            format!("Compression {} is not supported", cmp_type)
+        ));
+    }
+    let zbitpix = hdu.get_i64("ZBITPIX").unwrap_or(0);
+    let bytepix = match zbitpix {
+        8  => 1,
+        16 => 2,
+        32 => 4,
+        _  => return Err(Error::new(
+            ErrorKind::Unsupported,
+            format!("ZBITPIX = {} is not supported", zbitpix)
+        )),
+    };
+    let znaxis = hdu.get_i64("ZNAXIS").unwrap_or(0);
+    if znaxis != 2 {
+        return Err(Error::new(
+            ErrorKind::Unsupported,
+            format!("ZNAXIS = {} is not supported", znaxis)
+        ));
+    }
+    let width = hdu.get_i64("ZNAXIS1").unwrap_or(0).max(0) as usize;
+    let height = hdu.get_i64("ZNAXIS2").unwrap_or(0).max(0) as usize;
+    let tile_w = hdu.get_i64("ZTILE1").map(|v| v.max(1) as usize).unwrap_or(width);
+    let tile_h = hdu.get_i64("ZTILE2").map(|v| v.max(1) as usize).unwrap_or(1);
+    if width == 0 || height == 0 {
+        return Err(invalid_data("Wrong size of compressed image"));
+    }
+
+    let mut block_size = 32_usize;
+    for idx in 1.. {
+        let Some(name) = hdu.get_str(&format!("ZNAME{}", idx)) else { break; };
+        if name.eq_ignore_ascii_case("BLOCKSIZE") {
+            let value = hdu.get_i64(&format!("ZVAL{}", idx)).unwrap_or(0);
+            if value > 0 { block_size = value as usize; }
+        }
+    }
+
+    // Position of COMPRESSED_DATA column in binary table row
+
+    let tfields = hdu.get_i64("TFIELDS").unwrap_or(0);
+    let mut col_offset = 0_usize;
+    let mut descr_type = None;
+    for idx in 1..=tfields {
+        let tform = hdu.get_str(&format!("TFORM{}", idx)).unwrap_or_default();
+        let (col_width, col_type) = bintable_col_width(tform)
+            .ok_or_else(|| invalid_data(format!("Wrong TFORM{} = {}", idx, tform)))?;
+        let ttype = hdu.get_str(&format!("TTYPE{}", idx)).unwrap_or_default();
+        if ttype.eq_ignore_ascii_case("COMPRESSED_DATA") {
+            descr_type = Some(col_type);
+            break;
+        }
+        col_offset += col_width;
+    }
+    let Some(descr_type @ ('P'|'Q')) = descr_type else {
+        return Err(invalid_data("COMPRESSED_DATA column not found"));
+    };
+    let (row_len, rows_cnt) = match hdu.dims.as_slice() {
+        [row_len, rows_cnt] => (*row_len, *rows_cnt),
+        _ => return Err(invalid_data("Wrong binary table of compressed image")),
+    };
+    let table_pos = hdu.data_pos;
+    let heap_pos = table_pos + hdu.get_i64("THEAP")
+        .map(|v| v.max(0) as usize)
+        .unwrap_or(row_len * rows_cnt);
+
+    let read_be = |pos: usize, len: usize| -> Result<usize> {
+        let bytes = data.get(pos..pos+len)
+            .ok_or_else(|| invalid_data("Unexpected end of compressed data"))?;
+        Ok(bytes.iter().fold(0_usize, |acc, b| (acc << 8) | *b as usize))
+    };
+
+    // Decompress tiles
+
+    let tiles_x = width.div_ceil(tile_w);
+    let tiles_y = height.div_ceil(tile_h);
+    if tiles_x * tiles_y > rows_cnt {
+        return Err(invalid_data("Not enough tiles in compressed image"));
+    }
+    let mut pixels = vec![0_u32; width * height];
+    let mut tile_buf = Vec::new();
+    for (row, (ty, tx)) in (0..tiles_y).cartesian_product(0..tiles_x).enumerate() {
+        let descr_pos = table_pos + row * row_len + col_offset;
+        let (count, offset) = if descr_type == 'P' {
+            (read_be(descr_pos, 4)?, read_be(descr_pos + 4, 4)?)
+        } else {
+            (read_be(descr_pos, 8)?, read_be(descr_pos + 8, 8)?)
+        };
+        let tile_pos = heap_pos + offset;
+        let tile_data = data.get(tile_pos..tile_pos+count)
+            .ok_or_else(|| invalid_data("Unexpected end of compressed data"))?;
+        let x0 = tx * tile_w;
+        let y0 = ty * tile_h;
+        let tw = tile_w.min(width - x0);
+        let th = tile_h.min(height - y0);
+        tile_buf.resize(tw * th, 0);
+        rice_decompress(tile_data, bytepix, block_size, &mut tile_buf)?;
+        for (y, src_row) in tile_buf.chunks_exact(tw).enumerate() {
+            let dst_start = (y0 + y) * width + x0;
+            pixels[dst_start..dst_start+tw].copy_from_slice(src_row);
+        }
+    }
+
+    // Write uncompressed image
+
+    let mut result_hdr = Header::new();
+    result_hdr.set_bool("SIMPLE", true);
+    result_hdr.set_i64("BITPIX", zbitpix);
+    result_hdr.set_i64("NAXIS",  2);
+    result_hdr.set_i64("NAXIS1", width as i64);
+    result_hdr.set_i64("NAXIS2", height as i64);
+    for value in &hdu.values {
+        if !is_compression_keyword(&value.name) {
+            result_hdr.values.push(value.clone());
+        }
+    }
+
+    let mut result = Cursor::new(Vec::new());
+    FitsWriter::new().write_header(&mut result, &result_hdr)?;
+    let mut result = result.into_inner();
+    result.reserve(pixels.len() * bytepix + 2880);
+    for value in pixels {
+        result.extend_from_slice(&value.to_be_bytes()[4-bytepix..]);
+    }
+    let written = result.len() % 2880;
+    if written != 0 {
+        result.resize(result.len() + 2880 - written, 0);
+    }
+
+    Ok(result)
+}
+
+fn invalid_data(text: impl Into<String>) -> Error {
+    Error::new(ErrorKind::InvalidData, text.into())
+}
+
+fn is_compression_keyword(name: &str) -> bool {
+    const KEYS: &[&str] = &[
+        "SIMPLE", "EXTEND", "XTENSION", "BITPIX", "PCOUNT", "GCOUNT",
+        "TFIELDS", "THEAP", "EXTNAME", "CHECKSUM", "DATASUM",
+        "ZIMAGE", "ZCMPTYPE", "ZBITPIX", "ZQUANTIZ", "ZDITHER0",
+        "ZSIMPLE", "ZEXTEND", "ZBLOCKED", "ZTENSION", "ZPCOUNT",
+        "ZGCOUNT", "ZHECKSUM", "ZDATASUM", "ZMASKCMP",
+    ];
+    const PREFIXES: &[&str] = &[
+        "NAXIS", "TTYPE", "TFORM", "TUNIT", "TDIM",
+        "ZNAXIS", "ZTILE", "ZNAME", "ZVAL",
+    ];
+    let name = name.to_ascii_uppercase();
+    KEYS.contains(&name.as_str()) ||
+    PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+}
+
+/// Returns width in bytes and type of binary table column
+fn bintable_col_width(tform: &str) -> Option<(usize, char)> {
+    let digits_len = tform.chars().take_while(|c| c.is_ascii_digit()).count();
+    let repeat: usize = if digits_len != 0 { tform[..digits_len].parse().ok()? } else { 1 };
+    let col_type = tform[digits_len..].chars().next()?.to_ascii_uppercase();
+    let width = match col_type {
+        'L' | 'B' | 'A'       => repeat,
+        'I'                   => 2 * repeat,
+        'J' | 'E'             => 4 * repeat,
+        'K' | 'D' | 'C' | 'P' => 8 * repeat,
+        'M' | 'Q'             => 16 * repeat,
+        'X'                   => repeat.div_ceil(8),
+        _                     => return None,
+    };
+    Some((width, col_type))
+}
+
+struct RiceBytes<'a> {
+    data: &'a [u8],
+    pos:  usize,
+}
+
+impl RiceBytes<'_> {
+    fn next(&mut self) -> Result<u64> {
+        let result = self.data
+            .get(self.pos)
+            .ok_or_else(|| invalid_data("Compressed tile is truncated"))?;
+        self.pos += 1;
+        Ok(*result as u64)
+    }
+}
+
+/// Rice decompression (the same algorithm as `fits_rdecomp` in cfitsio).
+/// Values are returned as unsigned integers of `bytepix` width
+fn rice_decompress(
+    data:       &[u8],
+    bytepix:    usize,
+    block_size: usize,
+    result:     &mut [u32],
+) -> Result<()> {
+    let (fsbits, fsmax, bbits) = match bytepix {
+        1 => (3, 6, 8),
+        2 => (4, 14, 16),
+        4 => (5, 25, 32),
+        _ => return Err(Error::new(
+            ErrorKind::Unsupported,
+            format!("BYTEPIX = {} is not supported", bytepix)
+        )),
+    };
+    let mask = (1_u64 << bbits) - 1;
+    let unzigzag = |diff: u64| -> u64 {
+        if diff & 1 == 0 { diff >> 1 } else { !(diff >> 1) & mask }
+    };
+
+    let mut bytes = RiceBytes { data, pos: 0 };
+    let mut lastpix = 0_u64;
+    for _ in 0..bytepix {
+        lastpix = (lastpix << 8) | bytes.next()?;
+    }
+    let mut b = bytes.next()?;
+    let mut nbits: i32 = 8;
+    let mut i = 0_usize;
+    while i < result.len() {
+        nbits -= fsbits;
+        while nbits < 0 {
+            b = (b << 8) | bytes.next()?;
+            nbits += 8;
+        }
+        let fs = (b >> nbits) as i32 - 1;
+        b &= (1 << nbits) - 1;
+        let imax = (i + block_size).min(result.len());
+        let block = &mut result[i..imax];
+        i = imax;
+        if fs < 0 {
+            // low-entropy case: all differences are zero
+            block.fill(lastpix as u32);
+        } else if fs == fsmax {
+            // high-entropy case: differences are stored as is
+            for dst in block {
+                let mut k = bbits - nbits;
+                let mut diff = b << k;
+                k -= 8;
+                while k >= 0 {
+                    b = bytes.next()?;
+                    diff |= b << k;
+                    k -= 8;
+                }
+                if nbits > 0 {
+                    b = bytes.next()?;
+                    diff |= b >> -k;
+                    b &= (1 << nbits) - 1;
+                } else {
+                    b = 0;
+                }
+                lastpix = unzigzag(diff & mask).wrapping_add(lastpix) & mask;
+                *dst = lastpix as u32;
+            }
+        } else {
+            // normal case: Rice coding
+            for dst in block {
+                while b == 0 {
+                    nbits += 8;
+                    b = bytes.next()?;
+                }
+                let nzero = nbits - (64 - b.leading_zeros() as i32);
+                nbits -= nzero + 1;
+                b ^= 1 << nbits;
+                nbits -= fs;
+                while nbits < 0 {
+                    b = (b << 8) | bytes.next()?;
+                    nbits += 8;
+                }
+                let diff = ((nzero as u64) << fs) | (b >> nbits);
+                b &= (1 << nbits) - 1;
+                lastpix = unzigzag(diff & mask).wrapping_add(lastpix) & mask;
+                *dst = lastpix as u32;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+fn rice_compress(data: &[u32], bytepix: usize, block_size: usize) -> Vec<u8> {
+    let (fsbits, fsmax, bbits) = match bytepix {
+        1 => (3, 6, 8),
+        2 => (4, 14, 16),
+        _ => (5, 25, 32),
+    };
+    let mask = (1_u64 << bbits) - 1;
+    let mut bits = Vec::<bool>::new();
+    let mut put_bits = |value: u64, cnt: usize| {
+        for idx in (0..cnt).rev() {
+            bits.push((value >> idx) & 1 != 0);
+        }
+    };
+    let mut lastpix = data[0] as u64;
+    put_bits(lastpix, 8 * bytepix);
+    for block in data.chunks(block_size) {
+        let diffs = block.iter().map(|v| {
+            let diff = (*v as u64).wrapping_sub(lastpix) & mask;
+            lastpix = *v as u64;
+            let negative = diff >> (bbits - 1) != 0;
+            if negative { !(diff << 1) & mask } else { (diff << 1) & mask }
+        }).collect::<Vec<_>>();
+        let sum: u64 = diffs.iter().sum();
+        let dpsum = (sum as f64 - (diffs.len() / 2) as f64 - 1.0) / diffs.len() as f64;
+        let mut psum = (dpsum.max(0.0) as u64) >> 1;
+        let mut fs = 0;
+        while psum > 0 { psum >>= 1; fs += 1; }
+        if fs >= fsmax {
+            put_bits(fsmax as u64 + 1, fsbits);
+            for diff in diffs { put_bits(diff, bbits); }
+        } else if sum == 0 {
+            put_bits(0, fsbits);
+        } else {
+            put_bits(fs as u64 + 1, fsbits);
+            for diff in diffs {
+                put_bits(1, (diff >> fs) as usize + 1);
+                put_bits(diff & ((1 << fs) - 1), fs);
+            }
+        }
+    }
+    bits.chunks(8)
+        .map(|chunk| chunk.iter().enumerate().fold(0_u8, |acc, (idx, bit)| {
+            acc | ((*bit as u8) << (7 - idx))
+        }))
+        .collect()
+}
+
+#[test]
+fn test_rice_decompress() {
+    let mut data = Vec::new();
+    // low-entropy block
+    data.extend([1000_u32; 32]);
+    // normal block
+    data.extend((0..32_u32).map(|i| 1000 + (i * 37) % 23));
+    // high-entropy block
+    data.extend((0..32_u32).map(|i| (i * 40503) % 65536));
+    // incomplete block
+    data.extend((0..7_u32).map(|i| 65535 - i));
+
+    for bytepix in [2, 4] {
+        let compressed = rice_compress(&data, bytepix, 32);
+        let mut decompressed = vec![0_u32; data.len()];
+        rice_decompress(&compressed, bytepix, 32, &mut decompressed).unwrap();
+        assert_eq!(decompressed, data);
+    }
+
+    let data8 = data.iter().map(|v| v & 0xFF).collect::<Vec<_>>();
+    let compressed = rice_compress(&data8, 1, 16);
+    let mut decompressed = vec![0_u32; data8.len()];
+    rice_decompress(&compressed, 1, 16, &mut decompressed).unwrap();
+    assert_eq!(decompressed, data8);
+
+    assert!(is_fpack_format(".fits.fz"));
+    assert!(is_fpack_format("FZ"));
+    assert!(!is_fpack_format(".fits"));
+}
+
+#[test]
+fn test_decompress_fits() {
+    // 4x2 image with BZERO = 32768 compressed by rows.
+    // First row is coded by Rice, second one is constant
+    let data = include_bytes!("test_data/rice_4x2.fits.fz");
+    let decompressed = decompress_fits(data).unwrap();
+    assert!(decompressed.len() % 2880 == 0);
+
+    let mut stream = Cursor::new(decompressed.as_slice());
+    let reader = FitsReader::new(&mut stream).unwrap();
+    let hdu = &reader.headers[0];
+    assert!(hdu.bitpix() == 16);
+    assert!(hdu.dims() == &vec![4, 2]);
+    assert!(hdu.get_str("OBJECT") == Some("M31"));
+    assert!(hdu.get_str("ZCMPTYPE").is_none());
+    let mut pixels = [0_u16; 8];
+    FitsReader::read_data(hdu, &mut stream, 0, &mut pixels).unwrap();
+    assert!(pixels == [100, 101, 103, 102, 200, 200, 200, 200]);
+}
+
+#[test]
+fn test_add_values_to_header() {
+    let writer = FitsWriter::new();
+    let mut hdu = Header::new_2d(4, 2);
+    hdu.set_str("INSTRUME", "Camera");
+    hdu.set_f64("EXPTIME", 10.0);
+    let data = [1000_u16, 2000, 3000, 4000, 5000, 6000, 7000, 8000];
+    let mut stream = Cursor::new(Vec::new());
+    writer.write_header_and_data_u16(&mut stream, &hdu, &data).unwrap();
+    let orig_data = stream.into_inner();
+
+    let mut new_hdu = Header::new();
+    new_hdu.set_f64("EXPTIME", 20.0);
+    new_hdu.set_f64("CRVAL1", 1.5);
+    let new_data = writer.add_values_to_header(&orig_data, &new_hdu).unwrap();
+    assert!(new_data.len() == orig_data.len()); // new values fit into same header block
+
+    let mut stream = Cursor::new(new_data.as_slice());
+    let reader = FitsReader::new(&mut stream).unwrap();
+    let hdu = &reader.headers[0];
+    assert!(hdu.get_str("INSTRUME") == Some("Camera"));
+    assert!(hdu.get_f64("EXPTIME") == Some(20.0));
+    assert!(hdu.get_f64("CRVAL1") == Some(1.5));
+    assert!(hdu.dims() == &vec![4, 2]);
+    let mut read_data = [0_u16; 8];
+    FitsReader::read_data(hdu, &mut stream, 0, &mut read_data).unwrap();
+    assert!(read_data == data);
+
+    assert!(writer.add_values_to_header(&[b' '; 2880], &new_hdu).is_err());
+}
@@ -18,6 +18,7 @@ struct StackerTempChan {
 struct StackerChan {
     data: Vec<i32>,
     tmp: Vec<StackerTempChan>,
+    drz: Vec<f32>, // weighted sum for drizzle
 }
 
 impl StackerChan {
@@ -26,14 +27,23 @@ impl StackerChan {
         self.data.shrink_to_fit();
         self.tmp.clear();
         self.tmp.shrink_to_fit();
+        self.drz.clear();
+        self.drz.shrink_to_fit();
     }
 
     fn is_empty(&self) -> bool {
-        self.data.is_empty() && self.tmp.is_empty()
+        self.data.is_empty() && self.tmp.is_empty() && self.drz.is_empty()
     }
 
-    fn get(&self, dest: &mut [u16], from: usize, to: usize, cnt: &[u16]) {
-        if self.tmp.len() == 1 {
+    fn get(&self, dest: &mut [u16], from: usize, to: usize, cnt: &[u16], drz_weight: &[f32]) {
+        if !self.drz.is_empty() {
+            let data = &self.drz[from..to];
+            let weight = &drz_weight[from..to];
+            for (d, s, w) in izip!(dest, data, weight) {
+                let value = if *w > 0.0 { (*s / *w).round() } else { 0.0 };
+                *d = value.clamp(0.0, u16::MAX as f32) as u16;
+            }
+        } else if self.tmp.len() == 1 {
             dest.copy_from_slice(&self.tmp[0].data[from..to]);
         } else if self.tmp.len() == 2 {
             let src1 = &self.tmp[0].data[from..to];
@@ -90,6 +100,7 @@ pub struct Stacker {
     b: StackerChan,
     l: StackerChan,
     cnt: Vec<u16>,
+    drz_weight: Vec<f32>,
     tmp_idx: usize,
     width: usize,
     height: usize,
@@ -97,9 +108,13 @@ pub struct Stacker {
     total_exp: f64,
     frames_cnt: u32,
     no_tracks: bool,
+    drizzle: bool,
     raw_info: Option<RawImageInfo>,
 }
 
+/// Size of drizzle drop relative to input pixel
+const DRIZZLE_PIXFRAC: f64 = 0.7;
+
 impl Stacker {
     pub fn new() -> Self {
         Self {
@@ -108,6 +123,7 @@ impl Stacker {
             b: StackerChan::default(),
             l: StackerChan::default(),
             cnt: Vec::new(),
+            drz_weight: Vec::new(),
             tmp_idx: 0,
             width: 0,
             height: 0,
@@ -115,6 +131,7 @@ impl Stacker {
             total_exp: 0.0,
             frames_cnt: 0,
             no_tracks: false,
+            drizzle: false,
             raw_info: None,
         }
     }
@@ -124,7 +141,8 @@ impl Stacker {
         self.r.data.is_empty() &&
         self.g.data.is_empty() &&
         self.b.data.is_empty() &&
-        self.cnt.is_empty()
+        self.cnt.is_empty() &&
+        self.drz_weight.is_empty()
     }
 
     pub fn clear(&mut self) {
@@ -134,6 +152,8 @@ impl Stacker {
         self.b.clear();
         self.cnt.clear();
         self.cnt.shrink_to_fit();
+        self.drz_weight.clear();
+        self.drz_weight.shrink_to_fit();
         self.tmp_idx = 0;
         self.width = 0;
         self.height = 0;
@@ -151,25 +171,45 @@ impl Stacker {
         angle:     f64,
         exposure:  f64,
         no_tracks: bool,
+        drizzle:   bool, // 2x drizzle. `no_tracks` is ignored for drizzle
     ) {
         debug_assert!(!image.is_empty());
         if self.is_empty() {
-            let data_len = image.width() * image.height();
-            if image.is_color() {
-                self.r.data.resize(data_len, 0);
-                self.g.data.resize(data_len, 0);
-                self.b.data.resize(data_len, 0);
+            self.drizzle = drizzle;
+            self.no_tracks = no_tracks && !drizzle;
+            let scale = if drizzle { 2 } else { 1 };
+            self.width = scale * image.width();
+            self.height = scale * image.height();
+            let data_len = self.width * self.height;
+            if drizzle {
+                if image.is_color() {
+                    self.r.drz.resize(data_len, 0.0);
+                    self.g.drz.resize(data_len, 0.0);
+                    self.b.drz.resize(data_len, 0.0);
+                } else {
+                    self.l.drz.resize(data_len, 0.0);
+                }
+                self.drz_weight.resize(data_len, 0.0);
             } else {
-                self.l.data.resize(data_len, 0);
+                if image.is_color() {
+                    self.r.data.resize(data_len, 0);
+                    self.g.data.resize(data_len, 0);
+                    self.b.data.resize(data_len, 0);
+                } else {
+                    self.l.data.resize(data_len, 0);
+                }
+                self.cnt.resize(data_len, 0);
             }
-            self.cnt.resize(data_len, 0);
-            self.width = image.width();
-            self.height = image.height();
             self.max_value = image.max_value();
-            self.no_tracks = no_tracks;
             self.raw_info = image.raw_info.clone();
+            if let Some(raw_info) = &mut self.raw_info {
+                raw_info.width = self.width;
+                raw_info.height = self.height;
+            }
         }
-        if !self.no_tracks {
+        if self.drizzle {
+            self.add_drizzle(image, transl_x, transl_y, angle);
+        } else if !self.no_tracks {
             self.add_simple(image, transl_x, transl_y, angle);
         } else {
             self.add_no_tracks(image, hist, transl_x, transl_y, angle);
@@ -243,6 +283,82 @@ impl Stacker {
             });
     }
 
+    fn add_drizzle(
+        &mut self,
+        image:    &Image,
+        transl_x: f64,
+        transl_y: f64,
+        angle:    f64,
+    ) {
+        let weight = &mut self.drz_weight;
+        Self::add_drizzle_layer(&mut self.r, weight, &image.r, transl_x, transl_y, angle, false);
+        Self::add_drizzle_layer(&mut self.g, weight, &image.g, transl_x, transl_y, angle, false);
+        Self::add_drizzle_layer(&mut self.b, weight, &image.b, transl_x, transl_y, angle, true);
+        Self::add_drizzle_layer(&mut self.l, weight, &image.l, transl_x, transl_y, angle, true);
+    }
+
+    /// 2x drizzle. Every pixel of source image is shrunk to drop
+    /// (DRIZZLE_PIXFRAC of pixel size) and added into result pixels
+    /// with weight equal to area of overlapping
+    fn add_drizzle_layer(
+        dst:           &mut StackerChan,
+        weight:        &mut [f32],
+        src:           &ImageLayer<u16>,
+        transl_x:      f64,
+        transl_y:      f64,
+        angle:         f64,
+        update_weight: bool
+    ) {
+        if src.is_empty() {
+            return;
+        }
+
+        // Overlapping of result pixel and drop along one axis.
+        // `dist` is distance between their centers in result pixels
+        fn overlap(dist: f64) -> f64 {
+            let half_drop = DRIZZLE_PIXFRAC; // drop size is 2*DRIZZLE_PIXFRAC result pixels
+            let value = f64::min(half_drop, dist + 0.5) - f64::max(-half_drop, dist - 0.5);
+            value.max(0.0)
+        }
+
+        let cos_a = f64::cos(-angle);
+        let sin_a = f64::sin(-angle);
+        let center_x = (src.width() as f64 - 1.0) / 2.0;
+        let center_y = (src.height() as f64 - 1.0) / 2.0;
+        let dst_width = 2 * src.width();
+
+        dst.drz.par_chunks_exact_mut(dst_width)
+            .zip(weight.par_chunks_exact_mut(dst_width))
+            .enumerate()
+            .for_each(|(y, (dst_row, weight_row))| {
+                // center of result pixel in coordinates of source image
+                let y = (y as f64 - 0.5) / 2.0 - transl_y;
+                let dy = y - center_y;
+                for (x, (dst_v, weight_v)) in dst_row.iter_mut().zip(weight_row).enumerate() {
+                    let x = (x as f64 - 0.5) / 2.0 - transl_x;
+                    let dx = x - center_x;
+                    let rot_x = center_x + dx * cos_a - dy * sin_a;
+                    let rot_y = center_y + dy * cos_a + dx * sin_a;
+                    let src_x0 = rot_x.floor();
+                    let src_y0 = rot_y.floor();
+                    for src_y in [src_y0, src_y0 + 1.0] {
+                        let w_y = overlap(2.0 * (rot_y - src_y));
+                        if w_y == 0.0 { continue; }
+                        for src_x in [src_x0, src_x0 + 1.0] {
+                            let w_x = overlap(2.0 * (rot_x - src_x));
+                            if w_x == 0.0 { continue; }
+                            let Some(v) = src.get(src_x as isize, src_y as isize) else {
+                                continue;
+                            };
+                            let w = (w_x * w_y) as f32;
+                            *dst_v += v as f32 * w;
+                            if update_weight { *weight_v += w; }
+                        }
+                    }
+                }
+            });
+    }
+
     pub fn add_no_tracks(
         &mut self,
         image:    &Image,
@@ -404,7 +520,7 @@ impl Stacker {
         use tiff::encoder::*;
         let mut file = BufWriter::new(File::create(file_name)?);
         let mut decoder = TiffEncoder::new(&mut file)?;
-        if !self.l.data.is_empty() {
+        if !self.l.is_empty() {
             let mut tiff = decoder.new_image::<colortype::Gray16>(
                 self.width as u32,
                 self.height as u32
@@ -420,7 +536,7 @@ impl Stacker {
                 let from = pos;
                 let to = pos + samples_count;
                 values.resize(samples_count, 0);
-                self.l.get(&mut values, from, to, &self.cnt);
+                self.l.get(&mut values, from, to, &self.cnt, &self.drz_weight);
 
                 tiff.write_strip(&values)?;
                 pos += samples_count;
@@ -445,11 +561,11 @@ impl Stacker {
                 let from = pos;
                 let to = pos + samples_count;
                 r_values.resize(samples_count, 0);
-                self.r.get(&mut r_values, from, to, &self.cnt);
+                self.r.get(&mut r_values, from, to, &self.cnt, &self.drz_weight);
                 g_values.resize(samples_count, 0);
-                self.g.get(&mut g_values, from, to, &self.cnt);
+                self.g.get(&mut g_values, from, to, &self.cnt, &self.drz_weight);
                 b_values.resize(samples_count, 0);
-                self.b.get(&mut b_values, from, to, &self.cnt);
+                self.b.get(&mut b_values, from, to, &self.cnt, &self.drz_weight);
 
                 strip_data.clear();
                 for (r, g, b) in izip!(&r_values, &g_values, &b_values) {
@@ -479,7 +595,7 @@ impl Stacker {
                 .for_each(|(y, row)| {
                     let from = y * self.width;
                     let to = (y + 1) * self.width;
-                    chan.get(row, from, to, &self.cnt);
+                    chan.get(row, from, to, &self.cnt, &self.drz_weight);
                 });
         };
 
@@ -492,3 +608,30 @@ impl Stacker {
         image.raw_info = self.raw_info.clone();
     }
 }
+
+#[test]
+fn test_drizzle_of_aligned_frames() {
+    const WIDTH: usize = 7;
+    const HEIGHT: usize = 5;
+    let mut image = Image::new_empty();
+    image.make_monochrome(WIDTH, HEIGHT, 0, u16::MAX);
+    for (i, v) in image.l.as_slice_mut().iter_mut().enumerate() {
+        *v = (i * 7919 % 65536) as u16;
+    }
+    let hist = Histogram::new();
+    let mut stacker = Stacker::new();
+    for _ in 0..5 {
+        stacker.add(&image, &hist, 0.0, 0.0, 0.0, 1.0, false, true);
+    }
+    let mut result = Image::new_empty();
+    stacker.copy_to_image(&mut result);
+    assert_eq!(result.width(), 2 * WIDTH);
+    assert_eq!(result.height(), 2 * HEIGHT);
+    for y in 0..2 * HEIGHT {
+        for x in 0..2 * WIDTH {
+            let expected = image.l.get((x / 2) as isize, (y / 2) as isize);
+            let value = result.l.get(x as isize, y as isize);
+            assert_eq!(value, expected, "x={}, y={}", x, y);
+        }
+    }
+}
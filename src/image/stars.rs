@@ -1,5 +1,6 @@
 use std::{collections::{HashSet, VecDeque}, sync::Mutex, f64::consts::PI};
 use itertools::Itertools;
+use serde::{Serialize, Deserialize};
 use crate::utils::math::*;
 use super::{image::ImageLayer, raw::RawImageInfo};
 
@@ -7,6 +8,35 @@ const MAX_STAR_DIAM: usize = 32;
 const MAX_STARS_CNT: usize = 500;
 const MAX_STARS_FOR_STAR_IMAGE: usize = 200;
 
+/// Parameters of stars detector. Default values
+/// correspond to previous hardcoded behaviour
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct StarDetectionOptions {
+    pub threshold:  f32,   // detection threshold in noise units
+    pub min_pixels: usize, // minimum star area in pixels
+    pub max_pixels: usize, // maximum star area in pixels
+    pub saturation: f32,   // % of max value to mark star as overexposured
+}
+
+impl Default for StarDetectionOptions {
+    fn default() -> Self {
+        Self {
+            threshold:  120.0,
+            min_pixels: 1,
+            max_pixels: MAX_STAR_DIAM * MAX_STAR_DIAM,
+            saturation: 90.0,
+        }
+    }
+}
+
+impl StarDetectionOptions {
+    pub fn overexposured_bord(&self, max_value: u16) -> u16 {
+        let saturation = self.saturation.clamp(0.0, 100.0) as f64;
+        (saturation * max_value as f64 / 100.0) as u16
+    }
+}
+
 #[derive(Clone, Default)]
 pub struct Star {
     pub x:             f64,
@@ -17,10 +47,77 @@ pub struct Star {
     pub overexposured: bool,
     pub width:         usize,
     pub height:        usize,
+    pub eccentricity:  f32, // 0 - round star, 1 - line
+    pub angle:         f32, // direction of elongation in radians
 }
 
 pub type Stars = Vec<Star>;
 
+#[derive(Clone, Default)]
+pub struct StarsTiltCell {
+    pub stars_cnt:    usize,
+    pub eccentricity: f32,
+    pub angle:        f32,
+}
+
+/// Average star elongation in cells of 3x3 grid over frame.
+/// Uneven elongation across the frame indicates sensor tilt
+#[derive(Clone, Default)]
+pub struct StarsTilt {
+    pub cells: [[Option<StarsTiltCell>; Self::GRID_SIZE]; Self::GRID_SIZE],
+}
+
+impl StarsTilt {
+    pub const GRID_SIZE: usize = 3;
+    const MIN_STARS_IN_CELL: usize = 3;
+
+    pub fn new(stars: &[Star], img_width: usize, img_height: usize) -> Self {
+        let mut result = Self::default();
+        if img_width == 0 || img_height == 0 {
+            return result;
+        }
+        const N: usize = StarsTilt::GRID_SIZE;
+        let mut sums = [[(0_usize, 0_f64, 0_f64, 0_f64); N]; N];
+        for star in stars.iter().filter(|s| !s.overexposured) {
+            let col = usize::min(N * star.x as usize / img_width, N-1);
+            let row = usize::min(N * star.y as usize / img_height, N-1);
+            let (cnt, ecc, cos2, sin2) = &mut sums[row][col];
+            *cnt += 1;
+            *ecc += star.eccentricity as f64;
+            // angles are averaged as doubled vectors because
+            // orientation of elongation has period of PI
+            let angle2 = 2.0 * star.angle as f64;
+            *cos2 += star.eccentricity as f64 * f64::cos(angle2);
+            *sin2 += star.eccentricity as f64 * f64::sin(angle2);
+        }
+        for (row, sums_row) in sums.iter().enumerate() {
+            for (col, &(cnt, ecc, cos2, sin2)) in sums_row.iter().enumerate() {
+                if cnt < Self::MIN_STARS_IN_CELL { continue; }
+                result.cells[row][col] = Some(StarsTiltCell {
+                    stars_cnt:    cnt,
+                    eccentricity: (ecc / cnt as f64) as f32,
+                    angle:        (0.5 * f64::atan2(sin2, cos2)) as f32,
+                });
+            }
+        }
+        result
+    }
+
+    /// Difference between most and least elongated cells
+    pub fn max_diff(&self) -> Option<f32> {
+        let minmax = self.cells
+            .iter()
+            .flatten()
+            .flatten()
+            .map(|c| c.eccentricity)
+            .minmax();
+        match minmax {
+            itertools::MinMaxResult::MinMax(min, max) => Some(max - min),
+            _ => None,
+        }
+    }
+}
+
 pub struct StarsInfo {
     pub items:         Stars,
     pub fwhm:          Option<f32>,
@@ -28,6 +125,8 @@ pub struct StarsInfo {
     pub fwhm_is_ok:    bool,
     pub ovality:       Option<f32>,
     pub ovality_is_ok: bool,
+    pub hfd:           Option<f32>, // median half flux diameter
+    pub tilt:          StarsTilt,
 }
 
 impl StarsInfo {
@@ -40,6 +139,7 @@ impl StarsInfo {
         max_value:          u16,
         max_stars_fwhm:     Option<f32>,
         max_stars_ovality:  Option<f32>,
+        detection:          &StarDetectionOptions,
         mt:                 bool
     ) -> Self {
         let items = Self::find_stars_in_image(
@@ -48,6 +148,7 @@ impl StarsInfo {
             background,
             overexposured_bord,
             max_value,
+            detection,
             mt
         );
 
@@ -73,6 +174,10 @@ impl StarsInfo {
 
         let fwhm_angular = Self::calc_angular_fwhm(fwhm, raw_info);
 
+        let hfd = Self::calc_median_hfd(image, &items).map(|v| v as f32);
+
+        let tilt = StarsTilt::new(&items, image.width(), image.height());
+
         Self {
             items,
             fwhm,
@@ -80,6 +185,8 @@ impl StarsInfo {
             fwhm_is_ok,
             ovality,
             ovality_is_ok,
+            hfd,
+            tilt,
         }
     }
 
@@ -93,11 +200,13 @@ impl StarsInfo {
         background:         i32,
         overexposured_bord: u16,
         max_value:          u16,
+        detection:          &StarDetectionOptions,
         mt:                 bool
     ) -> Stars {
-        const MAX_STARS_POINTS_CNT: usize = MAX_STAR_DIAM * MAX_STAR_DIAM;
+        let max_star_points = detection.max_pixels.max(1);
+        let min_star_points = detection.min_pixels.max(1);
         let iir_filter_coeffs = IirFilterCoeffs::new(230);
-        let mut border = (noise * 120.0) as u32;
+        let mut border = (noise * detection.threshold) as u32;
         let range = max_value as i32 - background;
         if border <= 1 {
             border = u32::max(max_value as u32 / 100, 2);
@@ -181,6 +290,9 @@ impl StarsInfo {
             let border = border as u16;
             let mut x_summ = 0_f64;
             let mut y_summ = 0_f64;
+            let mut xx_summ = 0_f64;
+            let mut yy_summ = 0_f64;
+            let mut xy_summ = 0_f64;
             let mut crd_cnt = 0_f64;
             let mut brightness = 0_i32;
             let mut overexposured = false;
@@ -193,7 +305,7 @@ impl StarsInfo {
                     if hit {
                         if all_star_coords.contains(&(x, y))
                         || star_points.contains(&(x, y))
-                        || star_points.len() > MAX_STARS_POINTS_CNT {
+                        || star_points.len() > max_star_points {
                             return false;
                         }
                         if v > overexposured_bord {
@@ -204,6 +316,9 @@ impl StarsInfo {
                         let v_part = linear_interpolate(v as f64, bg as f64, max_v as f64, 0.0, 1.0);
                         x_summ += v_part * x as f64;
                         y_summ += v_part * y as f64;
+                        xx_summ += v_part * (x * x) as f64;
+                        yy_summ += v_part * (y * y) as f64;
+                        xy_summ += v_part * (x * y) as f64;
                         crd_cnt += v_part;
                         brightness += v as i32 - bg as i32;
                     }
@@ -211,11 +326,12 @@ impl StarsInfo {
                 }
             );
 
-            if star_points.len() > MAX_STARS_POINTS_CNT {
+            if star_points.len() > max_star_points {
                 big_cnt += 1;
             }
 
-            if star_points.len() < MAX_STARS_POINTS_CNT
+            if star_points.len() < max_star_points
+            && star_points.len() >= min_star_points
             && max_v > bg as u32
             && brightness > 0
             && Self::check_is_star_points_ok(&star_points) {
@@ -225,15 +341,24 @@ impl StarsInfo {
                 let max_y = star_points.iter().map(|(_, y)| *y).max().unwrap_or(y);
                 let width = 3 * isize::max(x-min_x+1, max_x-x+1);
                 let height = 3 * isize::max(y-min_y+1, max_y-y+1);
+                let star_x = x_summ / crd_cnt;
+                let star_y = y_summ / crd_cnt;
+                let (eccentricity, angle) = Self::calc_eccentricity_and_angle(
+                    xx_summ / crd_cnt - star_x * star_x,
+                    yy_summ / crd_cnt - star_y * star_y,
+                    xy_summ / crd_cnt - star_x * star_y,
+                );
                 stars.push(Star {
-                    x: x_summ / crd_cnt,
-                    y: y_summ / crd_cnt,
+                    x: star_x,
+                    y: star_y,
                     background: bg,
                     max_value: max_v as u16,
                     brightness: brightness as u32,
                     overexposured,
                     width: width as usize,
                     height: height as usize,
+                    eccentricity,
+                    angle,
                 });
             }
         }
@@ -247,6 +372,21 @@ impl StarsInfo {
         stars
     }
 
+    /// Calculates eccentricity and direction of star elongation
+    /// from central second moments of star points
+    fn calc_eccentricity_and_angle(mxx: f64, myy: f64, mxy: f64) -> (f32, f32) {
+        let half_sum = 0.5 * (mxx + myy);
+        let root = f64::sqrt(0.25 * (mxx - myy) * (mxx - myy) + mxy * mxy);
+        let major = half_sum + root;
+        let minor = half_sum - root;
+        if major <= 0.0 {
+            return (0.0, 0.0);
+        }
+        let eccentricity = f64::sqrt(f64::max(1.0 - f64::max(minor, 0.0) / major, 0.0));
+        let angle = 0.5 * f64::atan2(2.0 * mxy, mxx - myy);
+        (eccentricity as f32, angle as f32)
+    }
+
     fn check_is_star_points_ok(star_points: &HashSet<(isize, isize)>) -> bool {
         let real_perimeter = star_points
             .iter()
@@ -309,6 +449,46 @@ impl StarsInfo {
         result
     }
 
+    fn calc_median_hfd(image: &ImageLayer<u16>, stars: &[Star]) -> Option<f64> {
+        let mut hfds: Vec<_> = stars.iter()
+            .filter(|s| !s.overexposured)
+            .take(MAX_STARS_FOR_STAR_IMAGE)
+            .filter_map(|s| Self::calc_star_hfd(image, s))
+            .collect();
+        if hfds.is_empty() {
+            return None;
+        }
+        let pos = hfds.len() / 2;
+        Some(*hfds.select_nth_unstable_by(pos, f64::total_cmp).1)
+    }
+
+    /// Half flux diameter as doubled flux weighted
+    /// mean distance of star pixels from its center
+    fn calc_star_hfd(image: &ImageLayer<u16>, star: &Star) -> Option<f64> {
+        let radius = usize::min(usize::max(star.width, star.height), MAX_STAR_DIAM) / 2;
+        let radius_f = radius as f64;
+        let radius = radius as isize;
+        let center_x = star.x.round() as isize;
+        let center_y = star.y.round() as isize;
+        let mut flux_sum = 0_f64;
+        let mut flux_r_sum = 0_f64;
+        for y in center_y-radius..=center_y+radius {
+            for x in center_x-radius..=center_x+radius {
+                let Some(v) = image.get(x, y) else { continue; };
+                let flux = v as f64 - star.background as f64;
+                if flux <= 0.0 { continue; }
+                let r = f64::hypot(x as f64 - star.x, y as f64 - star.y);
+                if r > radius_f { continue; }
+                flux_sum += flux;
+                flux_r_sum += flux * r;
+            }
+        }
+        if flux_sum <= 0.0 {
+            return None;
+        }
+        Some(2.0 * flux_r_sum / flux_sum)
+    }
+
     fn calc_fwhm(star_image: &ImageLayer<u16>) -> Option<f64> {
         if star_image.is_empty() {
             return None;
@@ -419,3 +599,85 @@ impl FloodFiller {
         }
     }
 }
+
+#[test]
+fn test_star_detection_threshold() {
+    const SIZE: usize = 512;
+    const BACKGROUND: f64 = 1000.0;
+    let mut image = ImageLayer::<u16>::new_with_size(SIZE, SIZE);
+    let mut rnd = 12345_u32;
+    for v in image.as_slice_mut() {
+        rnd = rnd.wrapping_mul(1103515245).wrapping_add(12345);
+        *v = (BACKGROUND as u32 + (rnd >> 16) % 41 - 20) as u16;
+    }
+    // 8x8 stars with increasing brightness
+    for i in 0..64 {
+        let cx = 40 + 60 * (i % 8) as isize;
+        let cy = 40 + 60 * (i / 8) as isize;
+        let ampl = 100.0 + 50.0 * i as f64;
+        for y in cy-8..=cy+8 {
+            for x in cx-8..=cx+8 {
+                let r2 = ((x - cx) * (x - cx) + (y - cy) * (y - cy)) as f64;
+                let v = image.get(x, y).unwrap() as f64 + ampl * f64::exp(-r2 / 2.0);
+                image.set(x, y, v as u16);
+            }
+        }
+    }
+    let noise = image.calc_noise();
+    let background = image.calc_background(false) as i32;
+    let find_stars = |detection: &StarDetectionOptions| {
+        StarsInfo::find_stars_in_image(
+            &image, noise, background,
+            detection.overexposured_bord(u16::MAX),
+            u16::MAX, detection, false
+        ).len()
+    };
+    let counts: Vec<_> = [20.0, 50.0, 120.0, 300.0]
+        .iter()
+        .map(|&threshold| find_stars(&StarDetectionOptions {
+            threshold,
+            ..StarDetectionOptions::default()
+        }))
+        .collect();
+    assert!(counts.windows(2).all(|w| w[0] >= w[1]), "{:?}", counts);
+    assert!(counts[counts.len()-1] < counts[0], "{:?}", counts);
+
+    let big_only = find_stars(&StarDetectionOptions {
+        threshold: 20.0,
+        min_pixels: 10,
+        ..StarDetectionOptions::default()
+    });
+    assert!(big_only < counts[0], "{} {:?}", big_only, counts);
+}
+
+#[test]
+fn test_star_hfd() {
+    const SIGMA: f64 = 2.0;
+    let mut image = ImageLayer::<u16>::new_with_size(64, 64);
+    let (star_x, star_y) = (32.3, 32.3);
+    for y in 0..64 {
+        for x in 0..64 {
+            let r2 = (x as f64 - star_x).powi(2) + (y as f64 - star_y).powi(2);
+            let v = 1000.0 + 10000.0 * f64::exp(-r2 / (2.0 * SIGMA * SIGMA));
+            image.set(x, y, v as u16);
+        }
+    }
+    let star = Star {
+        x: star_x,
+        y: star_y,
+        background: 1000,
+        max_value: 11000,
+        brightness: 0,
+        overexposured: false,
+        width: 24,
+        height: 24,
+        eccentricity: 0.0,
+        angle: 0.0,
+    };
+    // HFD of gaussian profile is 2*sigma*sqrt(pi/2)
+    let expected = 2.0 * SIGMA * f64::sqrt(PI / 2.0);
+    let hfd = StarsInfo::calc_star_hfd(&image, &star).unwrap();
+    assert!((hfd - expected).abs() < 0.05, "{}", hfd);
+    let median_hfd = StarsInfo::calc_median_hfd(&image, &[]);
+    assert!(median_hfd.is_none());
+}
@@ -3,6 +3,7 @@
 pub struct Base64Decoder {
     table:  [u8; 256],
     result: Vec<u8>,
+    pos:    usize, // write position in `result`
     buffer: u32,
 }
 
@@ -23,6 +24,7 @@ impl Base64Decoder {
         Self {
             table,
             result: Vec::with_capacity(expected_size),
+            pos:    0,
             buffer: 1,
         }
     }
@@ -30,10 +32,26 @@ impl Base64Decoder {
     pub fn clear(&mut self, expected_size: usize) {
         self.result.clear();
         self.result.reserve(expected_size);
+        self.pos = 0;
+        self.buffer = 1;
+    }
+
+    /// Same as `clear` but next result will be written into pre-sized `buffer`.
+    /// Buffer is extended if decoded data is longer than it
+    pub fn clear_with_buffer(&mut self, buffer: Vec<u8>) {
+        self.result = buffer;
+        self.pos = 0;
         self.buffer = 1;
     }
 
     pub fn take_result(&mut self) -> Vec<u8> {
+        self.flush_tail();
+        self.result.truncate(self.pos);
+        self.pos = 0;
+        std::mem::take(&mut self.result)
+    }
+
+    fn flush_tail(&mut self) {
         if self.buffer != 1 {
             let mut extra_len = 0;
             while self.buffer & 0x01000000 == 0 {
@@ -42,12 +60,24 @@ impl Base64Decoder {
             }
             let bytes = self.buffer.to_be_bytes();
             match extra_len {
-                1 => self.result.extend_from_slice(&bytes[1..=2]),
-                2 => self.result.extend_from_slice(&bytes[1..=1]),
+                1 => self.write(&bytes[1..=2]),
+                2 => self.write(&bytes[1..=1]),
                 _ => unreachable!(),
             }
+            self.buffer = 1;
         }
-        std::mem::take(&mut self.result)
+    }
+
+    #[inline(always)]
+    fn write(&mut self, bytes: &[u8]) {
+        let end = self.pos + bytes.len();
+        if end <= self.result.len() {
+            self.result[self.pos..end].copy_from_slice(bytes);
+        } else {
+            self.result.truncate(self.pos);
+            self.result.extend_from_slice(bytes);
+        }
+        self.pos = end;
     }
 
     pub fn add_bytes(&mut self, base64_data: &[u8]) {
@@ -63,7 +93,7 @@ impl Base64Decoder {
         self.buffer = (self.buffer << 6) | index;
         if self.buffer & 0x01000000 != 0 {
             let bytes = self.buffer.to_be_bytes();
-            self.result.extend_from_slice(&bytes[1..]);
+            self.write(&bytes[1..]);
             self.buffer = 1;
         }
     }
@@ -88,3 +118,32 @@ fn test_base64_decoder() {
     test(b"c2hvcnRTMjM=",     b"shortS23");
     test(b"c2hvcnRTMjM0",     b"shortS234");
 }
+
+#[test]
+fn test_base64_decoder_buffer() {
+    let base64_items: &[&[u8]] = &[
+        b"", b"TWFu", b"TWF=", b"TW==", b"////////", b"///////=",
+        b"dGVzdCBhIHRlc3Q=", b"c2hvcnRTMg==", b"c2hvcnRTMjM=", b"c2hvcnRTMjM0",
+    ];
+    for base64 in base64_items {
+        let mut decoder = Base64Decoder::new(0);
+        decoder.add_bytes(base64);
+        let expected = decoder.take_result();
+
+        // Exactly sized, smaller and bigger buffers
+        for buf_len in [expected.len(), expected.len() / 2, expected.len() + 5] {
+            let mut decoder = Base64Decoder::new(0);
+            decoder.clear_with_buffer(vec![0; buf_len]);
+            decoder.add_bytes(base64);
+            assert_eq!(decoder.take_result(), expected);
+        }
+
+        // Decoder is reusable after buffer mode
+        let mut decoder = Base64Decoder::new(0);
+        decoder.clear_with_buffer(vec![0; 100]);
+        decoder.add_bytes(base64);
+        decoder.take_result();
+        decoder.add_bytes(base64);
+        assert_eq!(decoder.take_result(), expected);
+    }
+}
@@ -1,7 +1,7 @@
 #![allow(dead_code)]
 
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::io::{prelude::*, BufWriter, Cursor};
 use std::net::TcpStream;
 use std::process::{Command, Child, Stdio};
@@ -22,8 +22,19 @@ pub struct ConnSettings {
     pub server_exe: String,
     pub drivers: Vec<String>,
     pub activate_all_devices: bool,
+    pub blob_timeout: Option<Duration>,
+    /// Read timeout of INDI socket. Larger values reduce
+    /// spurious timeout processing for remote setups
+    pub read_timeout: Duration,
+    pub conn_attempts: usize,
+    /// Interval after first failed attempt. It is doubled
+    /// after every next attempt up to `MAX_CONN_RETRY_INTERVAL`
+    pub conn_retry_interval: Duration,
 }
 
+const MIN_READ_TIMEOUT: Duration = Duration::from_millis(100);
+const MAX_CONN_RETRY_INTERVAL: Duration = Duration::from_millis(1000);
+
 impl Default for ConnSettings {
     fn default() -> Self {
         Self {
@@ -32,6 +43,10 @@ impl Default for ConnSettings {
             server_exe: "indiserver".to_string(),
             drivers: Vec::new(),
             activate_all_devices: true,
+            blob_timeout: Some(Duration::from_secs(120)),
+            read_timeout: Duration::from_millis(1000),
+            conn_attempts: 20,
+            conn_retry_interval: Duration::from_millis(100),
         }
     }
 }
@@ -119,9 +134,16 @@ pub struct BlobStartEvent {
 
 }
 
+pub struct BlobTimeOutEvent {
+    pub device_name: Arc<String>,
+    pub prop_name:   Arc<String>,
+    pub elem_name:   Arc<String>,
+}
+
 #[derive(Clone)]
 pub enum Event {
     ConnChange(ConnState),
+    ConnAttempt{ attempt: usize, max: usize },
     ConnectionLost,
     NewDevice(NewDeviceEvent),
     DeviceConnected(Arc<DeviceConnectEvent>),
@@ -130,6 +152,7 @@ pub enum Event {
     ReadTimeOut,
     Message(Arc<MessageEvent>),
     BlobStart(Arc<BlobStartEvent>),
+    BlobTimeOut(Arc<BlobTimeOutEvent>),
 }
 
 type EventFun = dyn Fn(Event) + Send + 'static;
@@ -137,8 +160,21 @@ type EventFun = dyn Fn(Event) + Send + 'static;
 #[derive(Hash, Eq, PartialEq, Clone, Copy)]
 pub struct Subscription(u64);
 
+/// Group of subscription. `System` subscriptions are not removed
+/// by `Connection::unsubscribe_all`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SubscriptionGroup {
+    Ui,
+    System,
+}
+
+struct SubscriptionItem {
+    group: SubscriptionGroup,
+    fun:   Box<EventFun>,
+}
+
 struct Subscriptions {
-    items: HashMap<Subscription, Box<EventFun>>,
+    items: HashMap<Subscription, SubscriptionItem>,
     key:   u64,
 }
 
@@ -151,16 +187,17 @@ impl Subscriptions {
     }
 
     fn inform_all(&self, event: Event) {
-        for fun in self.items.values() {
-            fun(event.clone());
+        for item in self.items.values() {
+            (item.fun)(event.clone());
         }
     }
 }
 
 #[derive(Debug, Clone)]
 pub enum NumFormat {
-    Float{ width: Option<u8>, prec: u8 },
-    G,
+    Float{ plus: bool, zero: bool, width: Option<u8>, prec: u8 },
+    Int { plus: bool, zero: bool, width: Option<u8> },
+    G { width: Option<u8>, prec: Option<u8> },
     Sexagesimal { zero: bool, width: Option<u8>, frac: u8 },
     Unrecorgnized,
 }
@@ -170,19 +207,35 @@ impl NumFormat {
         use once_cell::sync::OnceCell;
         static FLOAT_RE: OnceCell<regex::Regex> = OnceCell::new();
         let float_re = FLOAT_RE.get_or_init(|| {
-            regex::Regex::new(r"%(\d*)\.(\d*)[Ff]").unwrap()
+            regex::Regex::new(r"%([-+ #0]*)(\d*)(?:\.(\d*))?[Ff]").unwrap()
         });
         if let Some(float_re_res) = float_re.captures(format_str) {
-            let width: Option<u8> = float_re_res[1].parse().ok();
-            let prec: u8 = float_re_res[2].parse().unwrap_or(0);
-            return NumFormat::Float { width, prec };
+            let flags = &float_re_res[1];
+            let plus = flags.contains('+');
+            let zero = flags.contains('0');
+            let width: Option<u8> = float_re_res[2].parse().ok();
+            let prec: u8 = float_re_res.get(3).map(|m| m.as_str().parse().unwrap_or(0)).unwrap_or(6);
+            return NumFormat::Float { plus, zero, width, prec };
+        }
+        static INT_RE: OnceCell<regex::Regex> = OnceCell::new();
+        let int_re = INT_RE.get_or_init(|| {
+            regex::Regex::new(r"%([-+ #0]*)(\d*)(?:l|ll|h|hh)?[diu]").unwrap()
+        });
+        if let Some(int_re_res) = int_re.captures(format_str) {
+            let flags = &int_re_res[1];
+            let plus = flags.contains('+');
+            let zero = flags.contains('0');
+            let width: Option<u8> = int_re_res[2].parse().ok();
+            return NumFormat::Int { plus, zero, width };
         }
         static G_RE: OnceCell<regex::Regex> = OnceCell::new();
         let g_re = G_RE.get_or_init(|| {
-            regex::Regex::new(r"%.*[Gg]").unwrap()
+            regex::Regex::new(r"%(\d*)(?:\.(\d*))?[Gg]").unwrap()
         });
-        if g_re.is_match(format_str) {
-            return NumFormat::G;
+        if let Some(g_re_res) = g_re.captures(format_str) {
+            let width: Option<u8> = g_re_res[1].parse().ok();
+            let prec: Option<u8> = g_re_res.get(2).map(|m| m.as_str().parse().unwrap_or(0));
+            return NumFormat::G { width, prec };
         }
         static SEX_RE: OnceCell<regex::Regex> = OnceCell::new();
         let sex_re = SEX_RE.get_or_init(|| {
@@ -200,22 +253,24 @@ impl NumFormat {
 
     pub fn value_to_string(&self, value: f64) -> String {
         match self {
-            NumFormat::Float { width, prec } =>
+            NumFormat::Float { plus, zero, width, prec } => {
+                let digits = format!("{:.prec$}", value.abs(), prec = *prec as usize);
+                add_sign_and_padding(value, digits, *plus, *zero, *width)
+            }
+            NumFormat::Int { plus, zero, width } => {
+                // INDI number is always f64 so it is rounded
+                // to nearest integer for integer formats
+                let value = value.round() as i64 as f64;
+                let digits = format!("{:.0}", value.abs());
+                add_sign_and_padding(value, digits, *plus, *zero, *width)
+            }
+            NumFormat::G { width, prec } => {
+                let result = value_to_g_string(value, prec.unwrap_or(6) as usize);
                 match width {
-                    Some(width) => format!(
-                        "{:width$.prec$}",
-                        value,
-                        width = *width as usize,
-                        prec = *prec as usize
-                    ),
-                    None => format!(
-                        "{:.prec$}",
-                        value,
-                        prec = *prec as usize
-                    ),
+                    Some(width) => format!("{:>width$}", result, width = *width as usize),
+                    None        => result,
                 }
-            NumFormat::G =>
-                value.to_string(),
+            }
             NumFormat::Sexagesimal { zero, frac, .. } =>
                 value_to_sexagesimal(value, *zero, *frac),
             NumFormat::Unrecorgnized =>
@@ -224,6 +279,70 @@ impl NumFormat {
     }
 }
 
+/// Adds sign and padding to `digits` (text of absolute value)
+/// like C printf does for `+` and `0` flags and width
+fn add_sign_and_padding(
+    value:  f64,
+    digits: String,
+    plus:   bool,
+    zero:   bool,
+    width:  Option<u8>,
+) -> String {
+    let sign = if value.is_sign_negative() && !value.is_nan() {
+        "-"
+    } else if plus {
+        "+"
+    } else {
+        ""
+    };
+    let width = width.unwrap_or(0) as usize;
+    let len = sign.len() + digits.len();
+    if len >= width {
+        return format!("{}{}", sign, digits);
+    }
+    if zero && value.is_finite() {
+        format!("{}{}{}", sign, "0".repeat(width - len), digits)
+    } else {
+        format!("{}{}{}", " ".repeat(width - len), sign, digits)
+    }
+}
+
+/// Formats value like `%.<prec>g` of C printf
+fn value_to_g_string(value: f64, prec: usize) -> String {
+    if value.is_nan() {
+        return "nan".to_string();
+    }
+    if value.is_infinite() {
+        return if value > 0.0 { "inf" } else { "-inf" }.to_string();
+    }
+    if value == 0.0 {
+        return if value.is_sign_negative() { "-0" } else { "0" }.to_string();
+    }
+
+    let prec = prec.max(1);
+
+    // Decimal exponent after rounding to `prec` significant digits
+    let exp_str = format!("{:.*e}", prec - 1, value);
+    let (mantissa, exp) = exp_str.split_once('e').unwrap_or((&exp_str, "0"));
+    let exp: i32 = exp.parse().unwrap_or(0);
+
+    let trim_zeros = |text: &str| -> String {
+        if text.contains('.') {
+            text.trim_end_matches('0').trim_end_matches('.').to_string()
+        } else {
+            text.to_string()
+        }
+    };
+
+    if exp < -4 || exp >= prec as i32 {
+        let sign = if exp < 0 { '-' } else { '+' };
+        format!("{}e{}{:02}", trim_zeros(mantissa), sign, exp.abs())
+    } else {
+        let frac_digits = (prec as i32 - 1 - exp) as usize;
+        trim_zeros(&format!("{:.*}", frac_digits, value))
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum PropState { Idle, Ok, Busy, Alert }
 
@@ -1186,12 +1305,41 @@ pub enum AfterCoordSetAction {
     Sync,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PierSide {
+    East,
+    West,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DomeShutterState {
+    Open,
+    Closed,
+}
+
+/// Scheme of mount properties for alignment model
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MountAlignScheme {
+    /// INDI alignment subsystem
+    AlignmentSubsystem,
+    /// EQMod driver own alignment
+    EqMod,
+}
+
+#[derive(Debug, Clone)]
+pub struct MountAlignInfo {
+    pub scheme: MountAlignScheme,
+    pub active: bool,
+    pub points: usize,
+}
+
 pub struct Connection {
     data:            Arc<Mutex<Option<ActiveConnData>>>,
     state:           Arc<Mutex<ConnState>>,
     devices:         Arc<Mutex<Devices>>,
     subscriptions:   Arc<Mutex<Subscriptions>>,
     drivers_started: AtomicBool,
+    mount_samples:   Mutex<HashMap<String, VecDeque<(f64, f64)>>>, // for mount settle detection
 }
 
 impl Connection {
@@ -1210,19 +1358,28 @@ impl Connection {
                 Mutex::new(Subscriptions::new())
             ),
             drivers_started: AtomicBool::new(false),
+            mount_samples:   Mutex::new(HashMap::new()),
         }
     }
 
     pub fn subscribe_events(
         &self,
         fun: impl Fn(Event) + Send + 'static
+    ) -> Subscription {
+        self.subscribe_events_in_group(SubscriptionGroup::Ui, fun)
+    }
+
+    pub fn subscribe_events_in_group(
+        &self,
+        group: SubscriptionGroup,
+        fun:   impl Fn(Event) + Send + 'static
     ) -> Subscription {
         let mut subscriptions = self.subscriptions.lock().unwrap();
         subscriptions.key += 1;
         let subscription = Subscription(subscriptions.key);
         subscriptions.items.insert(
             subscription,
-            Box::new(fun)
+            SubscriptionItem { group, fun: Box::new(fun) }
         );
         subscription
     }
@@ -1232,9 +1389,15 @@ impl Connection {
         subscriptions.items.remove(&subscription);
     }
 
+    /// Removes all subscriptions except `SubscriptionGroup::System` ones
     pub fn unsubscribe_all(&self) {
         let mut subscriptions = self.subscriptions.lock().unwrap();
-        subscriptions.items.clear();
+        subscriptions.items.retain(|_, item| item.group == SubscriptionGroup::System);
+    }
+
+    pub fn unsubscribe_group(&self, group: SubscriptionGroup) {
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        subscriptions.items.retain(|_, item| item.group != group);
     }
 
     fn start_indi_server(
@@ -1349,12 +1512,21 @@ impl Connection {
                 },
             };
 
-            // Try to connect INDI server during 3 seconds
+            // Try to connect INDI server with increasing interval between attempts
+            let sock_addrs: Vec<_> = sock_addrs.collect();
+            let max_attempts = settings.conn_attempts.max(1);
+            let mut retry_interval = settings.conn_retry_interval;
             let mut stream: Option<TcpStream> = None;
-            'outer: for addr in sock_addrs {
-                for _ in 0..3 {
+            'outer: for attempt in 1..=max_attempts {
+                if attempt != 1 {
+                    log::debug!("Connecting to {}, attempt {}/{}...", addr, attempt, max_attempts);
+                    self_.subscriptions.lock().unwrap().inform_all(
+                        Event::ConnAttempt { attempt, max: max_attempts }
+                    );
+                }
+                for sock_addr in &sock_addrs {
                     let conn_try_res = TcpStream::connect_timeout(
-                        &addr,
+                        sock_addr,
                         Duration::from_millis(1000)
                     );
                     if let Ok(res) = conn_try_res {
@@ -1362,6 +1534,10 @@ impl Connection {
                         break 'outer;
                     }
                 }
+                if attempt != max_attempts {
+                    std::thread::sleep(retry_interval);
+                    retry_interval = (2 * retry_interval).min(MAX_CONN_RETRY_INTERVAL);
+                }
             }
 
             // Failed to connect. Stop INDI server and exit
@@ -1412,6 +1588,8 @@ impl Connection {
                         stream,
                         XmlSender { xml_sender },
                         settings.activate_all_devices,
+                        settings.blob_timeout,
+                        settings.read_timeout,
                     );
                     receiver.main(events_sender);
                 })
@@ -1792,6 +1970,38 @@ impl Connection {
         Ok(())
     }
 
+    /// Some drivers briefly report busy state or all elements "Off"
+    /// for OneOfMany switch after it is set. Waits for such property
+    /// to settle before reading selected element
+    fn wait_for_switch_settled(
+        &self,
+        device_name: &str,
+        prop_name:   &str,
+    ) -> Result<()> {
+        const TIME_QUANT_MS: u64 = 50;
+        const TIMEOUT_MS: u64 = 500;
+        let mut timeout_ms = TIMEOUT_MS;
+        loop {
+            let devices = self.devices.lock().unwrap();
+            let device = devices.find_by_name_res(device_name)?;
+            let Some(prop) = device.get_property_opt(prop_name) else {
+                return Ok(());
+            };
+            let settled =
+                prop.type_ != PropType::Switch(SwitchRule::OneOfMany) || (
+                    prop.state != PropState::Busy &&
+                    prop.elements.iter().any(|e| e.value.to_i32().unwrap_or(0) != 0)
+                );
+            drop(devices);
+            if settled || timeout_ms < TIME_QUANT_MS {
+                return Ok(());
+            }
+            std::thread::sleep(Duration::from_millis(TIME_QUANT_MS));
+            timeout_ms -= TIME_QUANT_MS;
+            log::debug!("Waiting for {}.{} property to settle...", device_name, prop_name);
+        }
+    }
+
     pub fn command_set_num_property(
         &self,
         device_name: &str,
@@ -2208,22 +2418,48 @@ impl Connection {
         )
     }
 
+    pub fn camera_get_exposure_prop_state(
+        &self,
+        device_name: &str,
+        ccd:         CamCcd
+    ) -> Result<PropState> {
+        let devices = self.devices.lock().unwrap();
+        let (prop_name, _) = Self::exposure_prop_name(ccd);
+        let state = devices.get_property(device_name, prop_name)?.state;
+        Ok(state)
+    }
+
+    pub fn camera_is_abort_exposure_supported(
+        &self,
+        device_name: &str,
+        ccd:         CamCcd,
+    ) -> Result<bool> {
+        self.property_exists(
+            device_name,
+            Self::abort_exposure_prop_name(ccd),
+            Some("ABORT")
+        )
+    }
+
     pub fn camera_abort_exposure(
         &self,
         device_name: &str,
         ccd:         CamCcd,
     ) -> Result<()> {
-        let prop_name = match ccd {
-            CamCcd::Primary   => "CCD_ABORT_EXPOSURE",
-            CamCcd::Secondary => "GUIDER_ABORT_EXPOSURE",
-        };
         self.command_set_switch_property(
             device_name,
-            prop_name,
+            Self::abort_exposure_prop_name(ccd),
             &[("ABORT", true)]
         )
     }
 
+    fn abort_exposure_prop_name(ccd: CamCcd) -> &'static str {
+        match ccd {
+            CamCcd::Primary   => "CCD_ABORT_EXPOSURE",
+            CamCcd::Secondary => "GUIDER_ABORT_EXPOSURE",
+        }
+    }
+
     fn exposure_prop_name(ccd: CamCcd) -> (&'static str, &'static str) {
         match ccd {
             CamCcd::Primary   => ("CCD_EXPOSURE", "CCD_EXPOSURE_VALUE"),
@@ -2508,6 +2744,7 @@ impl Connection {
         &self,
         device_name: &str,
     ) -> Result<Option<Arc<String>>> {
+        self.wait_for_switch_settled(device_name, "CCD_RESOLUTION")?;
         let devices = self.devices.lock().unwrap();
         let device = devices.find_by_name_res(device_name)?;
         let Some(prop) = device.get_property_opt("CCD_RESOLUTION") else {
@@ -2728,6 +2965,7 @@ impl Connection {
         &self,
         device_name: &str,
     ) -> Result<Option<BinningMode>> {
+        self.wait_for_switch_settled(device_name, "CCD_BINNING_MODE")?;
         let is_add_mode = self.device_get_any_of_swicth_props(
             device_name,
             PROP_CAM_BIN_ADD
@@ -2891,6 +3129,147 @@ impl Connection {
     }
 
 
+    // Camera image compression
+
+    pub fn camera_is_compression_supported(
+        &self,
+        device_name: &str,
+    ) -> Result<bool> {
+        self.is_device_support_any_of_props(
+            device_name,
+            PROP_CAM_COMPRESSION_ON
+        )
+    }
+
+    pub fn camera_set_compression(
+        &self,
+        device_name: &str,
+        enable:      bool,
+        force_set:   bool,
+        timeout_ms:  Option<u64>,
+    ) -> Result<bool> {
+        let devices = self.devices.lock().unwrap();
+        let (prop, elem) = if enable {
+            devices.existing_prop_name(device_name, PROP_CAM_COMPRESSION_ON)?
+        } else {
+            devices.existing_prop_name(device_name, PROP_CAM_COMPRESSION_OFF)?
+        };
+        drop(devices);
+
+        self.command_set_switch_property_and_wait(
+            force_set,
+            timeout_ms,
+            device_name,
+            prop,
+            &[(elem, true)]
+        )?;
+        Ok(true)
+    }
+
+
+    // Camera video streaming
+
+    pub fn camera_is_video_stream_supported(
+        &self,
+        device_name: &str,
+    ) -> Result<bool> {
+        self.property_exists(
+            device_name,
+            "CCD_VIDEO_STREAM",
+            Some("STREAM_ON")
+        )
+    }
+
+    pub fn camera_start_video_stream(
+        &self,
+        device_name: &str,
+        force_set:   bool,
+        timeout_ms:  Option<u64>,
+    ) -> Result<()> {
+        self.camera_control_video_stream(device_name, true, force_set, timeout_ms)
+    }
+
+    pub fn camera_stop_video_stream(
+        &self,
+        device_name: &str,
+        force_set:   bool,
+        timeout_ms:  Option<u64>,
+    ) -> Result<()> {
+        self.camera_control_video_stream(device_name, false, force_set, timeout_ms)
+    }
+
+    fn camera_control_video_stream(
+        &self,
+        device_name: &str,
+        enable:      bool,
+        force_set:   bool,
+        timeout_ms:  Option<u64>,
+    ) -> Result<()> {
+        if !self.camera_is_video_stream_supported(device_name)? {
+            return Err(Error::NotSupported(
+                device_name.to_string(),
+                "video streaming".to_string()
+            ));
+        }
+        self.command_set_switch_property_and_wait(
+            force_set,
+            timeout_ms,
+            device_name,
+            "CCD_VIDEO_STREAM", &[
+            ("STREAM_ON",  enable),
+            ("STREAM_OFF", !enable),
+        ])
+    }
+
+    /// Selects uncompressed frames for video stream if driver
+    /// supports several stream encoders
+    pub fn camera_set_raw_stream_encoder(
+        &self,
+        device_name: &str,
+        force_set:   bool,
+        timeout_ms:  Option<u64>,
+    ) -> Result<()> {
+        if !self.property_exists(device_name, "CCD_STREAM_ENCODER", Some("RAW"))? {
+            return Ok(());
+        }
+        self.command_set_switch_property_and_wait(
+            force_set,
+            timeout_ms,
+            device_name,
+            "CCD_STREAM_ENCODER",
+            &[("RAW", true)]
+        )
+    }
+
+    pub fn camera_get_stream_frame_size(
+        &self,
+        device_name: &str,
+    ) -> Result<(usize, usize)> {
+        let width = self.get_num_property_value(device_name, "CCD_STREAM_FRAME", "WIDTH")?;
+        let height = self.get_num_property_value(device_name, "CCD_STREAM_FRAME", "HEIGHT")?;
+        Ok((width as usize, height as usize))
+    }
+
+    pub fn camera_set_stream_exposure(
+        &self,
+        device_name: &str,
+        exposure:    f64,
+        force_set:   bool,
+        timeout_ms:  Option<u64>,
+    ) -> Result<()> {
+        if !self.property_exists(device_name, "STREAMING_EXPOSURE", Some("STREAMING_EXPOSURE_VALUE"))? {
+            return Ok(());
+        }
+        self.command_set_num_property_and_wait(
+            force_set,
+            timeout_ms,
+            device_name,
+            "STREAMING_EXPOSURE",
+            &[("STREAMING_EXPOSURE_VALUE", exposure)]
+        )
+    }
+
+
     // Camera low noise mode
 
     pub fn camera_is_low_noise_ctrl_supported(
@@ -3042,6 +3421,43 @@ impl Connection {
         Ok((ra, dec))
     }
 
+    /// Adds current mount coordinates into samples and returns `true`
+    /// if last `samples` coordinates differ less than `tolerance_arcsec`.
+    /// Must be called periodically (for example once per second)
+    /// during waiting of mount to settle after slewing
+    pub fn mount_coords_are_stable(
+        &self,
+        device_name:      &str,
+        tolerance_arcsec: f64,
+        samples:          usize,
+    ) -> Result<bool> {
+        let (ra, dec) = self.mount_get_eq_ra_and_dec(device_name)?;
+        let mut all_samples = self.mount_samples.lock().unwrap();
+        let dev_samples = all_samples.entry(device_name.to_string()).or_default();
+        dev_samples.push_back((ra, dec));
+        let samples = samples.max(2);
+        while dev_samples.len() > samples {
+            dev_samples.pop_front();
+        }
+        if dev_samples.len() < samples {
+            return Ok(false);
+        }
+        let tolerance_deg = tolerance_arcsec / 3600.0;
+        let cos_dec = f64::cos(dec.to_radians());
+        let is_stable = dev_samples.iter().all(|(s_ra, s_dec)| {
+            let mut ra_diff = f64::abs(s_ra - ra) * 15.0; // hours to degrees
+            if ra_diff > 180.0 { ra_diff = 360.0 - ra_diff; }
+            f64::hypot(ra_diff * cos_dec, s_dec - dec) < tolerance_deg
+        });
+        Ok(is_stable)
+    }
+
+    /// Clears samples of mount coordinates. Call it before start of
+    /// waiting for mount to settle
+    pub fn mount_reset_coords_samples(&self, device_name: &str) {
+        self.mount_samples.lock().unwrap().remove(device_name);
+    }
+
     pub fn set_after_coord_set_action(
         &self,
         device_name:  &str,
@@ -3240,6 +3656,62 @@ impl Connection {
         )
     }
 
+    pub fn mount_is_pier_side_supported(&self, device_name: &str) -> Result<bool> {
+        self.property_exists(device_name, "TELESCOPE_PIER_SIDE", None)
+    }
+
+    /// Returns `None` if mount reports unknown pier side
+    pub fn mount_get_pier_side(&self, device_name: &str) -> Result<Option<PierSide>> {
+        if self.get_switch_property(device_name, "TELESCOPE_PIER_SIDE", "PIER_EAST")? {
+            return Ok(Some(PierSide::East));
+        }
+        if self.get_switch_property(device_name, "TELESCOPE_PIER_SIDE", "PIER_WEST")? {
+            return Ok(Some(PierSide::West));
+        }
+        Ok(None)
+    }
+
+    /// Returns alignment model info or `None` if
+    /// mount doesn't have known alignment properties
+    pub fn mount_get_align_info(&self, device_name: &str) -> Result<Option<MountAlignInfo>> {
+        let devices = self.devices.lock().unwrap();
+        if devices.property_exists(device_name, "ALIGNMENT_POINTSET_SIZE", None)? {
+            let points = devices.get_num_property(
+                device_name,
+                "ALIGNMENT_POINTSET_SIZE",
+                "ALIGNMENT_POINTSET_SIZE"
+            )?.value;
+            let active = devices.get_switch_property(
+                device_name,
+                "ALIGNMENT_SUBSYSTEM_ACTIVE",
+                "ALIGNMENT SUBSYSTEM ACTIVE"
+            ).unwrap_or(false);
+            return Ok(Some(MountAlignInfo {
+                scheme: MountAlignScheme::AlignmentSubsystem,
+                points: points as usize,
+                active,
+            }));
+        }
+        if devices.property_exists(device_name, "ALIGNCOUNT", None)? {
+            let points = devices.get_num_property(
+                device_name,
+                "ALIGNCOUNT",
+                "ALIGNCOUNT_POINTS"
+            )?.value;
+            let no_align = devices.get_switch_property(
+                device_name,
+                "ALIGNMODE",
+                "NOALIGN"
+            ).unwrap_or(true);
+            return Ok(Some(MountAlignInfo {
+                scheme: MountAlignScheme::EqMod,
+                points: points as usize,
+                active: !no_align,
+            }));
+        }
+        Ok(None)
+    }
+
     pub fn mount_get_timed_guide_max(
         &self,
         device_name: &str
@@ -3355,7 +3827,401 @@ impl Connection {
         Ok(())
     }
 
-    pub fn get_geo_lat_long_elev(&self, device_name: &str) -> Result<(f64, f64, f64)> {
+    pub fn dome_get_azimuth(&self, device_name: &str) -> Result<f64> {
+        self.get_num_property_value(
+            device_name,
+            "ABS_DOME_POSITION",
+            "DOME_ABSOLUTE_POSITION"
+        )
+    }
+
+    pub fn dome_get_azimuth_prop_state(&self, device_name: &str) -> Result<PropState> {
+        let devices = self.devices.lock().unwrap();
+        let state = devices.get_property(device_name, "ABS_DOME_POSITION")?.state;
+        Ok(state)
+    }
+
+    pub fn dome_set_azimuth(
+        &self,
+        device_name: &str,
+        azimuth:     f64,
+        force_set:   bool,
+        timeout_ms:  Option<u64>,
+    ) -> Result<()> {
+        self.command_set_num_property_and_wait(
+            force_set,
+            timeout_ms,
+            device_name,
+            "ABS_DOME_POSITION",
+            &[("DOME_ABSOLUTE_POSITION", azimuth)]
+        )
+    }
+
+    pub fn dome_start_move_cw(&self, device_name: &str) -> Result<()> {
+        self.command_set_switch_property(
+            device_name,
+            "DOME_MOTION",
+            &[("DOME_CW", true)]
+        )
+    }
+
+    pub fn dome_start_move_ccw(&self, device_name: &str) -> Result<()> {
+        self.command_set_switch_property(
+            device_name,
+            "DOME_MOTION",
+            &[("DOME_CCW", true)]
+        )
+    }
+
+    pub fn dome_abort_motion(&self, device_name: &str) -> Result<()> {
+        self.command_set_switch_property(
+            device_name,
+            "DOME_ABORT_MOTION",
+            &[("ABORT", true)]
+        )
+    }
+
+    pub fn dome_get_parked(&self, device_name: &str) -> Result<bool> {
+        self.get_switch_property(
+            device_name,
+            "DOME_PARK",
+            "PARK"
+        )
+    }
+
+    pub fn dome_set_parked(
+        &self,
+        device_name: &str,
+        parked:      bool,
+        force_set:   bool,
+        timeout_ms:  Option<u64>,
+    ) -> Result<()> {
+        let elem_name = if parked {
+            "PARK"
+        } else {
+            "UNPARK"
+        };
+        self.command_set_switch_property_and_wait(
+            force_set,
+            timeout_ms,
+            device_name,
+            "DOME_PARK",
+            &[(elem_name, true)]
+        )
+    }
+
+    pub fn dome_is_shutter_supported(&self, device_name: &str) -> Result<bool> {
+        self.property_exists(device_name, "DOME_SHUTTER", None)
+    }
+
+    /// Returns `None` if dome reports unknown shutter state
+    pub fn dome_get_shutter_state(&self, device_name: &str) -> Result<Option<DomeShutterState>> {
+        if self.get_switch_property(device_name, "DOME_SHUTTER", "SHUTTER_OPEN")? {
+            return Ok(Some(DomeShutterState::Open));
+        }
+        if self.get_switch_property(device_name, "DOME_SHUTTER", "SHUTTER_CLOSE")? {
+            return Ok(Some(DomeShutterState::Closed));
+        }
+        Ok(None)
+    }
+
+    pub fn dome_open_shutter(
+        &self,
+        device_name: &str,
+        force_set:   bool,
+        timeout_ms:  Option<u64>,
+    ) -> Result<()> {
+        self.command_set_switch_property_and_wait(
+            force_set,
+            timeout_ms,
+            device_name,
+            "DOME_SHUTTER",
+            &[("SHUTTER_OPEN", true)]
+        )
+    }
+
+    pub fn dome_close_shutter(
+        &self,
+        device_name: &str,
+        force_set:   bool,
+        timeout_ms:  Option<u64>,
+    ) -> Result<()> {
+        self.command_set_switch_property_and_wait(
+            force_set,
+            timeout_ms,
+            device_name,
+            "DOME_SHUTTER",
+            &[("SHUTTER_CLOSE", true)]
+        )
+    }
+
+    pub fn dome_set_slaved(
+        &self,
+        device_name: &str,
+        slaved:      bool,
+        force_set:   bool,
+        timeout_ms:  Option<u64>,
+    ) -> Result<()> {
+        let elem_name = if slaved {
+            "DOME_AUTOSYNC_ENABLE"
+        } else {
+            "DOME_AUTOSYNC_DISABLE"
+        };
+        self.command_set_switch_property_and_wait(
+            force_set,
+            timeout_ms,
+            device_name,
+            "DOME_AUTOSYNC",
+            &[(elem_name, true)]
+        )
+    }
+
+    // Filter wheel
+
+    pub fn filterwheel_get_slot_count(&self, device_name: &str) -> Result<usize> {
+        let devices = self.devices.lock().unwrap();
+        let slot = devices.get_num_property(device_name, "FILTER_SLOT", "FILTER_SLOT_VALUE")?;
+        Ok(slot.max as usize)
+    }
+
+    /// Returns current slot (starting from 1)
+    pub fn filterwheel_get_current_slot(&self, device_name: &str) -> Result<usize> {
+        let value = self.get_num_property_value(
+            device_name,
+            "FILTER_SLOT",
+            "FILTER_SLOT_VALUE"
+        )?;
+        Ok(value as usize)
+    }
+
+    pub fn filterwheel_set_slot(
+        &self,
+        device_name: &str,
+        slot:        usize, // starting from 1
+        force_set:   bool,
+        timeout_ms:  Option<u64>,
+    ) -> Result<()> {
+        self.command_set_num_property_and_wait(
+            force_set,
+            timeout_ms,
+            device_name,
+            "FILTER_SLOT",
+            &[("FILTER_SLOT_VALUE", slot as f64)]
+        )
+    }
+
+    pub fn filterwheel_get_names(&self, device_name: &str) -> Result<Vec<String>> {
+        let devices = self.devices.lock().unwrap();
+        let property = devices.get_property(device_name, "FILTER_NAME")?;
+        Ok(property.elements
+            .iter()
+            .map(|elem| match &elem.value {
+                PropValue::Text(text) => text.to_string(),
+                _                     => String::new(),
+            })
+            .collect())
+    }
+
+    pub fn filterwheel_set_names(
+        &self,
+        device_name: &str,
+        names:       &[&str],
+    ) -> Result<()> {
+        let devices = self.devices.lock().unwrap();
+        let property = devices.get_property(device_name, "FILTER_NAME")?;
+        let elem_names: Vec<_> = property.elements
+            .iter()
+            .map(|elem| Arc::clone(&elem.name))
+            .collect();
+        drop(devices);
+        let elements: Vec<_> = elem_names
+            .iter()
+            .zip(names)
+            .map(|(elem_name, name)| (elem_name.as_str(), *name))
+            .collect();
+        self.command_set_text_property(
+            device_name,
+            "FILTER_NAME",
+            &elements
+        )
+    }
+
+    // Rotator
+
+    pub fn rotator_get_angle(&self, device_name: &str) -> Result<f64> {
+        self.get_num_property_value(
+            device_name,
+            "ABS_ROTATOR_ANGLE",
+            "ANGLE"
+        )
+    }
+
+    pub fn rotator_set_angle(
+        &self,
+        device_name: &str,
+        angle:       f64, // in degrees
+        force_set:   bool,
+        timeout_ms:  Option<u64>,
+    ) -> Result<()> {
+        self.command_set_num_property_and_wait(
+            force_set,
+            timeout_ms,
+            device_name,
+            "ABS_ROTATOR_ANGLE",
+            &[("ANGLE", angle)]
+        )
+    }
+
+    pub fn rotator_is_reversed(&self, device_name: &str) -> Result<bool> {
+        self.get_switch_property(
+            device_name,
+            "ROTATOR_REVERSE",
+            "INDI_ENABLED"
+        )
+    }
+
+    pub fn rotator_set_reverse(
+        &self,
+        device_name: &str,
+        reverse:     bool,
+        force_set:   bool,
+        timeout_ms:  Option<u64>,
+    ) -> Result<()> {
+        let elem_name = if reverse {
+            "INDI_ENABLED"
+        } else {
+            "INDI_DISABLED"
+        };
+        self.command_set_switch_property_and_wait(
+            force_set,
+            timeout_ms,
+            device_name,
+            "ROTATOR_REVERSE",
+            &[(elem_name, true)]
+        )
+    }
+
+    pub fn rotator_abort(&self, device_name: &str) -> Result<()> {
+        self.command_set_switch_property(
+            device_name,
+            "ROTATOR_ABORT_MOTION",
+            &[("ABORT", true)]
+        )
+    }
+
+    // Weather
+
+    /// Returns (element name, value) pairs of `WEATHER_PARAMETERS`
+    pub fn weather_get_parameters(&self, device_name: &str) -> Result<Vec<(Arc<String>, f64)>> {
+        let devices = self.devices.lock().unwrap();
+        let property = devices.get_property(device_name, "WEATHER_PARAMETERS")?;
+        Ok(property.elements
+            .iter()
+            .filter_map(|elem| match &elem.value {
+                PropValue::Num(num) => Some((Arc::clone(&elem.name), num.value)),
+                _                   => None,
+            })
+            .collect())
+    }
+
+    /// Returns names of `WEATHER_STATUS` elements in alert state
+    pub fn weather_get_alerts(&self, device_name: &str) -> Result<Vec<Arc<String>>> {
+        let devices = self.devices.lock().unwrap();
+        let property = devices.get_property(device_name, "WEATHER_STATUS")?;
+        Ok(property.elements
+            .iter()
+            .filter(|elem| matches!(
+                &elem.value,
+                PropValue::Light(state) if state.as_str() == "Alert"
+            ))
+            .map(|elem| Arc::clone(&elem.name))
+            .collect())
+    }
+
+    pub fn weather_is_safe(&self, device_name: &str) -> Result<bool> {
+        Ok(self.weather_get_alerts(device_name)?.is_empty())
+    }
+
+    // Dust cap
+
+    pub fn dustcap_is_parked(&self, device_name: &str) -> Result<bool> {
+        self.get_switch_property(device_name, "CAP_PARK", "PARK")
+    }
+
+    pub fn dustcap_park(
+        &self,
+        device_name: &str,
+        force_set:   bool,
+        timeout_ms:  Option<u64>,
+    ) -> Result<()> {
+        self.command_set_switch_property_and_wait(
+            force_set,
+            timeout_ms,
+            device_name,
+            "CAP_PARK",
+            &[("PARK", true)]
+        )
+    }
+
+    pub fn dustcap_unpark(
+        &self,
+        device_name: &str,
+        force_set:   bool,
+        timeout_ms:  Option<u64>,
+    ) -> Result<()> {
+        self.command_set_switch_property_and_wait(
+            force_set,
+            timeout_ms,
+            device_name,
+            "CAP_PARK",
+            &[("UNPARK", true)]
+        )
+    }
+
+    // Light box (flat panel)
+
+    pub fn lightbox_enable(
+        &self,
+        device_name: &str,
+        enable:      bool,
+        force_set:   bool,
+        timeout_ms:  Option<u64>,
+    ) -> Result<()> {
+        let elem_name = if enable {
+            "FLAT_LIGHT_ON"
+        } else {
+            "FLAT_LIGHT_OFF"
+        };
+        self.command_set_switch_property_and_wait(
+            force_set,
+            timeout_ms,
+            device_name,
+            "FLAT_LIGHT_CONTROL",
+            &[(elem_name, true)]
+        )
+    }
+
+    pub fn lightbox_is_brightness_supported(&self, device_name: &str) -> Result<bool> {
+        self.property_exists(device_name, "FLAT_LIGHT_INTENSITY", None)
+    }
+
+    pub fn lightbox_set_brightness(
+        &self,
+        device_name: &str,
+        brightness:  f64,
+        force_set:   bool,
+        timeout_ms:  Option<u64>,
+    ) -> Result<()> {
+        self.command_set_num_property_and_wait(
+            force_set,
+            timeout_ms,
+            device_name,
+            "FLAT_LIGHT_INTENSITY",
+            &[("FLAT_LIGHT_INTENSITY_VALUE", brightness)]
+        )
+    }
+
+    pub fn mount_get_geo_coords(&self, device_name: &str) -> Result<(f64, f64, f64)> {
         let devices = self.devices.lock().unwrap();
         let latitude = devices.get_num_property(device_name, "GEOGRAPHIC_COORD", "LAT")?.value;
         let longitude = devices.get_num_property(device_name, "GEOGRAPHIC_COORD", "LONG")?.value;
@@ -3564,6 +4430,8 @@ struct XmlReceiver {
     xml_sender:    XmlSender,
     state:         XmlReceiverState,
     activate_devs: bool,
+    read_timeout:  Duration,
+    refetch_devs:  HashSet<String>, // devices for which properties are re-requested
 }
 
 impl XmlReceiver {
@@ -3573,20 +4441,26 @@ impl XmlReceiver {
         stream:        TcpStream,
         xml_sender:    XmlSender,
         activate_devs: bool,
+        blob_timeout:  Option<Duration>,
+        read_timeout:  Duration,
     ) -> Self {
+        let mut reader = XmlStreamReader::new();
+        reader.set_blob_timeout(blob_timeout);
         Self {
             conn_state,
             devices,
             stream,
-            reader: XmlStreamReader::new(),
+            reader,
             xml_sender,
             state: XmlReceiverState::Undef,
             activate_devs,
+            read_timeout: read_timeout.max(MIN_READ_TIMEOUT),
+            refetch_devs: HashSet::new(),
         }
     }
 
     fn main(&mut self, events_sender: mpsc::Sender<Event>) {
-        self.stream.set_read_timeout(Some(Duration::from_millis(1000))).unwrap(); // TODO: check error
+        self.stream.set_read_timeout(Some(self.read_timeout)).unwrap(); // TODO: check error
 
         self.xml_sender.command_get_properties_impl(None, None).unwrap(); // TODO: check error
         self.state = XmlReceiverState::WaitForDevicesList;
@@ -3610,6 +4484,19 @@ impl XmlReceiver {
                         &events_sender,
                     );
                 }
+                Ok(XmlStreamReaderResult::BlobTimeOut {
+                    device_name, prop_name, elem_name
+                }) => {
+                    log::warn!(
+                        "indi_api: BLOB {}.{}.{} downloading time out. BLOB is discarded",
+                        device_name, prop_name, elem_name
+                    );
+                    events_sender.send(Event::BlobTimeOut(Arc::new(BlobTimeOutEvent {
+                        device_name: Arc::new(device_name),
+                        prop_name:   Arc::new(prop_name),
+                        elem_name:   Arc::new(elem_name),
+                    }))).unwrap();
+                }
                 Ok(XmlStreamReaderResult::Xml{ xml, blobs }) => {
                     if log::log_enabled!(log::Level::Trace) {
                         log::trace!("indi_api: incoming xml =\n{}", xml);
@@ -3797,6 +4684,19 @@ impl XmlReceiver {
         }))).unwrap();
     }
 
+    /// INDI server can send setXXXVector before defXXXVector
+    /// (for example just after device is connected).
+    /// Properties of such device are requested once again
+    fn process_set_for_unknown_prop(&mut self, device_name: &str, prop_name: &str) {
+        log::debug!(
+            "indi_api: property {} of device {} is not defined yet",
+            prop_name, device_name
+        );
+        if self.refetch_devs.insert(device_name.to_string()) {
+            _ = self.xml_sender.command_get_properties_impl(Some(device_name), None);
+        }
+    }
+
     fn process_xml(
         &mut self,
         xml_text:      &str,
@@ -3810,6 +4710,7 @@ impl XmlReceiver {
             if device_name.is_empty() {
                 anyhow::bail!("Empty device name");
             }
+            self.refetch_devs.remove(&device_name);
             let mut devices = self.devices.lock().unwrap();
             let change_id = devices.change_id;
             let device_name = Arc::new(device_name);
@@ -3854,14 +4755,15 @@ impl XmlReceiver {
             devices.change_id += 1;
             let change_id = devices.change_id;
             let Some(device) = devices.find_by_name_opt_mut(&device_name) else {
-                anyhow::bail!(Error::DeviceNotExists(device_name));
+                drop(devices);
+                self.process_set_for_unknown_prop(&device_name, &prop_name);
+                return Ok(());
             };
             let device_name = Arc::clone(&device.name);
             let Some(property) = device.get_property_opt_mut(&prop_name) else {
-                anyhow::bail!(Error::PropertyNotExists(
-                    device_name.to_string(),
-                    prop_name
-                ));
+                drop(devices);
+                self.process_set_for_unknown_prop(&device_name, &prop_name);
+                return Ok(());
             };
             property.change_id = change_id;
             let prev_state = property.state.clone();
@@ -3998,6 +4900,12 @@ const PROP_CAM_LOW_NOISE_OFF: PropsNamePairs = &[
     ("TC_LOW_NOISE_CONTROL", "INDI_DISABLED"),
     ("TC_LOW_NOISE",         "INDI_DISABLED"),
 ];
+const PROP_CAM_COMPRESSION_ON: PropsNamePairs = &[
+    ("CCD_COMPRESSION", "CCD_COMPRESS"),
+];
+const PROP_CAM_COMPRESSION_OFF: PropsNamePairs = &[
+    ("CCD_COMPRESSION", "CCD_RAW"),
+];
 const PROP_CAM_VIDEO_FORMAT_RGB: PropsNamePairs = &[
     ("CCD_VIDEO_FORMAT", "TC_VIDEO_COLOR_RGB"),
 ];
@@ -4012,4 +4920,56 @@ const PROP_CAM_BIN_ADD: PropsNamePairs = &[
 ];
 const PROP_DEVICE_CRASH: PropsNamePairs = &[
     ("CCD_SIMULATE_CRASH", "CRASH"),
-];
\ No newline at end of file
+];
+
+#[test]
+fn test_num_format_g() {
+    let fmt = |format: &str, value: f64| -> String {
+        NumFormat::new_from_indi_format(format).value_to_string(value)
+    };
+    // expected values are taken from C printf
+    assert_eq!(fmt("%g", 0.0), "0");
+    assert_eq!(fmt("%g", 0.0001), "0.0001");
+    assert_eq!(fmt("%g", 0.00001), "1e-05");
+    assert_eq!(fmt("%g", 123456.0), "123456");
+    assert_eq!(fmt("%g", 1234567.0), "1.23457e+06");
+    assert_eq!(fmt("%g", 1.23456789), "1.23457");
+    assert_eq!(fmt("%g", 999999.5), "1e+06");
+    assert_eq!(fmt("%g", 1e100), "1e+100");
+    assert_eq!(fmt("%g", 0.000123456789), "0.000123457");
+    assert_eq!(fmt("%g", -12.5), "-12.5");
+    assert_eq!(fmt("%.3g", 1234.5), "1.23e+03");
+    assert_eq!(fmt("%.0g", 0.5), "0.5");
+    assert_eq!(fmt("%.10g", 12.5), "12.5");
+    assert_eq!(fmt("%10g", 1.5), "       1.5");
+    assert_eq!(fmt("%6.2g", 0.000012), "1.2e-05");
+}
+
+#[test]
+fn test_num_format_float_and_int() {
+    let fmt = |format: &str, value: f64| -> String {
+        NumFormat::new_from_indi_format(format).value_to_string(value)
+    };
+    // expected values are taken from C printf
+    assert_eq!(fmt("%f", 1.5), "1.500000");
+    assert_eq!(fmt("%.2f", 2.5), "2.50");
+    assert_eq!(fmt("%8.3f", 1.23456), "   1.235");
+    assert_eq!(fmt("%8.3f", -1.23456), "  -1.235");
+    assert_eq!(fmt("%3.0f", 5.4), "  5");
+    assert_eq!(fmt("%3.0f", 1234.0), "1234");
+    assert_eq!(fmt("%+06.2f", 1.23456), "+01.23");
+    assert_eq!(fmt("%+06.2f", -1.23456), "-01.23");
+    assert_eq!(fmt("%06.2f", -1.23456), "-01.23");
+    assert_eq!(fmt("%010.4f", 12.5), "00012.5000");
+    assert_eq!(fmt("%+.1f", 0.0), "+0.0");
+    assert_eq!(fmt("%+8.2f", 1.0), "   +1.00");
+    assert_eq!(fmt("%d", 5.0), "5");
+    assert_eq!(fmt("%d", -3.0), "-3");
+    assert_eq!(fmt("%+d", 5.0), "+5");
+    assert_eq!(fmt("%4d", 5.0), "   5");
+    assert_eq!(fmt("%04d", 5.0), "0005");
+    assert_eq!(fmt("%+05d", -5.0), "-0005");
+    assert_eq!(fmt("%+5d", 42.0), "  +42");
+    assert_eq!(fmt("%ld", 100000.0), "100000");
+    assert_eq!(fmt("%3i", 7.0), "  7");
+}
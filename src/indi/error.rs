@@ -30,6 +30,9 @@ pub enum Error {
     #[error("{0}")]
     WrongArgument(String),
 
+    #[error("Device `{0}` doesn't support {1}")]
+    NotSupported(String, String),
+
     #[error("Wrong sequense: {0}")]
     WrongSequense(String),
 
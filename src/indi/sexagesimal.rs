@@ -36,10 +36,42 @@ pub fn value_to_sexagesimal_impl(
         (8, true)  => format!("{}{:02}{}{:02}{}{:02}.{}{}",    sign, hours, unit1, minutes100 / 100, unit2, seconds100 / 100, (seconds100 % 100) / 10, unit3),
         (9, false) => format!("{}{}{}{:02}{}{:02}.{:02}{}",    sign, hours, unit1, minutes100 / 100, unit2, seconds100 / 100, seconds100 % 100, unit3),
         (9, true)  => format!("{}{:02}{}{:02}{}{:02}.{:02}{}", sign, hours, unit1, minutes100 / 100, unit2, seconds100 / 100, seconds100 % 100, unit3),
-        _          => value.to_string(),
+        _          => seconds_with_frac_to_str(value, zero, sign, frac, unit1, unit2, unit3),
     }
 }
 
+/// Formats `value` as hours (or degrees), minutes and seconds
+/// with `frac_digits` digits after decimal point of seconds
+fn seconds_with_frac_to_str(
+    value:       f64,
+    zero:        bool,
+    sign:        &str,
+    frac_digits: u8,
+    unit1:       &str,
+    unit2:       &str,
+    unit3:       &str,
+) -> String {
+    const MAX_FRAC_DIGITS: u8 = 15; // more digits has no sense for f64
+    let frac_digits = frac_digits.min(MAX_FRAC_DIGITS);
+    let frac_mult = 10_u128.pow(frac_digits as u32);
+    let total = (value * 3600.0 * frac_mult as f64).round() as u128;
+    let frac = total % frac_mult;
+    let total_seconds = total / frac_mult;
+    let hours = total_seconds / 3600;
+    let minutes = (total_seconds / 60) % 60;
+    let seconds = total_seconds % 60;
+    let mut result = if zero {
+        format!("{}{:02}{}{:02}{}{:02}", sign, hours, unit1, minutes, unit2, seconds)
+    } else {
+        format!("{}{}{}{:02}{}{:02}", sign, hours, unit1, minutes, unit2, seconds)
+    };
+    if frac_digits != 0 {
+        result += &format!(".{:0width$}", frac, width = frac_digits as usize);
+    }
+    result += unit3;
+    result
+}
+
 pub fn value_to_sexagesimal(value: f64, zero: bool, frac: u8) -> String {
     value_to_sexagesimal_impl(value, zero, false, frac, ":", ":", "")
 }
@@ -127,9 +159,60 @@ pub fn sexagesimal_to_value(text: &str) -> Option<f64> {
         return Some(if !is_neg {value} else {-value});
     }
 
+    // -00 00 00.0, -00h00m00.0s, -00d00m00.0s, -00°00'00.0"
+    static HMS_RE: OnceCell<regex::Regex> = OnceCell::new();
+    let hms_re = HMS_RE.get_or_init(|| {
+        regex::Regex::new(r#"^([+-]?)\s*(\d+)\s*[hHdD°\s]\s*(\d+)\s*[mM'′\s]\s*(\d+(?:\.\d+)?)\s*[sS"″]?$"#).unwrap()
+    });
+    if let Some(res) = hms_re.captures(text) {
+        let is_neg = &res[1] == "-";
+        let hours = res[2].parse::<f64>().unwrap_or(0.0);
+        let minutes = res[3].parse::<f64>().unwrap_or(0.0);
+        let seconds = res[4].parse::<f64>().unwrap_or(0.0);
+        let value = hours + minutes / 60.0 + seconds / 3600.0;
+        return Some(if !is_neg {value} else {-value});
+    }
+
+    // -00 00.0, -00h00.0m, -00d00.0m, -00°00.0'
+    static HM_RE: OnceCell<regex::Regex> = OnceCell::new();
+    let hm_re = HM_RE.get_or_init(|| {
+        regex::Regex::new(r"^([+-]?)\s*(\d+)\s*[hHdD°\s]\s*(\d+(?:\.\d+)?)\s*[mM'′]?$").unwrap()
+    });
+    if let Some(res) = hm_re.captures(text) {
+        let is_neg = &res[1] == "-";
+        let hours = res[2].parse::<f64>().unwrap_or(0.0);
+        let minutes = res[3].parse::<f64>().unwrap_or(0.0);
+        let value = hours + minutes / 60.0;
+        return Some(if !is_neg {value} else {-value});
+    }
+
+    // -00.00, -00.00h, -00.00°
+    static DEC_RE: OnceCell<regex::Regex> = OnceCell::new();
+    let dec_re = DEC_RE.get_or_init(|| {
+        regex::Regex::new(r"^([+-]?\d+(?:\.\d*)?|[+-]?\.\d+)\s*[hHdD°]?$").unwrap()
+    });
+    if let Some(res) = dec_re.captures(text) {
+        return res[1].parse::<f64>().ok();
+    }
+
     None
 }
 
+/// Parses value in sexagesimal (DD:MM:SS) or decimal format.
+/// Returns `None` if value is out of `min..=max`
+pub fn parse_coord_value(text: &str, min: f64, max: f64) -> Option<f64> {
+    let text = text.trim();
+    let value = if text.contains(':') {
+        sexagesimal_to_value(text)?
+    } else {
+        text.replace(',', ".").parse::<f64>().ok()?
+    };
+    if !value.is_finite() || value < min || value > max {
+        return None;
+    }
+    Some(value)
+}
+
 #[test]
 fn test_sexagesimal_to_value() {
     assert!(sexagesimal_to_value("").is_none());
@@ -141,5 +224,55 @@ fn test_sexagesimal_to_value() {
     assert!(sexagesimal_to_value("-10:30.3").unwrap() == -10.505);
     assert!(sexagesimal_to_value("10:30:00").unwrap() == 10.5);
     assert!(sexagesimal_to_value("10:30:30").unwrap() == 10.508333333333333);
-    // TODO: more tests
+}
+
+#[test]
+fn test_sexagesimal_to_value_other_formats() {
+    let check = |text: &str, expected: f64| {
+        let value = sexagesimal_to_value(text).unwrap();
+        assert!((value - expected).abs() < 1e-9, "{} -> {}", text, value);
+    };
+    check("10 30 00", 10.5);
+    check("-10 30 00", -10.5);
+    check("+10 30 30", 10.508333333333333);
+    check("10 30 30.5", 10.508472222222222);
+    check("10h30m00s", 10.5);
+    check("10h 30m 30.5s", 10.508472222222222);
+    check("-10d30m00s", -10.5);
+    check("-10° 30' 00\"", -10.5);
+    check("10 30", 10.5);
+    check("10h30m", 10.5);
+    check("-10°30.3'", -10.505);
+    check("10.5", 10.5);
+    check("-10.5", -10.5);
+    check("+10", 10.0);
+    check("10.5h", 10.5);
+    check("-10.5°", -10.5);
+    assert!(sexagesimal_to_value("abc").is_none());
+    assert!(sexagesimal_to_value("10 30 abc").is_none());
+}
+
+#[test]
+fn test_value_to_sexagesimal() {
+    // Special frac codes
+    assert_eq!(value_to_sexagesimal(10.5, false, 3), "10:30");
+    assert_eq!(value_to_sexagesimal(1.5, true, 3), "01:30");
+    assert_eq!(value_to_sexagesimal(10.505, false, 5), "10:30.3");
+    assert_eq!(value_to_sexagesimal(10.508333333333333, false, 6), "10:30:30");
+    assert_eq!(value_to_sexagesimal(-10.508333333333333, true, 6), "-10:30:30");
+    assert_eq!(value_to_sexagesimal(10.508347222222222, false, 8), "10:30:30.0");
+    assert_eq!(value_to_sexagesimal(10.508472222222222, false, 9), "10:30:30.50");
+
+    // Any digits count of seconds fraction
+    assert_eq!(value_to_sexagesimal(10.508472222222222, false, 0), "10:30:31");
+    assert_eq!(value_to_sexagesimal(10.508472222222222, false, 1), "10:30:30.5");
+    assert_eq!(value_to_sexagesimal(10.508472222222222, false, 2), "10:30:30.50");
+    assert_eq!(value_to_sexagesimal(1.508472222222222, true, 4), "01:30:30.5000");
+    assert_eq!(value_to_sexagesimal(-1.508472222222222, false, 7), "-1:30:30.5000000");
+
+    // Carry propagation
+    assert_eq!(value_to_sexagesimal(10.999999, false, 0), "11:00:00");
+    assert_eq!(value_to_sexagesimal(10.9999999, false, 2), "11:00:00.00");
+    assert_eq!(value_to_sexagesimal(-0.0166666, false, 1), "-0:01:00.0");
+    assert_eq!(hour_to_str(10.508472222222222), "10h30m30.50s");
 }
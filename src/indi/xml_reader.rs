@@ -23,6 +23,11 @@ pub enum XmlStreamReaderResult {
         xml:   String,
         blobs: Vec<XmlStreamReaderBlob>,
     },
+    BlobTimeOut {
+        device_name: String,
+        prop_name:   String,
+        elem_name:   String,
+    },
     TimeOut,
     Disconnected
 }
@@ -36,8 +41,12 @@ enum XmlStreamReaderState {
     WaitOneBlobTag,
     ReadingBlob,
     WaitOneBlobTagEnd,
+    SkippingBlob,
 }
 
+// Bigger BLOBs are decoded into buffer growing while receiving
+const MAX_PRESIZED_BLOB_LEN: usize = 100_000_000;
+
 pub struct XmlStreamReader {
     state:               XmlStreamReaderState,
     read_buffer:         Vec<u8>,
@@ -57,6 +66,7 @@ pub struct XmlStreamReader {
     blob_format:         String,
     blob_size:           Option<usize>,
     blob_dl_start:       std::time::Instant,
+    blob_timeout:        Option<std::time::Duration>,
     blobs:               Vec<XmlStreamReaderBlob>,
     xml_text:            String,
 }
@@ -82,6 +92,7 @@ impl XmlStreamReader {
             blob_format:         String::new(),
             blob_size:           None,
             blob_dl_start:       std::time::Instant::now(),
+            blob_timeout:        None,
             blobs:               Vec::new(),
             xml_text:            String::new(),
         }
@@ -91,6 +102,11 @@ impl XmlStreamReader {
         self.buf_size = buf_size;
     }
 
+    /// Maximum duration of one BLOB downloading. `None` means no limit
+    pub fn set_blob_timeout(&mut self, timeout: Option<std::time::Duration>) {
+        self.blob_timeout = timeout;
+    }
+
     pub fn recover_after_error(&mut self) {
         self.read_buffer.clear();
         self.blobs.clear();
@@ -174,7 +190,14 @@ impl XmlStreamReader {
                         let size = xml_elem.attributes.get("size").and_then(|attr| attr.parse::<usize>().ok());
                         let len = xml_elem.attributes.get("len").and_then(|attr| attr.parse::<usize>().ok());
                         self.blob_size = size.or(len);
-                        self.base64_decoder.clear(usize::min(self.blob_size.unwrap_or_default(), 100_000_000));
+                        match len {
+                            // `len` is exact size of decoded data so BLOB
+                            // is decoded into pre-sized buffer without growing
+                            Some(len) if len <= MAX_PRESIZED_BLOB_LEN =>
+                                self.base64_decoder.clear_with_buffer(vec![0; len]),
+                            _ =>
+                                self.base64_decoder.clear(usize::min(self.blob_size.unwrap_or_default(), MAX_PRESIZED_BLOB_LEN)),
+                        }
                         self.stream_buffer.clear();
                         self.stream_buffer.extend_from_slice(&self.read_buffer);
                         self.read_buffer.clear();
@@ -222,6 +245,24 @@ impl XmlStreamReader {
                         self.read_len = 0;
                         continue;
                     }
+                    self.read_len = 0;
+                    let blob_timed_out = self.blob_timeout
+                        .map(|timeout| self.blob_dl_start.elapsed() > timeout)
+                        .unwrap_or(false);
+                    if blob_timed_out {
+                        // Discard partially received BLOB and skip
+                        // the rest of it until </setBLOBVector>
+                        self.base64_decoder.clear(0);
+                        self.blobs.clear();
+                        self.xml_text.clear();
+                        self.read_buffer.clear();
+                        self.state = XmlStreamReaderState::SkippingBlob;
+                        return Ok(XmlStreamReaderResult::BlobTimeOut {
+                            device_name: self.blob_device.clone(),
+                            prop_name:   self.blob_prop.clone(),
+                            elem_name:   self.blob_elem.clone(),
+                        });
+                    }
                 }
                 XmlStreamReaderState::WaitOneBlobTagEnd => {
                     self.read_buffer.extend_from_slice(&self.stream_buffer[..self.read_len]);
@@ -235,6 +276,22 @@ impl XmlStreamReader {
                         continue;
                     }
                 }
+                XmlStreamReaderState::SkippingBlob => {
+                    let data = &self.stream_buffer[..self.read_len];
+                    if self.read_buffer.is_empty() {
+                        if let Some(pos) = data.iter().position(|b| *b == b'<') {
+                            self.read_buffer.extend_from_slice(&data[pos..]);
+                        }
+                    } else {
+                        self.read_buffer.extend_from_slice(data);
+                    }
+                    self.read_len = 0;
+                    if let Some(re_res) = self.set_blob_vec_end_re.find(&self.read_buffer) {
+                        self.read_buffer.drain(0..re_res.end());
+                        self.state = XmlStreamReaderState::WaitForTag;
+                        continue;
+                    }
+                }
             }
 
             self.stream_buffer.resize(self.buf_size, 0);
@@ -442,4 +499,37 @@ fn test_reader() {
     do_test(100);
     do_test(1000);
     do_test(10000);
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_reader_blob_timeout() {
+    let mut reader = XmlStreamReader::new();
+    reader.set_buf_size(4);
+    reader.set_blob_timeout(Some(std::time::Duration::ZERO));
+
+    let mut stream = std::io::Cursor::new(r#"
+        <setBLOBVector device="TestDev" name="Test1" state="Ok" timeout="60" timestamp="2023-06-03T19:31:34">
+            <oneBLOB name="CCD1" size="8" format=".text1" len="8">dGVzdHRlc3Q=</oneBLOB>
+        </setBLOBVector>
+        <xml1/>
+    "#);
+
+    let res = reader.receive_xml(&mut stream);
+    assert!(matches!(res.unwrap(), XmlStreamReaderResult::BlobBegin { .. }));
+
+    let res = reader.receive_xml(&mut stream);
+    let XmlStreamReaderResult::BlobTimeOut { device_name, prop_name, elem_name } = res.unwrap() else {
+        panic!("Not Blob time out");
+    };
+    assert_eq!(device_name, "TestDev");
+    assert_eq!(prop_name,   "Test1");
+    assert_eq!(elem_name,   "CCD1");
+
+    let res = reader.receive_xml(&mut stream);
+    let XmlStreamReaderResult::Xml { xml, blobs } = res.unwrap() else { panic!("Not XML"); };
+    assert_eq!(xml.trim(), "<xml1/>");
+    assert!(blobs.is_empty());
+
+    let res = reader.receive_xml(&mut stream);
+    assert!(matches!(res.unwrap(), XmlStreamReaderResult::Disconnected));
+}
@@ -16,7 +16,7 @@ mod core;
 mod options;
 mod sky_math;
 
-use std::{path::Path, sync::{Arc, RwLock}};
+use std::{path::Path, sync::{atomic::{AtomicBool, Ordering}, Arc, RwLock}, time::Duration};
 use gtk::{prelude::*, glib, glib::clone};
 use crate::{
     utils::io_utils::*,
@@ -26,8 +26,51 @@ use crate::{
     core::frame_processing::*
 };
 
+/// Best-effort stopping of all connected mounts.
+/// Motion of all mounts is aborted first and only then mounts are
+/// parked (without waiting) so slow parking of one mount doesn't
+/// delay stopping of others. Error for one mount doesn't prevent
+/// stopping of others
+fn stop_mounts_on_panic(indi: &Arc<indi::Connection>) {
+    if indi.state() != indi::ConnState::Connected {
+        return;
+    }
+
+    // INDI connection data can be locked by panicked thread
+    // so stopping is executed in separate thread with time limit
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let indi = Arc::clone(indi);
+    std::thread::spawn(move || {
+        let mounts: Vec<_> = indi
+            .get_devices_list()
+            .into_iter()
+            .filter(|device| device.interface.contains(indi::DriverInterface::TELESCOPE))
+            .collect();
+        for mount in &mounts {
+            log::info!("Aborting motion of mount {}...", mount.name);
+            if let Err(err) = indi.mount_abort_motion(&mount.name) {
+                log::error!("Can't abort motion of mount {}: {}", mount.name, err);
+            }
+        }
+        for mount in &mounts {
+            let park_supported = indi
+                .property_exists(&mount.name, "TELESCOPE_PARK", None)
+                .unwrap_or(false);
+            if park_supported {
+                log::info!("Parking mount {}...", mount.name);
+                if let Err(err) = indi.mount_set_parked(&mount.name, true, true, None) {
+                    log::error!("Can't park mount {}: {}", mount.name, err);
+                }
+            }
+        }
+        _ = sender.send(());
+    });
+    _ = receiver.recv_timeout(Duration::from_secs(5));
+}
+
 fn panic_handler(
     panic_info:        &std::panic::PanicHookInfo,
+    indi:              &Arc<indi::Connection>,
     stop_indi_servers: bool,
     logs_dir:          &Path,
     def_panic_handler: &Box<dyn Fn(&std::panic::PanicHookInfo<'_>) + 'static + Sync + Send>,
@@ -74,6 +117,12 @@ fn panic_handler(
         logs_dir.to_str().unwrap_or_default()
     );
 
+    // Panic inside stopping code must not lead to recursion
+    static STOPPING_MOUNTS: AtomicBool = AtomicBool::new(false);
+    if !STOPPING_MOUNTS.swap(true, Ordering::Relaxed) {
+        stop_mounts_on_panic(indi);
+    }
+
     if stop_indi_servers && cfg!(target_os = "linux") {
         log::info!("Stop INDI server...");
         _ = std::process::Command::new("pkill")
@@ -106,6 +155,7 @@ fn main() -> anyhow::Result<()> {
             Box::new(move |panic_info| {
                 panic_handler(
                     panic_info,
+                    &indi,
                     indi.is_drivers_started(),
                     &logs_dir,
                     &default_panic_handler
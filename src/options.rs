@@ -3,29 +3,34 @@ use std::path::PathBuf;
 use serde::{Serialize, Deserialize};
 
 use crate::{
-    core::consts::*, image::{preview::PreviewParams, raw::FrameType}
+    core::{consts::*, pointing_model::PointingModel},
+    image::{preview::PreviewParams, raw::FrameType, stars::StarDetectionOptions}
 };
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(default)]
 pub struct IndiOptions {
-    pub mount:    Option<String>,
-    pub camera:   Option<String>,
-    pub guid_cam: Option<String>,
-    pub focuser:  Option<String>,
-    pub remote:   bool,
-    pub address:  String,
+    pub mount:        Option<String>,
+    pub camera:       Option<String>,
+    pub guid_cam:     Option<String>,
+    pub focuser:      Option<String>,
+    pub remote:       bool,
+    pub address:      String,
+    pub blob_timeout: u32, // seconds, 0 = no limit
+    pub throttle_ui:  bool, // coalesce property changes in UI
 }
 
 impl Default for IndiOptions {
     fn default() -> Self {
         Self {
-            mount:    None,
-            camera:   None,
-            guid_cam: None,
-            focuser:  None,
-            remote:   false,
-            address:  "localhost".to_string(),
+            mount:        None,
+            camera:       None,
+            guid_cam:     None,
+            focuser:      None,
+            remote:       false,
+            address:      "localhost".to_string(),
+            blob_timeout: 120,
+            throttle_ui:  false,
         }
     }
 }
@@ -102,29 +107,48 @@ impl Default for CamCtrlOptions {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(default)]
 pub struct FrameOptions {
-    pub exp_main:   f64,
-    pub exp_bias:   f64,
-    pub exp_flat:   f64,
-    pub gain:       f64,
-    pub offset:     i32,
-    pub frame_type: FrameType,
-    pub binning:    Binning,
-    pub crop:       Crop,
-    pub low_noise:  bool,
+    pub exp_main:     f64,
+    pub exp_bias:     f64,
+    pub exp_flat:     f64,
+    pub gain:         f64,
+    pub offset:       i32,
+    pub frame_type:   FrameType,
+    pub binning:      Binning,
+    pub crop:         Crop,
+    pub low_noise:    bool,
+    pub compressed:   bool, // prefer compressed image download
+
+    /// Delay (in seconds) before every next frame
+    /// while saving frames or live stacking
+    pub delay:        f64,
+
+    /// Delay (in seconds) for mount and optics to settle after
+    /// dithering or filter change. Is used instead of `delay`
+    /// for first frame after these events
+    pub settle_delay: f64,
+
+    /// Shift of cropped area from sensor center (in pixels).
+    /// Used for dithering by frame offset
+    #[serde(skip)]
+    pub crop_offset:  (i32, i32),
 }
 
 impl Default for FrameOptions {
     fn default() -> Self {
         Self {
-            exp_main:   2.0,
-            exp_bias:   0.01,
-            exp_flat:   0.5,
-            gain:       1.0,
-            offset:     0,
-            frame_type: FrameType::default(),
-            binning:    Binning::default(),
-            crop:       Crop::default(),
-            low_noise:  false,
+            exp_main:     2.0,
+            exp_bias:     0.01,
+            exp_flat:     0.5,
+            gain:         1.0,
+            offset:       0,
+            frame_type:   FrameType::default(),
+            binning:      Binning::default(),
+            crop:         Crop::default(),
+            low_noise:    false,
+            compressed:   false,
+            delay:        0.0,
+            settle_delay: 0.0,
+            crop_offset:  (0, 0),
         }
     }
 }
@@ -157,21 +181,42 @@ impl FrameOptions {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(default)]
 pub struct CalibrOptions {
-    pub dark_library_path: PathBuf,
-    pub dark_frame_en:     bool,
-    pub flat_frame_en:     bool,
-    pub flat_frame_fname:  Option<PathBuf>,
-    pub hot_pixels:        bool,
+    pub dark_library_path:  PathBuf,
+    pub dark_frame_en:      bool,
+    pub flat_frame_en:      bool,
+    pub flat_frame_fname:   Option<PathBuf>,
+    pub hot_pixels:         bool,
+    #[serde(alias = "dark_scaling")]
+    pub allow_dark_scaling: bool, // scale nearest dark by exposure and temperature if there is no exact one
+    pub hot_pixel_removal:  bool, // remove hot pixels found in master dark
+    pub hot_pixel_sigma:    f64,
+    pub dark_temp_tol:      f64, // °C, tolerance of master dark automatic selection
+    pub dark_exp_tol:       f64, // %, tolerance of master dark automatic selection
+    pub flat_auto_exp:      bool, // find exposure for flat frames before capture
+    pub flat_adu_target:    f64, // %, target median of flat frame
+    pub flat_adu_tolerance: f64, // %
+    pub flat_panel:         bool, // close dust cap and turn on flat panel for flat frames
+    pub flat_panel_bright:  f64, // brightness of flat panel in device units
 }
 
 impl Default for CalibrOptions {
     fn default() -> Self {
         Self {
-            dark_library_path: PathBuf::new(),
-            dark_frame_en:     true,
-            flat_frame_en:     false,
-            flat_frame_fname:  None,
-            hot_pixels:        true,
+            dark_library_path:  PathBuf::new(),
+            dark_frame_en:      true,
+            flat_frame_en:      false,
+            flat_frame_fname:   None,
+            hot_pixels:         true,
+            allow_dark_scaling: false,
+            hot_pixel_removal:  true,
+            hot_pixel_sigma:    5.0,
+            dark_temp_tol:      2.0,
+            dark_exp_tol:       10.0,
+            flat_auto_exp:      false,
+            flat_adu_target:    50.0,
+            flat_adu_tolerance: 5.0,
+            flat_panel:         false,
+            flat_panel_bright:  100.0,
         }
     }
 }
@@ -191,22 +236,47 @@ impl CalibrOptions {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, PartialEq)]
+pub enum RawSaveFormat {
+    #[default]
+    Internal, // as received from camera driver
+    Fits,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(default)]
 pub struct RawFrameOptions {
-    pub out_path:      PathBuf,
-    pub frame_cnt:     usize,
-    pub use_cnt:       bool,
-    pub create_master: bool,
+    pub out_path:       PathBuf,
+    pub frame_cnt:      usize,
+    pub use_cnt:        bool,
+    pub create_master:  bool,
+    pub pause_low_disk: bool,
+    pub min_free_space: f64, // in GB
+    pub stop_by_alt:    bool,
+    pub stop_alt:       f64, // in degrees
+    pub stop_at_dawn:   bool,
+    pub wait_for_dark:  bool,
+    pub dark_sun_alt:   f64, // in degrees
+    pub save_format:    RawSaveFormat,
+    pub save_wcs:       bool, // plate solve light frames and write WCS into FITS files
 }
 
 impl Default for RawFrameOptions {
     fn default() -> Self {
         Self {
-            out_path:      PathBuf::new(),
-            frame_cnt:     100,
-            use_cnt:       true,
-            create_master: true,
+            out_path:       PathBuf::new(),
+            frame_cnt:      100,
+            use_cnt:        true,
+            create_master:  true,
+            pause_low_disk: false,
+            min_free_space: 2.0,
+            stop_by_alt:    false,
+            stop_alt:       20.0,
+            stop_at_dawn:   false,
+            wait_for_dark:  false,
+            dark_sun_alt:   -18.0,
+            save_format:    RawSaveFormat::default(),
+            save_wcs:       false,
         }
     }
 }
@@ -227,6 +297,10 @@ impl RawFrameOptions {
 }
 
 
+/// Drizzle integration for live stacking (result image is upscaled)
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, PartialEq)]
+pub enum Drizzle {#[default]Off, X2}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(default)]
 pub struct LiveStackingOptions {
@@ -235,6 +309,9 @@ pub struct LiveStackingOptions {
     pub save_enabled:  bool,
     pub out_dir:       PathBuf,
     pub remove_tracks: bool,
+    pub use_min_stars: bool,
+    pub min_stars:     usize,
+    pub drizzle:       Drizzle,
 }
 
 impl Default for LiveStackingOptions {
@@ -245,6 +322,9 @@ impl Default for LiveStackingOptions {
             save_enabled:  true,
             out_dir:       PathBuf::new(),
             remove_tracks: false,
+            use_min_stars: false,
+            min_stars:     10,
+            drizzle:       Drizzle::default(),
         }
     }
 }
@@ -271,6 +351,11 @@ pub struct QualityOptions {
     pub max_fwhm:        f32,
     pub use_max_ovality: bool,
     pub max_ovality:     f32,
+    pub score_w_fwhm:    f32,
+    pub score_w_ovality: f32,
+    pub score_w_stars:   f32,
+    pub score_w_bg:      f32,
+    pub star_detection:  StarDetectionOptions,
 }
 
 impl Default for QualityOptions {
@@ -280,6 +365,11 @@ impl Default for QualityOptions {
             max_fwhm:        20.0,
             use_max_ovality: true,
             max_ovality:     1.0,
+            score_w_fwhm:    4.0,
+            score_w_ovality: 2.0,
+            score_w_stars:   2.0,
+            score_w_bg:      1.0,
+            star_detection:  StarDetectionOptions::default(),
         }
     }
 }
@@ -306,6 +396,13 @@ pub struct PreviewOptions {
     pub wb_red:      f64,
     pub wb_green:    f64,
     pub wb_blue:     f64,
+    pub crosshair:   bool,
+    pub crosshair_r: Vec<f64>, // circles radii, arc minutes
+
+    // Separate stretch for live stacking result
+    pub ls_dark_lvl:  f64,
+    pub ls_light_lvl: f64,
+    pub ls_gamma:     f64,
 
     #[serde(skip_serializing)]
     pub color:       PreviewColorMode,
@@ -328,6 +425,11 @@ impl Default for PreviewOptions {
             wb_red:        1.0,
             wb_green:      1.0,
             wb_blue:       1.0,
+            crosshair:     false,
+            crosshair_r:   vec![5.0, 15.0, 30.0],
+            ls_dark_lvl:   0.2,
+            ls_light_lvl:  0.8,
+            ls_gamma:      2.2,
             color:         PreviewColorMode::Rgb,
             widget_width:  0,
             widget_height: 0,
@@ -336,6 +438,25 @@ impl Default for PreviewOptions {
 }
 
 impl PreviewOptions {
+    /// Dark level, light level and gamma for current preview source
+    pub fn levels(&self) -> (f64, f64, f64) {
+        match self.source {
+            PreviewSource::OrigFrame =>
+                (self.dark_lvl, self.light_lvl, self.gamma),
+            PreviewSource::LiveStacking =>
+                (self.ls_dark_lvl, self.ls_light_lvl, self.ls_gamma),
+        }
+    }
+
+    pub fn levels_mut(&mut self) -> (&mut f64, &mut f64, &mut f64) {
+        match self.source {
+            PreviewSource::OrigFrame =>
+                (&mut self.dark_lvl, &mut self.light_lvl, &mut self.gamma),
+            PreviewSource::LiveStacking =>
+                (&mut self.ls_dark_lvl, &mut self.ls_light_lvl, &mut self.ls_gamma),
+        }
+    }
+
     pub fn preview_params(&self) -> PreviewParams {
         let wb = if !self.wb_auto {
             Some([self.wb_red, self.wb_green, self.wb_blue])
@@ -343,10 +464,12 @@ impl PreviewOptions {
             None
         };
 
+        let (dark_lvl, light_lvl, gamma) = self.levels();
+
         PreviewParams {
-            dark_lvl:         self.dark_lvl,
-            light_lvl:        self.light_lvl,
-            gamma:            self.gamma,
+            dark_lvl,
+            light_lvl,
+            gamma,
             pr_area_width:    self.widget_width,
             pr_area_height:   self.widget_height,
             scale:            self.scale,
@@ -356,38 +479,70 @@ impl PreviewOptions {
             wb,
         }
     }
+
+    pub fn crosshair_r_from_str(text: &str) -> Vec<f64> {
+        text.split_whitespace()
+            .filter_map(|t| t.parse::<f64>().ok())
+            .filter(|v| *v > 0.0)
+            .collect()
+    }
+
+    pub fn crosshair_r_to_str(&self) -> String {
+        self.crosshair_r.iter()
+            .map(|v| format!("{}", v))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
 }
 
+/// Direction of final focuser approach to target position
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, PartialEq)]
+pub enum FocuserDirection {#[default]Increasing, Decreasing}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(default)]
 pub struct FocuserOptions {
-    pub device:          String,
-    pub on_temp_change:  bool,
-    pub max_temp_change: f64,
-    pub on_fwhm_change:  bool,
-    pub max_fwhm_change: u32,
-    pub periodically:    bool,
-    pub period_minutes:  u32,
-    pub measures:        u32,
-    pub step:            f64,
-    pub exposure:        f64,
-    pub gain:            Gain,
+    pub device:             String,
+    pub on_temp_change:     bool,
+    pub max_temp_change:    f64,
+    pub on_fwhm_change:     bool,
+    pub max_fwhm_change:    u32,
+    pub periodically:       bool,
+    pub period_minutes:     u32,
+    pub interrupt_exp:      bool,
+    pub measures:           u32,
+    pub step:               f64,
+    pub exposure:           f64,
+    pub gain:               Gain,
+    pub min_fit_r2:         f64,
+    pub temp_comp:          bool,
+    pub temp_coeff:         f64,         // steps per °C
+    pub temp_ref:           Option<f64>, // °C at last successful autofocus
+    pub backlash_steps:     f64,
+    pub backlash_direction: FocuserDirection,
 }
 
 impl Default for FocuserOptions {
     fn default() -> Self {
         Self {
-            device:          String::new(),
-            on_temp_change:  false,
-            max_temp_change: 5.0,
-            on_fwhm_change:  false,
-            max_fwhm_change: 20,
-            periodically:    false,
-            period_minutes:  120,
-            measures:        11,
-            step:            2000.0,
-            exposure:        2.0,
-            gain:            Gain::default(),
+            device:             String::new(),
+            on_temp_change:     false,
+            max_temp_change:    5.0,
+            on_fwhm_change:     false,
+            max_fwhm_change:    20,
+            periodically:       false,
+            period_minutes:     120,
+            interrupt_exp:      false,
+            measures:           11,
+            step:               2000.0,
+            exposure:           2.0,
+            gain:               Gain::default(),
+            min_fit_r2:         0.8,
+            temp_comp:          false,
+            temp_coeff:         0.0,
+            temp_ref:           None,
+            backlash_steps:     0.0,
+            backlash_direction: FocuserDirection::default(),
         }
     }
 }
@@ -406,6 +561,7 @@ impl FocuserOptions {
 pub enum PlateSolverType {
     #[default]
     Astrometry,
+    Astap,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -417,6 +573,11 @@ pub struct PlateSolverOptions {
     pub bin: Binning,
     pub timeout: u32,
     pub blind_timeout: u32,
+    pub astap_path: String, // empty means astap_cli from PATH
+    pub spiral_search: bool, // move mount by spiral around start point if platesolve fails
+    pub spiral_max_radius: f64, // in degrees
+    pub center_tol: f64, // in arcseconds, max distance to destination after goto with platesolving
+    pub center_iters: usize, // max number of mount corrections during goto with platesolving
 }
 
 impl Default for PlateSolverOptions {
@@ -428,6 +589,11 @@ impl Default for PlateSolverOptions {
             bin: Binning::Bin2,
             timeout: 10,
             blind_timeout: 30,
+            astap_path: String::new(),
+            spiral_search: false,
+            spiral_max_radius: 3.0,
+            center_tol: 30.0,
+            center_iters: 3,
         }
     }
 }
@@ -437,24 +603,117 @@ impl Default for PlateSolverOptions {
 pub struct SiteOptions {
     pub latitude:  f64,
     pub longitude: f64,
+    pub elevation: f64, // meters
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, PartialEq)]
+pub enum MountConnectAction {
+    #[default]
+    LeaveAsIs,
+    Unpark,
+    UnparkAndTrack,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(default)]
 pub struct MountOptions {
-    pub device: String,
-    pub inv_ns: bool,
-    pub inv_we: bool,
-    pub speed:  Option<String>,
+    pub device:          String,
+    pub inv_ns:          bool,
+    pub inv_we:          bool,
+    pub speed:           Option<String>,
+    pub connect_action:  MountConnectAction,
+    pub connect_delay:   u32, // in seconds
+    pub connect_confirm: bool,
+    pub merid_stop:      bool,
+    pub merid_limit:     f64, // degrees past meridian
+    pub merid_park:      bool,
+    pub flip_recenter:   bool, // plate solve and goto pre-flip position after pier side change
+    pub auto_flip:       bool, // meridian flip during saving light frames
+    pub flip_limit:      f64, // degrees past meridian
+    pub settle_tol:      f64, // arcseconds, max coordinates change of settled mount
+    pub settle_samples:  usize, // coordinates samples (one per second) to detect mount is settled
+    pub use_pt_model:    bool, // correct goto by software pointing model
+    pub pt_models:       Vec<PointingModel>, // pointing models for every mount device
 }
 
 impl Default for MountOptions {
     fn default() -> Self {
         Self {
-            device: String::new(),
-            inv_ns: false,
-            inv_we: false,
-            speed:  None,
+            device:          String::new(),
+            inv_ns:          false,
+            inv_we:          false,
+            speed:           None,
+            connect_action:  MountConnectAction::default(),
+            connect_delay:   5,
+            connect_confirm: true,
+            merid_stop:      false,
+            merid_limit:     5.0,
+            merid_park:      false,
+            flip_recenter:   false,
+            auto_flip:       false,
+            flip_limit:      2.0,
+            settle_tol:      5.0,
+            settle_samples:  3,
+            use_pt_model:    false,
+            pt_models:       Vec::new(),
+        }
+    }
+}
+
+impl MountOptions {
+    pub fn pointing_model(&self, device: &str) -> Option<&PointingModel> {
+        self.pt_models.iter().find(|m| m.device == device)
+    }
+
+    pub fn pointing_model_mut(&mut self, device: &str) -> &mut PointingModel {
+        if let Some(index) = self.pt_models.iter().position(|m| m.device == device) {
+            return &mut self.pt_models[index];
+        }
+        self.pt_models.push(PointingModel::new(device));
+        self.pt_models.last_mut().unwrap()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct DomeOptions {
+    pub device:          String,
+    pub slaving:         bool,
+    pub slave_threshold: f64, // degrees
+    pub park_at_end:     bool,
+}
+
+impl Default for DomeOptions {
+    fn default() -> Self {
+        Self {
+            device:          String::new(),
+            slaving:         false,
+            slave_threshold: 3.0,
+            park_at_end:     false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct WeatherOptions {
+    pub device:     String,
+    pub monitoring: bool,
+    pub max_clouds: f64, // %
+    pub max_rain:   f64, // mm/h
+    pub max_wind:   f64, // km/h
+    pub park:       bool,
+}
+
+impl Default for WeatherOptions {
+    fn default() -> Self {
+        Self {
+            device:     String::new(),
+            monitoring: false,
+            max_clouds: 80.0,
+            max_rain:   0.0,
+            max_wind:   40.0,
+            park:       false,
         }
     }
 }
@@ -481,11 +740,12 @@ impl TelescopeOptions {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
 #[serde(default)]
 pub struct DeviceAndProp {
     pub name: String,
     pub prop: String, // CCD1, CCD2... or emprty for any
+    pub index: usize, // index of camera among ones with same name
 }
 
 impl DeviceAndProp {
@@ -499,50 +759,155 @@ impl DeviceAndProp {
             } else {
                 "CCD1".to_string()
             };
+            result.index = splitted
+                .next()
+                .and_then(|idx| idx.trim().strip_prefix('#'))
+                .and_then(|idx| idx.parse::<usize>().ok())
+                .map(|idx| idx.saturating_sub(1))
+                .unwrap_or(0);
         }
         result
     }
 
     pub fn to_string(&self) -> String {
         let mut result = self.name.clone();
-        if !result.is_empty() && !self.prop.is_empty() && self.prop != "CCD1" {
+        if result.is_empty() {
+            return result;
+        }
+        if self.index != 0 {
+            result += &format!(" | {} | #{}", self.prop_or_default(), self.index + 1);
+        } else if !self.prop.is_empty() && self.prop != "CCD1" {
             result += " | ";
             result += &self.prop;
         }
         result
     }
 
+    /// Property name with empty value resolved to default "CCD1"
+    pub fn prop_or_default(&self) -> &str {
+        if self.prop.is_empty() { "CCD1" } else { &self.prop }
+    }
+
     pub fn to_file_name_part(&self) -> String {
         let mut result = self.name.clone();
         if !result.is_empty() && !self.prop.is_empty() && self.prop != "CCD1" {
             result += "_";
             result += &self.prop;
         }
+        if !result.is_empty() && self.index != 0 {
+            result += &format!("_{}", self.index + 1);
+        }
         result
     }
+
+    /// Key of stored per-camera options. Unlike `==` it also tells
+    /// apart cameras with same names
+    pub fn is_same_options_key(&self, other: &Self) -> bool {
+        self == other && self.index == other.index
+    }
+}
+
+// Empty prop and "CCD1" point to the same sensor. Index is not
+// compared because INDI addresses devices only by name
+impl PartialEq for DeviceAndProp {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name &&
+        self.prop_or_default() == other.prop_or_default()
+    }
+}
+
+/// Automatic gain correction in live view mode.
+/// Keeps median of image near target value
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct LiveAutoGainOptions {
+    pub enabled:  bool,
+    pub target:   f64, // target median in % of max value
+    pub min_gain: f64,
+    pub max_gain: f64,
+}
+
+impl Default for LiveAutoGainOptions {
+    fn default() -> Self {
+        Self {
+            enabled:  false,
+            target:   20.0,
+            min_gain: 0.0,
+            max_gain: 300.0,
+        }
+    }
+}
+
+/// Gain and offset used last time for frame type
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FrameCtrlSet {
+    pub gain:   f64,
+    pub offset: i32,
+}
+
+/// Gain and offset for frame types.
+/// Darks use the same set as lights because master darks
+/// have to be taken with the same gain and offset as lights
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct FrameTypeCtrls {
+    pub lights: Option<FrameCtrlSet>,
+    pub flats:  Option<FrameCtrlSet>,
+    pub biases: Option<FrameCtrlSet>,
+}
+
+impl FrameTypeCtrls {
+    fn get_mut(&mut self, frame_type: FrameType) -> &mut Option<FrameCtrlSet> {
+        match frame_type {
+            FrameType::Flats  => &mut self.flats,
+            FrameType::Biases => &mut self.biases,
+            _                 => &mut self.lights,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(default)]
 pub struct CamOptions {
-    pub device:    Option<DeviceAndProp>,
-    pub live_view: bool,
-    pub ctrl:      CamCtrlOptions,
-    pub frame:     FrameOptions,
+    pub device:         Option<DeviceAndProp>,
+    pub live_view:      bool,
+    pub live_ag:        LiveAutoGainOptions,
+    pub ctrl:           CamCtrlOptions,
+    pub frame:          FrameOptions,
+    pub type_ctrls:     FrameTypeCtrls,
+    pub show_e_per_adu: bool, // show gain in e-/ADU near raw gain value
 }
 
 impl Default for CamOptions {
     fn default() -> Self {
         Self {
-            device:    None,
-            live_view: false,
-            ctrl:      CamCtrlOptions::default(),
-            frame:     FrameOptions::default(),
+            device:         None,
+            live_view:      false,
+            live_ag:        LiveAutoGainOptions::default(),
+            ctrl:           CamCtrlOptions::default(),
+            frame:          FrameOptions::default(),
+            type_ctrls:     FrameTypeCtrls::default(),
+            show_e_per_adu: true,
         }
     }
 }
 
 impl CamOptions {
+    /// Remembers gain and offset for current frame type and
+    /// restores ones for new frame type (if they were stored before)
+    pub fn change_frame_type(&mut self, frame_type: FrameType) {
+        let cur_set = FrameCtrlSet {
+            gain:   self.frame.gain,
+            offset: self.frame.offset,
+        };
+        *self.type_ctrls.get_mut(self.frame.frame_type) = Some(cur_set);
+        if let Some(new_set) = self.type_ctrls.get_mut(frame_type) {
+            self.frame.gain = new_set.gain;
+            self.frame.offset = new_set.offset;
+        }
+        self.frame.frame_type = frame_type;
+    }
+
     pub fn calc_active_zone_mm(
         &self,
         sensor_width:    usize,
@@ -566,6 +931,21 @@ pub enum GuidingMode {
     Disabled,
     MainCamera,
     External,
+    FrameOffset, // dithering only, by shifting cropped frame
+    SimpleDither, // dithering only, by random guide pulses without mount calibration
+}
+
+/// Last mount calibration for guiding by main camera. It is valid
+/// only for the same mount, camera, focal length, pixel size and binning.
+/// Hash of them is stored in `key`
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct SavedMountCalibr {
+    pub key:        u64,
+    pub move_x_ra:  f64,
+    pub move_y_ra:  f64,
+    pub move_x_dec: f64,
+    pub move_y_dec: f64,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -575,6 +955,11 @@ pub struct MainCamGuidingOptions {
     pub calibr_exposure: f64,
     pub calibr_gain:     Gain,
     pub dith_dist:       i32,
+    pub auto_calibr:     bool,
+    pub min_pulse:       f64, // in milliseconds
+    pub max_correction:  f64, // in pixels, 0 - no limit
+    pub save_log:        bool,
+    pub saved_calibr:    Option<SavedMountCalibr>,
 }
 
 impl Default for MainCamGuidingOptions {
@@ -584,6 +969,11 @@ impl Default for MainCamGuidingOptions {
             calibr_exposure: 2.0,
             calibr_gain:     Gain::default(),
             dith_dist:       50,
+            auto_calibr:     true,
+            min_pulse:       0.0,
+            max_correction:  50.0,
+            save_log:        false,
+            saved_calibr:    None,
         }
     }
 }
@@ -604,22 +994,56 @@ impl Default for ExtGuiderOptions {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct FrameOffsetDitherOptions {
+    pub dith_dist: i32, // in pixels
+}
+
+impl Default for FrameOffsetDitherOptions {
+    fn default() -> Self {
+        Self {
+            dith_dist: 50,
+        }
+    }
+}
+
+/// Dithering by random guide pulses of mount. Doesn't need
+/// mount calibration so it is less accurate but simple to set up
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct SimpleDitherOptions {
+    pub pulse: f64, // max guide pulse in milliseconds
+}
+
+impl Default for SimpleDitherOptions {
+    fn default() -> Self {
+        Self {
+            pulse: 1000.0,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(default)]
 pub struct GuidingOptions {
-    pub mode:        GuidingMode,
-    pub dith_period: u32,  // in minutes, 0 - do not dither
-    pub main_cam:    MainCamGuidingOptions,
-    pub ext_guider:  ExtGuiderOptions,
+    pub mode:         GuidingMode,
+    pub dith_period:  u32,  // in minutes, 0 - do not dither
+    pub main_cam:     MainCamGuidingOptions,
+    pub ext_guider:   ExtGuiderOptions,
+    pub frame_offset: FrameOffsetDitherOptions,
+    pub simple_dith:  SimpleDitherOptions,
 }
 
 impl Default for GuidingOptions {
     fn default() -> Self {
         Self {
-            mode:        GuidingMode::Disabled,
-            dith_period: 2,
-            main_cam:    MainCamGuidingOptions::default(),
-            ext_guider:  ExtGuiderOptions::default(),
+            mode:         GuidingMode::Disabled,
+            dith_period:  2,
+            main_cam:     MainCamGuidingOptions::default(),
+            ext_guider:   ExtGuiderOptions::default(),
+            frame_offset: FrameOffsetDitherOptions::default(),
+            simple_dith:  SimpleDitherOptions::default(),
         }
     }
 }
@@ -658,6 +1082,103 @@ impl Default for PloarAlignOptions {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct TargetsOptions {
+    pub skip_low: bool,
+    pub min_alt:  f64, // degrees
+}
+
+impl Default for TargetsOptions {
+    fn default() -> Self {
+        Self {
+            skip_low: true,
+            min_alt:  20.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct SequenceStep {
+    pub filter_slot: Option<usize>, // starting from 1, None = don't change filter
+    pub count:       usize,
+    pub exposure:    f64, // in seconds
+    pub gain:        Option<f64>, // None = same as in camera options
+    pub binning:     Binning,
+}
+
+impl Default for SequenceStep {
+    fn default() -> Self {
+        Self {
+            filter_slot: None,
+            count:       10,
+            exposure:    60.0,
+            gain:        None,
+            binning:     Binning::default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct SequenceOptions {
+    pub filter_wheel: String,
+    pub steps:        Vec<SequenceStep>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct FlatsSessionStep {
+    pub filter_slot: Option<usize>, // starting from 1, None = don't change filter
+    pub count:       usize,
+}
+
+impl Default for FlatsSessionStep {
+    fn default() -> Self {
+        Self {
+            filter_slot: None,
+            count:       20,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct FlatsSessionOptions {
+    pub sky_sun_alt_min: f64, // °, sky flats are taken when the Sun altitude
+    pub sky_sun_alt_max: f64, // °, is between min and max (at dusk or dawn)
+    pub steps:           Vec<FlatsSessionStep>,
+}
+
+impl Default for FlatsSessionOptions {
+    fn default() -> Self {
+        Self {
+            sky_sun_alt_min: -8.0,
+            sky_sun_alt_max: -2.0,
+            steps:           Vec::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct ModeWatchdogOptions {
+    pub enabled:    bool,
+    pub timeout:    u32, // in seconds, without exposure time
+    pub auto_abort: bool,
+}
+
+impl Default for ModeWatchdogOptions {
+    fn default() -> Self {
+        Self {
+            enabled:    true,
+            timeout:    180,
+            auto_abort: false,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Default)]
 #[serde(default)]
 pub struct Options {
@@ -671,8 +1192,14 @@ pub struct Options {
     pub focuser:      FocuserOptions,
     pub plate_solver: PlateSolverOptions,
     pub mount:        MountOptions,
+    pub dome:         DomeOptions,
+    pub weather:      WeatherOptions,
     pub telescope:    TelescopeOptions,
     pub site:         SiteOptions,
     pub guiding:      GuidingOptions,
     pub polar_align:  PloarAlignOptions,
+    pub targets:      TargetsOptions,
+    pub sequence:     SequenceOptions,
+    pub flats:        FlatsSessionOptions,
+    pub mode_wd:      ModeWatchdogOptions,
 }
@@ -0,0 +1,250 @@
+use std::{collections::HashMap, io::Read, path::PathBuf, time::Instant};
+use chrono::Utc;
+use crate::{image::{image::Image, simple_fits::*}, ui::sky_map::math::{degree_to_radian, j2000_time, radian_to_degree, radian_to_hour, EpochCvt}};
+use super::*;
+
+const DEFAULT_EXECUTABLE_FNAME: &str = "astap_cli";
+
+// Exit codes of ASTAP which mean image is just not solved
+const EXIT_CODE_NO_SOLUTION: i32 = 1;
+const EXIT_CODE_NOT_ENOUGH_STARS: i32 = 2;
+
+pub struct AstapPlateSolver {
+    executable: String,
+    child:      Option<std::process::Child>,
+    file_name:  Option<PathBuf>,
+    img_width:  usize,
+    img_height: usize,
+    start_time: Option<Instant>,
+    time_out:   u32, // in seconds
+}
+
+impl AstapPlateSolver {
+    pub fn new(executable: &str) -> Self {
+        let executable = if executable.trim().is_empty() {
+            DEFAULT_EXECUTABLE_FNAME.to_string()
+        } else {
+            executable.trim().to_string()
+        };
+        Self {
+            executable,
+            child:      None,
+            file_name:  None,
+            img_width:  0,
+            img_height: 0,
+            start_time: None,
+            time_out:   0,
+        }
+    }
+
+    fn kill_child(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            _ = child.kill();
+            _ = child.wait();
+        }
+    }
+
+    fn clear_prev_resources(&mut self) {
+        self.kill_child();
+        if let Some(file_name) = self.file_name.take() {
+            _ = std::fs::remove_file(file_name.clone());
+            _ = std::fs::remove_file(file_name.with_extension("ini"));
+            _ = std::fs::remove_file(file_name.with_extension("wcs"));
+        }
+    }
+
+    fn save_image_file(&mut self, image: &Image) -> anyhow::Result<()> {
+        self.clear_prev_resources();
+        let layer = if !image.l.is_empty() { &image.l } else { &image.g };
+        let file_name = format!("astralite_platesolve_{}.fits", rand::random::<u64>());
+        let temp_file = std::env::temp_dir().join(&file_name);
+        log::debug!("Saving image into {:?} for plate solving...", temp_file);
+        let mut file = std::fs::File::create(&temp_file)?;
+        let writer = FitsWriter::new();
+        let hdu = Header::new_2d(layer.width(), layer.height());
+        writer.write_header_and_data_u16(&mut file, &hdu, layer.as_slice())?;
+        drop(file);
+        self.file_name = Some(temp_file);
+        self.img_width = layer.width();
+        self.img_height = layer.height();
+        Ok(())
+    }
+
+    fn exec_astap(&mut self, config: &PlateSolveConfig) -> anyhow::Result<()> {
+        let file_name = self.file_name.clone().unwrap();
+        _ = std::fs::remove_file(file_name.with_extension("ini"));
+        _ = std::fs::remove_file(file_name.with_extension("wcs"));
+
+        use std::process::*;
+        let mut cmd = Command::new(&self.executable);
+        cmd.stdout(Stdio::piped());
+        cmd.stderr(Stdio::piped());
+        cmd.arg("-f").arg(&file_name);
+        cmd.arg("-fov").arg("0");
+        if let Some(crd) = &config.eq_coord {
+            cmd.arg("-ra").arg(format!("{:.6}", radian_to_hour(crd.ra)));
+            cmd.arg("-spd").arg(format!("{:.6}", radian_to_degree(crd.dec) + 90.0));
+            cmd.arg("-r").arg("10");
+        } else {
+            cmd.arg("-r").arg("180");
+        }
+        log::debug!("Running {} args={:?}", self.executable, cmd.get_args());
+        let child = cmd.spawn().map_err(|e|
+            anyhow::format_err!("{} when trying to execute {}", e.to_string(), self.executable)
+        )?;
+        self.child = Some(child);
+        self.time_out = if config.eq_coord.is_some() {
+            config.time_out
+        } else {
+            config.blind_time_out
+        };
+        self.start_time = Some(Instant::now());
+        Ok(())
+    }
+
+    fn read_ini_file(&self) -> anyhow::Result<HashMap<String, String>> {
+        let file_name = self.file_name.as_ref().unwrap().with_extension("ini");
+        let text = std::fs::read_to_string(&file_name)?;
+        log::debug!("ASTAP result file:\n{}", text);
+        let result = text
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .collect();
+        Ok(result)
+    }
+
+    fn parse_result(&self) -> anyhow::Result<PlateSolveResult> {
+        let values = self.read_ini_file()?;
+        let get_f64 = |key: &str| -> Option<f64> {
+            values.get(key).and_then(|v| v.parse::<f64>().ok())
+        };
+
+        if values.get("PLTSOLVD").map(|v| v.as_str()) != Some("T") {
+            if let Some(error) = values.get("ERROR") {
+                log::error!("ASTAP error: {}", error);
+            }
+            return Ok(PlateSolveResult::Failed);
+        }
+
+        let (Some(ra), Some(dec), Some(cdelt1), Some(cdelt2)) = (
+            get_f64("CRVAL1"), get_f64("CRVAL2"),
+            get_f64("CDELT1"), get_f64("CDELT2"),
+        ) else {
+            log::error!("Can't extract data from ASTAP result file: {:?}", values);
+            return Ok(PlateSolveResult::Failed);
+        };
+
+        let crd_j2000 = EqCoord {
+            ra:  degree_to_radian(ra),
+            dec: degree_to_radian(dec),
+        };
+
+        // convert plate solving coordinate from j2000 to now
+        let j2000 = j2000_time();
+        let time = Utc::now().naive_utc();
+        let epoch_cvt = EpochCvt::new(&j2000, &time);
+        let crd_now = epoch_cvt.convert_eq(&crd_j2000);
+
+        let result = PlateSolveOkResult {
+            crd_j2000, crd_now,
+            width:    degree_to_radian(cdelt1.abs() * self.img_width as f64),
+            height:   degree_to_radian(cdelt2.abs() * self.img_height as f64),
+            rotation: degree_to_radian(get_f64("CROTA2").unwrap_or(0.0)),
+            time:     Utc::now(),
+        };
+        Ok(PlateSolveResult::Done(result))
+    }
+}
+
+impl Drop for AstapPlateSolver {
+    fn drop(&mut self) {
+        self.clear_prev_resources();
+    }
+}
+
+impl PlateSolverIface for AstapPlateSolver {
+    fn support_stars_as_input(&self) -> bool {
+        false
+    }
+
+    fn support_coordinates(&self) -> bool {
+        true
+    }
+
+    fn start(
+        &mut self,
+        data:   &PlateSolverInData,
+        config: &PlateSolveConfig
+    ) -> anyhow::Result<()> {
+        if self.child.is_some() {
+            anyhow::bail!("AstapPlateSolver already started");
+        }
+        match data {
+            PlateSolverInData::Image(image) => {
+                self.save_image_file(image)?;
+                self.exec_astap(config)?;
+            }
+            PlateSolverInData::Stars{..} => {
+                anyhow::bail!("ASTAP doesn't support stars as input");
+            }
+        }
+        Ok(())
+    }
+
+    fn restart(&mut self, config: &PlateSolveConfig) -> anyhow::Result<()> {
+        if self.child.is_some() {
+            anyhow::bail!("AstapPlateSolver already started");
+        }
+        self.exec_astap(config)?;
+        Ok(())
+    }
+
+    fn get_result(&mut self) -> anyhow::Result<PlateSolveResult> {
+        let Some(child) = &mut self.child else {
+            anyhow::bail!("Not started!");
+        };
+
+        let exit_status = match child.try_wait() {
+            Ok(Some(status)) => status,
+            Err(e)           => return Err(e.into()),
+            _                => {
+                // ASTAP has no time limit argument
+                let elapsed = self.start_time.map(|t| t.elapsed().as_secs()).unwrap_or(0);
+                if elapsed > self.time_out as u64 {
+                    log::error!("ASTAP time out ({}s). Terminating it...", self.time_out);
+                    self.kill_child();
+                    return Ok(PlateSolveResult::Failed);
+                }
+                return Ok(PlateSolveResult::Waiting);
+            }
+        };
+
+        let mut str_output = String::new();
+        if let Some(mut output) = child.stdout.take() {
+            _ = output.read_to_string(&mut str_output);
+        }
+        log::debug!("ASTAP stdout:\n{}", str_output);
+
+        let mut str_errors = String::new();
+        if let Some(mut output) = child.stderr.take() {
+            _ = output.read_to_string(&mut str_errors);
+        }
+
+        self.child = None;
+
+        match exit_status.code() {
+            Some(0) =>
+                self.parse_result(),
+            Some(EXIT_CODE_NO_SOLUTION|EXIT_CODE_NOT_ENOUGH_STARS) =>
+                Ok(PlateSolveResult::Failed),
+            code =>
+                Err(anyhow::format_err!(
+                    "{} exited with code {}\n\n{}",
+                    self.executable,
+                    code.unwrap_or_default(),
+                    str_errors
+                )),
+        }
+    }
+}
@@ -1,7 +1,12 @@
+#![allow(dead_code)]
+
+use std::{path::Path, time::{Duration, Instant}};
+use astap::*;
 use astrometry::*;
 use chrono::{DateTime, Utc};
-use crate::{image::{image::Image, stars::Stars}, options::PlateSolverType, ui::sky_map::math::EqCoord};
+use crate::{image::{image::Image, io::*, simple_fits::{FitsReader, Header}, stars::Stars}, options::{PlateSolverOptions, PlateSolverType}, ui::sky_map::math::EqCoord};
 
+mod astap;
 mod astrometry;
 
 #[derive(Debug, Default, Clone)]
@@ -35,6 +40,39 @@ impl PlateSolveOkResult {
             radian_to_degree(self.height),
         );
     }
+
+    /// Adds WCS keywords into FITS header of image with given size
+    pub fn add_wcs_to_fits_header(&self, hdu: &mut Header, img_width: usize, img_height: usize) {
+        use crate::indi::value_to_sexagesimal_impl;
+        use crate::ui::sky_map::math::*;
+        if img_width == 0 || img_height == 0 {
+            return;
+        }
+        let ra = radian_to_degree(self.crd_now.ra);
+        let dec = radian_to_degree(self.crd_now.dec);
+        let scale_x = radian_to_degree(self.width) / img_width as f64;
+        let scale_y = radian_to_degree(self.height) / img_height as f64;
+        let (sin_rot, cos_rot) = self.rotation.sin_cos();
+
+        // RA grows from right to left
+        let cdelt1 = -scale_x;
+        let cdelt2 = scale_y;
+
+        hdu.set_str("CTYPE1", "RA---TAN");
+        hdu.set_str("CTYPE2", "DEC--TAN");
+        hdu.set_str("CUNIT1", "deg");
+        hdu.set_str("CUNIT2", "deg");
+        hdu.set_f64("CRPIX1", 0.5 * img_width as f64 + 0.5);
+        hdu.set_f64("CRPIX2", 0.5 * img_height as f64 + 0.5);
+        hdu.set_f64("CRVAL1", ra);
+        hdu.set_f64("CRVAL2", dec);
+        hdu.set_f64("CD1_1",  cdelt1 * cos_rot);
+        hdu.set_f64("CD1_2",  -cdelt2 * sin_rot);
+        hdu.set_f64("CD2_1",  cdelt1 * sin_rot);
+        hdu.set_f64("CD2_2",  cdelt2 * cos_rot);
+        hdu.set_str("OBJCTRA",  &value_to_sexagesimal_impl(radian_to_hour(self.crd_now.ra), true, false, 9, " ", " ", ""));
+        hdu.set_str("OBJCTDEC", &value_to_sexagesimal_impl(dec, true, true, 8, " ", " ", ""));
+    }
 }
 
 pub enum PlateSolveResult {
@@ -58,10 +96,12 @@ pub enum PlateSolverInData<'a> {
 }
 
 impl PlateSolver {
-    pub fn new(tp: PlateSolverType) -> Self {
-        let solver = match tp {
+    pub fn new(options: &PlateSolverOptions) -> Self {
+        let solver: Box<dyn PlateSolverIface + Sync + Send + 'static> = match options.solver {
             PlateSolverType::Astrometry =>
                 Box::new(AstrometryPlateSolver::new()),
+            PlateSolverType::Astap =>
+                Box::new(AstapPlateSolver::new(&options.astap_path)),
         };
         Self {
             solver,
@@ -95,6 +135,51 @@ impl PlateSolver {
         Ok(())
     }
 
+    /// Plate solves FITS or TIFF file. Blocks until solver
+    /// finishes or time out is reached
+    pub fn solve_file(
+        &mut self,
+        file_name: &Path,
+        config:    &PlateSolveConfig
+    ) -> anyhow::Result<PlateSolveOkResult> {
+        let mut image = Image::new_empty();
+        let ext = file_name
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        match ext.as_str() {
+            "fit"|"fits" => {
+                let mut file = std::fs::File::open(file_name)?;
+                let reader = FitsReader::new(&mut file)?;
+                load_image_from_fits_reader(&mut image, &reader, &mut file)?;
+            }
+            "tif"|"tiff" =>
+                load_image_from_tif_file(&mut image, file_name)?,
+            _ =>
+                anyhow::bail!("Format of {} is not supported", file_name.display()),
+        }
+
+        self.start(&PlateSolverInData::Image(&image), config)?;
+        drop(image);
+
+        // Solver can be restarted in blind mode after fail
+        const EXTRA_TIME: u64 = 5; // seconds
+        let max_time = config.time_out as u64 + config.blind_time_out as u64 + EXTRA_TIME;
+        let start_time = Instant::now();
+        loop {
+            match self.get_result()? {
+                PlateSolveResult::Waiting => {}
+                PlateSolveResult::Done(result) => return Ok(result),
+                PlateSolveResult::Failed => anyhow::bail!("Can't platesolve {}", file_name.display()),
+            }
+            if start_time.elapsed().as_secs() > max_time {
+                anyhow::bail!("Plate solving time out (> {}s)", max_time);
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+    }
+
     pub fn get_result(&mut self) -> anyhow::Result<PlateSolveResult> {
         let result = self.solver.get_result();
 
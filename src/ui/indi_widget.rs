@@ -1,7 +1,7 @@
 use std::{sync::Arc, time::Duration, cell::RefCell, rc::Rc};
 use gtk::{prelude::*, glib, glib::clone};
 use itertools::{Itertools, izip};
-use crate::indi;
+use crate::{indi, utils::gtk_utils};
 
 pub struct IndiWidget {
     indi:      Arc<indi::Connection>,
@@ -102,6 +102,9 @@ impl IndiWidget {
                     data.prop_changed = false;
                     data.list_changed = false;
                     Self::show_all_props(&indi, &stack_for_handler, &mut data, list_changed);
+                    if list_changed {
+                        Self::update_props_visiblity(&data);
+                    }
                 }
                 glib::ControlFlow::Continue
             })
@@ -127,6 +130,9 @@ impl IndiWidget {
             data.list_changed = false;
             data.prop_changed = false;
             Self::show_all_props(&self.indi, &self.stack, &mut data, list_changed);
+            if list_changed {
+                Self::update_props_visiblity(&data);
+            }
         }
     }
 
@@ -134,22 +140,28 @@ impl IndiWidget {
         let mut data = self.data.borrow_mut();
         if data.filter_text_lc == text { return; }
         data.filter_text_lc = text.to_string();
-        drop(data);
-        self.update_props_visiblity();
+        Self::update_props_visiblity(&data);
     }
 
-    fn update_props_visiblity(&self) {
-        let data = self.data.borrow();
+    /// Hides properties not matching filter text. Device or group
+    /// matched by name shows all its properties
+    fn update_props_visiblity(data: &UiIndiGuiData) {
+        let filter = &data.filter_text_lc;
         for device in &data.devices {
+            let device_matched = device.name.to_lowercase().contains(filter);
+            let mut device_visible = false;
             for group in &device.groups {
+                let group_matched = device_matched || group.name.to_lowercase().contains(filter);
                 let mut group_visible = false;
                 for prop in &group.props {
-                    let visible = prop.test_filter(&data.filter_text_lc);
+                    let visible = group_matched || prop.test_filter(filter);
                     prop.set_visible(visible);
                     group_visible |= visible;
                 }
                 group.scrollwin.set_visible(group_visible);
+                device_visible |= group_visible;
             }
+            device.notebook.set_visible(device_visible || device.groups.is_empty());
         }
     }
 
@@ -320,6 +332,7 @@ impl IndiWidget {
                     widgets.push(separator.into());
                     ui_group.props.push(UiIndiProp {
                         name:      indi_prop.name.to_string(),
+                        name_lc:   indi_prop.name.to_lowercase(),
                         label_lc:  caption.to_lowercase(),
                         elements:  prop_ui_elements,
                         widgets,
@@ -479,7 +492,7 @@ impl IndiWidget {
             let indi = Arc::clone(indi);
             let device_string = property.device.to_string();
             let prop_name_string = property.name.to_string();
-            set_button.connect_clicked(move |_| {
+            set_button.connect_clicked(move |btn| {
                 let elements_tmp: Vec<_> = btn_click_data
                     .iter()
                     .map(|(name, entry)| (name.as_str(), entry.text().to_string()))
@@ -488,11 +501,12 @@ impl IndiWidget {
                     .iter()
                     .map(|(elem, value)| (*elem, value.as_str()))
                     .collect();
-                _ = indi.command_set_text_property(
+                let result = indi.command_set_text_property(
                     &device_string,
                     &prop_name_string,
                     &elements
                 );
+                Self::show_set_property_error(btn, &prop_name_string, result);
             });
             widgets.push(set_button.into());
         }
@@ -555,6 +569,11 @@ impl IndiWidget {
                         spin.set_digits(prec as _);
                         spin.set_increments(1.0, 10.0);
                     },
+                    indi::NumFormat::Int { .. } => {
+                        spin.set_numeric(true);
+                        spin.set_digits(0);
+                        spin.set_increments(1.0, 10.0);
+                    },
                     indi::NumFormat::Sexagesimal { frac, .. } => {
                         spin.set_numeric(false);
                         match frac {
@@ -608,16 +627,17 @@ impl IndiWidget {
             let indi = Arc::clone(indi);
             let device_string = property.device.to_string();
             let prop_name_string = property.name.to_string();
-            set_button.connect_clicked(move |_| {
+            set_button.connect_clicked(move |btn| {
                 let elements: Vec<_> = btn_click_data
                     .iter()
                     .map(|(name, spin)| (name.as_str(), spin.value()))
                     .collect();
-                _ = indi.command_set_num_property(
+                let result = indi.command_set_num_property(
                     &device_string,
                     &prop_name_string,
                     &elements
                 );
+                Self::show_set_property_error(btn, &prop_name_string, result);
             });
             widgets.push(set_button.into());
         }
@@ -639,6 +659,7 @@ impl IndiWidget {
             .orientation(gtk::Orientation::Horizontal)
             .build();
         grid.attach(&bx, 1, *next_row, 5, 1);
+        let ro = property.permition == indi::PropPermition::RO;
         for elem in &property.elements {
             let indi = Arc::clone(indi);
             let device_string = property.device.to_string();
@@ -649,16 +670,18 @@ impl IndiWidget {
                 let button = gtk::ToggleButton::builder()
                     .label(label_text)
                     .visible(true)
+                    .sensitive(!ro)
                     .build();
                 bx.add(&button);
                 let one_btn = property.elements.len() == 1;
                 button.connect_clicked(move |btn| {
                     if !btn.is_sensitive() { return; }
-                    _ = indi.command_set_switch_property(
+                    let result = indi.command_set_switch_property(
                         &device_string,
                         &prop_name_string,
                         &[(&elem_name, true)]
                     );
+                    Self::show_set_property_error(btn, &prop_name_string, result);
                     if one_btn {
                         btn.set_active(false);
                     } else {
@@ -670,15 +693,17 @@ impl IndiWidget {
                 let button = gtk::CheckButton::builder()
                     .label(label_text)
                     .visible(true)
+                    .sensitive(!ro)
                     .build();
                 bx.add(&button);
                 button.connect_active_notify(move |btn| {
                     if !btn.is_sensitive() { return; }
-                    _ = indi.command_set_switch_property(
+                    let result = indi.command_set_switch_property(
                         &device_string,
                         &prop_name_string,
                         &[(&elem_name, btn.is_active())]
                     );
+                    Self::show_set_property_error(btn, &prop_name_string, result);
                 });
                 UiIndiPropElemData::Switch(UiIndiPropSwithElem::Check(button))
             };
@@ -694,6 +719,24 @@ impl IndiWidget {
         result
     }
 
+    fn show_set_property_error(
+        widget:    &impl IsA<gtk::Widget>,
+        prop_name: &str,
+        result:    indi::Result<()>
+    ) {
+        let Err(err) = result else { return; };
+        log::error!("Setting property {} failed: {}", prop_name, err);
+        let Some(window) = widget.toplevel()
+            .and_then(|w| w.downcast::<gtk::Window>().ok()) else {
+            return;
+        };
+        gtk_utils::show_error_message(
+            &window,
+            "Property error",
+            &format!("Can't set property {}:\n{}", prop_name, err)
+        );
+    }
+
     fn create_blob_property_ui(
         _indi:    &Arc<indi::Connection>,
         property: &indi::Property,
@@ -826,6 +869,7 @@ impl IndiWidget {
         indi_prop: &indi::Property,
         _rule:     &indi::SwitchRule,
     ) {
+        let ro = indi_prop.permition == indi::PropPermition::RO;
         for ui_elem in &ui_prop.elements {
             let indi_elem = indi_prop.elements.iter().find(|p| p.name == ui_elem.name);
             let Some(indi_elem) = indi_elem else { continue; };
@@ -841,16 +885,16 @@ impl IndiWidget {
                     if button.is_active() != *value {
                         button.set_sensitive(false);
                         button.set_active(*value);
-                        button.set_sensitive(true);
+                        button.set_sensitive(!ro);
                     }
-                    if !button.is_sensitive() {
+                    if !ro && !button.is_sensitive() {
                         button.set_sensitive(true);
                     }
                 }
                 UiIndiPropSwithElem::Check(check) => {
                     check.set_sensitive(false);
                     check.set_active(*value);
-                    check.set_sensitive(true);
+                    check.set_sensitive(!ro);
                 },
             }
         }
@@ -904,6 +948,7 @@ struct UiIndiPropsGroup {
 
 struct UiIndiProp {
     name:       String,
+    name_lc:    String,
     label_lc:   String,
     elements:   Vec<UiIndiPropElem>,
     widgets:    Vec<gtk::Widget>,
@@ -919,7 +964,8 @@ impl UiIndiProp {
     }
 
     fn test_filter(&self, filter_text_lc: &str) -> bool {
-        if self.label_lc.contains(filter_text_lc) {
+        if self.label_lc.contains(filter_text_lc)
+        || self.name_lc.contains(filter_text_lc) {
             return true;
         }
         for elem in &self.elements {
@@ -941,6 +987,7 @@ struct UiIndiPropElem {
 impl UiIndiPropElem {
     fn test_filter(&self, filter_text_lc: &str) -> bool {
         self.label_lc.contains(filter_text_lc)
+        || self.name.to_lowercase().contains(filter_text_lc)
     }
 }
 
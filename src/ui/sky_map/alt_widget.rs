@@ -7,6 +7,87 @@ use crate::{utils::gtk_utils::{self, *}, utils::math::linear_interpolate};
 
 use super::{data::*, math::*, solar_system::*};
 
+/// Visibility of object during night (from sunset to sunrise)
+pub struct NightVisibility {
+    pub sunset:        NaiveDateTime, // UTC
+    pub sunrise:       NaiveDateTime, // UTC
+    pub transit:       Option<NaiveDateTime>, // meridian transit during night
+    pub above_min_alt: Vec<(NaiveDateTime, NaiveDateTime)>, // periods when object is above `min_alt`
+}
+
+/// Calculates visibility of `crd` from sunset of `date` to next sunrise.
+/// `date` is local date of evening. Returns `None` if sun doesn't set
+pub fn calc_night_visibility(
+    crd:      &EqCoord,
+    observer: &Observer,
+    date:     NaiveDate,
+    min_alt:  f64, // in radian
+) -> Option<NightVisibility> {
+    const STEP_MINUTES: i64 = 5;
+    const STEPS_PER_DAY: i64 = 24 * 60 / STEP_MINUTES;
+    let sunset_alt = degree_to_radian(-0.833);
+
+    let sun_alt = |time: &NaiveDateTime| {
+        let cvt = EqToSphereCvt::new(observer.longitude, observer.latitude, time);
+        let sun_crd = mini_sun(calc_julian_centuries(time));
+        HorizCoord::from_sphere_pt(&cvt.eq_to_sphere(&sun_crd)).alt
+    };
+
+    // local noon by mean solar time
+    let noon_offset = (12.0 - radian_to_hour(observer.longitude)) * 3600.0;
+    let noon = date.and_hms_opt(0, 0, 0)?
+        .checked_add_signed(chrono::Duration::seconds(noon_offset as i64))?;
+    let step_time = |i: i64| noon + chrono::Duration::minutes(i * STEP_MINUTES);
+
+    let sunset = if sun_alt(&noon) < sunset_alt {
+        noon // polar night
+    } else {
+        let i = (1..=STEPS_PER_DAY).find(|&i| sun_alt(&step_time(i)) < sunset_alt)?;
+        step_time(i)
+    };
+    let sunset_step = (sunset - noon).num_minutes() / STEP_MINUTES;
+    let sunrise = (sunset_step+1..=STEPS_PER_DAY)
+        .find(|&i| sun_alt(&step_time(i)) >= sunset_alt)
+        .map(step_time)
+        .unwrap_or_else(|| step_time(STEPS_PER_DAY));
+
+    let mut transit = None;
+    let mut above_min_alt = Vec::new();
+    let mut above_start = None;
+    let mut prev_hour_angle: Option<f64> = None;
+    let mut time = sunset;
+    while time <= sunrise {
+        let cvt = EqToSphereCvt::new(observer.longitude, observer.latitude, &time);
+        let alt = HorizCoord::from_sphere_pt(&cvt.eq_to_sphere(crd)).alt;
+
+        let lst = calc_sidereal_time(&time) + observer.longitude;
+        let mut hour_angle = f64::rem_euclid(lst - crd.ra, 2.0 * PI);
+        if hour_angle > PI {
+            hour_angle -= 2.0 * PI;
+        }
+        if let Some(prev_hour_angle) = prev_hour_angle {
+            if prev_hour_angle < 0.0 && hour_angle >= 0.0 && hour_angle - prev_hour_angle < PI {
+                let part = -prev_hour_angle / (hour_angle - prev_hour_angle);
+                let seconds = (part * (60 * STEP_MINUTES) as f64) as i64;
+                transit = Some(time - chrono::Duration::minutes(STEP_MINUTES) + chrono::Duration::seconds(seconds));
+            }
+        }
+        prev_hour_angle = Some(hour_angle);
+
+        if alt >= min_alt {
+            above_start.get_or_insert(time);
+        } else if let Some(start) = above_start.take() {
+            above_min_alt.push((start, time));
+        }
+        time += chrono::Duration::minutes(STEP_MINUTES);
+    }
+    if let Some(start) = above_start {
+        above_min_alt.push((start, sunrise));
+    }
+
+    Some(NightVisibility { sunset, sunrise, transit, above_min_alt })
+}
+
 pub fn paint_altitude_by_time(
     area:     &gtk::DrawingArea,
     cr:       &cairo::Context,
@@ -14,6 +95,7 @@ pub fn paint_altitude_by_time(
     dt_local: NaiveDateTime,
     observer: &Observer,
     crd:      &Option<EqCoord>,
+    min_alt:  Option<f64>, // altitude limit
 ) -> anyhow::Result<()> {
     let (_, dpmm_y) = gtk_utils::get_widget_dpmm(area)
         .unwrap_or((DEFAULT_DPMM, DEFAULT_DPMM));
@@ -119,8 +201,11 @@ pub fn paint_altitude_by_time(
 
     // Altitude plot
 
+    let alt_to_y = |alt: f64| linear_interpolate(alt, 0.0, 0.5 * PI, height, legend_height);
     let mut max_alt = None;
-    let mut min_alt = None;
+    let mut min_alt_value = None;
+    let mut alt_points = Vec::new();
+    let mut night_steps_above_limit = 0;
     if let Some(crd) = crd {
         for i in STEPS*PAST_HOUR..=STEPS*FUTU_HOUR {
             let hour_diff = chrono::Duration::minutes(60 * i / STEPS);
@@ -134,9 +219,12 @@ pub fn paint_altitude_by_time(
                 max_alt = max_alt
                     .map(|v| f64::max(v, horiz_crd.alt))
                     .or_else(|| Some(horiz_crd.alt));
-                min_alt = min_alt
+                min_alt_value = min_alt_value
                     .map(|v| f64::min(v, horiz_crd.alt))
                     .or_else(|| Some(horiz_crd.alt));
+                if min_alt.map(|min_alt| horiz_crd.alt >= min_alt).unwrap_or(false) {
+                    night_steps_above_limit += 1;
+                }
             }
             let x = linear_interpolate(
                 i as f64,
@@ -145,14 +233,68 @@ pub fn paint_altitude_by_time(
                 0.0,
                 width
             );
-            let y = linear_interpolate(horiz_crd.alt, 0.0, 0.5 * PI, height, legend_height);
-            if i == STEPS*PAST_HOUR { cr.move_to(x, y); } else { cr.line_to(x, y); }
+            alt_points.push((x, alt_to_y(horiz_crd.alt)));
         }
     }
 
+    let draw_alt_points = || {
+        for (i, (x, y)) in alt_points.iter().enumerate() {
+            if i == 0 { cr.move_to(*x, *y); } else { cr.line_to(*x, *y); }
+        }
+    };
+
     cr.set_line_width(f64::max(0.5 * dpmm_y, 2.0));
-    cr.set_source_rgba(0.0, 1.0, 0.0, 0.6);
-    cr.stroke()?;
+    if let Some(min_alt) = min_alt {
+        // below altitude limit
+        draw_alt_points();
+        cr.set_source_rgba(1.0, 0.3, 0.3, 0.6);
+        cr.stroke()?;
+
+        // above altitude limit
+        let limit_y = alt_to_y(min_alt);
+        cr.save()?;
+        cr.rectangle(0.0, legend_height, width, limit_y - legend_height);
+        cr.clip();
+        draw_alt_points();
+        cr.set_source_rgba(0.0, 1.0, 0.0, 0.6);
+        cr.stroke()?;
+        cr.restore()?;
+
+        cr.move_to(0.0, limit_y);
+        cr.line_to(width, limit_y);
+        cr.set_line_width(1.0);
+        cr.set_dash(&[4.0, 2.0], 0.0);
+        cr.set_source_rgba(1.0, 0.3, 0.3, 0.8);
+        cr.stroke()?;
+        cr.set_dash(&[], 0.0);
+    } else {
+        draw_alt_points();
+        cr.set_source_rgba(0.0, 1.0, 0.0, 0.6);
+        cr.stroke()?;
+    }
+
+    // Transit time
+
+    let transit_time = crd.as_ref().map(|crd| {
+        let lst = calc_sidereal_time(&dt) + observer.longitude;
+        let mut hour_angle = f64::rem_euclid(lst - crd.ra, 2.0 * PI);
+        if hour_angle > PI {
+            hour_angle -= 2.0 * PI;
+        }
+        const SIDEREAL_DAY_HOURS: f64 = 23.9344696;
+        let hour_diff = -SIDEREAL_DAY_HOURS * hour_angle / (2.0 * PI);
+        let x = linear_interpolate(hour_diff, PAST_HOUR as f64, FUTU_HOUR as f64, 0.0, width);
+        let pt_diff = chrono::Duration::seconds((60.0 * 60.0 * hour_diff) as i64);
+        let time_local = dt_local.checked_add_signed(pt_diff).unwrap_or(dt_local);
+        (x, time_local)
+    });
+    if let Some((x, _)) = transit_time {
+        cr.move_to(x, legend_height);
+        cr.line_to(x, height);
+        cr.set_line_width(1.0);
+        cr.set_source_rgba(0.0, 1.0, 0.0, 0.6);
+        cr.stroke()?;
+    }
 
     // hours scale
 
@@ -182,13 +324,46 @@ pub fn paint_altitude_by_time(
     // Text
 
     let mut text = String::new();
-    if let (Some(max_alt), Some(min_alt)) = (max_alt, min_alt) {
+    if let (Some(max_alt), Some(min_alt)) = (max_alt, min_alt_value) {
         text += &format!(
             "Altutude: {:.1}..{:.1}°",
             radian_to_degree(min_alt),
             radian_to_degree(max_alt)
         );
     }
+    if min_alt.is_some() && crd.is_some() {
+        let minutes = 60 * night_steps_above_limit / STEPS;
+        text += &format!(" Above limit: {}h{:02}m", minutes / 60, minutes % 60);
+    }
+    if let Some((_, time_local)) = transit_time {
+        text += &format!(" Transit: {}", time_local.format("%H:%M"));
+    }
+    if let (Some(crd), Some(min_alt)) = (crd, min_alt) {
+        // night which contains `dt` or next one
+        let solar_time_offset = chrono::Duration::seconds((radian_to_hour(observer.longitude) * 3600.0) as i64);
+        let night_date = (dt + solar_time_offset - chrono::Duration::hours(12)).date();
+        let local_offset = dt_local - dt;
+        if let Some(visibility) = calc_night_visibility(crd, observer, night_date, min_alt) {
+            let periods = visibility.above_min_alt.iter()
+                .map(|(start, end)| format!(
+                    "{}-{}",
+                    (*start + local_offset).format("%H:%M"),
+                    (*end + local_offset).format("%H:%M")
+                ))
+                .collect::<Vec<_>>();
+            text += &format!(
+                " Night: {}-{}",
+                (visibility.sunset + local_offset).format("%H:%M"),
+                (visibility.sunrise + local_offset).format("%H:%M")
+            );
+            if !periods.is_empty() {
+                text += &format!(" Visible: {}", periods.join(", "));
+                if let Some(transit) = visibility.transit {
+                    text += &format!(" (transit {})", (transit + local_offset).format("%H:%M"));
+                }
+            }
+        }
+    }
     if let Some(max_moon_phase) = max_moon_phase {
         text += &format!(" Moon phase = {:.0}%", 100.0 * max_moon_phase);
     }
@@ -206,4 +381,41 @@ pub fn paint_altitude_by_time(
     cr.stroke()?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_calc_night_visibility() {
+    let observer = Observer {
+        latitude:  degree_to_radian(55.75),
+        longitude: degree_to_radian(37.6),
+    };
+    let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+
+    // circumpolar object is visible all night
+    let crd = EqCoord { ra: 0.5, dec: degree_to_radian(89.0) };
+    let vis = calc_night_visibility(&crd, &observer, date, degree_to_radian(30.0)).unwrap();
+    let night_hours = (vis.sunrise - vis.sunset).num_minutes() as f64 / 60.0;
+    assert!(night_hours > 15.5 && night_hours < 17.0);
+    assert_eq!(vis.above_min_alt, vec![(vis.sunset, vis.sunrise)]);
+
+    // object which never rises
+    let crd = EqCoord { ra: 0.5, dec: degree_to_radian(-80.0) };
+    let vis = calc_night_visibility(&crd, &observer, date, 0.0).unwrap();
+    assert!(vis.above_min_alt.is_empty());
+
+    // object with transit at 21:00 UTC
+    let transit_time = date.and_hms_opt(21, 0, 0).unwrap();
+    let ra = f64::rem_euclid(calc_sidereal_time(&transit_time) + observer.longitude, 2.0 * PI);
+    let crd = EqCoord { ra, dec: degree_to_radian(20.0) };
+    let vis = calc_night_visibility(&crd, &observer, date, degree_to_radian(30.0)).unwrap();
+    let transit = vis.transit.unwrap();
+    assert!((transit - transit_time).num_minutes().abs() <= 1);
+    assert_eq!(vis.above_min_alt.len(), 1);
+    let (start, end) = vis.above_min_alt[0];
+    assert!(start < transit && transit < end);
+
+    // polar day
+    let observer = Observer { latitude: degree_to_radian(75.0), ..observer };
+    let date = NaiveDate::from_ymd_opt(2024, 6, 21).unwrap();
+    assert!(calc_night_visibility(&crd, &observer, date, 0.0).is_none());
+}
@@ -313,6 +313,25 @@ pub fn hour_to_radian(hour: f64) -> f64 {
     PI * hour / 12.0
 }
 
+/// Airmass by Kasten and Young formula. `alt` is in radian.
+/// Returns NaN if object is below horizon
+pub fn airmass(alt: f64) -> f64 {
+    if alt <= 0.0 {
+        return f64::NAN;
+    }
+    let alt_deg = radian_to_degree(alt);
+    1.0 / (f64::sin(alt) + 0.50572 * f64::powf(alt_deg + 6.07995, -1.6364))
+}
+
+#[test]
+fn test_airmass() {
+    assert!(f64::abs(airmass(degree_to_radian(90.0)) - 1.0) < 0.001);
+    assert!(f64::abs(airmass(degree_to_radian(30.0)) - 1.995) < 0.01);
+    assert!(airmass(degree_to_radian(1.0)) < 40.0);
+    assert!(airmass(0.0).is_nan());
+    assert!(airmass(degree_to_radian(-10.0)).is_nan());
+}
+
 pub fn calc_julian_day(date: &NaiveDate) -> i64 {
     let mon = date.month() as i64;
     let day = date.day() as i64;
@@ -4,7 +4,7 @@ pub mod painter;
 pub mod widget;
 pub mod math;
 pub mod utils;
+pub mod solar_system;
 
 mod consts;
-mod solar_system;
 mod perspective_painter;
@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 
 use std::f64::consts::PI;
+use chrono::NaiveDateTime;
 use super::math::*;
 
 // t = (JD - 2451545) / 36525
@@ -63,6 +64,45 @@ pub fn mini_moon(t: f64) -> EqCoord {
 }
 
 
+pub struct MoonInfo {
+    pub crd:         EqCoord, // equator and equinox of date
+    pub illuminated: f64, // illuminated fraction 0..1
+}
+
+pub fn calc_moon_info(utc_time: &NaiveDateTime) -> MoonInfo {
+    let julian_centuries = calc_julian_centuries(utc_time);
+    MoonInfo {
+        crd:         mini_moon(julian_centuries),
+        illuminated: moon_phase(julian_centuries),
+    }
+}
+
+#[test]
+fn test_calc_moon_info() {
+    use chrono::NaiveDate;
+    let time = |d, h, m| NaiveDate::from_ymd_opt(2024, 1, d).unwrap().and_hms_opt(h, m, 0).unwrap();
+    let sun_separation = |time: &NaiveDateTime, moon: &MoonInfo| {
+        let sun_crd = mini_sun(calc_julian_centuries(time));
+        radian_to_degree(EqCoord::angle_between(&sun_crd, &moon.crd))
+    };
+
+    // full moon
+    let full_moon_time = time(25, 17, 54);
+    let moon = calc_moon_info(&full_moon_time);
+    assert!(moon.illuminated > 0.99);
+    assert!(sun_separation(&full_moon_time, &moon) > 170.0);
+
+    // new moon
+    let new_moon_time = time(11, 11, 57);
+    let moon = calc_moon_info(&new_moon_time);
+    assert!(moon.illuminated < 0.01);
+    assert!(sun_separation(&new_moon_time, &moon) < 10.0);
+
+    // first quarter
+    let moon = calc_moon_info(&time(18, 3, 53));
+    assert!(f64::abs(moon.illuminated - 0.5) < 0.02);
+}
+
 // from https://celestialprogramming.com/meeus-illuminated_fraction_of_the_moon.html
 
 pub fn moon_phase(t: f64) -> f64 {
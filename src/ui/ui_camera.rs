@@ -2,7 +2,7 @@ use std::{rc::Rc, sync::*, cell::{RefCell, Cell}};
 use gtk::{cairo, glib::{self, clone}, prelude::*};
 use serde::{Serialize, Deserialize};
 use crate::{
-    core::{consts::*, core::*, events::*, frame_processing::*},
+    core::{consts::*, core::*, events::*, frame_processing::*, gain_advice::*, mode_flats_session::*, mode_sequence::*},
     image::{info::*, raw::FrameType},
     indi,
     options::*,
@@ -37,6 +37,7 @@ pub fn init_ui(
         conn_state:         RefCell::new(indi::ConnState::Disconnected),
         indi_evt_conn:      RefCell::new(None),
         closed:             Cell::new(false),
+        prop_throttler:     PropChangeThrottler::new(),
         full_screen_mode:   Cell::new(false),
         self_:              RefCell::new(None),
     });
@@ -142,6 +143,7 @@ struct CameraUi {
     conn_state:         RefCell<indi::ConnState>,
     indi_evt_conn:      RefCell<Option<indi::Subscription>>,
     closed:             Cell<bool>,
+    prop_throttler:     PropChangeThrottler,
     full_screen_mode:   Cell<bool>,
     self_:              RefCell<Option<Rc<CameraUi>>>,
 }
@@ -175,6 +177,18 @@ impl CameraUi {
 
         let spb_offset = self.builder.object::<gtk::SpinButton>("spb_offset").unwrap();
         spb_offset.set_range(0.0, 1_000_000.0);
+
+        let spb_live_ag_target = self.builder.object::<gtk::SpinButton>("spb_live_ag_target").unwrap();
+        spb_live_ag_target.set_range(1.0, 90.0);
+        spb_live_ag_target.set_digits(0);
+        spb_live_ag_target.set_increments(1.0, 10.0);
+
+        for spb_name in ["spb_live_ag_min", "spb_live_ag_max"] {
+            let spb = self.builder.object::<gtk::SpinButton>(spb_name).unwrap();
+            spb.set_range(0.0, 1_000_000.0);
+            spb.set_digits(0);
+            spb.set_increments(10.0, 100.0);
+        }
     }
 
     fn init_raw_widgets(&self) {
@@ -182,6 +196,71 @@ impl CameraUi {
         spb_raw_frames_cnt.set_range(1.0, 100_000.0);
         spb_raw_frames_cnt.set_digits(0);
         spb_raw_frames_cnt.set_increments(10.0, 100.0);
+
+        let spb_min_free_space = self.builder.object::<gtk::SpinButton>("spb_min_free_space").unwrap();
+        spb_min_free_space.set_range(0.1, 1000.0);
+        spb_min_free_space.set_digits(1);
+        spb_min_free_space.set_increments(0.5, 5.0);
+
+        let spb_frame_delay = self.builder.object::<gtk::SpinButton>("spb_frame_delay").unwrap();
+        spb_frame_delay.set_range(0.0, 600.0);
+        spb_frame_delay.set_digits(0);
+        spb_frame_delay.set_increments(1.0, 10.0);
+
+        let spb_settle_delay = self.builder.object::<gtk::SpinButton>("spb_settle_delay").unwrap();
+        spb_settle_delay.set_range(0.0, 600.0);
+        spb_settle_delay.set_digits(0);
+        spb_settle_delay.set_increments(1.0, 10.0);
+
+        let spb_stop_alt = self.builder.object::<gtk::SpinButton>("spb_stop_alt").unwrap();
+        spb_stop_alt.set_range(0.0, 90.0);
+        spb_stop_alt.set_digits(0);
+        spb_stop_alt.set_increments(1.0, 10.0);
+
+        let spb_dark_sun_alt = self.builder.object::<gtk::SpinButton>("spb_dark_sun_alt").unwrap();
+        spb_dark_sun_alt.set_range(-30.0, 0.0);
+        spb_dark_sun_alt.set_digits(0);
+        spb_dark_sun_alt.set_increments(1.0, 6.0);
+
+        let spb_sky_flat_sun_min = self.builder.object::<gtk::SpinButton>("spb_sky_flat_sun_min").unwrap();
+        spb_sky_flat_sun_min.set_range(-18.0, 10.0);
+        spb_sky_flat_sun_min.set_digits(0);
+        spb_sky_flat_sun_min.set_increments(1.0, 5.0);
+
+        let spb_sky_flat_sun_max = self.builder.object::<gtk::SpinButton>("spb_sky_flat_sun_max").unwrap();
+        spb_sky_flat_sun_max.set_range(-18.0, 10.0);
+        spb_sky_flat_sun_max.set_digits(0);
+        spb_sky_flat_sun_max.set_increments(1.0, 5.0);
+
+        let spb_hot_pixel_sigma = self.builder.object::<gtk::SpinButton>("spb_hot_pixel_sigma").unwrap();
+        spb_hot_pixel_sigma.set_range(2.0, 50.0);
+        spb_hot_pixel_sigma.set_digits(1);
+        spb_hot_pixel_sigma.set_increments(0.5, 5.0);
+
+        let spb_dark_temp_tol = self.builder.object::<gtk::SpinButton>("spb_dark_temp_tol").unwrap();
+        spb_dark_temp_tol.set_range(0.0, 20.0);
+        spb_dark_temp_tol.set_digits(1);
+        spb_dark_temp_tol.set_increments(0.5, 2.0);
+
+        let spb_dark_exp_tol = self.builder.object::<gtk::SpinButton>("spb_dark_exp_tol").unwrap();
+        spb_dark_exp_tol.set_range(0.0, 100.0);
+        spb_dark_exp_tol.set_digits(0);
+        spb_dark_exp_tol.set_increments(1.0, 10.0);
+
+        let spb_flat_adu_target = self.builder.object::<gtk::SpinButton>("spb_flat_adu_target").unwrap();
+        spb_flat_adu_target.set_range(10.0, 90.0);
+        spb_flat_adu_target.set_digits(0);
+        spb_flat_adu_target.set_increments(1.0, 10.0);
+
+        let spb_flat_adu_tol = self.builder.object::<gtk::SpinButton>("spb_flat_adu_tol").unwrap();
+        spb_flat_adu_tol.set_range(1.0, 20.0);
+        spb_flat_adu_tol.set_digits(0);
+        spb_flat_adu_tol.set_increments(1.0, 5.0);
+
+        let spb_flat_panel_bright = self.builder.object::<gtk::SpinButton>("spb_flat_panel_bright").unwrap();
+        spb_flat_panel_bright.set_range(0.0, 65535.0);
+        spb_flat_panel_bright.set_digits(0);
+        spb_flat_panel_bright.set_increments(1.0, 10.0);
     }
 
     fn init_live_stacking_widgets(&self) {
@@ -189,6 +268,11 @@ impl CameraUi {
         spb_live_minutes.set_range(1.0, 60.0);
         spb_live_minutes.set_digits(0);
         spb_live_minutes.set_increments(1.0, 10.0);
+
+        let spb_live_min_stars = self.builder.object::<gtk::SpinButton>("spb_live_min_stars").unwrap();
+        spb_live_min_stars.set_range(1.0, 1000.0);
+        spb_live_min_stars.set_digits(0);
+        spb_live_min_stars.set_increments(1.0, 10.0);
     }
 
     fn init_frame_quality_widgets(&self) {
@@ -201,6 +285,33 @@ impl CameraUi {
         spb_max_oval.set_range(0.2, 2.0);
         spb_max_oval.set_digits(1);
         spb_max_oval.set_increments(0.1, 1.0);
+
+        for spb_name in ["spb_qscore_w_fwhm", "spb_qscore_w_oval", "spb_qscore_w_stars", "spb_qscore_w_bg"] {
+            let spb = self.builder.object::<gtk::SpinButton>(spb_name).unwrap();
+            spb.set_range(0.0, 10.0);
+            spb.set_digits(1);
+            spb.set_increments(0.5, 1.0);
+        }
+
+        let spb_star_det_thres = self.builder.object::<gtk::SpinButton>("spb_star_det_thres").unwrap();
+        spb_star_det_thres.set_range(10.0, 1000.0);
+        spb_star_det_thres.set_digits(0);
+        spb_star_det_thres.set_increments(10.0, 100.0);
+
+        let spb_star_det_min_px = self.builder.object::<gtk::SpinButton>("spb_star_det_min_px").unwrap();
+        spb_star_det_min_px.set_range(1.0, 100.0);
+        spb_star_det_min_px.set_digits(0);
+        spb_star_det_min_px.set_increments(1.0, 10.0);
+
+        let spb_star_det_max_px = self.builder.object::<gtk::SpinButton>("spb_star_det_max_px").unwrap();
+        spb_star_det_max_px.set_range(4.0, 1024.0);
+        spb_star_det_max_px.set_digits(0);
+        spb_star_det_max_px.set_increments(10.0, 100.0);
+
+        let spb_star_det_satur = self.builder.object::<gtk::SpinButton>("spb_star_det_satur").unwrap();
+        spb_star_det_satur.set_range(50.0, 100.0);
+        spb_star_det_satur.set_digits(0);
+        spb_star_det_satur.set_increments(1.0, 5.0);
     }
 
     fn connect_common_events(self: &Rc<Self>) {
@@ -233,9 +344,14 @@ impl CameraUi {
         let ui = gtk_utils::UiHelper::new_from_builder(&self.builder);
         gtk_utils::connect_action   (&self.window, self, "take_shot",              Self::handler_action_take_shot);
         gtk_utils::connect_action   (&self.window, self, "stop_shot",              Self::handler_action_stop_shot);
+        gtk_utils::connect_action   (&self.window, self, "take_shot_or_stop",      Self::handler_action_take_shot_or_stop);
+        gtk_utils::connect_action   (&self.window, self, "toggle_live_view",       Self::handler_action_toggle_live_view);
+        gtk_utils::connect_action   (&self.window, self, "reset_camera",           Self::handler_action_reset_camera);
         gtk_utils::connect_action_rc(&self.window, self, "start_save_raw_frames",  Self::handler_action_start_save_raw_frames);
         gtk_utils::connect_action   (&self.window, self, "stop_save_raw_frames",   Self::handler_action_stop_save_raw_frames);
         gtk_utils::connect_action   (&self.window, self, "continue_save_raw",      Self::handler_action_continue_save_raw_frames);
+        gtk_utils::connect_action_rc(&self.window, self, "run_sequence",           Self::handler_action_run_sequence);
+        gtk_utils::connect_action_rc(&self.window, self, "run_flats_session",      Self::handler_action_run_flats_session);
         gtk_utils::connect_action_rc(&self.window, self, "start_live_stacking",    Self::handler_action_start_live_stacking);
         gtk_utils::connect_action   (&self.window, self, "stop_live_stacking",     Self::handler_action_stop_live_stacking);
         gtk_utils::connect_action   (&self.window, self, "continue_live_stacking", Self::handler_action_continue_live_stacking);
@@ -245,7 +361,7 @@ impl CameraUi {
             let Ok(mut options) = self_.options.try_write() else { return; };
             let Some(cur_id) = cb.active_id() else { return; };
             let new_device = DeviceAndProp::new(&cur_id);
-            if options.cam.device.as_ref() == Some(&new_device) {
+            if options.cam.device.as_ref().is_some_and(|dev| dev.is_same_options_key(&new_device)) {
                 return;
             }
 
@@ -261,6 +377,19 @@ impl CameraUi {
 
             self_.select_options_for_camera(&new_device, &mut options);
 
+            // Offset scale differs for different cameras so
+            // value from previous one can be out of range
+
+            if let Ok(offset_prop) = self_.indi.camera_get_offset_prop_value(&new_device.name) {
+                let offset = options.cam.frame.offset as f64;
+                if offset < offset_prop.min || offset > offset_prop.max {
+                    self_.core.event_subscriptions().notify(Event::Warning(format!(
+                        "Offset {} is out of range {}..{} of camera {} and will be corrected",
+                        offset, offset_prop.min, offset_prop.max, new_device.name
+                    )));
+                }
+            }
+
             // Assign new camera name
 
             options.cam.device = Some(new_device.clone());
@@ -287,7 +416,7 @@ impl CameraUi {
         cb_frame_mode.connect_active_id_notify(clone!(@weak self as self_ => move |cb| {
             let Ok(mut options) = self_.options.try_write() else { return; };
             let frame_type = FrameType::from_active_id(cb.active_id().as_deref());
-            options.cam.frame.frame_type = frame_type;
+            options.cam.change_frame_type(frame_type);
             drop(options);
             self_.correct_widgets_props();
         }));
@@ -334,13 +463,38 @@ impl CameraUi {
             self_.handler_live_view_changed();
         }));
 
+        let chb_video_stream = bldr.object::<gtk::CheckButton>("chb_video_stream").unwrap();
+        chb_video_stream.connect_active_notify(clone!(@weak self as self_ => move |chb| {
+            self_.handler_video_stream_changed(chb.is_active());
+        }));
+
+        let chb_live_ag = bldr.object::<gtk::CheckButton>("chb_live_ag").unwrap();
+        chb_live_ag.connect_active_notify(clone!(@weak self as self_ => move |_| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.read_live_auto_gain(&self_.builder);
+            drop(options);
+            self_.correct_widgets_props();
+        }));
+
+        for spb_name in ["spb_live_ag_target", "spb_live_ag_min", "spb_live_ag_max"] {
+            let spb = bldr.object::<gtk::SpinButton>(spb_name).unwrap();
+            spb.connect_value_changed(clone!(@weak self as self_ => move |_| {
+                let Ok(mut options) = self_.options.try_write() else { return; };
+                options.read_live_auto_gain(&self_.builder);
+            }));
+        }
+
         let cb_frame_mode = bldr.object::<gtk::ComboBoxText>("cb_frame_mode").unwrap();
         cb_frame_mode.connect_active_id_notify(clone!(@weak self as self_ => move |cb| {
-            let Ok(mut options) = self_.options.try_write() else { return; };
-            let frame_type = FrameType::from_active_id(cb.active_id().as_deref());
-            options.cam.frame.frame_type = frame_type;
-            ui.set_prop_f64("spb_exp.value", options.cam.frame.exposure());
+            let Ok(options) = self_.options.try_read() else { return; };
+            let frame = options.cam.frame.clone();
             drop(options);
+            if frame.frame_type != FrameType::from_active_id(cb.active_id().as_deref()) {
+                return;
+            }
+            ui.set_prop_f64("spb_exp.value", frame.exposure());
+            ui.set_prop_f64("spb_gain.value", frame.gain);
+            ui.set_prop_f64("spb_offset.value", frame.offset as f64);
             self_.show_total_raw_time();
         }));
 
@@ -356,6 +510,13 @@ impl CameraUi {
         spb_gain.connect_value_changed(clone!(@weak self as self_ => move |sb| {
             let Ok(mut options) = self_.options.try_write() else { return; };
             options.cam.frame.gain = sb.value();
+            drop(options);
+            self_.show_gain_e_per_adu();
+        }));
+
+        let btn_gain_advice = bldr.object::<gtk::Button>("btn_gain_advice").unwrap();
+        btn_gain_advice.connect_clicked(clone!(@weak self as self_ => move |_| {
+            self_.handler_btn_gain_advice();
         }));
 
         let spb_offset = bldr.object::<gtk::SpinButton>("spb_offset").unwrap();
@@ -384,6 +545,24 @@ impl CameraUi {
             options.cam.frame.low_noise = chb.is_active();
         }));
 
+        let chb_compressed = bldr.object::<gtk::CheckButton>("chb_compressed").unwrap();
+        chb_compressed.connect_active_notify(clone!(@weak self as self_ => move |chb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.cam.frame.compressed = chb.is_active();
+        }));
+
+        let spb_frame_delay = bldr.object::<gtk::SpinButton>("spb_frame_delay").unwrap();
+        spb_frame_delay.connect_value_changed(clone!(@weak self as self_ => move |sb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.cam.frame.delay = sb.value();
+        }));
+
+        let spb_settle_delay = bldr.object::<gtk::SpinButton>("spb_settle_delay").unwrap();
+        spb_settle_delay.connect_value_changed(clone!(@weak self as self_ => move |sb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.cam.frame.settle_delay = sb.value();
+        }));
+
         let spb_raw_frames_cnt = bldr.object::<gtk::SpinButton>("spb_raw_frames_cnt").unwrap();
         spb_raw_frames_cnt.connect_value_changed(clone!(@weak self as self_ => move |sb| {
             let Ok(mut options) = self_.options.try_write() else { return; };
@@ -392,6 +571,66 @@ impl CameraUi {
             self_.show_total_raw_time();
         }));
 
+        let chb_low_disk_pause = bldr.object::<gtk::CheckButton>("chb_low_disk_pause").unwrap();
+        chb_low_disk_pause.connect_active_notify(clone!(@weak self as self_ => move |chb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.raw_frames.pause_low_disk = chb.is_active();
+            drop(options);
+            self_.correct_widgets_props();
+        }));
+
+        let spb_min_free_space = bldr.object::<gtk::SpinButton>("spb_min_free_space").unwrap();
+        spb_min_free_space.connect_value_changed(clone!(@weak self as self_ => move |sb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.raw_frames.min_free_space = sb.value();
+        }));
+
+        let chb_stop_by_alt = bldr.object::<gtk::CheckButton>("chb_stop_by_alt").unwrap();
+        chb_stop_by_alt.connect_active_notify(clone!(@weak self as self_ => move |chb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.raw_frames.stop_by_alt = chb.is_active();
+            drop(options);
+            self_.correct_widgets_props();
+        }));
+
+        let spb_stop_alt = bldr.object::<gtk::SpinButton>("spb_stop_alt").unwrap();
+        spb_stop_alt.connect_value_changed(clone!(@weak self as self_ => move |sb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.raw_frames.stop_alt = sb.value();
+        }));
+
+        let chb_stop_at_dawn = bldr.object::<gtk::CheckButton>("chb_stop_at_dawn").unwrap();
+        chb_stop_at_dawn.connect_active_notify(clone!(@weak self as self_ => move |chb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.raw_frames.stop_at_dawn = chb.is_active();
+        }));
+
+        let chb_wait_for_dark = bldr.object::<gtk::CheckButton>("chb_wait_for_dark").unwrap();
+        chb_wait_for_dark.connect_active_notify(clone!(@weak self as self_ => move |chb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.raw_frames.wait_for_dark = chb.is_active();
+            drop(options);
+            self_.correct_widgets_props();
+        }));
+
+        let spb_dark_sun_alt = bldr.object::<gtk::SpinButton>("spb_dark_sun_alt").unwrap();
+        spb_dark_sun_alt.connect_value_changed(clone!(@weak self as self_ => move |sb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.raw_frames.dark_sun_alt = sb.value();
+        }));
+
+        let spb_sky_flat_sun_min = bldr.object::<gtk::SpinButton>("spb_sky_flat_sun_min").unwrap();
+        spb_sky_flat_sun_min.connect_value_changed(clone!(@weak self as self_ => move |sb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.flats.sky_sun_alt_min = sb.value();
+        }));
+
+        let spb_sky_flat_sun_max = bldr.object::<gtk::SpinButton>("spb_sky_flat_sun_max").unwrap();
+        spb_sky_flat_sun_max.connect_value_changed(clone!(@weak self as self_ => move |sb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.flats.sky_sun_alt_max = sb.value();
+        }));
+
         let da_shot_state = bldr.object::<gtk::DrawingArea>("da_shot_state").unwrap();
         da_shot_state.connect_draw(
             clone!(@weak self as self_ => @default-return glib::Propagation::Proceed,
@@ -428,10 +667,90 @@ impl CameraUi {
             options.quality.max_ovality = sb.value() as f32;
         }));
 
+        let spb_names = [
+            "spb_qscore_w_fwhm", "spb_qscore_w_oval", "spb_qscore_w_stars", "spb_qscore_w_bg",
+            "spb_star_det_thres", "spb_star_det_min_px", "spb_star_det_max_px", "spb_star_det_satur",
+        ];
+        for spb_name in spb_names {
+            let spb = bldr.object::<gtk::SpinButton>(spb_name).unwrap();
+            spb.connect_value_changed(clone!(@weak self as self_ => move |_| {
+                let Ok(mut options) = self_.options.try_write() else { return; };
+                options.read_frame_quality(&self_.builder);
+            }));
+        }
+
         let chb_master_dark = bldr.object::<gtk::CheckButton>("chb_master_dark").unwrap();
         chb_master_dark.connect_active_notify(clone!(@weak self as self_ => move |chb| {
             let Ok(mut options) = self_.options.try_write() else { return; };
             options.calibr.dark_frame_en = chb.is_active();
+            drop(options);
+            self_.correct_widgets_props();
+        }));
+
+        let chb_dark_scaling = bldr.object::<gtk::CheckButton>("chb_dark_scaling").unwrap();
+        chb_dark_scaling.connect_active_notify(clone!(@weak self as self_ => move |chb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.calibr.allow_dark_scaling = chb.is_active();
+        }));
+
+        let chb_dark_hot_pixels = bldr.object::<gtk::CheckButton>("chb_dark_hot_pixels").unwrap();
+        chb_dark_hot_pixels.connect_active_notify(clone!(@weak self as self_ => move |chb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.calibr.hot_pixel_removal = chb.is_active();
+            drop(options);
+            self_.correct_widgets_props();
+        }));
+
+        let spb_hot_pixel_sigma = bldr.object::<gtk::SpinButton>("spb_hot_pixel_sigma").unwrap();
+        spb_hot_pixel_sigma.connect_value_changed(clone!(@weak self as self_ => move |sb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.calibr.hot_pixel_sigma = sb.value();
+        }));
+
+        let spb_dark_temp_tol = bldr.object::<gtk::SpinButton>("spb_dark_temp_tol").unwrap();
+        spb_dark_temp_tol.connect_value_changed(clone!(@weak self as self_ => move |sb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.calibr.dark_temp_tol = sb.value();
+        }));
+
+        let spb_dark_exp_tol = bldr.object::<gtk::SpinButton>("spb_dark_exp_tol").unwrap();
+        spb_dark_exp_tol.connect_value_changed(clone!(@weak self as self_ => move |sb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.calibr.dark_exp_tol = sb.value();
+        }));
+
+        let chb_flat_auto_exp = bldr.object::<gtk::CheckButton>("chb_flat_auto_exp").unwrap();
+        chb_flat_auto_exp.connect_active_notify(clone!(@weak self as self_ => move |chb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.calibr.flat_auto_exp = chb.is_active();
+            drop(options);
+            self_.correct_widgets_props();
+        }));
+
+        let spb_flat_adu_target = bldr.object::<gtk::SpinButton>("spb_flat_adu_target").unwrap();
+        spb_flat_adu_target.connect_value_changed(clone!(@weak self as self_ => move |sb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.calibr.flat_adu_target = sb.value();
+        }));
+
+        let spb_flat_adu_tol = bldr.object::<gtk::SpinButton>("spb_flat_adu_tol").unwrap();
+        spb_flat_adu_tol.connect_value_changed(clone!(@weak self as self_ => move |sb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.calibr.flat_adu_tolerance = sb.value();
+        }));
+
+        let chb_flat_panel = bldr.object::<gtk::CheckButton>("chb_flat_panel").unwrap();
+        chb_flat_panel.connect_active_notify(clone!(@weak self as self_ => move |chb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.calibr.flat_panel = chb.is_active();
+            drop(options);
+            self_.correct_widgets_props();
+        }));
+
+        let spb_flat_panel_bright = bldr.object::<gtk::SpinButton>("spb_flat_panel_bright").unwrap();
+        spb_flat_panel_bright.connect_value_changed(clone!(@weak self as self_ => move |sb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.calibr.flat_panel_bright = sb.value();
         }));
 
         let chb_master_flat = bldr.object::<gtk::CheckButton>("chb_master_flat").unwrap();
@@ -465,6 +784,26 @@ impl CameraUi {
 
         }));
 
+        let chb_live_min_stars = bldr.object::<gtk::CheckButton>("chb_live_min_stars").unwrap();
+        chb_live_min_stars.connect_active_notify(clone!(@weak self as self_ => move |chb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.live.use_min_stars = chb.is_active();
+            drop(options);
+            self_.correct_frame_quality_widgets_props();
+        }));
+
+        let spb_live_min_stars = bldr.object::<gtk::SpinButton>("spb_live_min_stars").unwrap();
+        spb_live_min_stars.connect_value_changed(clone!(@weak self as self_ => move |sb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.live.min_stars = sb.value() as usize;
+        }));
+
+        let cbx_live_drizzle = bldr.object::<gtk::ComboBoxText>("cbx_live_drizzle").unwrap();
+        cbx_live_drizzle.connect_active_id_notify(clone!(@weak self as self_ => move |cbx| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.live.drizzle = Drizzle::from_active_id(cbx.active_id().as_deref());
+        }));
+
     }
 
     fn connect_main_ui_events(self: &Rc<Self>, handlers: &mut MainUiEventHandlers) {
@@ -475,7 +814,11 @@ impl CameraUi {
 
     fn handler_main_ui_event(&self, event: UiEvent) {
         match event {
-            UiEvent::Timer => {}
+            UiEvent::Timer => {
+                for event_data in self.prop_throttler.take() {
+                    self.process_prop_change_event(&event_data);
+                }
+            }
             UiEvent::FullScreen(full_screen) =>
                 self.set_full_screen_mode(full_screen),
             UiEvent::BeforeModeContinued =>
@@ -501,29 +844,10 @@ impl CameraUi {
             MainThreadEvent::Indi(indi::Event::ConnChange(conn_state)) =>
                 self.process_indi_conn_state_event(conn_state),
             MainThreadEvent::Indi(indi::Event::PropChange(event_data)) => {
-                match &event_data.change {
-                    indi::PropChange::New(value) =>
-                        self.process_indi_prop_change(
-                            &event_data.device_name,
-                            &event_data.prop_name,
-                            &value.elem_name,
-                            true,
-                            None,
-                            None,
-                            &value.prop_value
-                        ),
-                    indi::PropChange::Change{ value, prev_state, new_state } =>
-                        self.process_indi_prop_change(
-                            &event_data.device_name,
-                            &event_data.prop_name,
-                            &value.elem_name,
-                            false,
-                            Some(prev_state),
-                            Some(new_state),
-                            &value.prop_value
-                        ),
-                    indi::PropChange::Delete => {}
-                };
+                let throttle = self.options.read().unwrap().indi.throttle_ui;
+                for event_data in self.prop_throttler.push(event_data, throttle) {
+                    self.process_prop_change_event(&event_data);
+                }
             },
 
             MainThreadEvent::Indi(
@@ -549,6 +873,11 @@ impl CameraUi {
             MainThreadEvent::Core(Event::FrameProcessing(result)) => {
                 self.show_frame_processing_result(result);
             }
+
+            MainThreadEvent::Core(Event::LiveViewGainChanged(gain)) => {
+                let ui = gtk_utils::UiHelper::new_from_builder(&self.builder);
+                ui.set_prop_f64("spb_gain.value", gain);
+            }
             _ => {},
         }
     }
@@ -559,7 +888,7 @@ impl CameraUi {
         options: &Options
     ) {
         let mut ui_options = self.ui_options.borrow_mut();
-        let store_dest = match ui_options.all_cam_opts.iter_mut().find(|item| item.cam == *device) {
+        let store_dest = match ui_options.all_cam_opts.iter_mut().find(|item| item.cam.is_same_options_key(device)) {
             Some(existing) => existing,
             _ => {
                 let mut new_cam_opts = StoredCamOptions::default();
@@ -581,7 +910,7 @@ impl CameraUi {
     ) {
         // Restore previous options of selected camera
         let ui_options = self.ui_options.borrow();
-        if let Some(stored) = ui_options.all_cam_opts.iter().find(|item| &item.cam == camera_device) {
+        if let Some(stored) = ui_options.all_cam_opts.iter().find(|item| item.cam.is_same_options_key(camera_device)) {
             options.cam.frame = stored.frame.clone();
             options.cam.ctrl = stored.ctrl.clone();
             options.calibr = stored.calibr.clone();
@@ -760,8 +1089,15 @@ impl CameraUi {
             let gain_value = self.indi.camera_get_gain_prop_value(&camera.name);
             correct_spinbutton_by_cam_prop(&self.builder, "spb_gain", &gain_value, 0, None)
         }).unwrap_or(false);
+        let live_ag = ui.prop_bool("chb_live_ag.active");
         let offset_supported = camera.as_ref().map(|camera| {
             let offset_value = self.indi.camera_get_offset_prop_value(&camera.name);
+            if let Ok(offset_value) = &offset_value {
+                ui.set_prop_str("spb_offset.tooltip-text", Some(&format!(
+                    "Camera offset range: {}..{}",
+                    offset_value.min, offset_value.max
+                )));
+            }
             correct_spinbutton_by_cam_prop(&self.builder, "spb_offset", &offset_value, 0, None)
         }).unwrap_or(false);
         let bin_supported = camera.as_ref().map(|camera| {
@@ -777,6 +1113,12 @@ impl CameraUi {
         let low_noise_supported = camera.as_ref().map(|camera|
             self.indi.camera_is_low_noise_ctrl_supported(&camera.name).unwrap_or(false)
         ).unwrap_or(false);
+        let compression_supported = camera.as_ref().map(|camera|
+            self.indi.camera_is_compression_supported(&camera.name).unwrap_or(false)
+        ).unwrap_or(false);
+        let video_supported = camera.as_ref().map(|camera|
+            self.indi.camera_is_video_stream_supported(&camera.name).unwrap_or(false)
+        ).unwrap_or(false);
         let crop_supported = camera.as_ref().map(|camera| {
             let cam_ccd = indi::CamCcd::from_ccd_prop_name(&camera.prop);
             self.indi.camera_is_frame_supported(&camera.name, cam_ccd).unwrap_or(false)
@@ -797,10 +1139,14 @@ impl CameraUi {
         let waiting = mode_type == ModeType::Waiting;
         let single_shot = mode_type == ModeType::SingleShot;
         let liveview_active = mode_type == ModeType::LiveView;
-        let saving_frames = mode_type == ModeType::SavingRawFrames;
+        let video_active = mode_type == ModeType::VideoStream;
+        let saving_frames = matches!(
+            mode_type,
+            ModeType::SavingRawFrames|ModeType::Sequence|ModeType::FlatsSession
+        );
         let saving_frames_paused = mode_data.aborted_mode
             .as_ref()
-            .map(|mode| mode.get_type() == ModeType::SavingRawFrames)
+            .map(|mode| matches!(mode.get_type(), ModeType::SavingRawFrames|ModeType::Sequence))
             .unwrap_or(false);
         let live_active = mode_type == ModeType::LiveStacking;
         let livestacking_paused = mode_data.aborted_mode
@@ -817,6 +1163,7 @@ impl CameraUi {
             FrameType::Undef  => "Error :(",
         };
         ui.set_prop_str("btn_start_save_raw.label", Some(save_raw_btn_cap));
+        ui.set_prop_bool("chb_video_stream.active", video_active);
 
         let cam_active = self.indi
             .is_device_enabled(camera.as_ref().map(|c| c.name.as_str()).unwrap_or(""))
@@ -835,22 +1182,28 @@ impl CameraUi {
         gtk_utils::enable_actions(&self.window, &[
             ("take_shot",              exposure_supported && !single_shot && can_change_mode),
             ("stop_shot",              single_shot),
+            ("take_shot_or_stop",      (exposure_supported && waiting) || single_shot),
+            ("toggle_live_view",       (exposure_supported && liveview_active) || can_change_mode),
 
             ("start_save_raw_frames",  exposure_supported && !saving_frames && can_change_mode),
             ("stop_save_raw_frames",   saving_frames),
             ("continue_save_raw",      saving_frames_paused && can_change_mode),
+            ("run_sequence",           exposure_supported && !saving_frames && can_change_mode),
+            ("run_flats_session",      exposure_supported && !saving_frames && can_change_mode),
 
             ("start_live_stacking",    exposure_supported && !live_active && can_change_mode && frame_mode_is_lights),
             ("stop_live_stacking",     live_active),
             ("continue_live_stacking", livestacking_paused && can_change_mode),
             ("load_image",             waiting),
+            ("reset_camera",           cam_sensitive),
         ]);
 
         ui.show_widgets(&[
-            ("chb_fan",       fan_supported),
-            ("l_cam_heater",  heater_supported),
-            ("cb_cam_heater", heater_supported),
-            ("chb_low_noise", low_noise_supported),
+            ("chb_fan",        fan_supported),
+            ("l_cam_heater",   heater_supported),
+            ("cb_cam_heater",  heater_supported),
+            ("chb_low_noise",  low_noise_supported),
+            ("chb_compressed", compression_supported),
         ]);
 
         ui.enable_widgets(false, &[
@@ -860,24 +1213,44 @@ impl CameraUi {
             ("chb_cooler",         temp_supported && can_change_cam_opts),
             ("spb_temp",           cooler_active && temp_supported && can_change_cam_opts),
             ("chb_shots_cont",     (exposure_supported && liveview_active) || can_change_mode),
+            ("chb_video_stream",   video_supported && (waiting || video_active)),
+            ("chb_live_ag",        gain_supported),
+            ("spb_live_ag_target", gain_supported && live_ag),
+            ("spb_live_ag_min",    gain_supported && live_ag),
+            ("spb_live_ag_max",    gain_supported && live_ag),
             ("cb_frame_mode",      can_change_frame_opts),
             ("spb_exp",            exposure_supported && can_change_frame_opts),
             ("cb_crop",            crop_supported && can_change_frame_opts),
             ("spb_gain",           gain_supported && can_change_frame_opts),
+            ("btn_gain_advice",    gain_supported && can_change_frame_opts),
             ("spb_offset",         offset_supported && can_change_frame_opts),
             ("cb_bin",             bin_supported && can_change_frame_opts),
             ("chb_master_frame",   can_change_cal_ops && (frame_mode_is_flat || frame_mode_is_dark) && !saving_frames),
             ("chb_master_dark",    can_change_cal_ops),
+            ("chb_dark_scaling",   can_change_cal_ops && ui.prop_bool("chb_master_dark.active")),
+            ("spb_dark_temp_tol",  can_change_cal_ops && ui.prop_bool("chb_master_dark.active")),
+            ("spb_dark_exp_tol",   can_change_cal_ops && ui.prop_bool("chb_master_dark.active")),
+            ("chb_dark_hot_pixels", can_change_cal_ops && ui.prop_bool("chb_master_dark.active")),
+            ("spb_hot_pixel_sigma", can_change_cal_ops && ui.prop_bool("chb_master_dark.active") && ui.prop_bool("chb_dark_hot_pixels.active")),
             ("fch_dark_library",   can_change_cal_ops),
             ("chb_master_flat",    can_change_cal_ops),
             ("fch_master_flat",    can_change_cal_ops),
+            ("chb_flat_auto_exp",  can_change_cal_ops),
+            ("spb_flat_adu_target", can_change_cal_ops && ui.prop_bool("chb_flat_auto_exp.active")),
+            ("spb_flat_adu_tol",   can_change_cal_ops && ui.prop_bool("chb_flat_auto_exp.active")),
+            ("chb_flat_panel",     can_change_cal_ops),
+            ("spb_flat_panel_bright", can_change_cal_ops && ui.prop_bool("chb_flat_panel.active")),
             ("chb_raw_frames_cnt", !saving_frames && can_change_mode),
             ("spb_raw_frames_cnt", !saving_frames && can_change_mode),
+            ("spb_min_free_space", ui.prop_bool("chb_low_disk_pause.active")),
+            ("spb_stop_alt",       ui.prop_bool("chb_stop_by_alt.active")),
+            ("spb_dark_sun_alt",   ui.prop_bool("chb_wait_for_dark.active")),
 
             ("chb_live_save",      can_change_live_stacking_opts),
             ("spb_live_minutes",   can_change_live_stacking_opts),
             ("chb_live_save_orig", can_change_live_stacking_opts),
             ("fch_live_folder",    can_change_live_stacking_opts),
+            ("cbx_live_drizzle",   can_change_live_stacking_opts),
 
             ("grd_cam_ctrl",       cam_sensitive),
             ("grd_shot_settings",  cam_sensitive),
@@ -894,6 +1267,7 @@ impl CameraUi {
         drop(options);
         self.correct_widgets_props_impl(&camera);
         self.correct_frame_quality_widgets_props();
+        self.show_gain_e_per_adu();
     }
 
     fn correct_frame_quality_widgets_props(&self) {
@@ -901,6 +1275,7 @@ impl CameraUi {
         ui.enable_widgets(true, &[
             ("spb_max_fwhm", ui.prop_bool("chb_max_fwhm.active")),
             ("spb_max_oval", ui.prop_bool("chb_max_oval.active")),
+            ("spb_live_min_stars", ui.prop_bool("chb_live_min_stars.active")),
         ]);
     }
 
@@ -915,10 +1290,19 @@ impl CameraUi {
         for camera in cameras {
             for prop in ["CCD1", "CCD2", "CCD3"] {
                 if self.indi.property_exists(&camera.name, prop, None).unwrap_or(false) {
-                    let dev_and_prop = DeviceAndProp {
-                        name: camera.name.to_string(),
-                        prop: prop.to_string()
+                    let mut dev_and_prop = DeviceAndProp {
+                        name:  camera.name.to_string(),
+                        prop:  prop.to_string(),
+                        index: 0,
                     };
+                    // INDI addresses devices only by name so cameras with
+                    // same names are told apart by index in stored options
+                    while list.contains(&dev_and_prop.to_string()) {
+                        dev_and_prop.index += 1;
+                    }
+                    if dev_and_prop.index != 0 {
+                        log::warn!("Duplicated camera {} in devices list", dev_and_prop.name);
+                    }
                     list.push(dev_and_prop.to_string());
                 }
             }
@@ -931,7 +1315,7 @@ impl CameraUi {
         let camera_selected = fill_devices_list_into_combobox(
             &list,
             &cb,
-            cur_cam_device.as_ref().map(|d| d.name.as_str()),
+            cur_cam_device.as_ref().map(|d| d.to_string()).as_deref(),
             connected,
             |id| {
                 let Ok(mut options) = self.options.try_write() else { return; };
@@ -1066,6 +1450,27 @@ impl CameraUi {
         self.core.abort_active_mode();
     }
 
+    fn handler_action_take_shot_or_stop(&self) {
+        let mode_type = self.core.mode_data().mode.get_type();
+        match mode_type {
+            ModeType::Waiting => self.handler_action_take_shot(),
+            ModeType::SingleShot => self.handler_action_stop_shot(),
+            _ => {},
+        }
+    }
+
+    fn handler_action_toggle_live_view(&self) {
+        let chb_shots_cont = self.builder.object::<gtk::CheckButton>("chb_shots_cont").unwrap();
+        chb_shots_cont.set_active(!chb_shots_cont.is_active());
+    }
+
+    fn handler_action_reset_camera(&self) {
+        gtk_utils::exec_and_show_error(&self.window, || {
+            self.core.reset_camera()?;
+            Ok(())
+        });
+    }
+
     // TODO: move camera control code into `core` module
     fn control_camera_by_options(&self, force_set: bool) {
         let options = self.options.read().unwrap();
@@ -1144,6 +1549,20 @@ impl CameraUi {
         }
     }
 
+    fn handler_video_stream_changed(&self, active: bool) {
+        let video_active = self.core.mode_data().mode.get_type() == ModeType::VideoStream;
+        if active && !video_active {
+            self.get_options_from_widgets();
+            gtk_utils::exec_and_show_error(&self.window, || {
+                self.core.start_video_stream()?;
+                Ok(())
+            });
+            self.correct_widgets_props();
+        } else if !active && video_active {
+            self.core.abort_active_mode();
+        }
+    }
+
     fn handler_live_view_changed(&self) {
         if self.indi.state() != indi::ConnState::Connected {
             return;
@@ -1167,6 +1586,32 @@ impl CameraUi {
         self.correct_widgets_props();
     }
 
+    fn process_prop_change_event(&self, event_data: &indi::PropChangeEvent) {
+        match &event_data.change {
+            indi::PropChange::New(value) =>
+                self.process_indi_prop_change(
+                    &event_data.device_name,
+                    &event_data.prop_name,
+                    &value.elem_name,
+                    true,
+                    None,
+                    None,
+                    &value.prop_value
+                ),
+            indi::PropChange::Change{ value, prev_state, new_state } =>
+                self.process_indi_prop_change(
+                    &event_data.device_name,
+                    &event_data.prop_name,
+                    &value.elem_name,
+                    false,
+                    Some(prev_state),
+                    Some(new_state),
+                    &value.prop_value
+                ),
+            indi::PropChange::Delete => {}
+        };
+    }
+
     fn process_indi_prop_change(
         &self,
         device_name: &str,
@@ -1321,9 +1766,14 @@ impl CameraUi {
             if options.live.save_orig {
                 pairs.push(("Save originals".to_string(), "Yes".to_string()));
             }
-            if options.live.remove_tracks {
+            if options.live.drizzle == Drizzle::X2 {
+                pairs.push(("Drizzle".to_string(), "2x".to_string()));
+            } else if options.live.remove_tracks {
                 pairs.push(("Remove tracks".to_string(), "Yes".to_string()));
             }
+            if options.live.use_min_stars {
+                pairs.push(("Min. stars".to_string(), options.live.min_stars.to_string()));
+            }
         }
 
         if (for_live_stacking || light_frames)
@@ -1367,6 +1817,17 @@ impl CameraUi {
                         "Guiding".to_string(),
                         "By main camera".to_string(),
                     ));
+                    let calibr_value = if self.core.is_saved_mount_calibr_valid() {
+                        "Saved one will be used"
+                    } else if options.guiding.main_cam.auto_calibr {
+                        "Will be done before first frame"
+                    } else {
+                        "Not calibrated!"
+                    };
+                    pairs.push((
+                        "Mount calibration".to_string(),
+                        calibr_value.to_string(),
+                    ));
                     if options.guiding.dith_period != 0 {
                         pairs.push((
                             "Dithering".to_string(),
@@ -1394,6 +1855,32 @@ impl CameraUi {
                         ));
                     }
                 }
+                GuidingMode::FrameOffset => {
+                    if options.guiding.dith_period != 0 {
+                        let value = if options.cam.frame.crop != Crop::None {
+                            format!(
+                                "By frame offset {} px each {} minutes",
+                                options.guiding.frame_offset.dith_dist,
+                                options.guiding.dith_period
+                            )
+                        } else {
+                            "By frame offset (disabled: frame is not cropped)".to_string()
+                        };
+                        pairs.push(("Dithering".to_string(), value));
+                    }
+                }
+                GuidingMode::SimpleDither => {
+                    if options.guiding.dith_period != 0 {
+                        pairs.push((
+                            "Dithering".to_string(),
+                            format!(
+                                "By guide pulses up to {:.0} ms each {} minutes",
+                                options.guiding.simple_dith.pulse,
+                                options.guiding.dith_period
+                            )
+                        ));
+                    }
+                }
                 _ => {},
             }
         }
@@ -1427,11 +1914,129 @@ impl CameraUi {
         });
     }
 
+    fn handler_action_run_sequence(self: &Rc<Self>) {
+        let csv_filter = gtk::FileFilter::new();
+        csv_filter.set_name(Some("Sequence table (*.csv, *.txt)"));
+        csv_filter.add_pattern("*.csv");
+        csv_filter.add_pattern("*.txt");
+
+        let fc = gtk::FileChooserDialog::builder()
+            .action(gtk::FileChooserAction::Open)
+            .title("Select sequence file (filter slot, count, exposure, gain, binning)")
+            .modal(true)
+            .transient_for(&self.window)
+            .build();
+        fc.add_filter(csv_filter);
+        gtk_utils::add_ok_and_cancel_buttons(
+            fc.upcast_ref::<gtk::Dialog>(),
+            "_Run",    gtk::ResponseType::Accept,
+            "_Cancel", gtk::ResponseType::Cancel
+        );
+        fc.connect_response(clone!(@weak self as self_ => move |file_chooser, response| {
+            if response == gtk::ResponseType::Accept {
+                gtk_utils::exec_and_show_error(&self_.window, || {
+                    let Some(file_name) = file_chooser.file() else { return Ok(()); };
+                    let Some(file_name) = file_name.path() else { return Ok(()); };
+                    let steps = load_sequence_from_file(&file_name)?;
+                    self_.get_options_from_widgets();
+                    self_.options.write().unwrap().sequence.steps = steps;
+                    self_.core.start_sequence()?;
+                    self_.show_options();
+                    Ok(())
+                });
+            }
+            file_chooser.close();
+        }));
+        fc.show();
+    }
+
+    fn handler_action_run_flats_session(self: &Rc<Self>) {
+        let csv_filter = gtk::FileFilter::new();
+        csv_filter.set_name(Some("Flats table (*.csv, *.txt)"));
+        csv_filter.add_pattern("*.csv");
+        csv_filter.add_pattern("*.txt");
+
+        let fc = gtk::FileChooserDialog::builder()
+            .action(gtk::FileChooserAction::Open)
+            .title("Select flats file (filter slot, count)")
+            .modal(true)
+            .transient_for(&self.window)
+            .build();
+        fc.add_filter(csv_filter);
+        gtk_utils::add_ok_and_cancel_buttons(
+            fc.upcast_ref::<gtk::Dialog>(),
+            "_Run",    gtk::ResponseType::Accept,
+            "_Cancel", gtk::ResponseType::Cancel
+        );
+        fc.connect_response(clone!(@weak self as self_ => move |file_chooser, response| {
+            if response == gtk::ResponseType::Accept {
+                gtk_utils::exec_and_show_error(&self_.window, || {
+                    let Some(file_name) = file_chooser.file() else { return Ok(()); };
+                    let Some(file_name) = file_name.path() else { return Ok(()); };
+                    let steps = load_flats_session_from_file(&file_name)?;
+                    self_.get_options_from_widgets();
+                    self_.options.write().unwrap().flats.steps = steps;
+                    self_.core.start_flats_session()?;
+                    self_.show_options();
+                    Ok(())
+                });
+            }
+            file_chooser.close();
+        }));
+        fc.show();
+    }
+
     fn handler_action_stop_save_raw_frames(&self) {
         if !is_expanded(&self.builder, "exp_raw_frames") { return; }
         self.core.abort_active_mode();
     }
 
+    fn handler_btn_gain_advice(&self) {
+        gtk_utils::exec_and_show_error(&self.window, || {
+            let options = self.options.read().unwrap();
+            let Some(device) = options.cam.device.clone() else {
+                anyhow::bail!("Camera is not selected");
+            };
+            drop(options);
+            let gain_prop = self.indi.camera_get_gain_prop_value(&device.name)?;
+
+            let menu = gtk::Menu::new();
+            for goal in [GainGoal::MaxDynamicRange, GainGoal::Unity, GainGoal::LowReadNoise] {
+                let advice = suggest_gain(&device.name, &gain_prop, goal);
+                let menu_item = gtk::MenuItem::builder().label(advice.to_string()).build();
+                menu.append(&menu_item);
+                let builder = self.builder.clone();
+                menu_item.connect_activate(move |_| {
+                    let ui = gtk_utils::UiHelper::new_from_builder(&builder);
+                    ui.set_prop_f64("spb_gain.value", advice.gain);
+                    if let Some(offset) = advice.offset {
+                        if ui.prop_bool("spb_offset.sensitive") {
+                            ui.set_prop_f64("spb_offset.value", offset as f64);
+                        }
+                    }
+                });
+            }
+            menu.append(&gtk::SeparatorMenuItem::new());
+            let show_e_per_adu_item = gtk::CheckMenuItem::builder()
+                .label("Show gain in e-/ADU")
+                .active(self.options.read().unwrap().cam.show_e_per_adu)
+                .build();
+            menu.append(&show_e_per_adu_item);
+            let self_weak = self.self_.borrow().as_ref().map(Rc::downgrade);
+            show_e_per_adu_item.connect_activate(move |item| {
+                let Some(self_) = self_weak.as_ref().and_then(|w| w.upgrade()) else { return; };
+                self_.options.write().unwrap().cam.show_e_per_adu = item.is_active();
+                self_.show_gain_e_per_adu();
+            });
+
+            let menu_widget = self.builder.object::<gtk::Widget>("btn_gain_advice").unwrap();
+            menu.set_attach_widget(Some(&menu_widget));
+            menu.show_all();
+            menu.popup_easy(gtk::gdk::ffi::GDK_BUTTON_SECONDARY as u32, 0);
+            Ok(())
+        });
+    }
+
     fn show_total_raw_time_impl(&self, options: &Options) {
         let total_time = options.cam.frame.exposure() * options.raw_frames.frame_cnt as f64;
         let text = format!(
@@ -1444,6 +2049,20 @@ impl CameraUi {
         ui.set_prop_str("l_raw_time_info.label", Some(&text));
     }
 
+    fn show_gain_e_per_adu(&self) {
+        let options = self.options.read().unwrap();
+        let text = if let (true, Some(device)) = (options.cam.show_e_per_adu, &options.cam.device) {
+            gain_to_e_per_adu(&device.name, options.cam.frame.gain)
+                .map(|e_per_adu| format!("{:.2}e-/ADU", e_per_adu))
+                .unwrap_or_default()
+        } else {
+            String::new()
+        };
+        drop(options);
+        let ui = gtk_utils::UiHelper::new_from_builder(&self.builder);
+        ui.set_prop_str("l_gain_e_per_adu.label", Some(&text));
+    }
+
     fn show_total_raw_time(&self) {
         let options = self.options.read().unwrap();
         self.show_total_raw_time_impl(&options);
@@ -1,4 +1,4 @@
-use std::{cell::{Cell, RefCell}, rc::Rc, sync::{Arc, RwLock}};
+use std::{cell::{Cell, RefCell}, collections::HashMap, rc::Rc, sync::{Arc, RwLock}};
 use gtk::{gdk::ffi::GDK_CURRENT_TIME, glib::{self, clone}, prelude::*};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
@@ -31,6 +31,8 @@ pub fn init_ui(
         core:              Arc::clone(core),
         indi:              Arc::clone(indi),
         ui_options:        RefCell::new(ui_options),
+        dark_plan_keys:    RefCell::new(Vec::new()),
+        bias_plan_keys:    RefCell::new(Vec::new()),
         core_subscription: RefCell::new(None),
         closed:            Cell::new(false),
         self_:             RefCell::new(None),
@@ -227,6 +229,32 @@ impl DefectPixelsOptions {
     }
 }
 
+/// Key of set in capture plan. Used to store individual frames count for set
+fn program_item_key(item: &MasterFileCreationProgramItem) -> String {
+    let temp_str = match item.temperature {
+        Some(t) => format!("{:.1}", t),
+        None    => "none".to_string(),
+    };
+    format!(
+        "t{}_e{}_g{}_o{}_b{}_c{}",
+        temp_str, item.exposure, item.gain, item.offset,
+        item.binning.to_str(), item.crop.to_str()
+    )
+}
+
+/// Applies individual frames count for sets of program
+/// (frames count 0 means set is skipped)
+fn apply_count_overrides(
+    program:   &mut Vec<MasterFileCreationProgramItem>,
+    overrides: &HashMap<String, usize>
+) {
+    for item in program {
+        if let Some(count) = overrides.get(&program_item_key(item)) {
+            item.count = *count;
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(default)]
 struct ValuesItem {
@@ -255,6 +283,7 @@ struct MasterDarksOptions {
     offset:       ValuesItem,
     binning:      BinningOptions,
     crop:         CropOptions,
+    counts:       HashMap<String, usize>,
 }
 
 impl Default for MasterDarksOptions {
@@ -269,6 +298,7 @@ impl Default for MasterDarksOptions {
             offset:       ValuesItem::default(),
             binning:      BinningOptions::default(),
             crop:         CropOptions::default(),
+            counts:       HashMap::new(),
         }
     }
 }
@@ -279,6 +309,18 @@ impl MasterDarksOptions {
         cam_opts:   &CamOptions,
         indi:       &indi::Connection,
         cam_device: &DeviceAndProp
+    ) -> anyhow::Result<Vec<MasterFileCreationProgramItem>> {
+        let mut result = self.create_plan(cam_opts, indi, cam_device)?;
+        result.retain(|item| item.count != 0);
+        Ok(result)
+    }
+
+    /// All sets of program including skipped ones
+    fn create_plan(
+        &self,
+        cam_opts:   &CamOptions,
+        indi:       &indi::Connection,
+        cam_device: &DeviceAndProp
     ) -> anyhow::Result<Vec<MasterFileCreationProgramItem>> {
         let mut result = Vec::new();
 
@@ -328,7 +370,6 @@ impl MasterDarksOptions {
                                         multiple_of_5(cnt)
                                     }
                                 };
-                                if frame_count == 0 { continue; }
                                 let item = MasterFileCreationProgramItem {
                                     count:       frame_count,
                                     temperature: *t,
@@ -346,6 +387,8 @@ impl MasterDarksOptions {
             }
         }
 
+        apply_count_overrides(&mut result, &self.counts);
+
         return Ok(result);
     }
 }
@@ -360,6 +403,7 @@ struct MasterBiasesOptions {
     offset:       ValuesItem,
     binning:      BinningOptions,
     crop:         CropOptions,
+    counts:       HashMap<String, usize>,
 }
 
 impl Default for MasterBiasesOptions {
@@ -372,6 +416,7 @@ impl Default for MasterBiasesOptions {
             offset:       ValuesItem::default(),
             binning:      BinningOptions::default(),
             crop:         CropOptions::default(),
+            counts:       HashMap::new(),
         }
     }
 }
@@ -382,6 +427,18 @@ impl MasterBiasesOptions {
         cam_opts:   &CamOptions,
         indi:       &indi::Connection,
         cam_device: &DeviceAndProp
+    ) -> anyhow::Result<Vec<MasterFileCreationProgramItem>> {
+        let mut result = self.create_plan(cam_opts, indi, cam_device)?;
+        result.retain(|item| item.count != 0);
+        Ok(result)
+    }
+
+    /// All sets of program including skipped ones
+    fn create_plan(
+        &self,
+        cam_opts:   &CamOptions,
+        indi:       &indi::Connection,
+        cam_device: &DeviceAndProp
     ) -> anyhow::Result<Vec<MasterFileCreationProgramItem>> {
         let mut result = Vec::new();
 
@@ -438,6 +495,8 @@ impl MasterBiasesOptions {
             }
         }
 
+        apply_count_overrides(&mut result, &self.counts);
+
         Ok(result)
     }
 }
@@ -472,6 +531,8 @@ pub struct DarksLibraryUI {
     indi:              Arc<indi::Connection>,
     options:           Arc<RwLock<Options>>,
     ui_options:        RefCell<UiOptions>,
+    dark_plan_keys:    RefCell<Vec<String>>,
+    bias_plan_keys:    RefCell<Vec<String>>,
     core_subscription: RefCell<Option<Subscription>>,
     closed:            Cell<bool>,
     self_:             RefCell<Option<Rc<DarksLibraryUI>>>,
@@ -485,6 +546,7 @@ impl Drop for DarksLibraryUI {
 
 impl DarksLibraryUI {
     const CONF_FN: &str = "ui_darks_lib";
+    const PLAN_COUNT_COL: i32 = 6;
 
     fn init_widgets(&self) {
         let init_spinbutton = |name, min, max, digits, inc, inc_page| {
@@ -506,6 +568,19 @@ impl DarksLibraryUI {
 
         init_spinbutton("spb_bias_cnt", 5.0, 1000.0, 0, 5.0, 30.0);
         init_spinbutton("spb_bias_exp", 0.0001, 0.1, 5, 0.001, 0.01);
+
+        for tv_name in ["tv_dark_plan", "tv_bias_plan"] {
+            let tv = self.builder.object::<gtk::TreeView>(tv_name).unwrap();
+            gtk_utils::init_list_store_model_for_treeview(&tv, &[
+                /* 0 */ ("Temp.",    String::static_type(), "text"),
+                /* 1 */ ("Exposure", String::static_type(), "text"),
+                /* 2 */ ("Gain",     String::static_type(), "text"),
+                /* 3 */ ("Offset",   String::static_type(), "text"),
+                /* 4 */ ("Bin",      String::static_type(), "text"),
+                /* 5 */ ("Crop",     String::static_type(), "text"),
+                /* 6 */ ("Frames",   u32::static_type(),    "text"),
+            ]);
+        }
     }
 
     fn load_options(&self) {
@@ -809,6 +884,20 @@ impl DarksLibraryUI {
         connect_checkbtn("chb_bias_crop33");
         connect_checkbtn("chb_bias_crop25");
 
+        let connect_plan_count_edited = |tv_name, is_bias: bool| {
+            let tv = self.builder.object::<gtk::TreeView>(tv_name).unwrap();
+            let Some(col) = tv.column(Self::PLAN_COUNT_COL) else { return; };
+            let Some(cell) = col.cells().into_iter().next() else { return; };
+            let Ok(cell) = cell.downcast::<gtk::CellRendererText>() else { return; };
+            cell.set_editable(true);
+            cell.connect_edited(clone!(@weak self as self_ => move |_, path, text| {
+                self_.handler_plan_count_edited(is_bias, &path, text);
+            }));
+        };
+
+        connect_plan_count_edited("tv_dark_plan", false);
+        connect_plan_count_edited("tv_bias_plan", true);
+
         gtk_utils::connect_action(&self.window, self, "open_dark_lib_folder",   Self::handler_action_open_dark_lib_folder);
         gtk_utils::connect_action(&self.window, self, "create_def_pixls_files", Self::handler_action_create_def_pixls_files);
         gtk_utils::connect_action(&self.window, self, "stop_def_pxls_files",    Self::handler_action_stop_def_pxls_files);
@@ -924,26 +1013,81 @@ impl DarksLibraryUI {
             self.show_program_info(&defect_pixels_program, "l_def_info");
         };
 
-        if let Ok(dark_library_program) = ui_options.master_darks.create_program(
+        if let Ok(mut dark_library_plan) = ui_options.master_darks.create_plan(
             &options.cam,
             &self.indi,
             cam_device
         ) {
-            self.show_program_info(&dark_library_program, "l_dark_info");
+            self.show_plan(&dark_library_plan, "tv_dark_plan", &self.dark_plan_keys);
+            dark_library_plan.retain(|item| item.count != 0);
+            self.show_program_info(&dark_library_plan, "l_dark_info");
         }
 
-        if let Ok(bias_library_program) = ui_options.master_biases.create_program(
+        if let Ok(mut bias_library_plan) = ui_options.master_biases.create_plan(
             &options.cam,
             &self.indi,
             cam_device
         ) {
-            self.show_program_info(&bias_library_program, "l_bias_info");
+            self.show_plan(&bias_library_plan, "tv_bias_plan", &self.bias_plan_keys);
+            bias_library_plan.retain(|item| item.count != 0);
+            self.show_program_info(&bias_library_plan, "l_bias_info");
+        }
+    }
+
+    fn show_plan(
+        &self,
+        plan:    &[MasterFileCreationProgramItem],
+        tv_name: &str,
+        keys:    &RefCell<Vec<String>>,
+    ) {
+        let tv = self.builder.object::<gtk::TreeView>(tv_name).unwrap();
+        let Some(model) = tv.model() else { return; };
+        let model = model.downcast::<gtk::ListStore>().unwrap();
+        model.clear();
+        let mut keys = keys.borrow_mut();
+        keys.clear();
+        for item in plan {
+            let temp_str = match item.temperature {
+                Some(t) => format!("{:.1}°C", t),
+                None    => "-".to_string(),
+            };
+            let exp_str = if item.exposure >= 1.0 {
+                format!("{:.1}s", item.exposure)
+            } else {
+                format!("{}s", item.exposure)
+            };
+            model.insert_with_values(None, &[
+                (0, &temp_str),
+                (1, &exp_str),
+                (2, &format!("{:.0}", item.gain)),
+                (3, &item.offset.to_string()),
+                (4, &item.binning.to_str()),
+                (5, &item.crop.to_str()),
+                (Self::PLAN_COUNT_COL as u32, &(item.count as u32)),
+            ]);
+            keys.push(program_item_key(item));
         }
     }
 
+    fn handler_plan_count_edited(&self, is_bias: bool, path: &gtk::TreePath, text: &str) {
+        let Some(&row) = path.indices().first() else { return; };
+        let Ok(count) = text.trim().parse::<usize>() else { return; };
+        let keys = if is_bias { &self.bias_plan_keys } else { &self.dark_plan_keys };
+        let Some(key) = keys.borrow().get(row as usize).cloned() else { return; };
+        let mut ui_options = self.ui_options.borrow_mut();
+        let counts = if is_bias {
+            &mut ui_options.master_biases.counts
+        } else {
+            &mut ui_options.master_darks.counts
+        };
+        counts.insert(key, count);
+        drop(ui_options);
+        self.show_info();
+    }
+
     fn show_program_info(
         &self,
-        program:    &Vec<MasterFileCreationProgramItem>,
+        program:    &[MasterFileCreationProgramItem],
         label_name: &str
     ) {
         let duration: f64 = program.iter()
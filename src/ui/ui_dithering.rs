@@ -92,6 +92,16 @@ impl DitheringUi {
         spb_guid_max_err.set_digits(0);
         spb_guid_max_err.set_increments(1.0, 10.0);
 
+        let spb_guid_min_pulse = self.builder.object::<gtk::SpinButton>("spb_guid_min_pulse").unwrap();
+        spb_guid_min_pulse.set_range(0.0, 1000.0);
+        spb_guid_min_pulse.set_digits(0);
+        spb_guid_min_pulse.set_increments(10.0, 100.0);
+
+        let spb_guid_max_corr = self.builder.object::<gtk::SpinButton>("spb_guid_max_corr").unwrap();
+        spb_guid_max_corr.set_range(0.0, 1000.0);
+        spb_guid_max_corr.set_digits(0);
+        spb_guid_max_corr.set_increments(10.0, 100.0);
+
         let spb_mnt_cal_exp = self.builder.object::<gtk::SpinButton>("spb_mnt_cal_exp").unwrap();
         spb_mnt_cal_exp.set_range(0.5, 10.0);
         spb_mnt_cal_exp.set_digits(1);
@@ -106,6 +116,16 @@ impl DitheringUi {
         sb_ext_dith_dist.set_range(1.0, 300.0);
         sb_ext_dith_dist.set_digits(0);
         sb_ext_dith_dist.set_increments(1.0, 10.0);
+
+        let sb_frame_offs_dith_dist = self.builder.object::<gtk::SpinButton>("sb_frame_offs_dith_dist").unwrap();
+        sb_frame_offs_dith_dist.set_range(2.0, 500.0);
+        sb_frame_offs_dith_dist.set_digits(0);
+        sb_frame_offs_dith_dist.set_increments(2.0, 20.0);
+
+        let spb_simple_dith_pulse = self.builder.object::<gtk::SpinButton>("spb_simple_dith_pulse").unwrap();
+        spb_simple_dith_pulse.set_range(100.0, 10000.0);
+        spb_simple_dith_pulse.set_digits(0);
+        spb_simple_dith_pulse.set_increments(100.0, 1000.0);
     }
 
     fn connect_indi_and_core_events(self: &Rc<Self>) {
@@ -160,6 +180,8 @@ impl DitheringUi {
         connect_rbtn("rbtn_no_guiding");
         connect_rbtn("rbtn_guide_main_cam");
         connect_rbtn("rbtn_guide_ext");
+        connect_rbtn("rbtn_dith_frame_offs");
+        connect_rbtn("rbtn_simple_dith");
     }
 
     fn connect_main_ui_events(self: &Rc<Self>, handlers: &mut MainUiEventHandlers) {
@@ -207,6 +229,8 @@ impl DitheringUi {
         let disabled = ui.prop_bool("rbtn_no_guiding.active");
         let by_main_cam = ui.prop_bool("rbtn_guide_main_cam.active");
         let by_ext = ui.prop_bool("rbtn_guide_ext.active");
+        let by_frame_offs = ui.prop_bool("rbtn_dith_frame_offs.active");
+        let simple_dith = ui.prop_bool("rbtn_simple_dith.active");
 
         if let Some(cam_device) = cam_device {
             let cam_ccd = indi::CamCcd::from_ccd_prop_name(&cam_device.prop);
@@ -219,11 +243,19 @@ impl DitheringUi {
             ("rbtn_no_guiding",     can_change_mode),
             ("rbtn_guide_main_cam", can_change_mode),
             ("rbtn_guide_ext",      can_change_mode),
+            ("rbtn_dith_frame_offs", can_change_mode),
+            ("rbtn_simple_dith",    can_change_mode),
             ("cb_dith_perod",       !disabled && can_change_mode),
             ("sb_dith_dist",        by_main_cam && can_change_mode),
             ("spb_guid_max_err",    by_main_cam && can_change_mode),
+            ("spb_guid_min_pulse",  by_main_cam && can_change_mode),
+            ("spb_guid_max_corr",   by_main_cam && can_change_mode),
+            ("chb_guid_save_log",   by_main_cam && can_change_mode),
             ("spb_mnt_cal_exp",     by_main_cam && can_change_mode),
+            ("chb_mnt_cal_auto",    by_main_cam && can_change_mode),
             ("sb_ext_dith_dist",    by_ext && can_change_mode),
+            ("sb_frame_offs_dith_dist", by_frame_offs && can_change_mode),
+            ("spb_simple_dith_pulse", simple_dith && can_change_mode),
         ]);
 
         gtk_utils::enable_actions(&self.window, &[
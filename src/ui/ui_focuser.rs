@@ -36,6 +36,7 @@ pub fn init_ui(
         indi:            Arc::clone(indi),
         ui_options:      RefCell::new(ui_options),
         closed:          Cell::new(false),
+        prop_throttler:  PropChangeThrottler::new(),
         excl:            ExclusiveCaller::new(),
         indi_evt_conn:   RefCell::new(None),
         delayed_actions: DelayedActions::new(500),
@@ -63,6 +64,7 @@ struct FocuserUi {
     indi:            Arc<indi::Connection>,
     ui_options:      RefCell<UiOptions>,
     closed:          Cell<bool>,
+    prop_throttler:  PropChangeThrottler,
     excl:            ExclusiveCaller,
     indi_evt_conn:   RefCell<Option<indi::Subscription>>,
     delayed_actions: DelayedActions<DelayedAction>,
@@ -142,29 +144,10 @@ impl FocuserUi {
                 self.process_indi_conn_state_event(conn_state),
 
             MainThreadEvent::Indi(indi::Event::PropChange(event_data)) => {
-                match &event_data.change {
-                    indi::PropChange::New(value) =>
-                        self.process_indi_prop_change(
-                            &event_data.device_name,
-                            &event_data.prop_name,
-                            &value.elem_name,
-                            true,
-                            None,
-                            None,
-                            &value.prop_value
-                        ),
-                    indi::PropChange::Change{ value, prev_state, new_state } =>
-                        self.process_indi_prop_change(
-                            &event_data.device_name,
-                            &event_data.prop_name,
-                            &value.elem_name,
-                            false,
-                            Some(prev_state),
-                            Some(new_state),
-                            &value.prop_value
-                        ),
-                    indi::PropChange::Delete => {}
-                };
+                let throttle = self.options.read().unwrap().indi.throttle_ui;
+                for event_data in self.prop_throttler.push(event_data, throttle) {
+                    self.process_prop_change_event(&event_data);
+                }
             }
 
             MainThreadEvent::Indi(indi::Event::DeviceDelete(event)) => {
@@ -201,6 +184,32 @@ impl FocuserUi {
         }
     }
 
+    fn process_prop_change_event(&self, event_data: &indi::PropChangeEvent) {
+        match &event_data.change {
+            indi::PropChange::New(value) =>
+                self.process_indi_prop_change(
+                    &event_data.device_name,
+                    &event_data.prop_name,
+                    &value.elem_name,
+                    true,
+                    None,
+                    None,
+                    &value.prop_value
+                ),
+            indi::PropChange::Change{ value, prev_state, new_state } =>
+                self.process_indi_prop_change(
+                    &event_data.device_name,
+                    &event_data.prop_name,
+                    &value.elem_name,
+                    false,
+                    Some(prev_state),
+                    Some(new_state),
+                    &value.prop_value
+                ),
+            indi::PropChange::Delete => {}
+        };
+    }
+
     fn process_indi_prop_change(
         &self,
         _device_name: &str,
@@ -255,6 +264,21 @@ impl FocuserUi {
         spb_foc_auto_step.set_digits(0);
         spb_foc_auto_step.set_increments(100.0, 1000.0);
 
+        let spb_foc_min_r2 = self.builder.object::<gtk::SpinButton>("spb_foc_min_r2").unwrap();
+        spb_foc_min_r2.set_range(0.0, 0.99);
+        spb_foc_min_r2.set_digits(2);
+        spb_foc_min_r2.set_increments(0.05, 0.1);
+
+        let spb_foc_temp_coeff = self.builder.object::<gtk::SpinButton>("spb_foc_temp_coeff").unwrap();
+        spb_foc_temp_coeff.set_range(-10_000.0, 10_000.0);
+        spb_foc_temp_coeff.set_digits(1);
+        spb_foc_temp_coeff.set_increments(1.0, 10.0);
+
+        let spb_foc_backlash = self.builder.object::<gtk::SpinButton>("spb_foc_backlash").unwrap();
+        spb_foc_backlash.set_range(0.0, 100_000.0);
+        spb_foc_backlash.set_digits(0);
+        spb_foc_backlash.set_increments(10.0, 100.0);
+
         let spb_foc_exp = self.builder.object::<gtk::SpinButton>("spb_foc_exp").unwrap();
         spb_foc_exp.set_range(0.1, 60.0);
         spb_foc_exp.set_digits(1);
@@ -306,6 +330,11 @@ impl FocuserUi {
             self_.correct_widgets_props();
         }));
 
+        let chb_foc_temp_comp = bldr.object::<gtk::CheckButton>("chb_foc_temp_comp").unwrap();
+        chb_foc_temp_comp.connect_active_notify(clone!(@weak self as self_ => move |_| {
+            self_.correct_widgets_props();
+        }));
+
         let da_focusing = self.builder.object::<gtk::DrawingArea>("da_focusing").unwrap();
         da_focusing.connect_draw(
             clone!(@weak self as self_ => @default-return glib::Propagation::Proceed,
@@ -324,6 +353,11 @@ impl FocuserUi {
 
                 UiEvent::OptionsHasShown =>
                     self_.correct_widgets_props(),
+
+                UiEvent::Timer =>
+                    for event_data in self_.prop_throttler.take() {
+                        self_.process_prop_change_event(&event_data);
+                    },
                 _ => {},
             }
         }));
@@ -355,12 +389,17 @@ impl FocuserUi {
         let can_change_mode = waiting || single_shot;
 
         let device_enabled = self.indi.is_device_enabled(focuser_device).unwrap_or(false);
+        let interrupt_en =
+            ui.prop_bool("chb_foc_temp.active") ||
+            ui.prop_bool("chb_foc_period.active");
 
         ui.enable_widgets(false, &[
             ("grd_foc",       device_enabled),
             ("spb_foc_temp",  ui.prop_bool("chb_foc_temp.active")),
             ("cb_foc_fwhm",   ui.prop_bool("chb_foc_fwhm.active")),
             ("cb_foc_period", ui.prop_bool("chb_foc_period.active")),
+            ("chb_foc_interrupt", interrupt_en),
+            ("spb_foc_temp_coeff", ui.prop_bool("chb_foc_temp_comp.active")),
             ("spb_foc_val",   !focusing),
             ("cb_foc_list",   !focusing),
         ]);
@@ -6,12 +6,13 @@ use std::{
     io::{prelude::*, BufWriter},
     fs::File,
     sync::{RwLock, Arc},
+    time::Duration,
 };
 use gtk::{prelude::*, gdk, glib, glib::clone};
 use itertools::Itertools;
 use chrono::prelude::*;
 use crate::{
-    core::core::Core, guiding::{external_guider::ExtGuiderType, phd2_conn}, indi, options::*, utils::gtk_utils
+    core::{core::Core, events::*}, guiding::{external_guider::ExtGuiderType, phd2_conn}, indi, options::*, utils::gtk_utils
 };
 use super::{ui_main::*, indi_widget::*};
 
@@ -102,6 +103,7 @@ impl indi::ConnState {
 enum HardwareEvent {
     Indi(indi::Event),
     Phd2(phd2_conn::Event),
+    Core(Event),
 }
 
 struct HardwareUi {
@@ -137,6 +139,21 @@ impl HardwareUi {
         spb_barlow.set_digits(2);
         spb_barlow.set_increments(0.01, 0.1);
 
+        let spb_blob_timeout = self.builder.object::<gtk::SpinButton>("spb_blob_timeout").unwrap();
+        spb_blob_timeout.set_range(0.0, 3600.0);
+        spb_blob_timeout.set_digits(0);
+        spb_blob_timeout.set_increments(10.0, 60.0);
+
+        let spb_mode_wd_timeout = self.builder.object::<gtk::SpinButton>("spb_mode_wd_timeout").unwrap();
+        spb_mode_wd_timeout.set_range(30.0, 3600.0);
+        spb_mode_wd_timeout.set_digits(0);
+        spb_mode_wd_timeout.set_increments(10.0, 60.0);
+
+        let spb_site_elev = self.builder.object::<gtk::SpinButton>("spb_site_elev").unwrap();
+        spb_site_elev.set_range(-500.0, 9000.0);
+        spb_site_elev.set_digits(0);
+        spb_site_elev.set_increments(1.0, 100.0);
+
         let spb_guid_foc_len = self.builder.object::<gtk::SpinButton>("spb_guid_foc_len").unwrap();
         spb_guid_foc_len.set_range(0.0, 1000.0);
         spb_guid_foc_len.set_digits(0);
@@ -147,6 +164,7 @@ impl HardwareUi {
         gtk_utils::connect_action(&self.window, self, "help_save_indi",        HardwareUi::handler_action_help_save_indi);
         gtk_utils::connect_action(&self.window, self, "conn_indi",             HardwareUi::handler_action_conn_indi);
         gtk_utils::connect_action(&self.window, self, "disconn_indi",          HardwareUi::handler_action_disconn_indi);
+        gtk_utils::connect_action_rc(&self.window, self, "emergency_shutdown", HardwareUi::handler_action_emergency_shutdown);
         gtk_utils::connect_action(&self.window, self, "conn_phd2",             HardwareUi::handler_action_conn_phd2);
         gtk_utils::connect_action(&self.window, self, "disconn_phd2",          HardwareUi::handler_action_disconn_phd2);
         gtk_utils::connect_action(&self.window, self, "clear_hw_log",          HardwareUi::handler_action_clear_hw_log);
@@ -154,14 +172,53 @@ impl HardwareUi {
         gtk_utils::connect_action(&self.window, self, "disable_all_devs",      HardwareUi::handler_action_disable_all_devices);
         gtk_utils::connect_action(&self.window, self, "save_devs_options",     HardwareUi::handler_action_save_devices_options);
         gtk_utils::connect_action(&self.window, self, "load_devs_options",     HardwareUi::handler_action_load_devices_options);
-        gtk_utils::connect_action(&self.window, self, "get_site_from_devices", HardwareUi::handler_action_get_site_from_devices);
-
+        gtk_utils::connect_action(&self.window, self, "get_site_from_mount",   HardwareUi::handler_action_get_site_from_mount);
+        gtk_utils::connect_action(&self.window, self, "get_site_from_gps",     HardwareUi::handler_action_get_site_from_gps);
+
+        for (entry_name, max_abs) in [("e_site_lat", 90.0), ("e_site_long", 180.0)] {
+            let entry = self.builder.object::<gtk::Entry>(entry_name).unwrap();
+            entry.connect_changed(move |entry| {
+                let is_ok = indi::parse_coord_value(&entry.text(), -max_abs, max_abs).is_some();
+                let style = entry.style_context();
+                if is_ok {
+                    style.remove_class("wrongvalue");
+                } else {
+                    style.add_class("wrongvalue");
+                }
+            });
+        }
 
         let chb_remote = self.builder.object::<gtk::CheckButton>("chb_remote").unwrap();
         chb_remote.connect_active_notify(clone!(@weak self as self_ => move |_| {
             self_.correct_widgets_by_cur_state();
         }));
 
+        let chb_mode_wd = self.builder.object::<gtk::CheckButton>("chb_mode_wd").unwrap();
+        chb_mode_wd.connect_active_notify(clone!(@weak self as self_ => move |_| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.read_mode_watchdog(&self_.builder);
+            drop(options);
+            self_.correct_widgets_by_cur_state();
+        }));
+
+        let chb_mode_wd_abort = self.builder.object::<gtk::CheckButton>("chb_mode_wd_abort").unwrap();
+        chb_mode_wd_abort.connect_active_notify(clone!(@weak self as self_ => move |_| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.read_mode_watchdog(&self_.builder);
+        }));
+
+        let spb_mode_wd_timeout = self.builder.object::<gtk::SpinButton>("spb_mode_wd_timeout").unwrap();
+        spb_mode_wd_timeout.connect_value_changed(clone!(@weak self as self_ => move |_| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.read_mode_watchdog(&self_.builder);
+        }));
+
+        let chb_indi_throttle = self.builder.object::<gtk::CheckButton>("chb_indi_throttle").unwrap();
+        chb_indi_throttle.connect_active_notify(clone!(@weak self as self_ => move |chb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.indi.throttle_ui = chb.is_active();
+        }));
+
         let se_hw_prop_name = self.builder.object::<gtk::SearchEntry>("se_hw_prop_name").unwrap();
         se_hw_prop_name.connect_search_changed(clone!(@weak self as self_ => move |se| {
             let text_lc = se.text().to_lowercase();
@@ -217,6 +274,12 @@ impl HardwareUi {
             sender_clone.send_blocking(HardwareEvent::Phd2(event)).unwrap();
         });
 
+        // Connect core events
+        let sender_clone = sender.clone();
+        self.core.event_subscriptions().subscribe(move |event| {
+            sender_clone.send_blocking(HardwareEvent::Core(event)).unwrap();
+        });
+
         // Process incoming events in main thread
         glib::spawn_future_local(clone!(@weak self as self_ => async move {
             while let Ok(event) = receiver.recv().await {
@@ -225,11 +288,20 @@ impl HardwareUi {
                         self_.process_indi_event(event),
                     HardwareEvent::Phd2(event) =>
                         self_.process_phd2_event(event),
+                    HardwareEvent::Core(event) =>
+                        self_.process_core_event(event),
                 };
             }
         }));
     }
 
+    fn process_core_event(&self, event: Event) {
+        if let Event::SiteChanged = event {
+            let options = self.options.read().unwrap();
+            options.show_site(&self.builder);
+        }
+    }
+
     fn process_indi_event(&self, event: indi::Event) {
         match event {
             indi::Event::ConnectionLost => {
@@ -294,23 +366,42 @@ impl HardwareUi {
                     },
                 };
             }
+            indi::Event::NewDevice(_) => {
+                self.correct_widgets_by_cur_state();
+            }
             indi::Event::DeviceDelete(event) => {
                 log::debug!("(-) {:20}", &event.device_name);
+                self.correct_widgets_by_cur_state();
             }
             indi::Event::Message(message) => {
-                log::debug!("indi: device={}, text={}", message.device_name, message.text);
                 self.add_log_record(
                     &message.timestamp,
                     &message.device_name,
                     &message.text
                 );
             }
+            indi::Event::ConnAttempt { attempt, max } => {
+                if *self.indi_status.borrow() == indi::ConnState::Connecting {
+                    let ui = gtk_utils::UiHelper::new_from_builder(&self.builder);
+                    ui.set_prop_str(
+                        "lbl_indi_conn_status.label",
+                        Some(&format!("Connecting (attempt {}/{})...", attempt, max))
+                    );
+                }
+            }
             indi::Event::ReadTimeOut => {
                 log::debug!("indi: read time out");
             }
             indi::Event::BlobStart(_) => {
                 log::debug!("indi: blob start");
             }
+            indi::Event::BlobTimeOut(event) => {
+                self.add_log_record(
+                    &Some(Utc::now()),
+                    &event.device_name,
+                    "Image downloading time out. Image is discarded"
+                );
+            }
             indi::Event::DeviceConnected(dev) => {
                 log::debug!(
                     "indi: device {} {}",
@@ -395,10 +486,22 @@ impl HardwareUi {
         );
         let phd2_working = self.core.phd2().is_working();
         gtk_utils::enable_actions(&self.window, &[
-            ("conn_indi",    conn_en),
-            ("disconn_indi", disconn_en),
-            ("conn_phd2",    !phd2_working),
-            ("disconn_phd2", phd2_working),
+            ("conn_indi",          conn_en),
+            ("disconn_indi",       disconn_en),
+            ("emergency_shutdown", connected && !self.core.is_emergency_shutdown_active()),
+            ("conn_phd2",          !phd2_working),
+            ("disconn_phd2",       phd2_working),
+        ]);
+
+        let gps_exists = connected && !self.indi.get_devices_list_by_interface(
+            indi::DriverInterface::GPS
+        ).is_empty();
+        let mount_exists = connected && !self.indi.get_devices_list_by_interface(
+            indi::DriverInterface::TELESCOPE
+        ).is_empty();
+        gtk_utils::enable_actions(&self.window, &[
+            ("get_site_from_mount", mount_exists),
+            ("get_site_from_gps",   gps_exists),
         ]);
         ui.set_prop_str("lbl_indi_conn_status.label", Some(&status.to_str(false)));
 
@@ -427,6 +530,13 @@ impl HardwareUi {
             ("cb_focuser_drivers",  foc_sensitive),
             ("chb_remote",          !self.indi_drivers.groups.is_empty() && disconnected),
             ("e_remote_addr",       remote && disconnected),
+            ("spb_blob_timeout",    disconnected),
+        ]);
+
+        let mode_wd_enabled = ui.prop_bool("chb_mode_wd.active");
+        ui.enable_widgets(false, &[
+            ("spb_mode_wd_timeout", mode_wd_enabled),
+            ("chb_mode_wd_abort",   mode_wd_enabled),
         ]);
 
         gtk_utils::enable_actions(&self.window, &[
@@ -486,6 +596,11 @@ impl HardwareUi {
                 remote:               options.indi.remote,
                 host:                 options.indi.address.clone(),
                 activate_all_devices: !options.indi.remote,
+                blob_timeout:         if options.indi.blob_timeout != 0 {
+                    Some(Duration::from_secs(options.indi.blob_timeout as u64))
+                } else {
+                    None
+                },
                 .. Default::default()
             };
             self.is_remote.set(options.indi.remote);
@@ -510,6 +625,33 @@ impl HardwareUi {
         });
     }
 
+    fn handler_action_emergency_shutdown(self: &Rc<Self>) {
+        let dialog = gtk::MessageDialog::builder()
+            .transient_for(&self.window)
+            .title("Emergency shutdown")
+            .text("Abort current operation, park mount, warm up camera and disconnect INDI?")
+            .modal(true)
+            .message_type(gtk::MessageType::Warning)
+            .build();
+        gtk_utils::add_ok_and_cancel_buttons(
+            dialog.upcast_ref::<gtk::Dialog>(),
+            "Shutdown", gtk::ResponseType::Yes,
+            "Cancel",   gtk::ResponseType::No,
+        );
+        dialog.show();
+
+        dialog.connect_response(clone!(@weak self as self_ =>
+            move |dlg, response| {
+            dlg.close();
+            if response != gtk::ResponseType::Yes {
+                return;
+            }
+            self_.main_ui.exec_before_disconnect_handlers();
+            self_.core.emergency_shutdown();
+            self_.correct_widgets_by_cur_state();
+        }));
+    }
+
     fn handler_action_conn_phd2(&self) {
         gtk_utils::exec_and_show_error(&self.window, || {
             self.read_options_from_widgets();
@@ -746,57 +888,74 @@ impl HardwareUi {
         });
     }
 
-    fn handler_action_get_site_from_devices(&self) {
+    fn handler_action_get_site_from_mount(&self) {
+        self.get_site_from_devices(
+            indi::DriverInterface::TELESCOPE,
+            "btn_get_site_from_mount"
+        );
+    }
+
+    fn handler_action_get_site_from_gps(&self) {
+        self.get_site_from_devices(
+            indi::DriverInterface::GPS,
+            "btn_get_site_from_gps"
+        );
+    }
+
+    fn get_site_from_devices(&self, iface: indi::DriverInterface, btn_name: &str) {
         gtk_utils::exec_and_show_error(&self.window, || {
             let indi = &self.indi;
             if indi.state() != indi::ConnState::Connected {
                 anyhow::bail!("INDI is not connected!");
             }
-            let devices = indi.get_devices_list_by_interface(
-                indi::DriverInterface::GPS |
-                indi::DriverInterface::TELESCOPE
-            );
+            let devices = indi.get_devices_list_by_interface(iface);
 
             let result: Vec<_> = devices
                 .iter()
                 .filter_map(|dev|
-                    indi.get_geo_lat_long_elev(&dev.name)
+                    indi.mount_get_geo_coords(&dev.name)
                         .ok()
-                        .map(|(lat,long,_)| (dev, lat, long))
+                        .map(|(lat, long, elev)| (dev, lat, long, elev))
                 )
-                .filter(|(_, lat,long)| *lat != 0.0 && *long != 0.0)
+                .filter(|(_, lat, long, _)| *lat != 0.0 && *long != 0.0)
                 .collect();
 
             if result.is_empty() {
-                anyhow::bail!("No GPS or geographic data found!");
+                anyhow::bail!("No geographic data found!");
             }
 
+            let set_site = |builder: &gtk::Builder, lat: f64, long: f64, elev: f64| {
+                // INDI uses 0..360 range for longitude
+                let long = if long > 180.0 { long - 360.0 } else { long };
+                let ui = gtk_utils::UiHelper::new_from_builder(builder);
+                ui.set_prop_str("e_site_lat.text", Some(&indi::value_to_sexagesimal(lat, true, 6)));
+                ui.set_prop_str("e_site_long.text", Some(&indi::value_to_sexagesimal(long, true, 6)));
+                ui.set_prop_f64("spb_site_elev.value", elev);
+            };
+
             if result.len() == 1 {
-                let (_, latitude, longitude) = result[0];
-                let ui = gtk_utils::UiHelper::new_from_builder(&self.builder);
-                ui.set_prop_str("e_site_lat.text", Some(&indi::value_to_sexagesimal(latitude, true, 6)));
-                ui.set_prop_str("e_site_long.text", Some(&indi::value_to_sexagesimal(longitude, true, 6)));
+                let (_, latitude, longitude, elevation) = result[0];
+                set_site(&self.builder, latitude, longitude, elevation);
                 return Ok(());
             }
 
             let menu = gtk::Menu::new();
-            for (dev, lat, long) in result {
+            for (dev, lat, long, elev) in result {
                 let mi_text = format!(
-                    "{} {} ({})",
+                    "{} {} {:.0}m ({})",
                     indi::value_to_sexagesimal(lat, true, 6),
                     indi::value_to_sexagesimal(long, true, 6),
+                    elev,
                     dev.name
                 );
                 let menu_item = gtk::MenuItem::builder().label(mi_text).build();
                 menu.append(&menu_item);
                 let builder = self.builder.clone();
                 menu_item.connect_activate(move |_| {
-                    let ui = gtk_utils::UiHelper::new_from_builder(&builder);
-                    ui.set_prop_str("e_site_lat.text", Some(&indi::value_to_sexagesimal(lat, true, 6)));
-                    ui.set_prop_str("e_site_lat.text", Some(&indi::value_to_sexagesimal(long, true, 6)));
+                    set_site(&builder, lat, long, elev);
                 });
             }
-            let menu_widget = self.builder.object::<gtk::Widget>("btn_get_site_from_devices").unwrap();
+            let menu_widget = self.builder.object::<gtk::Widget>(btn_name).unwrap();
             menu.set_attach_widget(Some(&menu_widget));
             menu.show_all();
             menu.popup_easy(gtk::gdk::ffi::GDK_BUTTON_SECONDARY as u32, 0);
@@ -129,6 +129,7 @@ pub fn init_ui(
     );
 
     data.connect_widgets_events();
+    data.init_accelerators(app);
     data.correct_widgets_props();
     data.connect_state_events();
     data.update_window_title();
@@ -223,6 +224,9 @@ const CSS: &[u8] = b"
 .bold {
   font-weight: bold;
 }
+.wrongvalue {
+    background: rgba(255, 0, 0, .3);
+}
 ";
 
 #[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
@@ -232,12 +236,25 @@ enum Theme {
     Light,
 }
 
+/// Main window geometry for one configuration of monitors
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+struct WindowGeometry {
+    monitors:  String, // see `monitors_config`
+    left:      i32,
+    top:       i32,
+    width:     i32,
+    height:    i32,
+    maximized: bool,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(default)]
 struct UiOptions {
-    win_width:     i32,
+    win_width:     i32, // used if there is no geometry for current monitors
     win_height:    i32,
     win_maximized: bool,
+    win_geometry:  Vec<WindowGeometry>,
     theme:         Theme,
 }
 
@@ -247,6 +264,7 @@ impl Default for UiOptions {
             win_width:     -1,
             win_height:    -1,
             win_maximized: false,
+            win_geometry:  Vec::new(),
             theme:         Theme::default(),
         }
     }
@@ -279,6 +297,41 @@ impl MainUi {
     const CONF_FN: &'static str = "ui_common";
     const OPTIONS_FN: &'static str = "options";
 
+    const ACCELERATORS: &'static [(&'static str, &'static str, &'static str)] = &[
+        // action                  accel    menu item
+        ("win.take_shot_or_stop", "space", "mi_take_shot_or_stop"),
+        ("win.toggle_live_view",  "l",     "mi_toggle_live_view"),
+        ("win.manual_focus",      "f",     "mi_start_focusing"),
+    ];
+
+    fn init_accelerators(&self, app: &gtk::Application) {
+        for &(action, accel, mi_name) in Self::ACCELERATORS {
+            app.set_accels_for_action(action, &[accel]);
+
+            // Show accelerator in menu
+            let mi = self.builder.object::<gtk::MenuItem>(mi_name).unwrap();
+            if let Some(label) = mi.child().and_then(|w| w.downcast::<gtk::AccelLabel>().ok()) {
+                let (key, mods) = gtk::accelerator_parse(accel);
+                label.set_accel(key, mods);
+            }
+        }
+
+        // Accelerators without modifiers must not steal keys from text entries
+        self.window.connect_key_press_event(|window, event| {
+            let with_modifiers = event.state().intersects(
+                gtk::gdk::ModifierType::CONTROL_MASK |
+                gtk::gdk::ModifierType::MOD1_MASK
+            );
+            let text_is_focused = window.focused_widget()
+                .map(|widget| widget.is::<gtk::Editable>() || widget.is::<gtk::TextView>())
+                .unwrap_or(false);
+            if !with_modifiers && text_is_focused && window.propagate_key_event(event) {
+                return glib::Propagation::Stop;
+            }
+            glib::Propagation::Proceed
+        });
+    }
+
     fn connect_widgets_events(self: &Rc<Self>) {
         let mi_dark_theme = self.builder.object::<gtk::RadioMenuItem>("mi_dark_theme").unwrap();
         mi_dark_theme.connect_activate(clone!(@weak self as self_ => move |mi| {
@@ -364,6 +417,17 @@ impl MainUi {
                             &err
                         );
                     }
+                    Event::Warning(text) => {
+                        gtk_utils::show_message(
+                            &self_.window,
+                            "Warning",
+                            &text,
+                            gtk::MessageType::Warning
+                        );
+                    }
+                    Event::ModeStuck(mode_type, progress_str) => {
+                        self_.show_mode_stuck_dialog(mode_type, &progress_str);
+                    }
                     Event::ModeChanged => {
                         self_.correct_widgets_props();
                         self_.show_mode_caption();
@@ -380,6 +444,31 @@ impl MainUi {
         }));
     }
 
+    fn show_mode_stuck_dialog(self: &Rc<Self>, mode_type: ModeType, progress_str: &str) {
+        let dialog = gtk::MessageDialog::builder()
+            .transient_for(&self.window)
+            .title("Operation is stuck")
+            .text(format!("{} has no progress for a long time.\nAbort it?", progress_str))
+            .modal(true)
+            .message_type(gtk::MessageType::Warning)
+            .build();
+        gtk_utils::add_ok_and_cancel_buttons(
+            dialog.upcast_ref::<gtk::Dialog>(),
+            "Abort", gtk::ResponseType::Yes,
+            "Continue", gtk::ResponseType::No,
+        );
+        dialog.show();
+
+        dialog.connect_response(clone!(@weak self as self_ =>
+            move |dlg, response| {
+            if response == gtk::ResponseType::Yes
+            && self_.core.mode_data().mode.get_type() == mode_type {
+                self_.core.abort_active_mode();
+            }
+            dlg.close();
+        }));
+    }
+
     fn handler_close_window(self: &Rc<Self>) -> glib::Propagation {
         if self.core.mode_data().mode.get_type() != ModeType::Waiting {
             let dialog = gtk::MessageDialog::builder()
@@ -432,12 +521,34 @@ impl MainUi {
     fn apply_options(&self) {
         let options = self.ui_options.borrow();
 
-        if options.win_width != -1 && options.win_height != -1 {
-            self.window.resize(options.win_width, options.win_height);
-        }
-
-        if options.win_maximized {
-            self.window.maximize();
+        let display = self.window.display();
+        let monitors = monitors_config(&display);
+        let work_areas = monitors_work_areas(&display);
+        let geometry = options.win_geometry
+            .iter()
+            .find(|geometry| geometry.monitors == monitors);
+        if let Some(geometry) = geometry {
+            log::info!("Restoring main window geometry for monitors {}", monitors);
+            let (left, top, width, height) = clamp_window_rect(
+                (geometry.left, geometry.top, geometry.width, geometry.height),
+                &work_areas
+            );
+            self.window.move_(left, top);
+            self.window.resize(width, height);
+            if geometry.maximized {
+                self.window.maximize();
+            }
+        } else {
+            if options.win_width != -1 && options.win_height != -1 {
+                let (_, _, width, height) = clamp_window_rect(
+                    (0, 0, options.win_width, options.win_height),
+                    &work_areas
+                );
+                self.window.resize(width, height);
+            }
+            if options.win_maximized {
+                self.window.maximize();
+            }
         }
 
         let mi_dark_theme = self.builder.object::<gtk::RadioMenuItem>("mi_dark_theme").unwrap();
@@ -460,9 +571,35 @@ impl MainUi {
     fn read_ui_options_from_widgets(&self) {
         let mut options = self.ui_options.borrow_mut();
         let (width, height) = self.window.size();
+        let (left, top) = self.window.position();
+        let maximized = self.window.is_maximized();
         options.win_width = width;
         options.win_height = height;
-        options.win_maximized = self.window.is_maximized();
+        options.win_maximized = maximized;
+
+        let monitors = monitors_config(&self.window.display());
+        let index = options.win_geometry
+            .iter()
+            .position(|geometry| geometry.monitors == monitors);
+        let geometry = if let Some(index) = index {
+            &mut options.win_geometry[index]
+        } else {
+            options.win_geometry.push(WindowGeometry {
+                monitors,
+                left, top, width, height,
+                maximized: false,
+            });
+            options.win_geometry.last_mut().unwrap()
+        };
+        // Size and position of maximized window are not stored
+        // to restore normal geometry after unmaximizing
+        if !maximized {
+            geometry.left = left;
+            geometry.top = top;
+            geometry.width = width;
+            geometry.height = height;
+        }
+        geometry.maximized = maximized;
     }
 
     fn handler_draw_progress(
@@ -586,3 +723,53 @@ impl MainUi {
         TabPage::from_tab_index(page_index)
     }
 }
+
+/// Key of current monitors configuration like `1920x1080+0+0,1280x1024+1920+0`
+fn monitors_config(display: &gtk::gdk::Display) -> String {
+    (0..display.n_monitors())
+        .filter_map(|i| display.monitor(i))
+        .map(|monitor| {
+            let rect = monitor.geometry();
+            format!("{}x{}+{}+{}", rect.width(), rect.height(), rect.x(), rect.y())
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Visible areas of monitors (without panels and docks) as (left, top, width, height)
+fn monitors_work_areas(display: &gtk::gdk::Display) -> Vec<(i32, i32, i32, i32)> {
+    (0..display.n_monitors())
+        .filter_map(|i| display.monitor(i))
+        .map(|monitor| {
+            let rect = monitor.workarea();
+            (rect.x(), rect.y(), rect.width(), rect.height())
+        })
+        .collect()
+}
+
+/// Moves and shrinks window rectangle to fit into work area of monitor
+/// it overlaps most of all (or first monitor if there is no overlapping)
+fn clamp_window_rect(
+    rect:       (i32, i32, i32, i32),
+    work_areas: &[(i32, i32, i32, i32)],
+) -> (i32, i32, i32, i32) {
+    let (left, top, width, height) = rect;
+    let overlapping = |area: &&(i32, i32, i32, i32)| {
+        let (a_left, a_top, a_width, a_height) = **area;
+        let w = i32::min(left + width, a_left + a_width) - i32::max(left, a_left);
+        let h = i32::min(top + height, a_top + a_height) - i32::max(top, a_top);
+        i32::max(w, 0) as i64 * i32::max(h, 0) as i64
+    };
+    let best_area = work_areas.iter()
+        .max_by_key(overlapping)
+        .filter(|area| overlapping(area) > 0)
+        .or(work_areas.first());
+    let Some(&(a_left, a_top, a_width, a_height)) = best_area else {
+        return rect;
+    };
+    let width = width.clamp(1, i32::max(a_width, 1));
+    let height = height.clamp(1, i32::max(a_height, 1));
+    let left = left.clamp(a_left, a_left + a_width - width);
+    let top = top.clamp(a_top, a_top + a_height - height);
+    (left, top, width, height)
+}
@@ -1,11 +1,14 @@
 use std::{cell::{Cell, RefCell}, rc::Rc, sync::{Arc, RwLock}};
 use gtk::{glib, prelude::*, glib::clone};
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     core::{consts::INDI_SET_PROP_TIMEOUT, core::{Core, ModeType}, events::*},
     indi,
     options::*,
+    plate_solve::PlateSolveOkResult,
+    ui::sky_map::math::*,
     utils::{gtk_utils, io_utils::*},
 };
 
@@ -38,6 +41,8 @@ pub fn init_ui(
         delayed_actions: DelayedActions::new(500),
         ui_options:      RefCell::new(ui_options),
         closed:          Cell::new(false),
+        prop_throttler:  PropChangeThrottler::new(),
+        ps_result:       RefCell::new(None),
         indi_evt_conn:   RefCell::new(None),
         self_:           RefCell::new(None),
     });
@@ -58,6 +63,11 @@ pub fn init_ui(
             UiEvent::OptionsHasShown =>
                 data.correct_widgets_props(),
 
+            UiEvent::Timer =>
+                for event_data in data.prop_throttler.take() {
+                    data.process_prop_change_event(&event_data);
+                },
+
             _ => {},
         }
     }));
@@ -84,6 +94,8 @@ struct MountUi {
     delayed_actions: DelayedActions<DelayedAction>,
     ui_options:      RefCell<UiOptions>,
     closed:          Cell<bool>,
+    prop_throttler:  PropChangeThrottler,
+    ps_result:       RefCell<Option<PlateSolveOkResult>>,
     indi_evt_conn:   RefCell<Option<indi::Subscription>>,
     self_:           RefCell<Option<Rc<MountUi>>>,
 }
@@ -99,6 +111,7 @@ enum DelayedAction {
     FillDevicesList,
     CorrectWidgetsProps,
     FillMountSpdList,
+    ApplyConnectAction,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -118,6 +131,11 @@ impl Default for UiOptions {
 impl MountUi {
     const CONF_FN: &'static str = "ui_mount";
 
+    const DEVICES_INTERFACE: indi::DriverInterface =
+        indi::DriverInterface::TELESCOPE
+            .union(indi::DriverInterface::DOME)
+            .union(indi::DriverInterface::WEATHER);
+
     const MOUNT_NAV_BUTTON_NAMES: &'static [&'static str] = &[
         "btn_left_top",    "btn_top",        "btn_right_top",
         "btn_left",        "btn_stop_mount", "btn_right",
@@ -125,6 +143,45 @@ impl MountUi {
     ];
 
     fn init_widgets(&self) {
+        let spb_mnt_conn_delay = self.builder.object::<gtk::SpinButton>("spb_mnt_conn_delay").unwrap();
+        spb_mnt_conn_delay.set_range(0.0, 600.0);
+        spb_mnt_conn_delay.set_digits(0);
+        spb_mnt_conn_delay.set_increments(1.0, 10.0);
+
+        let spb_mnt_merid_limit = self.builder.object::<gtk::SpinButton>("spb_mnt_merid_limit").unwrap();
+        spb_mnt_merid_limit.set_range(-30.0, 30.0);
+        spb_mnt_merid_limit.set_digits(1);
+        spb_mnt_merid_limit.set_increments(0.5, 5.0);
+
+        let spb_mnt_flip_limit = self.builder.object::<gtk::SpinButton>("spb_mnt_flip_limit").unwrap();
+        spb_mnt_flip_limit.set_range(-30.0, 30.0);
+        spb_mnt_flip_limit.set_digits(1);
+        spb_mnt_flip_limit.set_increments(0.5, 5.0);
+
+        let spb_weather_max_clouds = self.builder.object::<gtk::SpinButton>("spb_weather_max_clouds").unwrap();
+        spb_weather_max_clouds.set_range(0.0, 100.0);
+        spb_weather_max_clouds.set_digits(0);
+        spb_weather_max_clouds.set_increments(5.0, 20.0);
+
+        let spb_weather_max_rain = self.builder.object::<gtk::SpinButton>("spb_weather_max_rain").unwrap();
+        spb_weather_max_rain.set_range(0.0, 100.0);
+        spb_weather_max_rain.set_digits(1);
+        spb_weather_max_rain.set_increments(0.1, 1.0);
+
+        let spb_weather_max_wind = self.builder.object::<gtk::SpinButton>("spb_weather_max_wind").unwrap();
+        spb_weather_max_wind.set_range(0.0, 200.0);
+        spb_weather_max_wind.set_digits(0);
+        spb_weather_max_wind.set_increments(1.0, 10.0);
+
+        let spb_mnt_settle_tol = self.builder.object::<gtk::SpinButton>("spb_mnt_settle_tol").unwrap();
+        spb_mnt_settle_tol.set_range(0.5, 120.0);
+        spb_mnt_settle_tol.set_digits(1);
+        spb_mnt_settle_tol.set_increments(0.5, 5.0);
+
+        let spb_mnt_settle_samples = self.builder.object::<gtk::SpinButton>("spb_mnt_settle_samples").unwrap();
+        spb_mnt_settle_samples.set_range(2.0, 10.0);
+        spb_mnt_settle_samples.set_digits(0);
+        spb_mnt_settle_samples.set_increments(1.0, 2.0);
     }
 
     fn connect_core_and_indi_events(self: &Rc<Self>) {
@@ -205,6 +262,126 @@ impl MountUi {
                 self_.correct_widgets_props();
             });
         }));
+
+        let read_mount_options = clone!(@weak self as self_ => move || {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.read_mount(&self_.builder);
+        });
+        let chb_mnt_merid_stop = self.builder.object::<gtk::CheckButton>("chb_mnt_merid_stop").unwrap();
+        chb_mnt_merid_stop.connect_active_notify(clone!(@strong read_mount_options => move |_| {
+            read_mount_options();
+        }));
+        let chb_mnt_merid_park = self.builder.object::<gtk::CheckButton>("chb_mnt_merid_park").unwrap();
+        chb_mnt_merid_park.connect_active_notify(clone!(@strong read_mount_options => move |_| {
+            read_mount_options();
+        }));
+        let chb_mnt_flip_recenter = self.builder.object::<gtk::CheckButton>("chb_mnt_flip_recenter").unwrap();
+        chb_mnt_flip_recenter.connect_active_notify(clone!(@strong read_mount_options => move |_| {
+            read_mount_options();
+        }));
+        let chb_mnt_auto_flip = self.builder.object::<gtk::CheckButton>("chb_mnt_auto_flip").unwrap();
+        chb_mnt_auto_flip.connect_active_notify(clone!(@strong read_mount_options => move |_| {
+            read_mount_options();
+        }));
+        let spb_mnt_merid_limit = self.builder.object::<gtk::SpinButton>("spb_mnt_merid_limit").unwrap();
+        spb_mnt_merid_limit.connect_value_changed(clone!(@strong read_mount_options => move |_| {
+            read_mount_options();
+        }));
+        let spb_mnt_flip_limit = self.builder.object::<gtk::SpinButton>("spb_mnt_flip_limit").unwrap();
+        spb_mnt_flip_limit.connect_value_changed(clone!(@strong read_mount_options => move |_| {
+            read_mount_options();
+        }));
+        let chb_mnt_use_pt_model = self.builder.object::<gtk::CheckButton>("chb_mnt_use_pt_model").unwrap();
+        chb_mnt_use_pt_model.connect_active_notify(move |_| {
+            read_mount_options();
+        });
+
+        let cb_dome_list = self.builder.object::<gtk::ComboBoxText>("cb_dome_list").unwrap();
+        cb_dome_list.connect_active_id_notify(clone!(@weak self as self_ => move |cb| {
+            let Some(cur_id) = cb.active_id() else { return; };
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            if options.dome.device == cur_id.as_str() { return; }
+            options.dome.device = cur_id.to_string();
+            drop(options);
+            self_.show_cur_dome_state();
+            self_.correct_widgets_props();
+        }));
+
+        let chb_dome_slaving = self.builder.object::<gtk::CheckButton>("chb_dome_slaving").unwrap();
+        chb_dome_slaving.connect_active_notify(clone!(@weak self as self_ => move |chb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.dome.slaving = chb.is_active();
+        }));
+
+        let chb_dome_parked = self.builder.object::<gtk::CheckButton>("chb_dome_parked").unwrap();
+        chb_dome_parked.connect_active_notify(clone!(@weak self as self_ => move |chb| {
+            self_.excl.exec(|| {
+                let options = self_.options.read().unwrap();
+                if options.dome.device.is_empty() { return; }
+                gtk_utils::exec_and_show_error(&self_.window, || {
+                    self_.indi.dome_set_parked(&options.dome.device, chb.is_active(), true, None)?;
+                    Ok(())
+                });
+                drop(options);
+                self_.correct_widgets_props();
+            });
+        }));
+
+        let connect_dome_btn = |btn_name: &str, fun: fn(&indi::Connection, &str) -> indi::Result<()>| {
+            let btn = self.builder.object::<gtk::Button>(btn_name).unwrap();
+            btn.connect_clicked(clone!(@weak self as self_ => move |_| {
+                let options = self_.options.read().unwrap();
+                if options.dome.device.is_empty() { return; }
+                gtk_utils::exec_and_show_error(&self_.window, || {
+                    fun(&self_.indi, &options.dome.device)?;
+                    Ok(())
+                });
+            }));
+        };
+        connect_dome_btn("btn_dome_cw",   indi::Connection::dome_start_move_cw);
+        connect_dome_btn("btn_dome_ccw",  indi::Connection::dome_start_move_ccw);
+        connect_dome_btn("btn_dome_stop", indi::Connection::dome_abort_motion);
+
+        let cb_weather_list = self.builder.object::<gtk::ComboBoxText>("cb_weather_list").unwrap();
+        cb_weather_list.connect_active_id_notify(clone!(@weak self as self_ => move |cb| {
+            let Some(cur_id) = cb.active_id() else { return; };
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            if options.weather.device == cur_id.as_str() { return; }
+            options.weather.device = cur_id.to_string();
+            drop(options);
+            self_.show_weather_state();
+            self_.correct_widgets_props();
+        }));
+
+        let read_weather_options = clone!(@weak self as self_ => move || {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.read_weather(&self_.builder);
+        });
+        let chb_weather_monitor = self.builder.object::<gtk::CheckButton>("chb_weather_monitor").unwrap();
+        chb_weather_monitor.connect_active_notify(clone!(@weak self as self_, @strong read_weather_options => move |_| {
+            read_weather_options();
+            self_.correct_widgets_props();
+        }));
+        let chb_weather_park = self.builder.object::<gtk::CheckButton>("chb_weather_park").unwrap();
+        chb_weather_park.connect_active_notify(clone!(@strong read_weather_options => move |_| {
+            read_weather_options();
+        }));
+        for spb_name in ["spb_weather_max_clouds", "spb_weather_max_rain", "spb_weather_max_wind"] {
+            let spb = self.builder.object::<gtk::SpinButton>(spb_name).unwrap();
+            spb.connect_value_changed(clone!(@strong read_weather_options => move |_| {
+                read_weather_options();
+            }));
+        }
+
+        let btn_mnt_add_align_point = self.builder.object::<gtk::Button>("btn_mnt_add_align_point").unwrap();
+        btn_mnt_add_align_point.connect_clicked(clone!(@weak self as self_ => move |_| {
+            self_.handler_add_align_point();
+        }));
+
+        let btn_mnt_clear_pt_model = self.builder.object::<gtk::Button>("btn_mnt_clear_pt_model").unwrap();
+        btn_mnt_clear_pt_model.connect_clicked(clone!(@weak self as self_ => move |_| {
+            self_.handler_clear_pointing_model();
+        }));
     }
 
     fn correct_widgets_props(&self) {
@@ -242,6 +419,39 @@ impl MountUi {
         for &btn_name in Self::MOUNT_NAV_BUTTON_NAMES {
             ui.set_prop_bool_ex(btn_name, "sensitive", move_enabled);
         }
+
+        let align_supported = self.indi.mount_get_align_info(&mount).ok().flatten().is_some();
+        let have_ps_result = self.ps_result.borrow().is_some();
+        ui.enable_widgets(false, &[
+            ("btn_mnt_add_align_point", mount_ctrl_sensitive && align_supported && have_ps_result),
+        ]);
+
+        let have_pt_model_points = self.options.read().unwrap().mount
+            .pointing_model(&mount)
+            .map(|model| !model.points.is_empty())
+            .unwrap_or(false);
+        ui.enable_widgets(false, &[
+            ("btn_mnt_clear_pt_model", (waiting || live_view) && have_pt_model_points),
+        ]);
+
+        let dome = self.options.read().unwrap().dome.device.clone();
+        let dome_active = indi_connected && self.indi.is_device_enabled(&dome).unwrap_or(false);
+        let dome_move_enabled = dome_active && !ui.prop_bool("chb_dome_parked.active");
+        ui.enable_widgets(false, &[
+            ("chb_dome_parked",  dome_active),
+            ("btn_dome_cw",      dome_move_enabled),
+            ("btn_dome_ccw",     dome_move_enabled),
+            ("btn_dome_stop",    dome_active),
+            ("chb_dome_slaving", dome_active),
+        ]);
+
+        let weather_monitor = ui.prop_bool("chb_weather_monitor.active");
+        ui.enable_widgets(false, &[
+            ("spb_weather_max_clouds", weather_monitor),
+            ("spb_weather_max_rain",   weather_monitor),
+            ("spb_weather_max_wind",   weather_monitor),
+            ("chb_weather_park",       weather_monitor),
+        ]);
     }
 
     fn handler_closing(&self) {
@@ -274,17 +484,28 @@ impl MountUi {
     fn process_event_in_main_thread(&self, event: MainThreadEvent) {
         match event {
             MainThreadEvent::Indi(indi::Event::NewDevice(event)) =>
-                if event.interface.contains(indi::DriverInterface::TELESCOPE) {
+                if event.interface.intersects(Self::DEVICES_INTERFACE) {
                     self.delayed_actions.schedule(DelayedAction::FillDevicesList);
                 },
 
-            MainThreadEvent::Indi(indi::Event::DeviceConnected(event)) =>
+            MainThreadEvent::Indi(indi::Event::DeviceConnected(event)) => {
                 if event.interface.contains(indi::DriverInterface::TELESCOPE) {
                     self.delayed_actions.schedule(DelayedAction::CorrectWidgetsProps);
-                },
+                    if event.connected {
+                        self.schedule_connect_action(&event.device_name);
+                    }
+                }
+                if event.interface.contains(indi::DriverInterface::DOME) {
+                    self.delayed_actions.schedule(DelayedAction::CorrectWidgetsProps);
+                }
+                if event.interface.contains(indi::DriverInterface::WEATHER) {
+                    self.delayed_actions.schedule(DelayedAction::CorrectWidgetsProps);
+                    self.show_weather_state();
+                }
+            }
 
             MainThreadEvent::Indi(indi::Event::DeviceDelete(event)) => {
-                if event.drv_interface.contains(indi::DriverInterface::TELESCOPE) {
+                if event.drv_interface.intersects(Self::DEVICES_INTERFACE) {
                     self.delayed_actions.schedule(DelayedAction::FillDevicesList);
                     self.delayed_actions.schedule(DelayedAction::CorrectWidgetsProps);
                 }
@@ -297,32 +518,20 @@ impl MountUi {
             }
 
             MainThreadEvent::Indi(indi::Event::PropChange(event_data)) => {
-                match &event_data.change {
-                    indi::PropChange::New(value) =>
-                        self.process_indi_prop_change(
-                            &event_data.device_name,
-                            &event_data.prop_name,
-                            &value.elem_name,
-                            true,
-                            None,
-                            None,
-                            &value.prop_value
-                        ),
-                    indi::PropChange::Change{ value, prev_state, new_state } =>
-                        self.process_indi_prop_change(
-                            &event_data.device_name,
-                            &event_data.prop_name,
-                            &value.elem_name,
-                            false,
-                            Some(prev_state),
-                            Some(new_state),
-                            &value.prop_value
-                        ),
-                    indi::PropChange::Delete => {}
-                };
+                let throttle = self.options.read().unwrap().indi.throttle_ui;
+                for event_data in self.prop_throttler.push(event_data, throttle) {
+                    self.process_prop_change_event(&event_data);
+                }
             }
 
             MainThreadEvent::Core(Event::ModeChanged) => {
+                self.show_pointing_model_info();
+                self.correct_widgets_props();
+            }
+
+            MainThreadEvent::Core(Event::PlateSolve(ps_event)) => {
+                *self.ps_result.borrow_mut() = Some(ps_event.result);
+                self.show_pointing_model_info();
                 self.correct_widgets_props();
             }
             _ => {}
@@ -431,6 +640,49 @@ impl MountUi {
                 options.mount.device = id.to_string();
             }
         );
+
+        let options = self.options.read().unwrap();
+        let cur_dome = options.dome.device.clone();
+        drop(options);
+
+        let cb = self.builder.object::<gtk::ComboBoxText>("cb_dome_list").unwrap();
+        let list = self.indi
+            .get_devices_list_by_interface(indi::DriverInterface::DOME)
+            .iter()
+            .map(|dev| dev.name.to_string())
+            .collect();
+        fill_devices_list_into_combobox(
+            &list,
+            &cb,
+            if !cur_dome.is_empty() { Some(cur_dome.as_str()) } else { None },
+            connected,
+            |id| {
+                let Ok(mut options) = self.options.try_write() else { return; };
+                options.dome.device = id.to_string();
+            }
+        );
+
+        let options = self.options.read().unwrap();
+        let cur_weather = options.weather.device.clone();
+        drop(options);
+
+        let cb = self.builder.object::<gtk::ComboBoxText>("cb_weather_list").unwrap();
+        let list = self.indi
+            .get_devices_list_by_interface(indi::DriverInterface::WEATHER)
+            .iter()
+            .map(|dev| dev.name.to_string())
+            .collect();
+        fill_devices_list_into_combobox(
+            &list,
+            &cb,
+            if !cur_weather.is_empty() { Some(cur_weather.as_str()) } else { None },
+            connected,
+            |id| {
+                let Ok(mut options) = self.options.try_write() else { return; };
+                options.weather.device = id.to_string();
+            }
+        );
+        self.show_weather_state();
     }
 
     fn fill_mount_speed_list_widget(&self) {
@@ -480,9 +732,113 @@ impl MountUi {
             let tracking = self.indi.mount_get_tracking(&device).unwrap_or(false);
             self.show_mount_tracking_state(tracking);
         });
+        self.show_mount_align_info();
+        self.show_pointing_model_info();
+    }
+
+    fn show_mount_align_info(&self) {
+        let device = self.options.read().unwrap().mount.device.clone();
+        let align_info = self.indi.mount_get_align_info(&device).ok().flatten();
+        let text = match align_info {
+            Some(info) => format!(
+                "Alignment: {} point(s){}",
+                info.points,
+                if info.active { "" } else { " (not active)" }
+            ),
+            None =>
+                "Alignment: not supported".to_string(),
+        };
+        let ui = gtk_utils::UiHelper::new_from_builder(&self.builder);
+        ui.set_prop_str("l_mnt_align.label", Some(&text));
+    }
+
+    fn show_pointing_model_info(&self) {
+        let options = self.options.read().unwrap();
+        let points = options.mount
+            .pointing_model(&options.mount.device)
+            .map(|model| model.points.len())
+            .unwrap_or(0);
+        drop(options);
+        let ui = gtk_utils::UiHelper::new_from_builder(&self.builder);
+        ui.set_prop_str("l_mnt_pt_model.label", Some(&format!("{} point(s)", points)));
+    }
+
+    fn handler_clear_pointing_model(&self) {
+        let mut options = self.options.write().unwrap();
+        let device = options.mount.device.clone();
+        if device.is_empty() { return; }
+        options.mount.pointing_model_mut(&device).clear();
+        drop(options);
+        log::info!("Pointing model of {} is cleared", device);
+        self.show_pointing_model_info();
+        self.correct_widgets_props();
+    }
+
+    fn handler_add_align_point(&self) {
+        let Some(ps_result) = self.ps_result.borrow().clone() else { return; };
+        let mount = self.options.read().unwrap().mount.device.clone();
+        if mount.is_empty() { return; }
+        gtk_utils::exec_and_show_error(&self.window, || {
+            // Sync adds point into mount alignment model
+            self.indi.set_after_coord_set_action(
+                &mount,
+                indi::AfterCoordSetAction::Sync,
+                true,
+                INDI_SET_PROP_TIMEOUT
+            )?;
+            self.indi.mount_set_eq_coord(
+                &mount,
+                radian_to_hour(ps_result.crd_now.ra),
+                radian_to_degree(ps_result.crd_now.dec),
+                true,
+                INDI_SET_PROP_TIMEOUT
+            )?;
+            Ok(())
+        });
+    }
+
+    fn show_dome_parked_state(&self, parked: bool) {
+        self.excl.exec(|| {
+            let ui = gtk_utils::UiHelper::new_from_builder(&self.builder);
+            ui.set_prop_bool("chb_dome_parked.active", parked);
+        });
+    }
+
+    fn show_cur_dome_state(&self) {
+        let device = self.options.read().unwrap().dome.device.clone();
+        let parked = self.indi.dome_get_parked(&device).unwrap_or(false);
+        self.show_dome_parked_state(parked);
+    }
+
+    fn show_weather_state(&self) {
+        let ui = gtk_utils::UiHelper::new_from_builder(&self.builder);
+        let device = self.options.read().unwrap().weather.device.clone();
+        let active = !device.is_empty() && self.indi.is_device_enabled(&device).unwrap_or(false);
+        let state_str = if !active {
+            "---".to_string()
+        } else {
+            match self.indi.weather_get_alerts(&device) {
+                Ok(alerts) if alerts.is_empty() =>
+                    "Status: OK".to_string(),
+                Ok(alerts) =>
+                    format!("Status: alert ({})", alerts.iter().join(", ")),
+                Err(_) =>
+                    "---".to_string(),
+            }
+        };
+        let params_str = self.indi.weather_get_parameters(&device)
+            .unwrap_or_default()
+            .iter()
+            .map(|(name, value)| format!("{} = {:.1}", name, value))
+            .join("\n");
+        ui.set_prop_str("l_weather_state.label", Some(&state_str));
+        ui.set_prop_str(
+            "l_weather_state.tooltip-text",
+            if !params_str.is_empty() { Some(params_str.as_str()) } else { None }
+        );
     }
 
-    fn handler_delayed_action(&self, action: &DelayedAction) {
+    fn handler_delayed_action(self: &Rc<Self>, action: &DelayedAction) {
         match action {
             DelayedAction::CorrectWidgetsProps => {
                 self.correct_widgets_props();
@@ -493,7 +849,113 @@ impl MountUi {
             DelayedAction::FillDevicesList => {
                 self.fill_devices_list();
             }
+            DelayedAction::ApplyConnectAction => {
+                self.ask_and_apply_connect_action();
+            }
+        }
+    }
+
+    fn schedule_connect_action(&self, device_name: &str) {
+        let mut options = self.options.write().unwrap();
+        options.read_mount(&self.builder);
+        if options.mount.device != device_name
+        || options.mount.connect_action == MountConnectAction::LeaveAsIs {
+            return;
+        }
+        let delay_ms = 1000 * options.mount.connect_delay as u64;
+        drop(options);
+        log::info!(
+            "Mount {} connected. Connect action will be applied in {} ms",
+            device_name, delay_ms
+        );
+        self.delayed_actions.schedule_ex(DelayedAction::ApplyConnectAction, delay_ms);
+    }
+
+    fn ask_and_apply_connect_action(self: &Rc<Self>) {
+        let options = self.options.read().unwrap();
+        let action = options.mount.connect_action;
+        let confirm = options.mount.connect_confirm;
+        let device = options.mount.device.clone();
+        drop(options);
+        if action == MountConnectAction::LeaveAsIs || device.is_empty() {
+            return;
+        }
+        if !self.indi.is_device_enabled(&device).unwrap_or(false) {
+            return;
         }
+        if !confirm {
+            self.apply_connect_action(&device, action);
+            return;
+        }
+        let text = match action {
+            MountConnectAction::Unpark =>
+                format!("Unpark mount {}?", device),
+            _ =>
+                format!("Unpark mount {} and start tracking?", device),
+        };
+        let dialog = gtk::MessageDialog::builder()
+            .transient_for(&self.window)
+            .title("Mount is connected")
+            .text(&text)
+            .modal(true)
+            .message_type(gtk::MessageType::Question)
+            .build();
+        gtk_utils::add_ok_and_cancel_buttons(
+            dialog.upcast_ref::<gtk::Dialog>(),
+            "Yes", gtk::ResponseType::Yes,
+            "No", gtk::ResponseType::No,
+        );
+        dialog.show();
+
+        dialog.connect_response(clone!(@weak self as self_ =>
+            move |dlg, response| {
+            if response == gtk::ResponseType::Yes {
+                self_.apply_connect_action(&device, action);
+            } else {
+                log::info!("Unparking of mount {} is cancelled by user", device);
+            }
+            dlg.close();
+        }));
+    }
+
+    fn apply_connect_action(&self, device: &str, action: MountConnectAction) {
+        gtk_utils::exec_and_show_error(&self.window, || {
+            if self.indi.mount_get_parked(device)? {
+                log::info!("Unparking mount {} after connect", device);
+                self.indi.mount_set_parked(device, false, true, None)?;
+            }
+            if action == MountConnectAction::UnparkAndTrack {
+                log::info!("Enabling tracking of mount {} after connect", device);
+                self.indi.mount_set_tracking(device, true, true, None)?;
+            }
+            Ok(())
+        });
+    }
+
+    fn process_prop_change_event(&self, event_data: &indi::PropChangeEvent) {
+        match &event_data.change {
+            indi::PropChange::New(value) =>
+                self.process_indi_prop_change(
+                    &event_data.device_name,
+                    &event_data.prop_name,
+                    &value.elem_name,
+                    true,
+                    None,
+                    None,
+                    &value.prop_value
+                ),
+            indi::PropChange::Change{ value, prev_state, new_state } =>
+                self.process_indi_prop_change(
+                    &event_data.device_name,
+                    &event_data.prop_name,
+                    &value.elem_name,
+                    false,
+                    Some(prev_state),
+                    Some(new_state),
+                    &value.prop_value
+                ),
+            indi::PropChange::Delete => {}
+        };
     }
 
     fn process_indi_prop_change(
@@ -533,6 +995,32 @@ impl MountUi {
                 self.show_mount_parked_state(parked);
             }
 
+            ("ALIGNMENT_POINTSET_SIZE"|"ALIGNMENT_SUBSYSTEM_ACTIVE"|"ALIGNCOUNT"|"ALIGNMODE", ..) => {
+                let selected_device = self.options.read().unwrap().mount.device.clone();
+                if selected_device != device_name { return; }
+                self.show_mount_align_info();
+                if new_prop {
+                    self.delayed_actions.schedule(DelayedAction::CorrectWidgetsProps);
+                }
+            }
+
+            ("WEATHER_STATUS"|"WEATHER_PARAMETERS", ..) => {
+                let selected_device = self.options.read().unwrap().weather.device.clone();
+                if selected_device != device_name { return; }
+                self.show_weather_state();
+            }
+
+            ("DOME_PARK", elem, indi::PropValue::Switch(prop_value)) => {
+                let selected_device = self.options.read().unwrap().dome.device.clone();
+                if selected_device != device_name { return; }
+                let parked =
+                    if elem == "PARK" { *prop_value }
+                    else if elem == "UNPARK" { !*prop_value }
+                    else { return; };
+                self.show_dome_parked_state(parked);
+                self.correct_widgets_props();
+            }
+
             _ => {}
         }
     }
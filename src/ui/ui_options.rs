@@ -1,5 +1,5 @@
 use gtk::prelude::*;
-use crate::{image::raw::FrameType, indi::{sexagesimal_to_value, value_to_sexagesimal}, options::*, utils::gtk_utils};
+use crate::{image::raw::FrameType, indi::{parse_coord_value, value_to_sexagesimal}, options::*, utils::gtk_utils};
 
 impl Options {
     /* read */
@@ -22,17 +22,29 @@ impl Options {
         self.read_focuser_cam(builder);
         self.read_plate_solve(builder);
         self.read_mount(builder);
+        self.read_dome(builder);
+        self.read_weather(builder);
         self.read_polar_align(builder);
+        self.read_mode_watchdog(builder);
     }
 
     pub fn read_indi(&mut self, builder: &gtk::Builder) {
         let ui = gtk_utils::UiHelper::new_from_builder(builder);
-        self.indi.mount    = ui.prop_string("cb_mount_drivers.active-id");
-        self.indi.camera   = ui.prop_string("cb_camera_drivers.active-id");
-        self.indi.guid_cam = ui.prop_string("cb_guid_cam_drivers.active-id");
-        self.indi.focuser  = ui.prop_string("cb_focuser_drivers.active-id");
-        self.indi.remote   = ui.prop_bool  ("chb_remote.active");
-        self.indi.address  = ui.prop_string("e_remote_addr.text").unwrap_or_default();
+        self.indi.mount        = ui.prop_string("cb_mount_drivers.active-id");
+        self.indi.camera       = ui.prop_string("cb_camera_drivers.active-id");
+        self.indi.guid_cam     = ui.prop_string("cb_guid_cam_drivers.active-id");
+        self.indi.focuser      = ui.prop_string("cb_focuser_drivers.active-id");
+        self.indi.remote       = ui.prop_bool  ("chb_remote.active");
+        self.indi.address      = ui.prop_string("e_remote_addr.text").unwrap_or_default();
+        self.indi.blob_timeout = ui.prop_f64("spb_blob_timeout.value") as u32;
+        self.indi.throttle_ui  = ui.prop_bool("chb_indi_throttle.active");
+    }
+
+    pub fn read_mode_watchdog(&mut self, builder: &gtk::Builder) {
+        let ui = gtk_utils::UiHelper::new_from_builder(builder);
+        self.mode_wd.enabled    = ui.prop_bool("chb_mode_wd.active");
+        self.mode_wd.timeout    = ui.prop_f64("spb_mode_wd_timeout.value") as u32;
+        self.mode_wd.auto_abort = ui.prop_bool("chb_mode_wd_abort.active");
     }
 
     pub fn read_telescope(&mut self, builder: &gtk::Builder) {
@@ -44,13 +56,14 @@ impl Options {
     pub fn read_site(&mut self, builder: &gtk::Builder) {
         let ui = gtk_utils::UiHelper::new_from_builder(builder);
         let lat_string = ui.prop_string("e_site_lat.text").unwrap_or_default();
-        if let Some(latitude) = sexagesimal_to_value(&lat_string) {
+        if let Some(latitude) = parse_coord_value(&lat_string, -90.0, 90.0) {
             self.site.latitude = latitude;
         }
         let long_str = ui.prop_string("e_site_long.text").unwrap_or_default();
-        if let Some(longitude) = sexagesimal_to_value(&long_str) {
+        if let Some(longitude) = parse_coord_value(&long_str, -180.0, 180.0) {
             self.site.longitude = longitude;
         }
+        self.site.elevation = ui.prop_f64("spb_site_elev.value");
     }
 
     pub fn read_guiding(&mut self, builder: &gtk::Builder) {
@@ -60,13 +73,19 @@ impl Options {
                 GuidingMode::MainCamera
             } else if ui.prop_bool("rbtn_guide_ext.active") {
                 GuidingMode::External
+            } else if ui.prop_bool("rbtn_dith_frame_offs.active") {
+                GuidingMode::FrameOffset
+            } else if ui.prop_bool("rbtn_simple_dith.active") {
+                GuidingMode::SimpleDither
             } else {
                 GuidingMode::Disabled
             };
 
-        self.guiding.dith_period          = ui.prop_string("cb_dith_perod.active-id").and_then(|v| v.parse().ok()).unwrap_or(0);
-        self.guiding.ext_guider.foc_len   = ui.prop_f64("spb_guid_foc_len.value");
-        self.guiding.ext_guider.dith_dist = ui.prop_f64("sb_ext_dith_dist.value") as i32;
+        self.guiding.dith_period            = ui.prop_string("cb_dith_perod.active-id").and_then(|v| v.parse().ok()).unwrap_or(0);
+        self.guiding.ext_guider.foc_len     = ui.prop_f64("spb_guid_foc_len.value");
+        self.guiding.ext_guider.dith_dist   = ui.prop_f64("sb_ext_dith_dist.value") as i32;
+        self.guiding.frame_offset.dith_dist = ui.prop_f64("sb_frame_offs_dith_dist.value") as i32;
+        self.guiding.simple_dith.pulse      = ui.prop_f64("spb_simple_dith_pulse.value");
     }
 
     pub fn read_guiding_cam(&mut self, builder: &gtk::Builder) {
@@ -75,12 +94,25 @@ impl Options {
         self.guiding.main_cam.calibr_exposure = ui.prop_f64("spb_mnt_cal_exp.value");
         self.guiding.main_cam.calibr_gain     = Gain::from_active_id(ui.prop_string("cbx_mnt_cal_gain.active-id").as_deref());
         self.guiding.main_cam.max_error       = ui.prop_f64("spb_guid_max_err.value");
+        self.guiding.main_cam.auto_calibr     = ui.prop_bool("chb_mnt_cal_auto.active");
+        self.guiding.main_cam.min_pulse       = ui.prop_f64("spb_guid_min_pulse.value");
+        self.guiding.main_cam.max_correction  = ui.prop_f64("spb_guid_max_corr.value");
+        self.guiding.main_cam.save_log        = ui.prop_bool("chb_guid_save_log.active");
     }
 
     pub fn read_cam(&mut self, builder: &gtk::Builder) {
         let ui = gtk_utils::UiHelper::new_from_builder(builder);
         self.cam.live_view = ui.prop_bool("chb_shots_cont.active");
         self.cam.device    = ui.prop_string("cb_camera_list.active-id").map(|str| DeviceAndProp::new(&str));
+        self.read_live_auto_gain(builder);
+    }
+
+    pub fn read_live_auto_gain(&mut self, builder: &gtk::Builder) {
+        let ui = gtk_utils::UiHelper::new_from_builder(builder);
+        self.cam.live_ag.enabled  = ui.prop_bool("chb_live_ag.active");
+        self.cam.live_ag.target   = ui.prop_f64("spb_live_ag_target.value");
+        self.cam.live_ag.min_gain = ui.prop_f64("spb_live_ag_min.value");
+        self.cam.live_ag.max_gain = ui.prop_f64("spb_live_ag_max.value");
     }
 
     pub fn read_cam_ctrl(&mut self, builder: &gtk::Builder) {
@@ -97,6 +129,9 @@ impl Options {
         self.cam.frame.gain         = ui.prop_f64("spb_gain.value");
         self.cam.frame.offset       = ui.prop_f64("spb_offset.value") as i32;
         self.cam.frame.low_noise    = ui.prop_bool("chb_low_noise.active");
+        self.cam.frame.compressed   = ui.prop_bool("chb_compressed.active");
+        self.cam.frame.delay        = ui.prop_f64("spb_frame_delay.value");
+        self.cam.frame.settle_delay = ui.prop_f64("spb_settle_delay.value");
         self.cam.frame.binning      = Binning::from_active_id(ui.prop_string("cb_bin.active-id").as_deref());
         self.cam.frame.crop         = Crop::from_active_id(ui.prop_string("cb_crop.active-id").as_deref());
     }
@@ -108,6 +143,16 @@ impl Options {
         self.calibr.flat_frame_en = ui.prop_bool("chb_master_flat.active");
         self.calibr.flat_frame_fname    = ui.fch_pathbuf("fch_master_flat");
         self.calibr.hot_pixels    = ui.prop_bool("chb_hot_pixels.active");
+        self.calibr.allow_dark_scaling = ui.prop_bool("chb_dark_scaling.active");
+        self.calibr.hot_pixel_removal = ui.prop_bool("chb_dark_hot_pixels.active");
+        self.calibr.hot_pixel_sigma   = ui.prop_f64("spb_hot_pixel_sigma.value");
+        self.calibr.dark_temp_tol     = ui.prop_f64("spb_dark_temp_tol.value");
+        self.calibr.dark_exp_tol      = ui.prop_f64("spb_dark_exp_tol.value");
+        self.calibr.flat_auto_exp      = ui.prop_bool("chb_flat_auto_exp.active");
+        self.calibr.flat_adu_target    = ui.prop_f64("spb_flat_adu_target.value");
+        self.calibr.flat_adu_tolerance = ui.prop_f64("spb_flat_adu_tol.value");
+        self.calibr.flat_panel         = ui.prop_bool("chb_flat_panel.active");
+        self.calibr.flat_panel_bright  = ui.prop_f64("spb_flat_panel_bright.value");
     }
 
     pub fn read_raw(&mut self, builder: &gtk::Builder) {
@@ -116,6 +161,17 @@ impl Options {
         self.raw_frames.frame_cnt     = ui.prop_f64("spb_raw_frames_cnt.value") as usize;
         self.raw_frames.out_path      = ui.fch_pathbuf("fcb_raw_frames_path").unwrap_or_default();
         self.raw_frames.create_master = ui.prop_bool("chb_master_frame.active");
+        self.raw_frames.pause_low_disk = ui.prop_bool("chb_low_disk_pause.active");
+        self.raw_frames.min_free_space = ui.prop_f64("spb_min_free_space.value");
+        self.raw_frames.stop_by_alt    = ui.prop_bool("chb_stop_by_alt.active");
+        self.raw_frames.stop_alt       = ui.prop_f64("spb_stop_alt.value");
+        self.raw_frames.stop_at_dawn   = ui.prop_bool("chb_stop_at_dawn.active");
+        self.raw_frames.wait_for_dark  = ui.prop_bool("chb_wait_for_dark.active");
+        self.raw_frames.dark_sun_alt   = ui.prop_f64("spb_dark_sun_alt.value");
+        self.raw_frames.save_format    = RawSaveFormat::from_active_id(ui.prop_string("cbx_raw_save_fmt.active-id").as_deref());
+        self.raw_frames.save_wcs       = ui.prop_bool("chb_raw_save_wcs.active");
+        self.flats.sky_sun_alt_min     = ui.prop_f64("spb_sky_flat_sun_min.value");
+        self.flats.sky_sun_alt_max     = ui.prop_f64("spb_sky_flat_sun_max.value");
     }
 
     pub fn read_live_stacking(&mut self, builder: &gtk::Builder) {
@@ -125,6 +181,9 @@ impl Options {
         self.live.save_minutes  = ui.prop_f64("spb_live_minutes.value") as usize;
         self.live.out_dir       = ui.fch_pathbuf("fch_live_folder").unwrap_or_default();
         self.live.remove_tracks = ui.prop_bool("chb_live_no_tracks.active");
+        self.live.use_min_stars = ui.prop_bool("chb_live_min_stars.active");
+        self.live.min_stars     = ui.prop_f64("spb_live_min_stars.value") as usize;
+        self.live.drizzle       = Drizzle::from_active_id(ui.prop_string("cbx_live_drizzle.active-id").as_deref());
     }
 
     pub fn read_frame_quality(&mut self, builder: &gtk::Builder) {
@@ -133,6 +192,16 @@ impl Options {
         self.quality.max_fwhm        = ui.prop_f64("spb_max_fwhm.value") as f32;
         self.quality.use_max_ovality = ui.prop_bool("chb_max_oval.active");
         self.quality.max_ovality     = ui.prop_f64("spb_max_oval.value") as f32;
+        self.quality.score_w_fwhm    = ui.prop_f64("spb_qscore_w_fwhm.value") as f32;
+        self.quality.score_w_ovality = ui.prop_f64("spb_qscore_w_oval.value") as f32;
+        self.quality.score_w_stars   = ui.prop_f64("spb_qscore_w_stars.value") as f32;
+        self.quality.score_w_bg      = ui.prop_f64("spb_qscore_w_bg.value") as f32;
+
+        let star_detection = &mut self.quality.star_detection;
+        star_detection.threshold  = ui.prop_f64("spb_star_det_thres.value") as f32;
+        star_detection.min_pixels = ui.prop_f64("spb_star_det_min_px.value") as usize;
+        star_detection.max_pixels = ui.prop_f64("spb_star_det_max_px.value") as usize;
+        star_detection.saturation = ui.prop_f64("spb_star_det_satur.value") as f32;
     }
 
     pub fn read_preview(&mut self, builder: &gtk::Builder) {
@@ -142,14 +211,19 @@ impl Options {
             self.preview.source = PreviewSource::from_active_id(
             ui.prop_string("cb_preview_src.active-id").as_deref()
         );
-        self.preview.gamma       = ui.range_value("scl_gamma");
-        self.preview.dark_lvl    = ui.range_value("scl_dark");
-        self.preview.light_lvl   = ui.range_value("scl_highlight");
+        let (dark_lvl, light_lvl, gamma) = self.preview.levels_mut();
+        *gamma     = ui.range_value("scl_gamma");
+        *dark_lvl  = ui.range_value("scl_dark");
+        *light_lvl = ui.range_value("scl_highlight");
         self.preview.remove_grad = ui.prop_bool("chb_rem_grad.active");
         self.preview.wb_auto     = ui.prop_bool("chb_wb_auto.active");
         self.preview.wb_red      = ui.range_value("scl_wb_red");
         self.preview.wb_green    = ui.range_value("scl_wb_green");
         self.preview.wb_blue     = ui.range_value("scl_wb_blue");
+        self.preview.crosshair   = ui.prop_bool("chb_crosshair.active");
+        self.preview.crosshair_r = PreviewOptions::crosshair_r_from_str(
+            &ui.prop_string("e_crosshair_circles.text").unwrap_or_default()
+        );
     }
 
     pub fn read_focuser(&mut self, builder: &gtk::Builder) {
@@ -160,8 +234,14 @@ impl Options {
         self.focuser.max_fwhm_change = ui.prop_string("cb_foc_fwhm.active-id").and_then(|v| v.parse().ok()).unwrap_or(20);
         self.focuser.periodically    = ui.prop_bool("chb_foc_period.active");
         self.focuser.period_minutes  = ui.prop_string("cb_foc_period.active-id").and_then(|v| v.parse().ok()).unwrap_or(120);
+        self.focuser.interrupt_exp   = ui.prop_bool("chb_foc_interrupt.active");
         self.focuser.measures        = ui.prop_f64("spb_foc_measures.value") as u32;
         self.focuser.step            = ui.prop_f64("spb_foc_auto_step.value");
+        self.focuser.min_fit_r2      = ui.prop_f64("spb_foc_min_r2.value");
+        self.focuser.temp_comp       = ui.prop_bool("chb_foc_temp_comp.active");
+        self.focuser.temp_coeff      = ui.prop_f64("spb_foc_temp_coeff.value");
+        self.focuser.backlash_steps  = ui.prop_f64("spb_foc_backlash.value");
+        self.focuser.backlash_direction = FocuserDirection::from_active_id(ui.prop_string("cbx_foc_backlash_dir.active-id").as_deref());
     }
 
     pub fn read_focuser_cam(&mut self, builder: &gtk::Builder) {
@@ -178,6 +258,11 @@ impl Options {
         self.plate_solver.solver        = PlateSolverType::from_active_id(ui.prop_string("cbx_ps_solver.active-id").as_deref());
         self.plate_solver.timeout       = ui.prop_f64("spb_ps_timeout.value") as _;
         self.plate_solver.blind_timeout = ui.prop_f64("spb_ps_blind_timeout.value") as _;
+        self.plate_solver.astap_path    = ui.prop_string("e_ps_astap_path.text").unwrap_or_default();
+        self.plate_solver.spiral_search = ui.prop_bool("chb_ps_spiral.active");
+        self.plate_solver.spiral_max_radius = ui.prop_f64("spb_ps_spiral_radius.value");
+        self.plate_solver.center_tol    = ui.prop_f64("spb_ps_center_tol.value");
+        self.plate_solver.center_iters  = ui.prop_f64("spb_ps_center_iters.value") as usize;
     }
 
     pub fn read_mount(&mut self, builder: &gtk::Builder) {
@@ -185,6 +270,33 @@ impl Options {
         self.mount.inv_ns = ui.prop_bool("chb_inv_ns.active");
         self.mount.inv_we = ui.prop_bool("chb_inv_we.active");
         self.mount.speed  = ui.prop_string("cb_mnt_speed.active-id");
+        self.mount.connect_action  = MountConnectAction::from_active_id(ui.prop_string("cbx_mnt_on_connect.active-id").as_deref());
+        self.mount.connect_delay   = ui.prop_f64("spb_mnt_conn_delay.value") as u32;
+        self.mount.connect_confirm = ui.prop_bool("chb_mnt_conn_confirm.active");
+        self.mount.merid_stop      = ui.prop_bool("chb_mnt_merid_stop.active");
+        self.mount.merid_limit     = ui.prop_f64("spb_mnt_merid_limit.value");
+        self.mount.merid_park      = ui.prop_bool("chb_mnt_merid_park.active");
+        self.mount.flip_recenter   = ui.prop_bool("chb_mnt_flip_recenter.active");
+        self.mount.auto_flip       = ui.prop_bool("chb_mnt_auto_flip.active");
+        self.mount.flip_limit      = ui.prop_f64("spb_mnt_flip_limit.value");
+        self.mount.settle_tol      = ui.prop_f64("spb_mnt_settle_tol.value");
+        self.mount.settle_samples  = ui.prop_f64("spb_mnt_settle_samples.value") as usize;
+        self.mount.use_pt_model    = ui.prop_bool("chb_mnt_use_pt_model.active");
+    }
+
+    pub fn read_dome(&mut self, builder: &gtk::Builder) {
+        let ui = gtk_utils::UiHelper::new_from_builder(builder);
+        self.dome.slaving     = ui.prop_bool("chb_dome_slaving.active");
+        self.dome.park_at_end = ui.prop_bool("chb_dome_park_at_end.active");
+    }
+
+    pub fn read_weather(&mut self, builder: &gtk::Builder) {
+        let ui = gtk_utils::UiHelper::new_from_builder(builder);
+        self.weather.monitoring = ui.prop_bool("chb_weather_monitor.active");
+        self.weather.max_clouds = ui.prop_f64("spb_weather_max_clouds.value");
+        self.weather.max_rain   = ui.prop_f64("spb_weather_max_rain.value");
+        self.weather.max_wind   = ui.prop_f64("spb_weather_max_wind.value");
+        self.weather.park       = ui.prop_bool("chb_weather_park.active");
     }
 
     pub fn read_polar_align(&mut self, builder: &gtk::Builder) {
@@ -214,13 +326,25 @@ impl Options {
         self.show_plate_solve(builder);
         self.show_focuser(builder);
         self.show_mount(builder);
+        self.show_dome(builder);
+        self.show_weather(builder);
         self.show_polar_align(builder);
+        self.show_mode_watchdog(builder);
     }
 
     pub fn show_indi(&self, builder: &gtk::Builder) {
         let ui = gtk_utils::UiHelper::new_from_builder(builder);
         ui.set_prop_bool("chb_remote.active", self.indi.remote);
         ui.set_prop_str("e_remote_addr.text", Some(&self.indi.address));
+        ui.set_prop_f64("spb_blob_timeout.value", self.indi.blob_timeout as f64);
+        ui.set_prop_bool("chb_indi_throttle.active", self.indi.throttle_ui);
+    }
+
+    pub fn show_mode_watchdog(&self, builder: &gtk::Builder) {
+        let ui = gtk_utils::UiHelper::new_from_builder(builder);
+        ui.set_prop_bool("chb_mode_wd.active",         self.mode_wd.enabled);
+        ui.set_prop_f64 ("spb_mode_wd_timeout.value",  self.mode_wd.timeout as f64);
+        ui.set_prop_bool("chb_mode_wd_abort.active",   self.mode_wd.auto_abort);
     }
 
     pub fn show_telescope(&self, builder: &gtk::Builder) {
@@ -233,6 +357,7 @@ impl Options {
         let ui = gtk_utils::UiHelper::new_from_builder(builder);
         ui.set_prop_str("e_site_lat.text", Some(&value_to_sexagesimal(self.site.latitude, true, 6)));
         ui.set_prop_str("e_site_long.text", Some(&value_to_sexagesimal(self.site.longitude, true, 6)));
+        ui.set_prop_f64("spb_site_elev.value", self.site.elevation);
     }
 
     pub fn show_guiding(&self, builder: &gtk::Builder) {
@@ -244,19 +369,33 @@ impl Options {
                 ui.set_prop_bool("rbtn_guide_main_cam.active", true),
             GuidingMode::External =>
                 ui.set_prop_bool("rbtn_guide_ext.active", true),
+            GuidingMode::FrameOffset =>
+                ui.set_prop_bool("rbtn_dith_frame_offs.active", true),
+            GuidingMode::SimpleDither =>
+                ui.set_prop_bool("rbtn_simple_dith.active", true),
         }
         ui.set_prop_str("cb_dith_perod.active-id",    Some(self.guiding.dith_period.to_string().as_str()));
         ui.set_prop_f64("spb_guid_foc_len.value",     self.guiding.ext_guider.foc_len);
         ui.set_prop_f64("sb_ext_dith_dist.value",     self.guiding.ext_guider.dith_dist as f64);
+        ui.set_prop_f64("sb_frame_offs_dith_dist.value", self.guiding.frame_offset.dith_dist as f64);
+        ui.set_prop_f64("spb_simple_dith_pulse.value", self.guiding.simple_dith.pulse);
         ui.set_prop_f64("spb_guid_max_err.value",     self.guiding.main_cam.max_error);
+        ui.set_prop_f64("spb_guid_min_pulse.value",   self.guiding.main_cam.min_pulse);
+        ui.set_prop_f64("spb_guid_max_corr.value",    self.guiding.main_cam.max_correction);
+        ui.set_prop_bool("chb_guid_save_log.active",  self.guiding.main_cam.save_log);
         ui.set_prop_f64("sb_dith_dist.value",         self.guiding.main_cam.dith_dist as f64);
         ui.set_prop_f64("spb_mnt_cal_exp.value",      self.guiding.main_cam.calibr_exposure);
         ui.set_prop_str("cbx_mnt_cal_gain.active-id", Some(self.guiding.main_cam.calibr_gain.to_active_id()));
+        ui.set_prop_bool("chb_mnt_cal_auto.active",   self.guiding.main_cam.auto_calibr);
     }
 
     pub fn show_cam(&self, builder: &gtk::Builder) {
         let ui = gtk_utils::UiHelper::new_from_builder(builder);
-        ui.set_prop_bool("chb_shots_cont.active", self.cam.live_view);
+        ui.set_prop_bool("chb_shots_cont.active",      self.cam.live_view);
+        ui.set_prop_bool("chb_live_ag.active",         self.cam.live_ag.enabled);
+        ui.set_prop_f64 ("spb_live_ag_target.value",   self.cam.live_ag.target);
+        ui.set_prop_f64 ("spb_live_ag_min.value",      self.cam.live_ag.min_gain);
+        ui.set_prop_f64 ("spb_live_ag_max.value",      self.cam.live_ag.max_gain);
 
         let cb_camera_list = builder.object::<gtk::ComboBoxText>("cb_camera_list").unwrap();
 
@@ -281,6 +420,9 @@ impl Options {
         ui.set_prop_str ("cb_bin.active-id",        self.cam.frame.binning.to_active_id());
         ui.set_prop_str ("cb_crop.active-id",       self.cam.frame.crop.to_active_id());
         ui.set_prop_bool("chb_low_noise.active",    self.cam.frame.low_noise);
+        ui.set_prop_bool("chb_compressed.active",   self.cam.frame.compressed);
+        ui.set_prop_f64 ("spb_frame_delay.value",   self.cam.frame.delay);
+        ui.set_prop_f64 ("spb_settle_delay.value",  self.cam.frame.settle_delay);
     }
 
     pub fn show_calibr(&self, builder: &gtk::Builder) {
@@ -290,6 +432,16 @@ impl Options {
         ui.set_prop_bool("chb_master_flat.active", self.calibr.flat_frame_en);
         ui.set_fch_path ("fch_master_flat",        self.calibr.flat_frame_fname.as_deref());
         ui.set_prop_bool("chb_hot_pixels.active",  self.calibr.hot_pixels);
+        ui.set_prop_bool("chb_dark_scaling.active", self.calibr.allow_dark_scaling);
+        ui.set_prop_bool("chb_dark_hot_pixels.active", self.calibr.hot_pixel_removal);
+        ui.set_prop_f64 ("spb_hot_pixel_sigma.value", self.calibr.hot_pixel_sigma);
+        ui.set_prop_f64 ("spb_dark_temp_tol.value",   self.calibr.dark_temp_tol);
+        ui.set_prop_f64 ("spb_dark_exp_tol.value",    self.calibr.dark_exp_tol);
+        ui.set_prop_bool("chb_flat_auto_exp.active",  self.calibr.flat_auto_exp);
+        ui.set_prop_f64 ("spb_flat_adu_target.value", self.calibr.flat_adu_target);
+        ui.set_prop_f64 ("spb_flat_adu_tol.value",    self.calibr.flat_adu_tolerance);
+        ui.set_prop_bool("chb_flat_panel.active",     self.calibr.flat_panel);
+        ui.set_prop_f64 ("spb_flat_panel_bright.value", self.calibr.flat_panel_bright);
 
         ui.enable_widgets(false, &[("l_hot_pixels_warn", self.calibr.hot_pixels)]);
     }
@@ -307,6 +459,17 @@ impl Options {
         ui.set_prop_f64 ("spb_raw_frames_cnt.value",  self.raw_frames.frame_cnt as f64);
         ui.set_fch_path ("fcb_raw_frames_path",       Some(&self.raw_frames.out_path));
         ui.set_prop_bool("chb_master_frame.active",   self.raw_frames.create_master);
+        ui.set_prop_bool("chb_low_disk_pause.active", self.raw_frames.pause_low_disk);
+        ui.set_prop_f64 ("spb_min_free_space.value",  self.raw_frames.min_free_space);
+        ui.set_prop_bool("chb_stop_by_alt.active",    self.raw_frames.stop_by_alt);
+        ui.set_prop_f64 ("spb_stop_alt.value",        self.raw_frames.stop_alt);
+        ui.set_prop_bool("chb_stop_at_dawn.active",   self.raw_frames.stop_at_dawn);
+        ui.set_prop_bool("chb_wait_for_dark.active",  self.raw_frames.wait_for_dark);
+        ui.set_prop_f64 ("spb_dark_sun_alt.value",    self.raw_frames.dark_sun_alt);
+        ui.set_prop_str ("cbx_raw_save_fmt.active-id", self.raw_frames.save_format.to_active_id());
+        ui.set_prop_bool("chb_raw_save_wcs.active",   self.raw_frames.save_wcs);
+        ui.set_prop_f64 ("spb_sky_flat_sun_min.value", self.flats.sky_sun_alt_min);
+        ui.set_prop_f64 ("spb_sky_flat_sun_max.value", self.flats.sky_sun_alt_max);
     }
 
     pub fn show_live_stacking(&self, builder: &gtk::Builder) {
@@ -316,6 +479,9 @@ impl Options {
         ui.set_prop_f64 ("spb_live_minutes.value",    self.live.save_minutes as f64);
         ui.set_fch_path ("fch_live_folder",           Some(&self.live.out_dir));
         ui.set_prop_bool("chb_live_no_tracks.active", self.live.remove_tracks);
+        ui.set_prop_bool("chb_live_min_stars.active", self.live.use_min_stars);
+        ui.set_prop_f64 ("spb_live_min_stars.value",  self.live.min_stars as f64);
+        ui.set_prop_str ("cbx_live_drizzle.active-id", self.live.drizzle.to_active_id());
     }
 
     pub fn show_frame_quality(&self, builder: &gtk::Builder) {
@@ -324,6 +490,16 @@ impl Options {
         ui.set_prop_f64 ("spb_max_fwhm.value",  self.quality.max_fwhm as f64);
         ui.set_prop_bool("chb_max_oval.active", self.quality.use_max_ovality);
         ui.set_prop_f64 ("spb_max_oval.value",  self.quality.max_ovality as f64);
+        ui.set_prop_f64 ("spb_qscore_w_fwhm.value",  self.quality.score_w_fwhm as f64);
+        ui.set_prop_f64 ("spb_qscore_w_oval.value",  self.quality.score_w_ovality as f64);
+        ui.set_prop_f64 ("spb_qscore_w_stars.value", self.quality.score_w_stars as f64);
+        ui.set_prop_f64 ("spb_qscore_w_bg.value",    self.quality.score_w_bg as f64);
+
+        let star_detection = &self.quality.star_detection;
+        ui.set_prop_f64 ("spb_star_det_thres.value",  star_detection.threshold as f64);
+        ui.set_prop_f64 ("spb_star_det_min_px.value", star_detection.min_pixels as f64);
+        ui.set_prop_f64 ("spb_star_det_max_px.value", star_detection.max_pixels as f64);
+        ui.set_prop_f64 ("spb_star_det_satur.value",  star_detection.saturation as f64);
     }
 
     pub fn show_preview(&self, builder: &gtk::Builder) {
@@ -331,14 +507,17 @@ impl Options {
         ui.set_prop_str   ("cb_preview_src.active-id",   self.preview.source.to_active_id());
         ui.set_prop_str   ("cb_preview_scale.active-id", self.preview.scale.to_active_id());
         ui.set_prop_str   ("cb_preview_color.active-id", self.preview.color.to_active_id());
-        ui.set_range_value("scl_dark",                   self.preview.dark_lvl);
-        ui.set_range_value("scl_highlight",              self.preview.light_lvl);
-        ui.set_range_value("scl_gamma",                  self.preview.gamma);
+        let (dark_lvl, light_lvl, gamma) = self.preview.levels();
+        ui.set_range_value("scl_dark",                   dark_lvl);
+        ui.set_range_value("scl_highlight",              light_lvl);
+        ui.set_range_value("scl_gamma",                  gamma);
         ui.set_prop_bool  ("chb_rem_grad.active",        self.preview.remove_grad);
         ui.set_prop_bool  ("chb_wb_auto.active",         self.preview.wb_auto);
         ui.set_range_value("scl_wb_red",                 self.preview.wb_red);
         ui.set_range_value("scl_wb_green",               self.preview.wb_green);
         ui.set_range_value("scl_wb_blue",                self.preview.wb_blue);
+        ui.set_prop_bool  ("chb_crosshair.active",       self.preview.crosshair);
+        ui.set_prop_str   ("e_crosshair_circles.text",   Some(&self.preview.crosshair_r_to_str()));
     }
 
     pub fn show_plate_solve(&self, builder: &gtk::Builder) {
@@ -349,6 +528,11 @@ impl Options {
         ui.set_prop_str("cbx_ps_solver.active-id",    self.plate_solver.solver.to_active_id());
         ui.set_prop_f64("spb_ps_timeout.value",       self.plate_solver.timeout as f64);
         ui.set_prop_f64("spb_ps_blind_timeout.value", self.plate_solver.blind_timeout as f64);
+        ui.set_prop_str("e_ps_astap_path.text",       Some(&self.plate_solver.astap_path));
+        ui.set_prop_bool("chb_ps_spiral.active",      self.plate_solver.spiral_search);
+        ui.set_prop_f64("spb_ps_spiral_radius.value", self.plate_solver.spiral_max_radius);
+        ui.set_prop_f64("spb_ps_center_tol.value",    self.plate_solver.center_tol);
+        ui.set_prop_f64("spb_ps_center_iters.value",  self.plate_solver.center_iters as f64);
     }
 
     pub fn show_focuser(&self, builder: &gtk::Builder) {
@@ -359,8 +543,14 @@ impl Options {
         ui.set_prop_str ("cb_foc_fwhm.active-id",   Some(self.focuser.max_fwhm_change.to_string()).as_deref());
         ui.set_prop_bool("chb_foc_period.active",   self.focuser.periodically);
         ui.set_prop_str ("cb_foc_period.active-id", Some(self.focuser.period_minutes.to_string()).as_deref());
+        ui.set_prop_bool("chb_foc_interrupt.active", self.focuser.interrupt_exp);
         ui.set_prop_f64 ("spb_foc_measures.value",  self.focuser.measures as f64);
         ui.set_prop_f64 ("spb_foc_auto_step.value", self.focuser.step);
+        ui.set_prop_f64 ("spb_foc_min_r2.value",    self.focuser.min_fit_r2);
+        ui.set_prop_bool("chb_foc_temp_comp.active", self.focuser.temp_comp);
+        ui.set_prop_f64 ("spb_foc_temp_coeff.value", self.focuser.temp_coeff);
+        ui.set_prop_f64 ("spb_foc_backlash.value",  self.focuser.backlash_steps);
+        ui.set_prop_str ("cbx_foc_backlash_dir.active-id", self.focuser.backlash_direction.to_active_id());
         ui.set_prop_f64 ("spb_foc_exp.value",       self.focuser.exposure);
         ui.set_prop_str ("cbx_foc_gain.active-id",  Some(self.focuser.gain.to_active_id()));
     }
@@ -369,6 +559,33 @@ impl Options {
         let ui = gtk_utils::UiHelper::new_from_builder(builder);
         ui.set_prop_bool("chb_inv_ns.active", self.mount.inv_ns);
         ui.set_prop_bool("chb_inv_we.active", self.mount.inv_we);
+        ui.set_prop_str ("cbx_mnt_on_connect.active-id", self.mount.connect_action.to_active_id());
+        ui.set_prop_f64 ("spb_mnt_conn_delay.value",     self.mount.connect_delay as f64);
+        ui.set_prop_bool("chb_mnt_conn_confirm.active",  self.mount.connect_confirm);
+        ui.set_prop_bool("chb_mnt_merid_stop.active",    self.mount.merid_stop);
+        ui.set_prop_f64 ("spb_mnt_merid_limit.value",    self.mount.merid_limit);
+        ui.set_prop_bool("chb_mnt_merid_park.active",    self.mount.merid_park);
+        ui.set_prop_bool("chb_mnt_flip_recenter.active", self.mount.flip_recenter);
+        ui.set_prop_bool("chb_mnt_auto_flip.active",     self.mount.auto_flip);
+        ui.set_prop_f64 ("spb_mnt_flip_limit.value",     self.mount.flip_limit);
+        ui.set_prop_f64 ("spb_mnt_settle_tol.value",     self.mount.settle_tol);
+        ui.set_prop_f64 ("spb_mnt_settle_samples.value", self.mount.settle_samples as f64);
+        ui.set_prop_bool("chb_mnt_use_pt_model.active",  self.mount.use_pt_model);
+    }
+
+    pub fn show_dome(&self, builder: &gtk::Builder) {
+        let ui = gtk_utils::UiHelper::new_from_builder(builder);
+        ui.set_prop_bool("chb_dome_slaving.active",     self.dome.slaving);
+        ui.set_prop_bool("chb_dome_park_at_end.active", self.dome.park_at_end);
+    }
+
+    pub fn show_weather(&self, builder: &gtk::Builder) {
+        let ui = gtk_utils::UiHelper::new_from_builder(builder);
+        ui.set_prop_bool("chb_weather_monitor.active",    self.weather.monitoring);
+        ui.set_prop_f64 ("spb_weather_max_clouds.value",  self.weather.max_clouds);
+        ui.set_prop_f64 ("spb_weather_max_rain.value",    self.weather.max_rain);
+        ui.set_prop_f64 ("spb_weather_max_wind.value",    self.weather.max_wind);
+        ui.set_prop_bool("chb_weather_park.active",       self.weather.park);
     }
 
     pub fn show_polar_align(&self, builder: &gtk::Builder) {
@@ -542,17 +759,70 @@ impl PreviewColorMode {
     }
 }
 
+impl RawSaveFormat {
+    pub fn from_active_id(active_id: Option<&str>) -> Self {
+        match active_id {
+            Some("internal") => Self::Internal,
+            Some("fits")     => Self::Fits,
+            _                => Self::Internal,
+        }
+    }
+
+    pub fn to_active_id(&self) -> Option<&'static str> {
+        match self {
+            Self::Internal => Some("internal"),
+            Self::Fits     => Some("fits"),
+        }
+    }
+}
+
+impl FocuserDirection {
+    pub fn from_active_id(active_id: Option<&str>) -> Self {
+        match active_id {
+            Some("inc") => Self::Increasing,
+            Some("dec") => Self::Decreasing,
+            _           => Self::Increasing,
+        }
+    }
+
+    pub fn to_active_id(&self) -> Option<&'static str> {
+        match self {
+            Self::Increasing => Some("inc"),
+            Self::Decreasing => Some("dec"),
+        }
+    }
+}
+
+impl Drizzle {
+    pub fn from_active_id(active_id: Option<&str>) -> Self {
+        match active_id {
+            Some("off") => Self::Off,
+            Some("x2")  => Self::X2,
+            _           => Self::Off,
+        }
+    }
+
+    pub fn to_active_id(&self) -> Option<&'static str> {
+        match self {
+            Self::Off => Some("off"),
+            Self::X2  => Some("x2"),
+        }
+    }
+}
+
 impl PlateSolverType {
     pub fn from_active_id(active_id: Option<&str>) -> Self {
         match active_id {
             Some("astrometry.net") => Self::Astrometry,
-            _                  => Self::Astrometry,
+            Some("astap")          => Self::Astap,
+            _                      => Self::Astrometry,
         }
     }
 
     pub fn to_active_id(&self) -> Option<&'static str> {
         match self {
             Self::Astrometry => Some("astrometry.net"),
+            Self::Astap      => Some("astap"),
         }
     }
 }
@@ -573,3 +843,21 @@ impl PloarAlignDir {
         }
     }
 }
+
+impl MountConnectAction {
+    pub fn from_active_id(active_id: Option<&str>) -> Self {
+        match active_id {
+            Some("unpark")       => Self::Unpark,
+            Some("unpark_track") => Self::UnparkAndTrack,
+            _                    => Self::LeaveAsIs,
+        }
+    }
+
+    pub fn to_active_id(&self) -> Option<&'static str> {
+        match self {
+            Self::LeaveAsIs      => Some("leave"),
+            Self::Unpark         => Some("unpark"),
+            Self::UnparkAndTrack => Some("unpark_track"),
+        }
+    }
+}
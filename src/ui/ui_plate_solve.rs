@@ -6,6 +6,8 @@ use crate::{
     core::{core::{Core, ModeType}, events::*},
     indi,
     options::*,
+    plate_solve::PlateSolveOkResult,
+    ui::sky_map::math::*,
     utils::{gtk_utils, io_utils::*},
 };
 
@@ -34,6 +36,7 @@ pub fn init_ui(
         core:            Arc::clone(core),
         indi:            Arc::clone(indi),
         ui_options:      RefCell::new(ui_options),
+        ps_result:       RefCell::new(None),
         closed:          Cell::new(false),
         indi_evt_conn:   RefCell::new(None),
         delayed_actions: DelayedActions::new(200),
@@ -69,6 +72,7 @@ struct PlateSolveUi {
     core:            Arc<Core>,
     indi:            Arc<indi::Connection>,
     ui_options:      RefCell<UiOptions>,
+    ps_result:       RefCell<Option<PlateSolveOkResult>>,
     closed:          Cell<bool>,
     indi_evt_conn:   RefCell<Option<indi::Subscription>>,
     delayed_actions: DelayedActions<DelayedAction>,
@@ -84,17 +88,84 @@ impl Drop for PlateSolveUi {
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(default)]
 struct UiOptions {
-    expanded: bool,
+    expanded:   bool,
+    copy_epoch: CopyEpoch,
+    copy_fmt:   CopyFormat,
 }
 
 impl Default for UiOptions {
     fn default() -> Self {
         Self {
-            expanded: false
+            expanded:   false,
+            copy_epoch: CopyEpoch::J2000,
+            copy_fmt:   CopyFormat::Sexagesimal,
         }
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+enum CopyEpoch {
+    J2000,
+    Now,
+}
+
+impl CopyEpoch {
+    fn from_active_id(id: Option<&str>) -> Self {
+        match id {
+            Some("now") => Self::Now,
+            _           => Self::J2000,
+        }
+    }
+
+    fn to_active_id(&self) -> Option<&'static str> {
+        match self {
+            Self::J2000 => Some("j2000"),
+            Self::Now   => Some("now"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+enum CopyFormat {
+    Sexagesimal,
+    HoursDegrees,
+    Degrees,
+}
+
+impl CopyFormat {
+    fn from_active_id(id: Option<&str>) -> Self {
+        match id {
+            Some("hours_degrees") => Self::HoursDegrees,
+            Some("degrees")       => Self::Degrees,
+            _                     => Self::Sexagesimal,
+        }
+    }
+
+    fn to_active_id(&self) -> Option<&'static str> {
+        match self {
+            Self::Sexagesimal  => Some("sexagesimal"),
+            Self::HoursDegrees => Some("hours_degrees"),
+            Self::Degrees      => Some("degrees"),
+        }
+    }
+}
+
+fn eq_coord_to_string(crd: &EqCoord, fmt: CopyFormat) -> String {
+    let ra_hour = radian_to_hour(crd.ra);
+    let dec_deg = radian_to_degree(crd.dec);
+    match fmt {
+        CopyFormat::Sexagesimal => format!(
+            "{} {}",
+            indi::value_to_sexagesimal(ra_hour, true, 9),
+            indi::value_to_sexagesimal_impl(dec_deg, true, true, 8, ":", ":", ""),
+        ),
+        CopyFormat::HoursDegrees =>
+            format!("{:.6} {:+.6}", ra_hour, dec_deg),
+        CopyFormat::Degrees =>
+            format!("{:.6} {:+.6}", radian_to_degree(crd.ra), dec_deg),
+    }
+}
+
 impl PlateSolveUi {
     const CONF_FN: &'static str = "ui_plate_solve";
 
@@ -113,6 +184,21 @@ impl PlateSolveUi {
         spb_ps_blind_timeout.set_range(5.0, 120.0);
         spb_ps_blind_timeout.set_digits(0);
         spb_ps_blind_timeout.set_increments(5.0, 20.0);
+
+        let spb_ps_spiral_radius = self.builder.object::<gtk::SpinButton>("spb_ps_spiral_radius").unwrap();
+        spb_ps_spiral_radius.set_range(0.5, 20.0);
+        spb_ps_spiral_radius.set_digits(1);
+        spb_ps_spiral_radius.set_increments(0.5, 2.0);
+
+        let spb_ps_center_tol = self.builder.object::<gtk::SpinButton>("spb_ps_center_tol").unwrap();
+        spb_ps_center_tol.set_range(1.0, 600.0);
+        spb_ps_center_tol.set_digits(0);
+        spb_ps_center_tol.set_increments(5.0, 30.0);
+
+        let spb_ps_center_iters = self.builder.object::<gtk::SpinButton>("spb_ps_center_iters").unwrap();
+        spb_ps_center_iters.set_range(1.0, 10.0);
+        spb_ps_center_iters.set_digits(0);
+        spb_ps_center_iters.set_increments(1.0, 2.0);
     }
 
     fn handler_closing(&self) {
@@ -155,6 +241,17 @@ impl PlateSolveUi {
     fn connect_widgets_events(self: &Rc<Self>) {
         gtk_utils::connect_action_rc(&self.window, self, "capture_platesolve",   Self::handler_action_capture_platesolve);
         gtk_utils::connect_action   (&self.window, self, "plate_solve_and_goto", Self::handler_action_plate_solve_and_goto);
+        gtk_utils::connect_action   (&self.window, self, "quick_platesolve",     Self::handler_action_quick_platesolve);
+
+        let bt_ps_copy = self.builder.object::<gtk::Button>("bt_ps_copy").unwrap();
+        bt_ps_copy.connect_clicked(clone!(@weak self as self_ => move |_| {
+            self_.handler_copy_ps_result();
+        }));
+
+        let chb_ps_spiral = self.builder.object::<gtk::CheckButton>("chb_ps_spiral").unwrap();
+        chb_ps_spiral.connect_active_notify(clone!(@weak self as self_ => move |_| {
+            self_.correct_widgets_props();
+        }));
     }
 
     fn connect_main_ui_events(self: &Rc<Self>, handlers: &mut MainUiEventHandlers) {
@@ -181,6 +278,10 @@ impl PlateSolveUi {
 
     fn process_event_in_main_thread(&self, event: MainThreadEvent) {
         match event {
+            MainThreadEvent::Core(Event::PlateSolve(ps_event)) => {
+                *self.ps_result.borrow_mut() = Some(ps_event.result);
+                self.show_ps_result();
+            }
             MainThreadEvent::Core(Event::ModeChanged) => {
                 self.delayed_actions.schedule(DelayedAction::CorrectWidgetsProps);
             }
@@ -212,12 +313,16 @@ impl PlateSolveUi {
         let ui = gtk_utils::UiHelper::new_from_builder(&self.builder);
         let options = self.ui_options.borrow();
         ui.set_prop_bool("exp_plate_solving.expanded", options.expanded);
+        ui.set_prop_str("cbx_ps_copy_epoch.active-id", options.copy_epoch.to_active_id());
+        ui.set_prop_str("cbx_ps_copy_fmt.active-id", options.copy_fmt.to_active_id());
     }
 
     fn get_ui_options_from_widgets(&self) {
         let ui = gtk_utils::UiHelper::new_from_builder(&self.builder);
         let mut options = self.ui_options.borrow_mut();
         options.expanded = ui.prop_bool("exp_plate_solving.expanded");
+        options.copy_epoch = CopyEpoch::from_active_id(ui.prop_string("cbx_ps_copy_epoch.active-id").as_deref());
+        options.copy_fmt = CopyFormat::from_active_id(ui.prop_string("cbx_ps_copy_fmt.active-id").as_deref());
     }
 
     fn correct_widgets_props_impl(&self, mount_device: &str, cam_device: &Option<DeviceAndProp>) {
@@ -238,6 +343,8 @@ impl PlateSolveUi {
             mnt_active && cam_active &&
             (waiting || single_shot || live_view);
 
+        let spiral_search = ui.prop_bool("chb_ps_spiral.active");
+
         if let Some(cam_device) = cam_device {
             let cam_ccd = indi::CamCcd::from_ccd_prop_name(&cam_device.prop);
             let exp_value = self.indi.camera_get_exposure_prop_value(&cam_device.name, cam_ccd);
@@ -252,11 +359,14 @@ impl PlateSolveUi {
             ("cbx_ps_gain", plate_solve_sensitive),
             ("l_ps_bin", plate_solve_sensitive),
             ("cbx_ps_bin", plate_solve_sensitive),
+            ("l_ps_spiral_radius", spiral_search),
+            ("spb_ps_spiral_radius", spiral_search),
         ]);
 
         gtk_utils::enable_actions(&self.window, &[
             ("capture_platesolve", plate_solve_sensitive),
-            ("plate_solve_and_goto", plate_solve_sensitive)
+            ("plate_solve_and_goto", plate_solve_sensitive),
+            ("quick_platesolve", waiting),
         ]);
     }
 
@@ -295,4 +405,63 @@ impl PlateSolveUi {
             Ok(())
         });
     }
+
+    fn handler_action_quick_platesolve(&self) {
+        if !is_expanded(&self.builder, "exp_plate_solving") { return; }
+
+        self.options.write().unwrap().read_all(&self.builder);
+        gtk_utils::exec_and_show_error(&self.window, || {
+            self.core.start_quick_platesolve()?;
+            Ok(())
+        });
+    }
+
+    fn show_ps_result(&self) {
+        let ps_result = self.ps_result.borrow();
+        let Some(result) = &*ps_result else { return; };
+
+        // Image scale is calculated by size of current image.
+        // It can be wrong if image was changed during platesolving
+        let image = self.core.cur_frame().image.read().unwrap();
+        let img_width = image.width();
+        drop(image);
+        let scale_str = if img_width != 0 {
+            let scale = radian_to_degree(result.width) * 3600.0 / img_width as f64;
+            format!(", {:.2}\"/px", scale)
+        } else {
+            String::new()
+        };
+
+        let text = format!(
+            "J2000: {}, {}\nNow: {}, {}\nField: {:.2}° x {:.2}°{}\nRotation: {:.1}°",
+            indi::hour_to_str(radian_to_hour(result.crd_j2000.ra)),
+            indi::degree_to_str(radian_to_degree(result.crd_j2000.dec)),
+            indi::hour_to_str(radian_to_hour(result.crd_now.ra)),
+            indi::degree_to_str(radian_to_degree(result.crd_now.dec)),
+            radian_to_degree(result.width),
+            radian_to_degree(result.height),
+            scale_str,
+            radian_to_degree(result.rotation),
+        );
+
+        let ui = gtk_utils::UiHelper::new_from_builder(&self.builder);
+        ui.set_prop_str("l_ps_result.label", Some(&text));
+        ui.enable_widgets(false, &[("bt_ps_copy", true)]);
+    }
+
+    fn handler_copy_ps_result(&self) {
+        let ps_result = self.ps_result.borrow();
+        let Some(result) = &*ps_result else { return; };
+        self.get_ui_options_from_widgets();
+        let ui_options = self.ui_options.borrow();
+        let crd = match ui_options.copy_epoch {
+            CopyEpoch::J2000 => &result.crd_j2000,
+            CopyEpoch::Now   => &result.crd_now,
+        };
+        let text = eq_coord_to_string(crd, ui_options.copy_fmt);
+        drop(ui_options);
+        let clipboard = gtk::Clipboard::get(&gtk::gdk::SELECTION_CLIPBOARD);
+        clipboard.set_text(&text);
+        log::info!("Platesolve result copied to clipboard: {}", text);
+    }
 }
\ No newline at end of file
@@ -2,13 +2,13 @@ use std::{cell::{Cell, RefCell}, rc::Rc, sync::{Arc, RwLock}};
 use gtk::{glib::{self, clone}, pango, prelude::*};
 use serde::{Deserialize, Serialize};
 use crate::{
-    core::{core::{Core, ModeType}, events::*, mode_polar_align::PolarAlignmentEvent},
-    indi::{self, degree_to_str},
+    core::{core::{Core, ModeType}, events::*, mode_polar_align::{PolarAlignAdjDir, PolarAlignResult, PolarAlignmentEvent}},
+    indi,
     options::*,
     sky_math::math::radian_to_degree,
     utils::{gtk_utils, io_utils::*}
 };
-use super::{sky_map::math::HorizCoord, ui_main::*, utils::*};
+use super::{ui_main::*, utils::*};
 
 pub fn init_ui(
     _app:     &gtk::Application,
@@ -270,8 +270,8 @@ impl PolarAlignUi {
             }
             MainThreadEvent::Core(Event::PolarAlignment(event)) => {
                 match event {
-                    PolarAlignmentEvent::Error(error) =>
-                        self.show_polar_alignment_error(&error),
+                    PolarAlignmentEvent::Result(result) =>
+                        self.show_polar_alignment_result(&result),
                 }
             }
             MainThreadEvent::Indi(
@@ -309,19 +309,26 @@ impl PolarAlignUi {
         self.core.abort_active_mode();
     }
 
-    fn show_polar_alignment_error(&self, error: &HorizCoord) {
+    fn show_polar_alignment_result(&self, result: &PolarAlignResult) {
         let ui = gtk_utils::UiHelper::new_from_builder(&self.builder);
-        let alt_err_str = degree_to_str(radian_to_degree(error.alt));
-        let az_err_str = degree_to_str(radian_to_degree(error.az));
-        let alt_label = format!("Alt: {}", alt_err_str);
-        let az_label = format!("Az: {}", az_err_str);
+        let dir_str = |dir: PolarAlignAdjDir| -> (&'static str, &'static str) {
+            match dir {
+                PolarAlignAdjDir::Up    => ("↑", "move up"),
+                PolarAlignAdjDir::Down  => ("↓", "move down"),
+                PolarAlignAdjDir::Left  => ("←", "move left"),
+                PolarAlignAdjDir::Right => ("→", "move right"),
+            }
+        };
+        let (alt_err_arrow, alt_dir_str) = dir_str(result.alt_dir);
+        let (az_err_arrow, az_dir_str) = dir_str(result.az_dir);
+        let alt_label = format!("Alt: {:.1}' ({})", result.alt_arcmin, alt_dir_str);
+        let az_label = format!("Az: {:.1}' ({})", result.az_arcmin, az_dir_str);
         ui.set_prop_str("l_pa_alt_err.label", Some(&alt_label));
         ui.set_prop_str("l_pa_az_err.label", Some(&az_label));
+        ui.set_prop_str("l_pa_alt_err_arr.label", Some(alt_err_arrow));
+        ui.set_prop_str("l_pa_az_err_arr.label", Some(az_err_arrow));
 
-        let alt_err_arrow = if error.alt < 0.0 { "↑" } else { "↓" };
-        let az_err_arrow = if error.az < 0.0 { "→" } else { "←" };
-        ui.set_prop_str("l_pa_alt_err_arr.label", Some(&alt_err_arrow));
-        ui.set_prop_str("l_pa_az_err_arr.label", Some(&az_err_arrow));
+        let error = &result.error;
 
         let set_all_label_size = |label_name: &str, err: f64| {
             let err_minutes = f64::abs(radian_to_degree(err) * 60.0);
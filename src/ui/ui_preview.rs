@@ -1,10 +1,10 @@
-use std::{rc::Rc, sync::*, cell::{RefCell, Cell}, path::PathBuf};
+use std::{rc::Rc, sync::*, cell::{RefCell, Cell}, path::{Path, PathBuf}, f64::consts::PI, io::Write};
 use chrono::{DateTime, Local, Utc};
 use gtk::{cairo, glib::{self, clone}, prelude::*};
 use serde::{Serialize, Deserialize};
 use crate::{
     core::{core::*, events::*, frame_processing::*},
-    image::{histogram::*, info::*, io::save_image_to_tif_file, preview::*, raw::{CalibrMethods, FrameType}, stars_offset::Offset},
+    image::{histogram::*, image::Image, info::*, io::save_image_to_tif_file, preview::*, raw::{CalibrMethods, FrameType}, stars::StarsTilt, stars_offset::Offset},
     options::*,
     utils::{gtk_utils::{self, *}, io_utils::*, log_utils::*}
 };
@@ -39,7 +39,9 @@ pub fn init_ui(
         light_history:      RefCell::new(Vec::new()),
         calibr_history:     RefCell::new(Vec::new()),
         flat_info:          RefCell::new(FlatImageInfo::default()),
+        stars_tilt:         RefCell::new(StarsTilt::default()),
         is_color_image:     Cell::new(false),
+        preview_img_scale:  Cell::new(None),
         self_:              RefCell::new(None),
     });
 
@@ -88,8 +90,12 @@ struct LightHistoryItem {
     stars_ovality:  Option<f32>,
     bad_ovality:    bool,
     stars_count:    usize,
+    snr:            Option<f32>,
+    hfd:            Option<f32>, // px
+    airmass:        Option<f32>,
     noise:          Option<f32>, // %
     background:     f32, // %
+    score:          f32, // 0..100
     offset:         Option<Offset>,
     bad_offset:     bool,
     calibr_methods: CalibrMethods,
@@ -117,7 +123,9 @@ struct PreviewUi {
     calibr_history:     RefCell<Vec<CalibrHistoryItem>>,
     closed:             Cell<bool>,
     flat_info:          RefCell<FlatImageInfo>,
+    stars_tilt:         RefCell<StarsTilt>,
     is_color_image:     Cell<bool>,
+    preview_img_scale:  Cell<Option<f64>>,
     self_:              RefCell<Option<Rc<PreviewUi>>>,
 }
 
@@ -189,6 +197,7 @@ impl PreviewUi {
         gtk_utils::connect_action   (&self.window, self, "save_image_preview",  Self::handler_action_save_image_preview);
         gtk_utils::connect_action   (&self.window, self, "save_image_linear",   Self::handler_action_save_image_linear);
         gtk_utils::connect_action   (&self.window, self, "clear_light_history", Self::handler_action_clear_light_history);
+        gtk_utils::connect_action   (&self.window, self, "export_light_history", Self::handler_action_export_light_history);
         gtk_utils::connect_action_rc(&self.window, self, "load_image",          Self::handler_action_open_image);
 
         let ch_hist_logy = self.builder.object::<gtk::CheckButton>("ch_hist_logy").unwrap();
@@ -219,7 +228,16 @@ impl PreviewUi {
             let Ok(mut options) = self_.options.try_write() else { return; };
             let source = PreviewSource::from_active_id(cb.active_id().as_deref());
             options.preview.source = source;
+
+            // Show levels of new preview source. Handlers of scales
+            // do nothing here because options are locked
+            let (dark_lvl, light_lvl, gamma) = options.preview.levels();
+            let ui = gtk_utils::UiHelper::new_from_builder(&self_.builder);
+            ui.set_range_value("scl_dark", dark_lvl);
+            ui.set_range_value("scl_highlight", light_lvl);
+            ui.set_range_value("scl_gamma", gamma);
             drop(options);
+
             self_.create_and_show_preview_image();
             self_.repaint_histogram();
             self_.show_histogram_stat();
@@ -254,7 +272,8 @@ impl PreviewUi {
         let scl_dark = self.builder.object::<gtk::Scale>("scl_dark").unwrap();
         scl_dark.connect_value_changed(clone!(@weak self as self_ => move |scl| {
             let Ok(mut options) = self_.options.try_write() else { return; };
-            options.preview.dark_lvl = scl.value();
+            let (dark_lvl, _, _) = options.preview.levels_mut();
+            *dark_lvl = scl.value();
             drop(options);
             self_.create_and_show_preview_image();
         }));
@@ -262,7 +281,8 @@ impl PreviewUi {
         let scl_highlight = self.builder.object::<gtk::Scale>("scl_highlight").unwrap();
         scl_highlight.connect_value_changed(clone!(@weak self as self_ => move |scl| {
             let Ok(mut options) = self_.options.try_write() else { return; };
-            options.preview.light_lvl = scl.value();
+            let (_, light_lvl, _) = options.preview.levels_mut();
+            *light_lvl = scl.value();
             drop(options);
             self_.create_and_show_preview_image();
         }));
@@ -270,7 +290,8 @@ impl PreviewUi {
         let scl_gamma = self.builder.object::<gtk::Scale>("scl_gamma").unwrap();
         scl_gamma.connect_value_changed(clone!(@weak self as self_ => move |scl| {
             let Ok(mut options) = self_.options.try_write() else { return; };
-            options.preview.gamma = scl.value();
+            let (_, _, gamma) = options.preview.levels_mut();
+            *gamma = scl.value();
             drop(options);
             self_.create_and_show_preview_image();
         }));
@@ -295,6 +316,47 @@ impl PreviewUi {
             })
         );
 
+        let da_tilt = self.builder.object::<gtk::DrawingArea>("da_tilt").unwrap();
+        da_tilt.connect_draw(
+            clone!(@weak self as self_ => @default-return glib::Propagation::Proceed,
+            move |area, cr| {
+                gtk_utils::exec_and_show_error(&self_.window, || {
+                    self_.handler_draw_tilt(area, cr)?;
+                    Ok(())
+                });
+                glib::Propagation::Proceed
+            })
+        );
+
+        let da_preview_overlay = self.builder.object::<gtk::DrawingArea>("da_preview_overlay").unwrap();
+        da_preview_overlay.connect_draw(
+            clone!(@weak self as self_ => @default-return glib::Propagation::Proceed,
+            move |area, cr| {
+                gtk_utils::exec_and_show_error(&self_.window, || {
+                    self_.handler_draw_preview_overlay(area, cr)?;
+                    Ok(())
+                });
+                glib::Propagation::Proceed
+            })
+        );
+
+        let chb_crosshair = self.builder.object::<gtk::CheckButton>("chb_crosshair").unwrap();
+        chb_crosshair.connect_active_notify(clone!(@weak self as self_ => move |chb| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.preview.crosshair = chb.is_active();
+            drop(options);
+            self_.correct_widgets_props();
+            self_.redraw_preview_overlay();
+        }));
+
+        let e_crosshair_circles = self.builder.object::<gtk::Entry>("e_crosshair_circles").unwrap();
+        e_crosshair_circles.connect_text_notify(clone!(@weak self as self_ => move |entry| {
+            let Ok(mut options) = self_.options.try_write() else { return; };
+            options.preview.crosshair_r = PreviewOptions::crosshair_r_from_str(&entry.text());
+            drop(options);
+            self_.redraw_preview_overlay();
+        }));
+
         let chb_wb_auto = self.builder.object::<gtk::CheckButton>("chb_wb_auto").unwrap();
         chb_wb_auto.connect_active_notify(clone!(@weak self as self_ => move |chb| {
             let Ok(mut options) = self_.options.try_write() else { return; };
@@ -441,18 +503,20 @@ impl PreviewUi {
         let ui = gtk_utils::UiHelper::new_from_builder(&self.builder);
 
         let auto_color_checked = ui.prop_bool("chb_wb_auto.active");
+        let crosshair = ui.prop_bool("chb_crosshair.active");
         let is_color_image = self.is_color_image.get();
         let rgb_enabled = !auto_color_checked && is_color_image;
 
         ui.enable_widgets(false, &[
-            ("cb_preview_color", is_color_image),
-            ("chb_wb_auto",      is_color_image),
-            ("l_wb_red",         rgb_enabled),
-            ("scl_wb_red",       rgb_enabled),
-            ("l_wb_green",       rgb_enabled),
-            ("scl_wb_green",     rgb_enabled),
-            ("l_wb_blue",        rgb_enabled),
-            ("scl_wb_blue",      rgb_enabled),
+            ("cb_preview_color",    is_color_image),
+            ("chb_wb_auto",         is_color_image),
+            ("l_wb_red",            rgb_enabled),
+            ("scl_wb_red",          rgb_enabled),
+            ("l_wb_green",          rgb_enabled),
+            ("scl_wb_green",        rgb_enabled),
+            ("l_wb_blue",           rgb_enabled),
+            ("scl_wb_blue",         rgb_enabled),
+            ("e_crosshair_circles", crosshair),
         ]);
     }
 
@@ -497,6 +561,8 @@ impl PreviewUi {
                 ui.set_prop_str("e_background.text", Some(&format!("{:.2}%", bg)));
                 let noise = 100_f64 * info.noise as f64 / info.max_value as f64;
                 ui.set_prop_str("e_noise.text", Some(&format!("{:.4}%", noise)));
+                *self.stars_tilt.borrow_mut() = info.stars.tilt.clone();
+                self.show_stars_tilt_info();
                 update_info_panel_vis(true, false, false);
             },
             ResultImageInfo::FlatInfo(info) => {
@@ -531,6 +597,67 @@ impl PreviewUi {
         }
     }
 
+    fn show_stars_tilt_info(&self) {
+        let tilt = self.stars_tilt.borrow();
+        let mut tooltip = String::new();
+        for row in &tilt.cells {
+            if !tooltip.is_empty() { tooltip += "\n"; }
+            let row_str = row.iter()
+                .map(|cell| match cell {
+                    Some(cell) => format!("{:.2} ({})", cell.eccentricity, cell.stars_cnt),
+                    None       => "----".to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join("  ");
+            tooltip += &row_str;
+        }
+        if let Some(diff) = tilt.max_diff() {
+            tooltip += &format!("\nMax difference: {:.2}", diff);
+        }
+        let da_tilt = self.builder.object::<gtk::DrawingArea>("da_tilt").unwrap();
+        da_tilt.set_tooltip_text(Some(&tooltip));
+        da_tilt.queue_draw();
+    }
+
+    fn handler_draw_tilt(
+        &self,
+        area: &gtk::DrawingArea,
+        cr:   &cairo::Context
+    ) -> anyhow::Result<()> {
+        const N: usize = StarsTilt::GRID_SIZE;
+        let tilt = self.stars_tilt.borrow();
+        let cell_width = area.allocated_width() as f64 / N as f64;
+        let cell_height = area.allocated_height() as f64 / N as f64;
+        for (row_idx, row) in tilt.cells.iter().enumerate() {
+            for (col_idx, cell) in row.iter().enumerate() {
+                let x = col_idx as f64 * cell_width;
+                let y = row_idx as f64 * cell_height;
+                let Some(cell) = cell else {
+                    cr.set_source_rgb(0.5, 0.5, 0.5);
+                    cr.rectangle(x, y, cell_width - 1.0, cell_height - 1.0);
+                    cr.fill()?;
+                    continue;
+                };
+                let ecc = cell.eccentricity.clamp(0.0, 1.0) as f64;
+                cr.set_source_rgb(ecc, 1.0 - ecc, 0.0);
+                cr.rectangle(x, y, cell_width - 1.0, cell_height - 1.0);
+                cr.fill()?;
+
+                // Direction of elongation
+                let len = 0.5 * ecc * f64::min(cell_width, cell_height);
+                let (sin, cos) = (cell.angle as f64).sin_cos();
+                let center_x = x + 0.5 * cell_width;
+                let center_y = y + 0.5 * cell_height;
+                cr.set_source_rgb(0.0, 0.0, 0.0);
+                cr.set_line_width(1.0);
+                cr.move_to(center_x - len * cos, center_y - len * sin);
+                cr.line_to(center_x + len * cos, center_y + len * sin);
+                cr.stroke()?;
+            }
+        }
+        Ok(())
+    }
+
     fn show_flat_info(&self) {
         let info = self.flat_info.borrow();
         let ui = gtk_utils::UiHelper::new_from_builder(&self.builder);
@@ -636,15 +763,89 @@ impl PreviewUi {
             }
             img_preview.set_pixbuf(Some(&pixbuf));
             is_color_image = rgb_bytes.is_color_image;
+            let img_scale =
+                if pp.scale != PreviewScale::CenterAndCorners && rgb_bytes.orig_width != 0 {
+                    Some(pixbuf.width() as f64 / rgb_bytes.orig_width as f64)
+                } else {
+                    None
+                };
+            self.preview_img_scale.set(img_scale);
         } else {
             img_preview.clear();
             img_preview.set_pixbuf(None);
+            self.preview_img_scale.set(None);
         }
+        self.redraw_preview_overlay();
 
         self.is_color_image.set(is_color_image);
 
     }
 
+    fn redraw_preview_overlay(&self) {
+        let da_preview_overlay = self.builder.object::<gtk::DrawingArea>("da_preview_overlay").unwrap();
+        da_preview_overlay.queue_draw();
+    }
+
+    /// Angular size of image pixel in arc seconds
+    fn preview_image_pixel_scale(&self) -> Option<f64> {
+        let source = self.options.read().unwrap().preview.source.clone();
+        let raw_info = match source {
+            PreviewSource::OrigFrame =>
+                self.core.cur_frame().image.try_read().ok()?.raw_info.clone()?,
+            PreviewSource::LiveStacking =>
+                self.core.live_stacking().image.try_read().ok()?.raw_info.clone()?,
+        };
+        let focal_len = raw_info.focal_len?;
+        let pixel_size = 0.5 * (raw_info.pixel_size_x? + raw_info.pixel_size_y?);
+        if focal_len <= 0.0 || pixel_size <= 0.0 { return None; }
+        let angle = f64::atan2(pixel_size / 1_000.0, focal_len);
+        Some(radian_to_degree(angle) * 3600.0)
+    }
+
+    fn handler_draw_preview_overlay(
+        &self,
+        area: &gtk::DrawingArea,
+        cr:   &cairo::Context
+    ) -> anyhow::Result<()> {
+        let options = self.options.read().unwrap();
+        if !options.preview.crosshair { return Ok(()); }
+        let radii = options.preview.crosshair_r.clone();
+        drop(options);
+
+        let img_preview = self.builder.object::<gtk::Image>("img_preview").unwrap();
+        let Some(pixbuf) = img_preview.pixbuf() else { return Ok(()); };
+
+        // image is centered inside of widget
+        let img_width = pixbuf.width() as f64;
+        let img_height = pixbuf.height() as f64;
+        let left = 0.5 * (area.allocated_width() as f64 - img_width);
+        let top = 0.5 * (area.allocated_height() as f64 - img_height);
+        let center_x = left + 0.5 * img_width;
+        let center_y = top + 0.5 * img_height;
+
+        cr.set_source_rgba(1.0, 0.3, 0.3, 0.8);
+        cr.set_line_width(1.0);
+
+        cr.move_to(center_x, top);
+        cr.line_to(center_x, top + img_height);
+        cr.move_to(left, center_y);
+        cr.line_to(left + img_width, center_y);
+        cr.stroke()?;
+
+        let Some(img_scale) = self.preview_img_scale.get() else { return Ok(()); };
+        let Some(pixel_scale) = self.preview_image_pixel_scale() else { return Ok(()); };
+
+        for radius in radii {
+            let radius_px = img_scale * 60.0 * radius / pixel_scale;
+            if radius_px < 2.0 { continue; }
+            cr.new_sub_path();
+            cr.arc(center_x, center_y, radius_px, 0.0, 2.0 * PI);
+        }
+        cr.stroke()?;
+
+        Ok(())
+    }
+
     fn handler_action_save_image_preview(&self) {
         gtk_utils::exec_and_show_error(&self.window, || {
             let options = self.options.read().unwrap();
@@ -663,11 +864,13 @@ impl PreviewUi {
                 fn_prefix,
                 Utc::now().format("%Y-%m-%d_%H-%M-%S").to_string()
             );
-            let Some(file_name) = gtk_utils::select_file_name_to_save(
+            let Some(file_name) = gtk_utils::select_file_name_to_save_by_filters(
                 &self.window,
-                "Enter file name to save preview image as jpeg",
-                "Jpeg images", "*.jpg",
-                "jpg",
+                "Enter file name to save stretched preview image",
+                &[
+                    ("Jpeg images", "*.jpg", "jpg"),
+                    ("PNG images",  "*.png", "png"),
+                ],
                 &def_file_name,
             ) else {
                 return Ok(());
@@ -687,11 +890,47 @@ impl PreviewUi {
                 rgb_data.height as i32,
                 (rgb_data.width * 3) as i32,
             );
-            pixbuf.savev(file_name, "jpeg", &[("quality", "90")])?;
+            let is_png = file_name.extension()
+                .map(|ext| ext.eq_ignore_ascii_case("png"))
+                .unwrap_or(false);
+            if is_png {
+                let text_items = Self::preview_image_metadata(&image)
+                    .into_iter()
+                    .map(|(key, value)| (format!("tEXt::{}", key), value))
+                    .collect::<Vec<_>>();
+                let png_options = text_items.iter()
+                    .map(|(key, value)| (key.as_str(), value.as_str()))
+                    .collect::<Vec<_>>();
+                pixbuf.savev(file_name, "png", &png_options)?;
+            } else {
+                pixbuf.savev(file_name, "jpeg", &[("quality", "90")])?;
+            }
             Ok(())
         });
     }
 
+    /// Text items for PNG tEXt chunks of saved preview image
+    fn preview_image_metadata(image: &Image) -> Vec<(&'static str, String)> {
+        let mut result = vec![
+            ("Software", format!("AstraLite {}", env!("CARGO_PKG_VERSION"))),
+        ];
+        let Some(info) = &image.raw_info else {
+            return result;
+        };
+        result.push(("Exposure", format!("{} s", info.exposure)));
+        result.push(("Gain", info.gain.to_string()));
+        if !info.camera.is_empty() {
+            result.push(("Camera", info.camera.clone()));
+        }
+        if let Some(object) = &info.object {
+            result.push(("Title", object.clone()));
+        }
+        if let Some(time) = &info.time {
+            result.push(("Creation Time", time.format("%Y-%m-%dT%H:%M:%S").to_string()));
+        }
+        result
+    }
+
     fn handler_action_save_image_linear(&self) {
         gtk_utils::exec_and_show_error(&self.window, || {
             let options = self.options.read().unwrap();
@@ -764,11 +1003,14 @@ impl PreviewUi {
 
         match result.data {
             FrameProcessResultData::ShotProcessingFinished {
-                processing_time, blob_dl_time, ..
+                processing_time, blob_dl_time, blob, ..
             } => {
                 let perf_str = format!(
-                    "Download time = {:.2}s, img. process time = {:.2}s",
-                    blob_dl_time, processing_time
+                    "Download time = {:.2}s ({:.1} MB, {}), img. process time = {:.2}s",
+                    blob_dl_time,
+                    blob.data.len() as f64 / (1024.0 * 1024.0),
+                    blob.format,
+                    processing_time
                 );
                 self.main_ui.set_perf_string(perf_str);
             }
@@ -813,7 +1055,17 @@ impl PreviewUi {
                 self.repaint_histogram();
                 self.show_histogram_stat();
             }
-            FrameProcessResultData::LightFrameInfo(info) => {
+            // frames of video stream are too frequent for history
+            FrameProcessResultData::LightFrameInfo(info)
+            if result.mode_type != ModeType::VideoStream => {
+                let options = self.options.read().unwrap();
+                let score = info.quality_score(
+                    options.quality.score_w_fwhm,
+                    options.quality.score_w_ovality,
+                    options.quality.score_w_stars,
+                    options.quality.score_w_bg,
+                );
+                drop(options);
                 let history_item = LightHistoryItem {
                     mode_type:      result.mode_type,
                     time:           info.time.clone(),
@@ -825,6 +1077,10 @@ impl PreviewUi {
                     background:     info.bg_percent,
                     noise:          info.raw_noise.map(|n| 100.0 * n / info.max_value as f32),
                     stars_count:    info.stars.items.len(),
+                    snr:            info.snr,
+                    hfd:            info.stars.hfd,
+                    airmass:        info.airmass,
+                    score,
                     offset:         info.stars_offset.clone(),
                     bad_offset:     !info.offset_is_ok,
                     calibr_methods: info.calibr_methods.clone(),
@@ -968,6 +1224,7 @@ impl PreviewUi {
             ModeType::MasterDark|
             ModeType::MasterBias        => "Master",
             ModeType::PolarAlignment    => "PA",
+            ModeType::VideoStream       => "Video",
             _                           => "???",
         }
     }
@@ -985,12 +1242,16 @@ impl PreviewUi {
                     /* 2 */  ("FWHM",       String::static_type(), "markup"),
                     /* 3 */  ("Ovality",    String::static_type(), "markup"),
                     /* 4 */  ("Stars",      u32::static_type(),    "text"),
-                    /* 5 */  ("Noise",      String::static_type(), "text"),
-                    /* 6 */  ("Background", String::static_type(), "text"),
-                    /* 7 */  ("Calibr.",    String::static_type(), "text"),
-                    /* 8 */  ("Offs.X",     String::static_type(), "markup"),
-                    /* 9 */  ("Offs.Y",     String::static_type(), "markup"),
-                    /* 10 */ ("Rot.",       String::static_type(), "markup"),
+                    /* 5 */  ("SNR",        String::static_type(), "text"),
+                    /* 6 */  ("HFD",        String::static_type(), "text"),
+                    /* 7 */  ("Airmass",    String::static_type(), "text"),
+                    /* 8 */  ("Noise",      String::static_type(), "text"),
+                    /* 9 */  ("Background", String::static_type(), "text"),
+                    /* 10 */ ("Calibr.",    String::static_type(), "text"),
+                    /* 11 */ ("Offs.X",     String::static_type(), "markup"),
+                    /* 12 */ ("Offs.Y",     String::static_type(), "markup"),
+                    /* 13 */ ("Rot.",       String::static_type(), "markup"),
+                    /* 14 */ ("Score",      String::static_type(), "text"),
                 ])
             },
         };
@@ -1032,10 +1293,23 @@ impl PreviewUi {
                 ovality_str = make_bad_str(&ovality_str);
             }
             let stars_cnt = item.stars_count as u32;
+            let snr_str = item.snr
+                .filter(|v| v.is_finite())
+                .map(|v| format!("{:.1}", v))
+                .unwrap_or_else(|| "—".to_string());
+            let hfd_str = item.hfd
+                .filter(|v| v.is_finite())
+                .map(|v| format!("{:.2}px", v))
+                .unwrap_or_else(|| "—".to_string());
+            let airmass_str = item.airmass
+                .filter(|v| v.is_finite())
+                .map(|v| format!("{:.2}", v))
+                .unwrap_or_else(|| "—".to_string());
             let noise_str = item.noise
                 .map(|v| format!("{:.3}%", v))
                 .unwrap_or_else(|| "???".to_string());
             let bg_str = format!("{:.1}%", item.background);
+            let score_str = format!("{:.0}", item.score);
 
             let (x_str, y_str, angle_str) = if let Some(offset) = &item.offset {
                 let x_str = if !item.bad_offset {
@@ -1067,12 +1341,16 @@ impl PreviewUi {
                 (2, &fwhm_str),
                 (3, &ovality_str),
                 (4, &stars_cnt),
-                (5, &noise_str),
-                (6, &bg_str),
-                (7, &calibr_str),
-                (8, &x_str),
-                (9, &y_str),
-                (10, &angle_str),
+                (5, &snr_str),
+                (6, &hfd_str),
+                (7, &airmass_str),
+                (8, &noise_str),
+                (9, &bg_str),
+                (10, &calibr_str),
+                (11, &x_str),
+                (12, &y_str),
+                (13, &angle_str),
+                (14, &score_str),
             ]);
             if last_is_selected || models_row_cnt == 0 {
                 // Select and scroll to last row
@@ -1186,6 +1464,88 @@ impl PreviewUi {
         }
     }
 
+    fn handler_action_export_light_history(&self) {
+        let def_file_name = format!(
+            "light_history_{}.csv",
+            Local::now().format("%Y-%m-%d_%H-%M-%S")
+        );
+        let Some(file_name) = gtk_utils::select_file_name_to_save(
+            &self.window,
+            "Enter file name to export light frames history",
+            "CSV files", "*.csv",
+            "csv",
+            &def_file_name,
+        ) else {
+            return;
+        };
+        gtk_utils::exec_and_show_error(&self.window, || {
+            self.save_light_history_to_csv(&file_name)?;
+            Ok(())
+        });
+    }
+
+    fn save_light_history_to_csv(&self, file_name: &Path) -> anyhow::Result<()> {
+        fn escape(text: &str) -> String {
+            if text.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", text.replace('"', "\"\""))
+            } else {
+                text.to_string()
+            }
+        }
+        fn opt_to_str(value: Option<f32>, prec: usize) -> String {
+            value
+                .filter(|v| v.is_finite())
+                .map(|v| format!("{:.*}", prec, v))
+                .unwrap_or_default()
+        }
+
+        let file = std::fs::File::create(file_name)?;
+        let mut writer = std::io::BufWriter::new(file);
+        writeln!(
+            writer,
+            "Time,Mode,FWHM,Ovality,Stars,SNR,HFD,Airmass,Noise %,Background %,\
+            Offset X,Offset Y,Rotation,Score,Rejected"
+        )?;
+        for item in self.light_history.borrow().iter() {
+            let time_str = item.time
+                .map(|time| {
+                    let local_time: DateTime<Local> = DateTime::from(time);
+                    local_time.format("%Y-%m-%d %H:%M:%S").to_string()
+                })
+                .unwrap_or_default();
+            let (x_str, y_str, angle_str) = match (&item.offset, item.bad_offset) {
+                (Some(offset), false) => (
+                    format!("{:.2}", offset.x),
+                    format!("{:.2}", offset.y),
+                    format!("{:.4}", offset.angle),
+                ),
+                _ => (String::new(), String::new(), String::new()),
+            };
+            let rejected = item.bad_fwhm || item.bad_ovality || item.bad_offset;
+            let fields = [
+                time_str,
+                Self::mode_type_to_history_str(item.mode_type).to_string(),
+                opt_to_str(item.fwhm, 2),
+                opt_to_str(item.stars_ovality, 2),
+                item.stars_count.to_string(),
+                opt_to_str(item.snr, 1),
+                opt_to_str(item.hfd, 2),
+                opt_to_str(item.airmass, 3),
+                opt_to_str(item.noise, 4),
+                format!("{:.2}", item.background),
+                x_str,
+                y_str,
+                angle_str,
+                format!("{:.0}", item.score),
+                if rejected { "1" } else { "0" }.to_string(),
+            ];
+            let line = fields.iter().map(|f| escape(f)).collect::<Vec<_>>().join(",");
+            writeln!(writer, "{}", line)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
     fn set_full_screen_mode(&self, _full_screen: bool) {
         let options = self.options.read().unwrap();
         if matches!(options.preview.scale, PreviewScale::FitWindow|PreviewScale::CenterAndCorners) {
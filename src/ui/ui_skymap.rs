@@ -3,13 +3,13 @@ use chrono::{prelude::*, Days, Duration, Months};
 use serde::{Serialize, Deserialize};
 use gtk::{cairo, gdk, glib::{self, clone}, prelude::*};
 use crate::{
-    core::{core::*, events::*, frame_processing::*, mode_goto::GotoConfig},
+    core::{core::*, events::*, frame_processing::*, mode_goto::GotoConfig, mode_targets::load_targets_from_file},
     indi::{self, degree_to_str, hour_to_str},
     options::*,
     plate_solve::PlateSolveOkResult,
     utils::{gtk_utils::{self, *}, io_utils::*},
 };
-use super::{sky_map::{alt_widget::paint_altitude_by_time, data::*, math::*, painter::*}, ui_main::*, ui_skymap_options::SkymapOptionsDialog, utils::*};
+use super::{sky_map::{alt_widget::paint_altitude_by_time, data::*, math::*, painter::*, solar_system::calc_moon_info}, ui_main::*, ui_skymap_options::SkymapOptionsDialog, utils::*};
 use super::sky_map::{data::Observer, widget::SkymapWidget};
 
 pub fn init_ui(
@@ -111,6 +111,8 @@ pub struct UiOptions {
     show_ccd:   bool,
     show_ps:    bool,
     exp_dt:     bool,
+    pub moon_warn_sep:   f64, // degrees
+    pub moon_warn_illum: f64, // %
 }
 
 impl Default for UiOptions {
@@ -121,6 +123,8 @@ impl Default for UiOptions {
             show_ccd:   true,
             exp_dt:     true,
             show_ps:    true,
+            moon_warn_sep:   30.0,
+            moon_warn_illum: 50.0,
         }
     }
 }
@@ -334,6 +338,7 @@ impl MapUi {
         gtk_utils::connect_action_rc(&self.window, self, "sm_goto_selected",  Self::handler_goto_selected);
         gtk_utils::connect_action_rc(&self.window, self, "sm_goto_sel_solve", Self::handler_goto_sel_and_solve);
         gtk_utils::connect_action_rc(&self.window, self, "sm_goto_point",     Self::handler_goto_point);
+        gtk_utils::connect_action_rc(&self.window, self, "sm_run_targets",    Self::handler_run_targets);
 
         let connect_spin_btn_evt = |widget_name: &str| {
             let spin_btn = self.builder.object::<gtk::SpinButton>(widget_name).unwrap();
@@ -903,6 +908,7 @@ impl MapUi {
 
         let mut zenith_str = String::new();
         let mut azimuth_str = String::new();
+        let mut moon_str = String::new();
 
         if let Some(obj) = &*obj {
             names = obj.names().join(", ");
@@ -946,6 +952,21 @@ impl MapUi {
 
             zenith_str = degree_to_str(radian_to_degree(h_crd.alt));
             azimuth_str = degree_to_str(radian_to_degree(h_crd.az));
+
+            let moon = calc_moon_info(&time);
+            let moon_h_crd = HorizCoord::from_sphere_pt(&cvt.eq_to_sphere(&moon.crd));
+            let moon_sep = radian_to_degree(EqCoord::angle_between(&moon.crd, &now_crd));
+            let moon_illum = 100.0 * moon.illuminated;
+            moon_str = format!("{:.0}° away, illum. {:.0}%", moon_sep, moon_illum);
+            let ui_options = self.ui_options.borrow();
+            let moon_is_disturbing =
+                moon_h_crd.alt > 0.0 &&
+                moon_sep < ui_options.moon_warn_sep &&
+                moon_illum > ui_options.moon_warn_illum;
+            drop(ui_options);
+            if moon_is_disturbing {
+                moon_str = format!(r##"<span color="#FF4040">{}</span>"##, moon_str);
+            }
         }
 
         ui.set_prop_str("e_sm_sel_names.text", Some(&names));
@@ -960,6 +981,7 @@ impl MapUi {
         ui.set_prop_str("l_sm_sel_dec_now.label", Some(&dec_now_str));
         ui.set_prop_str("l_sm_sel_zenith.label", Some(&zenith_str));
         ui.set_prop_str("l_sm_sel_az.label", Some(&azimuth_str));
+        ui.set_prop_str("l_sm_sel_moon.label", Some(&moon_str));
     }
 
     fn init_search_result_treeview(&self) {
@@ -1128,8 +1150,15 @@ impl MapUi {
         let crd = selected_item.as_ref().map(|item| item.crd());
         drop(selected_item);
         let observer = self.create_observer();
+        let opts = self.options.read().unwrap();
+        let min_alt = if opts.targets.skip_low {
+            Some(degree_to_radian(opts.targets.min_alt))
+        } else {
+            None
+        };
+        drop(opts);
 
-        paint_altitude_by_time(area, cr, cur_dt, cur_dt_local, &observer, &crd)?;
+        paint_altitude_by_time(area, cr, cur_dt, cur_dt_local, &observer, &crd, min_alt)?;
         Ok(())
     }
 
@@ -1150,6 +1179,11 @@ impl MapUi {
                 "sm_goto_point",
                 indi_is_active && eq_coord.is_some() && !self.goto_started.get(),
             );
+            gtk_utils::enable_action(
+                &self.window,
+                "sm_run_targets",
+                indi_is_active && !self.goto_started.get(),
+            );
             let m_sm_goto_sel = self.builder.object::<gtk::Menu>("m_sm_widget").unwrap();
             m_sm_goto_sel.set_attach_widget(Some(self.map_widget.get_widget()));
             m_sm_goto_sel.popup_at_pointer(None);
@@ -1190,6 +1224,44 @@ impl MapUi {
     }
 
     fn goto_coordinate(self: &Rc<Self>, coord: &EqCoord, only_goto: bool) {
+        let observer = self.create_observer();
+        let now = Utc::now().naive_utc();
+        let cvt = EqToSphereCvt::new(observer.longitude, observer.latitude, &now);
+        let h_crd = HorizCoord::from_sphere_pt(&cvt.eq_to_sphere(coord));
+        if h_crd.alt >= 0.0 {
+            self.start_goto_coordinate(coord, only_goto);
+            return;
+        }
+        let text = format!(
+            "Destination is below horizon (altitude {}). Slew anyway?",
+            degree_to_str(radian_to_degree(h_crd.alt))
+        );
+        let dialog = gtk::MessageDialog::builder()
+            .transient_for(&self.window)
+            .title("Goto")
+            .text(&text)
+            .modal(true)
+            .message_type(gtk::MessageType::Warning)
+            .build();
+        gtk_utils::add_ok_and_cancel_buttons(
+            dialog.upcast_ref::<gtk::Dialog>(),
+            "Yes", gtk::ResponseType::Yes,
+            "No", gtk::ResponseType::No,
+        );
+        dialog.show();
+        let coord = *coord;
+        dialog.connect_response(clone!(@weak self as self_ =>
+            move |dlg, response| {
+            if response == gtk::ResponseType::Yes {
+                self_.start_goto_coordinate(&coord, only_goto);
+            } else {
+                log::info!("Goto below horizon is cancelled by user");
+            }
+            dlg.close();
+        }));
+    }
+
+    fn start_goto_coordinate(self: &Rc<Self>, coord: &EqCoord, only_goto: bool) {
         let mut options = self.options.write().unwrap();
         options.read_all(&self.builder);
         drop(options);
@@ -1204,6 +1276,61 @@ impl MapUi {
         });
     }
 
+    fn handler_run_targets(self: &Rc<Self>) {
+        let opts = self.options.read().unwrap();
+        let chb_skip_low = gtk::CheckButton::builder()
+            .label("Skip targets with altitude below (°)")
+            .active(opts.targets.skip_low)
+            .build();
+        let spb_min_alt = gtk::SpinButton::with_range(0.0, 80.0, 1.0);
+        spb_min_alt.set_value(opts.targets.min_alt);
+        drop(opts);
+        let bx_extra = gtk::Box::builder()
+            .orientation(gtk::Orientation::Horizontal)
+            .spacing(5)
+            .build();
+        bx_extra.add(&chb_skip_low);
+        bx_extra.add(&spb_min_alt);
+        bx_extra.show_all();
+
+        let csv_filter = gtk::FileFilter::new();
+        csv_filter.set_name(Some("Target list (*.csv, *.txt)"));
+        csv_filter.add_pattern("*.csv");
+        csv_filter.add_pattern("*.txt");
+
+        let fc = gtk::FileChooserDialog::builder()
+            .action(gtk::FileChooserAction::Open)
+            .title("Select target list file (name, RA, Dec, exposure, count)")
+            .modal(true)
+            .transient_for(&self.window)
+            .build();
+        fc.add_filter(csv_filter);
+        fc.set_extra_widget(&bx_extra);
+        gtk_utils::add_ok_and_cancel_buttons(
+            fc.upcast_ref::<gtk::Dialog>(),
+            "_Run",    gtk::ResponseType::Accept,
+            "_Cancel", gtk::ResponseType::Cancel
+        );
+        fc.connect_response(clone!(@weak self as self_ => move |file_chooser, response| {
+            if response == gtk::ResponseType::Accept {
+                gtk_utils::exec_and_show_error(&self_.window, || {
+                    let Some(file_name) = file_chooser.file() else { return Ok(()); };
+                    let Some(file_name) = file_name.path() else { return Ok(()); };
+                    let targets = load_targets_from_file(&file_name)?;
+                    let mut options = self_.options.write().unwrap();
+                    options.read_all(&self_.builder);
+                    options.targets.skip_low = chb_skip_low.is_active();
+                    options.targets.min_alt = spb_min_alt.value();
+                    drop(options);
+                    self_.core.start_targets(&targets)?;
+                    Ok(())
+                });
+            }
+            file_chooser.close();
+        }));
+        fc.show();
+    }
+
     fn set_full_screen_mode(&self, full_screen: bool) {
         let bx_skymap_panel = self.builder.object::<gtk::Widget>("bx_skymap_panel").unwrap();
         if full_screen {
@@ -27,6 +27,16 @@ impl SkymapOptionsDialog {
         spb_horiz_glow_angle.set_digits(0);
         spb_horiz_glow_angle.set_increments(1.0, 5.0);
 
+        let spb_moon_warn_sep = builder.object::<gtk::SpinButton>("spb_moon_warn_sep").unwrap();
+        spb_moon_warn_sep.set_range(1.0, 180.0);
+        spb_moon_warn_sep.set_digits(0);
+        spb_moon_warn_sep.set_increments(1.0, 10.0);
+
+        let spb_moon_warn_illum = builder.object::<gtk::SpinButton>("spb_moon_warn_illum").unwrap();
+        spb_moon_warn_illum.set_range(0.0, 100.0);
+        spb_moon_warn_illum.set_digits(0);
+        spb_moon_warn_illum.set_increments(5.0, 20.0);
+
         let result = Rc::new(SkymapOptionsDialog {
             builder: builder.clone(),
             dialog,
@@ -53,6 +63,9 @@ impl SkymapOptionsDialog {
 
         let c = &ui_options.paint.eq_grid.text_color;
         ui.set_color("clrb_eq_grid_text", c.r, c.g, c.b, c.a);
+
+        ui.set_prop_f64("spb_moon_warn_sep.value", ui_options.moon_warn_sep);
+        ui.set_prop_f64("spb_moon_warn_illum.value", ui_options.moon_warn_illum);
     }
 
     pub fn get_options(
@@ -73,6 +86,9 @@ impl SkymapOptionsDialog {
         let (r, g, b, a) = ui.color("clrb_eq_grid_text");
         ui_options.paint.eq_grid.text_color = Color { r, g, b, a };
 
+        ui_options.moon_warn_sep = ui.prop_f64("spb_moon_warn_sep.value");
+        ui_options.moon_warn_illum = ui.prop_f64("spb_moon_warn_illum.value");
+
         return Ok(());
     }
 
@@ -1,4 +1,4 @@
-use std::{rc::Rc, cell::{RefCell, Cell}, time::Duration, collections::HashMap, hash::Hash};
+use std::{rc::Rc, cell::{RefCell, Cell}, time::Duration, collections::HashMap, hash::Hash, sync::Arc};
 use gtk::{prelude::*, glib, glib::clone, cairo, gdk};
 use crate::{image::histogram::*, indi};
 
@@ -113,6 +113,81 @@ impl<Action: Hash+Eq + 'static> DelayedActions<Action> {
     }
 }
 
+/// Coalesces high-rate INDI property changes (like CCD_EXPOSURE_VALUE).
+/// Only latest change of every property element is kept until
+/// next UI timer tick. Changes of property state are never coalesced
+pub struct PropChangeThrottler {
+    items:    RefCell<Vec<Arc<indi::PropChangeEvent>>>,
+    received: Cell<usize>,
+    applied:  Cell<usize>,
+}
+
+impl PropChangeThrottler {
+    const STAT_LOG_PERIOD: usize = 1000;
+
+    pub fn new() -> Self {
+        Self {
+            items:    RefCell::new(Vec::new()),
+            received: Cell::new(0),
+            applied:  Cell::new(0),
+        }
+    }
+
+    /// Returns events which have to be processed right now
+    pub fn push(
+        &self,
+        event:   Arc<indi::PropChangeEvent>,
+        enabled: bool
+    ) -> Vec<Arc<indi::PropChangeEvent>> {
+        let elem_name = match &event.change {
+            indi::PropChange::Change { value, prev_state, new_state }
+            if enabled && prev_state == new_state =>
+                Some(Arc::clone(&value.elem_name)),
+            _ =>
+                None,
+        };
+        let Some(elem_name) = elem_name else {
+            let mut result = self.take();
+            result.push(event);
+            return result;
+        };
+        self.received.set(self.received.get() + 1);
+        let mut items = self.items.borrow_mut();
+        let existing = items.iter_mut().find(|item| {
+            item.device_name == event.device_name &&
+            item.prop_name == event.prop_name &&
+            matches!(
+                &item.change,
+                indi::PropChange::Change { value, .. } if value.elem_name == elem_name
+            )
+        });
+        if let Some(existing) = existing {
+            *existing = event;
+        } else {
+            items.push(event);
+        }
+        Vec::new()
+    }
+
+    /// Takes collected events. Call it in UI timer handler
+    pub fn take(&self) -> Vec<Arc<indi::PropChangeEvent>> {
+        let result = std::mem::take(&mut *self.items.borrow_mut());
+        if result.is_empty() {
+            return result;
+        }
+        self.applied.set(self.applied.get() + result.len());
+        if self.received.get() >= Self::STAT_LOG_PERIOD {
+            log::debug!(
+                "Property changes coalesced: {} received, {} applied",
+                self.received.get(), self.applied.get()
+            );
+            self.received.set(0);
+            self.applied.set(0);
+        }
+        result
+    }
+}
+
 pub fn draw_histogram(
     hist:   &Histogram,
     area:   &gtk::DrawingArea,
@@ -500,6 +500,51 @@ pub fn select_file_name_to_save(
     }
 }
 
+/// Same as `select_file_name_to_save` but for several file types.
+/// `filters` items are (filter name, pattern, extension). Extension of
+/// entered file name is kept if it is known, otherwise extension of
+/// selected filter is used
+pub fn select_file_name_to_save_by_filters(
+    parent:        &impl IsA<gtk::Window>,
+    title:         &str,
+    filters:       &[(&str, &str, &str)],
+    def_file_name: &str,
+) -> Option<PathBuf> {
+    let fc = gtk::FileChooserDialog::builder()
+        .action(gtk::FileChooserAction::Save)
+        .title(title)
+        .modal(true)
+        .transient_for(parent)
+        .build();
+    for &(filter_name, filter_ext, _) in filters {
+        let ff = gtk::FileFilter::new();
+        ff.set_name(Some(filter_name));
+        ff.add_pattern(filter_ext);
+        fc.add_filter(ff);
+    }
+    fc.set_current_name(def_file_name);
+    add_ok_and_cancel_buttons(
+        fc.upcast_ref::<gtk::Dialog>(),
+        "_Save",   gtk::ResponseType::Accept,
+        "_Cancel", gtk::ResponseType::Cancel,
+    );
+    let resp = fc.run();
+    fc.close();
+    if resp != gtk::ResponseType::Accept {
+        return None;
+    }
+    let path = fc.file()?.path()?;
+    let file_ext = path.extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+    if filters.iter().any(|(_, _, ext)| ext.eq_ignore_ascii_case(file_ext)) {
+        return Some(path);
+    }
+    let filter_name = fc.filter().and_then(|ff| ff.name());
+    let (_, _, ext) = filters.iter()
+        .find(|(name, _, _)| filter_name.as_deref() == Some(*name))
+        .or(filters.first())?;
+    Some(path.with_extension(ext))
+}
+
 pub const DEFAULT_DPMM: f64 = 3.8;
 
 pub fn get_widget_dpmm(widget: &impl IsA<gtk::Widget>) -> Option<(f64, f64)> {
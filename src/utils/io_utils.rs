@@ -91,3 +91,27 @@ pub fn get_free_file_name(path: &Path) -> PathBuf {
     }
     return result;
 }
+
+/// Returns free space (in bytes) of disk where `path` is located.
+/// Uses `df` utility
+pub fn get_free_disk_space(path: &Path) -> anyhow::Result<u64> {
+    // `df` requires existing path
+    let path = path.ancestors()
+        .find(|p| p.exists())
+        .ok_or_else(|| anyhow::anyhow!("Path {} doesn't exist", path.to_str().unwrap_or_default()))?;
+    let output = std::process::Command::new("df")
+        .arg("-Pk")
+        .arg(path)
+        .output()?;
+    if !output.status.success() {
+        anyhow::bail!("df returns error: {}", String::from_utf8_lossy(&output.stderr));
+    }
+    let text = String::from_utf8_lossy(&output.stdout);
+    let avail_kb = text
+        .lines()
+        .nth(1)
+        .and_then(|line| line.split_whitespace().nth(3))
+        .and_then(|value| value.parse::<u64>().ok())
+        .ok_or_else(|| anyhow::anyhow!("Can't parse df output: {}", text))?;
+    Ok(avail_kb * 1024)
+}